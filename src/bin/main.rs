@@ -1,33 +1,80 @@
 use axum::{
     Json, RequestExt, Router,
     body::Body,
-    extract::{FromRequest, Query, Request, State},
+    extract::{FromRequest, Path, Query, Request, State},
     http::{
-        StatusCode,
+        StatusCode, Uri,
         header::{self, HeaderValue},
     },
-    response::{Html, IntoResponse, Response},
-    routing::{get, post, post_service},
+    response::{
+        Html, IntoResponse, Response,
+        sse::{Event, KeepAlive, Sse},
+    },
+    routing::{delete, get, patch, post},
 };
-use clap::{Arg, ArgAction, Command, value_parser};
+use bytes::Bytes;
+use clap::{Arg, ArgAction, ArgMatches, Command, parser::ValueSource, value_parser};
 use clap_complete::aot::{Generator, Shell, generate};
-use futures_util::stream::unfold;
-use serde::Deserialize;
-use std::net::{IpAddr, SocketAddr};
-use tokio::{net::TcpListener, sync::mpsc};
-use tower::service_fn;
-use utsuru::{mirrors::DiscordLiveBuilder, sources::WHIP};
+use futures_util::stream::{Stream as EventStream, unfold};
+use http_body_util::{BodyExt, Full};
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
+use hyper_util::{
+    client::legacy::{Client, connect::HttpConnector},
+    rt::TokioExecutor,
+};
+use rustls::{ClientConfig, RootCertStore};
+use serde::{Deserialize, Deserializer, Serialize, de::DeserializeOwned};
+use serde_json::Value;
+use std::{
+    collections::{BTreeMap, HashMap},
+    convert::Infallible,
+    env,
+    fmt::{Display, Write as FmtWrite},
+    fs::{self, OpenOptions},
+    io::{self, ErrorKind, IsTerminal, Write},
+    net::{IpAddr, SocketAddr},
+    path::{Path as StdPath, PathBuf},
+    str::FromStr,
+    sync::{Arc, Mutex as StdMutex},
+    time::Duration,
+};
+use tokio::{
+    net::TcpListener,
+    sync::{
+        RwLock,
+        broadcast::{self, error::RecvError},
+        mpsc,
+    },
+};
+use tracing::{info, level_filters::LevelFilter, warn};
+use utsuru::{
+    hub::{
+        Hub, MirrorDescriptor, MirrorState, Notification, OverflowPolicy, QueueConfig,
+        SourceDescriptor,
+    },
+    mirrors::{
+        DiscordLiveBuilder, DiscordLiveBuilderState, HLS, RecorderBuilder, WHEP, WHIPClientBuilder,
+    },
+    sources::{RTMP, RTMPServer, TakeoverPolicy, WHIP, WHIPRejection, has_bearer_token},
+    utils::{codecs::Codec, ice::IceConfig},
+};
+use uuid::Uuid;
 
 const INDEX_HTML: &str = include_str!("../../web_dist/index.html");
 const INDEX_CSS: &str = include_str!("../../web_dist/bundle.css");
 const INDEX_JS: &str = include_str!("../../web_dist/bundle.js");
 const FAVICON_PNG: &[u8] = include_bytes!("../../web_dist/favicon.png");
 
+/// The stream published at `/whip`, whose mirrors are managed through
+/// `/api/mirrors`.
+const DEFAULT_STREAM: &str = "live";
+
 pub fn main() {
     let result = start();
 
     if let Some(err) = result.err() {
         println!("Error: {err}");
+        std::process::exit(1);
     }
 }
 
@@ -41,7 +88,16 @@ async fn start() -> Result<(), Box<dyn std::error::Error>> {
         return Ok(());
     }
 
-    let log: tracing::level_filters::LevelFilter = *matches.get_one("verbosity").unwrap();
+    if let Some((command, matches)) = matches.subcommand() {
+        return Remote::new(matches)?.run(command, matches).await;
+    }
+
+    let config = match matches.get_one::<PathBuf>("config") {
+        Some(path) => Config::load(path)?,
+        None => Config::default(),
+    };
+
+    let log: LevelFilter = setting(&matches, "verbosity", config.log.verbosity).unwrap();
     tracing_subscriber::fmt()
         .compact()
         .with_env_filter(
@@ -73,8 +129,8 @@ async fn start() -> Result<(), Box<dyn std::error::Error>> {
     println!("    We are currently conducting internal preparations. Please wait...");
     println!();
 
-    let ip: IpAddr = *matches.get_one("host").unwrap();
-    let port: u16 = *matches.get_one("port").unwrap();
+    let ip: IpAddr = setting(&matches, "host", config.server.host).unwrap();
+    let port: u16 = setting(&matches, "port", config.server.port).unwrap();
     let addr = SocketAddr::from((ip, port));
     let listener = match TcpListener::bind(&addr).await {
         Ok(sock) => sock,
@@ -86,8 +142,66 @@ async fn start() -> Result<(), Box<dyn std::error::Error>> {
         }
     };
 
-    let whip = WHIP::new(addr.ip());
-    let whip_service = service_fn(whip.into_closure());
+    let (store, restored) = match setting(&matches, "state", config.server.state) {
+        Some(path) => {
+            let (store, restored) = MirrorStore::open(path)?;
+            (Some(store), restored)
+        }
+        None => (None, Vec::new()),
+    };
+    let recordings: PathBuf = setting(&matches, "recordings", config.server.recordings).unwrap();
+    let whip_token: String = setting(&matches, "whip-token", config.whip.token).unwrap();
+    let whip_tokens = config
+        .streams
+        .iter()
+        .filter_map(|(name, stream)| Some((name.clone(), stream.whip_token.as_deref()?.into())))
+        .collect();
+    let stream_config = StreamConfig {
+        host: addr.ip(),
+        whip_token: whip_token.as_str().into(),
+        whip_tokens: Arc::new(whip_tokens),
+        whip_takeover: setting(&matches, "whip-takeover", config.whip.takeover).unwrap(),
+        pli_interval: setting(&matches, "pli-interval", config.whip.pli_interval)
+            .map(Duration::from_secs),
+        queue: QueueConfig {
+            capacity: setting(&matches, "queue-size", config.queue.size).unwrap(),
+            policy: setting(&matches, "overflow-policy", config.queue.overflow_policy).unwrap(),
+        },
+        ice: IceConfig {
+            nat_ips: config.ice.nat_ips,
+            udp_ports: config.ice.udp_ports,
+            servers: config.ice.servers,
+        },
+        recordings,
+    };
+    let mut mirrors = Vec::new();
+    for (name, stream) in config.streams {
+        for mirror in stream.mirrors {
+            let builder = MirrorBuilder::new(mirror, &stream_config)
+                .map_err(|_| format!("invalid mirror in stream {name} of the configuration"))?;
+            mirrors.push((name.clone(), builder));
+        }
+    }
+    let streams = Streams::new(stream_config, store).await?;
+    streams.configure(mirrors).await;
+    streams.restore(restored).await;
+    let rtmp_addr = match setting(&matches, "rtmp-port", config.server.rtmp_port) {
+        Some(rtmp_port) => {
+            let rtmp_addr = SocketAddr::from((ip, rtmp_port));
+            let rtmp = match RTMPServer::bind(rtmp_addr).await {
+                Ok(rtmp) => rtmp,
+                Err(e) => {
+                    println!("  - An error has occured:");
+                    println!("    {e}");
+                    println!();
+                    return Ok(());
+                }
+            };
+            tokio::spawn(route_rtmp(rtmp, streams.clone()));
+            Some(rtmp_addr)
+        }
+        None => None,
+    };
 
     let app = Router::new()
         .route("/", get(Html(INDEX_HTML)))
@@ -100,10 +214,27 @@ async fn start() -> Result<(), Box<dyn std::error::Error>> {
             get(|| assets_get("application/javascript; charset=utf-8", INDEX_JS)),
         )
         .route("/favicon.png", get(|| assets_get("image/png", FAVICON_PNG)))
-        .route("/api/mirrors", get(mirrors_get))
-        .route("/api/mirrors", post(mirrors_post))
-        .route("/whip", post_service(whip_service))
-        .with_state(whip);
+        .route("/api/mirrors", get(mirrors_get).post(mirrors_post))
+        .route("/api/streams", get(streams_get))
+        .route("/api/stored", get(stored_get))
+        .route("/api/stored/{key}", delete(stored_delete))
+        .route("/api/events", get(events_get))
+        .route("/metrics", get(metrics_get))
+        .route(
+            "/api/streams/{stream}/mirrors",
+            get(stream_mirrors_get).post(stream_mirrors_post),
+        )
+        .route("/whip", post(whip_default_post))
+        .route("/whip/{stream}", post(whip_post))
+        .route(
+            "/whip/{stream}/{session}",
+            patch(whip_session).delete(whip_session),
+        )
+        .route("/whep", post(whep_default_post))
+        .route("/whep/{stream}", post(whep_post))
+        .route("/whep/{stream}/{session}", delete(whep_session))
+        .route("/hls/{stream}/{*path}", get(hls_get))
+        .with_state(streams);
 
     println!("  - {} is ready! Listening on:", env!("CARGO_CRATE_NAME"));
     println!("    Web UI:      http://{}", listener.local_addr().unwrap());
@@ -111,7 +242,27 @@ async fn start() -> Result<(), Box<dyn std::error::Error>> {
         "    WHIP Server: http://{}/whip",
         listener.local_addr().unwrap()
     );
-    println!("    WHIP Token:  {}", env!("CARGO_CRATE_NAME"));
+    println!(
+        "    Streams:     http://{}/whip/<stream>",
+        listener.local_addr().unwrap()
+    );
+    println!("    WHIP Token:  {whip_token}");
+    if let Some(rtmp_addr) = rtmp_addr {
+        println!("    RTMP Server: rtmp://{rtmp_addr}/live");
+        println!("    Stream Key:  {DEFAULT_STREAM}?token={whip_token}");
+    }
+    println!(
+        "    WHEP Server: http://{}/whep",
+        listener.local_addr().unwrap()
+    );
+    println!(
+        "    HLS Stream:  http://{}/hls/live/index.m3u8",
+        listener.local_addr().unwrap()
+    );
+    println!(
+        "    Metrics:     http://{}/metrics",
+        listener.local_addr().unwrap()
+    );
     println!();
 
     axum::serve(listener, app).await.unwrap();
@@ -127,18 +278,940 @@ async fn assets_get(header: &'static str, body: impl IntoResponse) -> Response {
         .into_response()
 }
 
-async fn mirrors_get(State(whip): State<WHIP>) -> Result<Json<Vec<bool>>, StatusCode> {
-    let Ok(mirrors) = whip.view_mirrors().await else {
+/// A stream published under its name, with its own hub and mirrors. Each
+/// stream can be pulled through its own WHEP endpoint and HLS playlist.
+#[derive(Clone)]
+struct Stream {
+    hub: Hub,
+    whip: WHIP,
+    rtmp: RTMP,
+    whep: WHEP,
+    hls: HLS,
+}
+
+/// How every stream is set up.
+#[derive(Clone)]
+struct StreamConfig {
+    host: IpAddr,
+    whip_token: Arc<str>,
+    /// Tokens of the streams which do not take `whip_token`.
+    whip_tokens: Arc<HashMap<String, Arc<str>>>,
+    whip_takeover: TakeoverPolicy,
+    pli_interval: Option<Duration>,
+    queue: QueueConfig,
+    ice: IceConfig,
+    /// Where recorders write, their paths being taken relative to it.
+    recordings: PathBuf,
+}
+
+impl StreamConfig {
+    /// The token WHIP publishers of the stream must present.
+    fn whip_token(&self, name: &str) -> &str {
+        self.whip_tokens.get(name).unwrap_or(&self.whip_token)
+    }
+}
+
+impl Stream {
+    async fn new(
+        name: &str,
+        config: &StreamConfig,
+        notifications: &broadcast::Sender<StreamNotification>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let hub = Hub::with_queue_config(config.queue);
+        tokio::spawn(forward_notifications(
+            name.to_owned(),
+            hub.subscribe(),
+            notifications.clone(),
+        ));
+        let mut whip = WHIP::new(config.host)
+            .token(config.whip_token(name))
+            .takeover(config.whip_takeover)
+            .ice(config.ice.clone());
+        if let Some(pli_interval) = config.pli_interval {
+            whip = whip.pli_interval(pli_interval);
+        }
+        hub.add_source(whip.clone()).await?;
+        let rtmp = RTMP::new();
+        hub.add_source(rtmp.clone()).await?;
+        let whep = if name == DEFAULT_STREAM {
+            WHEP::new(config.host)
+        } else {
+            WHEP::new(config.host).path(format!("/whep/{name}"))
+        }
+        .ice(config.ice.clone());
+        hub.add_mirror(whep.clone()).await?;
+        let hls = HLS::new(name);
+        hub.add_mirror(hls.clone()).await?;
+
+        Ok(Self {
+            hub,
+            whip,
+            rtmp,
+            whep,
+            hls,
+        })
+    }
+}
+
+/// Every stream by name. Streams are created as publishers or mirrors first
+/// ask for them, and live on once their publisher leaves so their mirrors
+/// stay in place.
+#[derive(Clone)]
+struct Streams {
+    config: StreamConfig,
+    default: Stream,
+    streams: Arc<RwLock<BTreeMap<String, Stream>>>,
+    /// What happens in every stream, for `/api/events`.
+    notifications: broadcast::Sender<StreamNotification>,
+    store: Option<MirrorStore>,
+}
+
+impl Streams {
+    async fn new(
+        config: StreamConfig,
+        store: Option<MirrorStore>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let (notifications, _) = broadcast::channel(NOTIFICATION_CAPACITY);
+        let default = Stream::new(DEFAULT_STREAM, &config, &notifications).await?;
+        let streams = BTreeMap::from([(DEFAULT_STREAM.to_owned(), default.clone())]);
+        if let Some(store) = &store {
+            tokio::spawn(store.clone().follow(notifications.subscribe()));
+        }
+
+        Ok(Self {
+            config,
+            default,
+            streams: Arc::new(RwLock::new(streams)),
+            notifications,
+            store,
+        })
+    }
+
+    /// Add the mirrors of the configuration file, each in the background. They
+    /// are left out of the state file, as the configuration file brings them
+    /// back on every start anyway.
+    async fn configure(&self, mirrors: Vec<(String, MirrorBuilder)>) {
+        for (stream, builder) in mirrors {
+            let Ok(Stream { hub, .. }) = self.get_or_create(&stream).await else {
+                warn!("[Config] cannot create stream {stream}");
+                continue;
+            };
+            tokio::spawn(async move {
+                match builder.connect(&hub, None).await {
+                    Ok(id) => info!("[Config] mirror {id} added to stream {stream}"),
+                    Err(e) => warn!("[Config] failed to add a mirror to stream {stream}: {e}"),
+                }
+            });
+        }
+    }
+
+    /// Bring back the mirrors of the state file, each in the background as
+    /// Discord mirrors take a while to connect.
+    async fn restore(&self, mirrors: Vec<StoredMirror>) {
+        let Some(store) = &self.store else {
+            return;
+        };
+        for StoredMirror {
+            key,
+            stream,
+            mirror,
+        } in mirrors
+        {
+            let hub = self.get_or_create(&stream).await.map(|stream| stream.hub);
+            let builder = MirrorBuilder::new(mirror, &self.config);
+            let (Ok(hub), Ok(builder)) = (hub, builder) else {
+                warn!("[Store] cannot restore mirror {key} of stream {stream}");
+                continue;
+            };
+            let store = store.clone();
+            tokio::spawn(async move {
+                match builder.connect(&hub, None).await {
+                    Ok(id) => {
+                        info!("[Store] mirror {key} restored as {id} in stream {stream}");
+                        store.track(&stream, id, key);
+                    }
+                    // Kept in the state file, to be tried again next start.
+                    Err(e) => warn!("[Store] failed to restore mirror {key}: {e}"),
+                }
+            });
+        }
+    }
+
+    async fn get(&self, name: &str) -> Option<Stream> {
+        self.streams.read().await.get(name).cloned()
+    }
+
+    async fn get_or_create(&self, name: &str) -> Result<Stream, StatusCode> {
+        if !is_stream_name(name) {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+
+        let mut streams = self.streams.write().await;
+        if let Some(stream) = streams.get(name) {
+            return Ok(stream.clone());
+        }
+        let stream = Stream::new(name, &self.config, &self.notifications)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        streams.insert(name.to_owned(), stream.clone());
+        info!("[Streams] stream {name} created");
+        Ok(stream)
+    }
+
+    async fn names(&self) -> Vec<String> {
+        self.streams.read().await.keys().cloned().collect()
+    }
+
+    async fn all(&self) -> Vec<(String, Stream)> {
+        self.streams
+            .read()
+            .await
+            .iter()
+            .map(|(name, stream)| (name.clone(), stream.clone()))
+            .collect()
+    }
+}
+
+/// Mirrors added through the API, written to a state file so they come back
+/// on the next start. Their tokens are kept apart, in a secrets file only the
+/// owner may read.
+#[derive(Clone)]
+struct MirrorStore {
+    path: PathBuf,
+    secrets_path: PathBuf,
+    state: Arc<StdMutex<StoreState>>,
+}
+
+#[derive(Default)]
+struct StoreState {
+    /// Definitions by the key they are stored under, along with their
+    /// stream.
+    mirrors: BTreeMap<String, (String, Value)>,
+    /// Tokens taken out of the definitions, by key.
+    secrets: BTreeMap<String, String>,
+    /// Keys of the running mirrors, by stream and ID. Stored mirrors missing
+    /// here failed to come back or stopped on their own.
+    running: HashMap<(String, u64), String>,
+}
+
+/// A stored mirror as listed by `/api/stored`, without its token.
+#[derive(Serialize)]
+struct StoredEntry {
+    key: String,
+    stream: String,
+    /// The ID the mirror runs under, if it runs.
+    id: Option<u64>,
+    mirror: Value,
+}
+
+/// A mirror read back from the state file.
+struct StoredMirror {
+    key: String,
+    stream: String,
+    mirror: CreatePayload,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct StateFile {
+    mirrors: BTreeMap<String, StateEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StateEntry {
+    stream: String,
+    mirror: Value,
+}
+
+impl MirrorStore {
+    /// Open the state file at `path`, along with the secrets file next to it,
+    /// returning the mirrors to restore. Missing files count as empty.
+    fn open(path: PathBuf) -> Result<(Self, Vec<StoredMirror>), Box<dyn std::error::Error>> {
+        let mut secrets_path = path.clone().into_os_string();
+        secrets_path.push(".secrets");
+        let secrets_path = PathBuf::from(secrets_path);
+
+        let state: StateFile = read_json(&path)?.unwrap_or_default();
+        let mut store = StoreState {
+            secrets: read_json(&secrets_path)?.unwrap_or_default(),
+            ..StoreState::default()
+        };
+        let mut mirrors = Vec::new();
+        for (key, StateEntry { stream, mirror }) in state.mirrors {
+            let mut definition = mirror.clone();
+            if let (Some(token), Some(fields)) =
+                (store.secrets.get(&key), definition.as_object_mut())
+            {
+                fields.insert("token".into(), token.clone().into());
+            }
+            match serde_json::from_value(definition) {
+                Ok(definition) => mirrors.push(StoredMirror {
+                    key: key.clone(),
+                    stream: stream.clone(),
+                    mirror: definition,
+                }),
+                Err(e) => warn!("[Store] skipping mirror {key}: {e}"),
+            }
+            store.mirrors.insert(key, (stream, mirror));
+        }
+        store
+            .secrets
+            .retain(|key, _| store.mirrors.contains_key(key));
+
+        let store = Self {
+            path,
+            secrets_path,
+            state: Arc::new(StdMutex::new(store)),
+        };
+        Ok((store, mirrors))
+    }
+
+    fn add(&self, stream: &str, id: u64, definition: &CreatePayload) {
+        let Ok(mut definition) = serde_json::to_value(definition) else {
+            return;
+        };
+        let token = definition
+            .as_object_mut()
+            .and_then(|fields| fields.remove("token"));
+        let key = Uuid::new_v4().simple().to_string();
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        if let Some(Value::String(token)) = token {
+            state.secrets.insert(key.clone(), token);
+        }
+        state
+            .mirrors
+            .insert(key.clone(), (stream.to_owned(), definition));
+        state.running.insert((stream.to_owned(), id), key);
+        self.save(&state);
+    }
+
+    /// Note the ID a restored mirror runs under.
+    fn track(&self, stream: &str, id: u64, key: String) {
+        if let Ok(mut state) = self.state.lock() {
+            state.running.insert((stream.to_owned(), id), key);
+        }
+    }
+
+    /// Forget the running mirror, as it is removed through the API.
+    fn remove(&self, stream: &str, id: u64) {
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        let Some(key) = state.running.remove(&(stream.to_owned(), id)) else {
+            return;
+        };
+        state.mirrors.remove(&key);
+        state.secrets.remove(&key);
+        self.save(&state);
+    }
+
+    /// Forget the mirror stored under `key`, running or not, returning its
+    /// stream along with the ID it runs under. `None` if there is no such
+    /// mirror.
+    fn forget(&self, key: &str) -> Option<(String, Option<u64>)> {
+        let mut state = self.state.lock().ok()?;
+        let (stream, _) = state.mirrors.remove(key)?;
+        state.secrets.remove(key);
+        let running = state
+            .running
+            .iter()
+            .find_map(|(running, running_key)| (running_key == key).then(|| running.clone()));
+        let id = running.map(|running| {
+            state.running.remove(&running);
+            running.1
+        });
+        self.save(&state);
+        Some((stream, id))
+    }
+
+    fn list(&self) -> Vec<StoredEntry> {
+        let Ok(state) = self.state.lock() else {
+            return Vec::new();
+        };
+        state
+            .mirrors
+            .iter()
+            .map(|(key, (stream, mirror))| StoredEntry {
+                key: key.clone(),
+                stream: stream.clone(),
+                id: state
+                    .running
+                    .iter()
+                    .find_map(|((_, id), running_key)| (running_key == key).then_some(*id)),
+                mirror: mirror.clone(),
+            })
+            .collect()
+    }
+
+    /// Note mirrors leaving their hub on their own, such as Discord mirrors
+    /// giving up on reconnecting, as no longer running. They stay stored, to
+    /// be tried again on the next start or removed through `/api/stored`.
+    async fn follow(self, mut notifications_rx: broadcast::Receiver<StreamNotification>) {
+        loop {
+            match notifications_rx.recv().await {
+                Ok(StreamNotification {
+                    stream,
+                    notification: Notification::MirrorRemoved { id },
+                }) => {
+                    if let Ok(mut state) = self.state.lock() {
+                        state.running.remove(&(stream, id));
+                    }
+                }
+                Ok(_) | Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => break,
+            }
+        }
+    }
+
+    fn save(&self, state: &StoreState) {
+        let file = StateFile {
+            mirrors: state
+                .mirrors
+                .iter()
+                .map(|(key, (stream, mirror))| {
+                    let entry = StateEntry {
+                        stream: stream.clone(),
+                        mirror: mirror.clone(),
+                    };
+                    (key.clone(), entry)
+                })
+                .collect(),
+        };
+        if let Err(e) = write_json(&self.path, &file, false)
+            .and_then(|_| write_json(&self.secrets_path, &state.secrets, true))
+        {
+            warn!("[Store] failed to save mirrors: {e}");
+        }
+    }
+}
+
+/// Read a JSON file, `None` if there is none.
+fn read_json<T: DeserializeOwned>(path: &StdPath) -> Result<Option<T>, Box<dyn std::error::Error>> {
+    match fs::read(path) {
+        Ok(data) => Ok(Some(serde_json::from_slice(&data)?)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Replace a JSON file through a temporary file, so a crash never leaves it
+/// half written. Private files are only readable by their owner.
+fn write_json<T: Serialize>(path: &StdPath, value: &T, private: bool) -> io::Result<()> {
+    let data = serde_json::to_vec_pretty(value).map_err(io::Error::other)?;
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    if private {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    #[cfg(not(unix))]
+    let _ = private;
+    let mut file = options.open(&tmp_path)?;
+    // The mode above only applies to new files, so a temporary file left
+    // behind keeps whatever permissions it had.
+    #[cfg(unix)]
+    if private {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    }
+    file.write_all(&data)?;
+    file.sync_all()?;
+    fs::rename(&tmp_path, path)
+}
+
+/// Notifications subscribers of `/api/events` may fall behind by before
+/// missing some.
+const NOTIFICATION_CAPACITY: usize = 256;
+
+/// A notification of a hub, along with the stream it belongs to.
+#[derive(Clone, Serialize)]
+struct StreamNotification {
+    stream: String,
+    #[serde(flatten)]
+    notification: Notification,
+}
+
+/// Pass the notifications of a stream's hub on to the subscribers of every
+/// stream.
+async fn forward_notifications(
+    stream: String,
+    mut notifications_rx: broadcast::Receiver<Notification>,
+    notifications_tx: broadcast::Sender<StreamNotification>,
+) {
+    loop {
+        let notification = match notifications_rx.recv().await {
+            Ok(notification) => notification,
+            Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => break,
+        };
+        let _ = notifications_tx.send(StreamNotification {
+            stream: stream.clone(),
+            notification,
+        });
+    }
+}
+
+/// Stream names end up in URLs and HLS paths, so keep them to a safe set.
+fn is_stream_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 64
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+}
+
+async fn whip_default_post(State(streams): State<Streams>, mut req: Request) -> Response {
+    // Publish as if to the default stream by name, so its sessions live
+    // under the name as well.
+    let Ok(uri) = format!("/whip/{DEFAULT_STREAM}").parse::<Uri>() else {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+    *req.uri_mut() = uri;
+    whip_serve(&streams.default.whip, req).await
+}
+
+async fn whip_post(
+    State(streams): State<Streams>,
+    Path(name): Path<String>,
+    req: Request,
+) -> Response {
+    // Turn away publishers without the token of the stream before they get
+    // to create it.
+    if !has_bearer_token(&req, streams.config.whip_token(&name)) {
+        warn!("[Streams] rejecting publisher of {name} without a valid bearer token");
+        return WHIPRejection::Unauthorized.response().into_response();
+    }
+
+    match streams.get_or_create(&name).await {
+        Ok(stream) => whip_serve(&stream.whip, req).await,
+        Err(status) => status.into_response(),
+    }
+}
+
+/// Hand the RTMP publishers over to the streams their keys name. Like WHIP
+/// publishers, they must present the token of the stream. The hub of the
+/// stream turns them away while another source is live.
+async fn route_rtmp(server: RTMPServer, streams: Streams) {
+    while let Some(request) = server.next_request().await {
+        let streams = streams.clone();
+        tokio::spawn(async move {
+            let name = request.stream().to_owned();
+            if !request.has_token(streams.config.whip_token(&name)) {
+                warn!("[Streams] rejecting RTMP publisher of {name} without a valid token");
+                return request.reject();
+            }
+            let Ok(stream) = streams.get_or_create(&name).await else {
+                warn!("[Streams] rejecting RTMP publisher of unknown stream {name}");
+                return request.reject();
+            };
+            request.accept(&stream.rtmp);
+        });
+    }
+}
+
+async fn whip_session(
+    State(streams): State<Streams>,
+    Path((name, _session)): Path<(String, String)>,
+    req: Request,
+) -> Response {
+    let Some(stream) = streams.get(&name).await else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    whip_serve(&stream.whip, req).await
+}
+
+async fn whip_serve(whip: &WHIP, req: Request) -> Response {
+    let Ok(res) = whip.into_closure()(req).await;
+    res.into_response()
+}
+
+async fn whep_default_post(State(streams): State<Streams>, mut req: Request) -> Response {
+    // Watch as if the default stream by name, so its viewers live under the
+    // name as well.
+    let Ok(uri) = format!("/whep/{DEFAULT_STREAM}").parse::<Uri>() else {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+    *req.uri_mut() = uri;
+    whep_serve(&streams.default.whep, req).await
+}
+
+async fn whep_post(
+    State(streams): State<Streams>,
+    Path(name): Path<String>,
+    req: Request,
+) -> Response {
+    let Some(stream) = streams.get(&name).await else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    whep_serve(&stream.whep, req).await
+}
+
+async fn whep_session(
+    State(streams): State<Streams>,
+    Path((name, _session)): Path<(String, String)>,
+    req: Request,
+) -> Response {
+    let Some(stream) = streams.get(&name).await else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    whep_serve(&stream.whep, req).await
+}
+
+async fn whep_serve(whep: &WHEP, req: Request) -> Response {
+    let Ok(res) = whep.into_closure()(req).await;
+    res.into_response()
+}
+
+async fn hls_get(
+    State(streams): State<Streams>,
+    Path((name, _path)): Path<(String, String)>,
+    req: Request,
+) -> Response {
+    let Some(stream) = streams.get(&name).await else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    let Ok(res) = stream.hls.into_closure()(req).await;
+    res.into_response()
+}
+
+async fn streams_get(State(streams): State<Streams>) -> Json<Vec<String>> {
+    Json(streams.names().await)
+}
+
+/// Stream the notifications of every stream as server-sent events, each one
+/// a JSON object telling what happened in its `event` field.
+async fn events_get(
+    State(streams): State<Streams>,
+) -> Sse<impl EventStream<Item = Result<Event, Infallible>>> {
+    let notifications_rx = streams.notifications.subscribe();
+    let stream = unfold(notifications_rx, async |mut notifications_rx| {
+        loop {
+            let data = match notifications_rx.recv().await {
+                Ok(notification) => serde_json::to_string(&notification),
+                // Let the subscriber know to catch up through the REST API.
+                Err(RecvError::Lagged(missed)) => {
+                    Ok(format!("{{\"event\":\"lagged\",\"missed\":{missed}}}"))
+                }
+                Err(RecvError::Closed) => return None,
+            };
+            let Ok(data) = data else {
+                continue;
+            };
+            return Some((Ok(Event::default().data(data)), notifications_rx));
+        }
+    });
+
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+async fn mirrors_get(
+    State(streams): State<Streams>,
+) -> Result<Json<Vec<MirrorDescriptor>>, StatusCode> {
+    view_mirrors(streams.default.hub).await
+}
+
+async fn mirrors_post(
+    State(streams): State<Streams>,
+    action: Action,
+) -> Result<Response, StatusCode> {
+    let hub = streams.default.hub.clone();
+    handle_action(&streams, DEFAULT_STREAM, hub, action).await
+}
+
+async fn stored_get(State(streams): State<Streams>) -> Json<Vec<StoredEntry>> {
+    Json(streams.store.map(|store| store.list()).unwrap_or_default())
+}
+
+/// Remove a stored mirror, along with the mirror it runs as, if it runs.
+async fn stored_delete(
+    State(streams): State<Streams>,
+    Path(key): Path<String>,
+) -> Result<Response, StatusCode> {
+    let Some(store) = &streams.store else {
+        return Err(StatusCode::NOT_FOUND);
+    };
+    let Some((name, id)) = store.forget(&key) else {
+        return Err(StatusCode::NOT_FOUND);
+    };
+    if let (Some(id), Some(stream)) = (id, streams.get(&name).await) {
+        let _ = stream.hub.remove_mirror(id).await;
+    }
+
+    Ok(().into_response())
+}
+
+async fn stream_mirrors_get(
+    State(streams): State<Streams>,
+    Path(name): Path<String>,
+) -> Result<Json<Vec<MirrorDescriptor>>, StatusCode> {
+    let Some(stream) = streams.get(&name).await else {
+        return Err(StatusCode::NOT_FOUND);
+    };
+
+    view_mirrors(stream.hub).await
+}
+
+async fn stream_mirrors_post(
+    State(streams): State<Streams>,
+    Path(name): Path<String>,
+    action: Action,
+) -> Result<Response, StatusCode> {
+    // Mirrors may be set up ahead of the publisher, creating the stream.
+    let stream = match action {
+        Action::Create(_) => streams.get_or_create(&name).await?,
+        Action::Delete(_) => streams.get(&name).await.ok_or(StatusCode::NOT_FOUND)?,
+    };
+
+    handle_action(&streams, &name, stream.hub, action).await
+}
+
+async fn metrics_get(State(streams): State<Streams>) -> Result<Response, StatusCode> {
+    let mut sources = Vec::new();
+    let mut mirrors = Vec::new();
+    for (name, stream) in streams.all().await {
+        let (Ok(source), Ok(stream_mirrors)) = (
+            stream.hub.view_source().await,
+            stream.hub.view_mirrors().await,
+        ) else {
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        };
+        sources.push((name.clone(), source));
+        mirrors.extend(
+            stream_mirrors
+                .into_iter()
+                .map(|mirror| (name.clone(), mirror)),
+        );
+    }
+
+    let mut metrics = Metrics::default();
+    let source_metrics: [Metric<SourceDescriptor>; 5] = [
+        (
+            "connected",
+            "gauge",
+            "Whether a publisher is connected.",
+            |source| source.connected as u64,
+        ),
+        (
+            "rtp_packets_total",
+            "counter",
+            "RTP packets received.",
+            |source| source.stats.rtp_packets,
+        ),
+        (
+            "rtp_bytes_total",
+            "counter",
+            "RTP payload bytes received.",
+            |source| source.stats.rtp_bytes,
+        ),
+        (
+            "keyframes_total",
+            "counter",
+            "Video keyframes received.",
+            |source| source.keyframes,
+        ),
+        (
+            "dropped_packets_total",
+            "counter",
+            "RTP packets dropped while reassembling samples.",
+            |source| source.stats.dropped_packets,
+        ),
+    ];
+    for (name, kind, help, value) in source_metrics {
+        metrics.family(&format!("utsuru_source_{name}"), kind, help);
+        for (stream, source) in &sources {
+            metrics.sample(&[("stream", stream)], value(source));
+        }
+    }
+    metrics.family(
+        "utsuru_source_frames_total",
+        "counter",
+        "Samples received, by track.",
+    );
+    for (stream, source) in &sources {
+        metrics.sample(
+            &[("stream", stream), ("track", "audio")],
+            source.audio_frames,
+        );
+        metrics.sample(
+            &[("stream", stream), ("track", "video")],
+            source.video_frames,
+        );
+    }
+    metrics.family(
+        "utsuru_source_jitter_seconds",
+        "gauge",
+        "Interarrival jitter of the RTP packets, by track.",
+    );
+    for (stream, source) in &sources {
+        metrics.sample(
+            &[("stream", stream), ("track", "audio")],
+            source.stats.audio_jitter,
+        );
+        metrics.sample(
+            &[("stream", stream), ("track", "video")],
+            source.stats.video_jitter,
+        );
+    }
+
+    let mirror_metrics: [Metric<MirrorDescriptor>; 6] = [
+        (
+            "samples_written_total",
+            "counter",
+            "Samples the mirror took.",
+            |mirror| mirror.written,
+        ),
+        (
+            "write_errors_total",
+            "counter",
+            "Samples the mirror failed to take.",
+            |mirror| mirror.write_errors,
+        ),
+        (
+            "queued_samples",
+            "gauge",
+            "Samples waiting in the mirror's queue.",
+            |mirror| mirror.queued as u64,
+        ),
+        (
+            "encryption_failures_total",
+            "counter",
+            "Samples sent unencrypted as DAVE failed to encrypt them.",
+            |mirror| mirror.encryption_failures,
+        ),
+        (
+            "connected",
+            "gauge",
+            "Whether the mirror is connected.",
+            |mirror| mirror.connected as u64,
+        ),
+        (
+            "live",
+            "gauge",
+            "Whether the mirror is receiving from a live source.",
+            |mirror| (mirror.state == MirrorState::Live) as u64,
+        ),
+    ];
+    for (name, kind, help, value) in mirror_metrics {
+        metrics.family(&format!("utsuru_mirror_{name}"), kind, help);
+        for (stream, mirror) in &mirrors {
+            let id = mirror.id.to_string();
+            metrics.sample(
+                &[("stream", stream), ("id", &id), ("kind", mirror.kind)],
+                value(mirror),
+            );
+        }
+    }
+    metrics.family(
+        "utsuru_mirror_dropped_samples_total",
+        "counter",
+        "Samples dropped as the mirror fell behind, by track.",
+    );
+    for (stream, mirror) in &mirrors {
+        let id = mirror.id.to_string();
+        for (track, dropped) in [
+            ("audio", mirror.dropped_audio),
+            ("video", mirror.dropped_video),
+        ] {
+            metrics.sample(
+                &[
+                    ("stream", stream),
+                    ("id", &id),
+                    ("kind", mirror.kind),
+                    ("track", track),
+                ],
+                dropped,
+            );
+        }
+    }
+
+    Ok((
+        [(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("text/plain; version=0.0.4"),
+        )],
+        metrics.body,
+    )
+        .into_response())
+}
+
+/// Name, type, help and value of a metric family with one sample per item.
+type Metric<T> = (&'static str, &'static str, &'static str, fn(&T) -> u64);
+
+/// Metrics in the Prometheus text format, written one family at a time.
+#[derive(Default)]
+struct Metrics {
+    body: String,
+    family: String,
+}
+
+impl Metrics {
+    fn family(&mut self, name: &str, kind: &str, help: &str) {
+        let _ = writeln!(self.body, "# HELP {name} {help}");
+        let _ = writeln!(self.body, "# TYPE {name} {kind}");
+        self.family = name.to_owned();
+    }
+
+    fn sample(&mut self, labels: &[(&str, &str)], value: impl Display) {
+        self.body.push_str(&self.family);
+        for (i, (label, label_value)) in labels.iter().enumerate() {
+            let separator = if i == 0 { '{' } else { ',' };
+            let label_value = label_value
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n");
+            let _ = write!(self.body, "{separator}{label}=\"{label_value}\"");
+        }
+        if !labels.is_empty() {
+            self.body.push('}');
+        }
+        let _ = writeln!(self.body, " {value}");
+    }
+}
+
+async fn view_mirrors(hub: Hub) -> Result<Json<Vec<MirrorDescriptor>>, StatusCode> {
+    let Ok(mirrors) = hub.view_mirrors().await else {
         return Err(StatusCode::INTERNAL_SERVER_ERROR);
     };
 
     Ok(Json(mirrors))
 }
 
-async fn mirrors_post(State(whip): State<WHIP>, action: Action) -> Result<Response, StatusCode> {
+async fn handle_action(
+    streams: &Streams,
+    name: &str,
+    hub: Hub,
+    action: Action,
+) -> Result<Response, StatusCode> {
     match action {
-        Action::Create(payload) => create_mirror(whip, payload).await,
-        Action::Delete(payload) => delete_mirror(whip, payload).await,
+        Action::Create(payload) => {
+            let store = streams.store.clone();
+            let name = name.to_owned();
+            let definition = payload.clone();
+            let added = move |id| {
+                if let Some(store) = store {
+                    store.add(&name, id, &definition);
+                }
+            };
+            let builder = MirrorBuilder::new(payload, &streams.config)?;
+            create_mirror(hub, builder, added).await
+        }
+        Action::Delete(payload) => {
+            // Forgotten ahead of leaving the hub, which on its own would
+            // only mark the mirror as no longer running.
+            if let Some(store) = &streams.store {
+                store.remove(name, payload.id);
+            }
+            delete_mirror(hub, payload).await
+        }
     }
 }
 
@@ -183,41 +1256,92 @@ enum Params {
     Other,
 }
 
-#[derive(Deserialize)]
-struct CreatePayload {
+/// A mirror to create, its kind told by its fields. Each kind refuses the
+/// fields it does not know, so a misspelled one fails the request instead of
+/// going unnoticed.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+enum CreatePayload {
+    Discord(DiscordPayload),
+    WHIPClient(WHIPClientPayload),
+    Recorder(RecorderPayload),
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DiscordPayload {
     token: String,
     guild_id: u64,
     channel_id: u64,
+    /// Given along with `height`, or not at all.
+    width: Option<u32>,
+    height: Option<u32>,
+    framerate: Option<u32>,
+    bitrate: Option<u64>,
+    codec: Option<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WHIPClientPayload {
+    url: String,
+    token: Option<String>,
+    codec: Option<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RecorderPayload {
+    path: String,
+    max_duration: Option<u64>,
+    max_size: Option<u64>,
 }
 
 #[derive(Deserialize)]
 struct DeletePayload {
-    id: usize,
+    id: u64,
 }
 
-async fn create_mirror(whip: WHIP, payload: CreatePayload) -> Result<Response, StatusCode> {
+/// Connect the mirror and add it to the hub, streaming the progress of
+/// Discord mirrors as text lines. Every line ends with a newline, the last
+/// one being `success` or the error. `added` is told the ID of the mirror
+/// once it is in place.
+async fn create_mirror(
+    hub: Hub,
+    builder: MirrorBuilder,
+    added: impl FnOnce(u64) + Send + 'static,
+) -> Result<Response, StatusCode> {
+    if !matches!(builder, MirrorBuilder::Discord(_)) {
+        let body = match builder.connect(&hub, None).await {
+            Ok(id) => {
+                added(id);
+                "success".into()
+            }
+            Err(e) => e,
+        };
+        return Ok(format!("{body}\n").into_response());
+    }
+
     let (trace_tx, trace_rx) = mpsc::unbounded_channel();
-    let client = DiscordLiveBuilder::new(payload.token, payload.guild_id, payload.channel_id)
-        .connect(Some(trace_tx));
+    let client = async move {
+        let id = builder.connect(&hub, Some(trace_tx)).await?;
+        added(id);
+        Ok::<_, String>(id)
+    };
     let client = Box::pin(client);
 
-    let stream = unfold(Some((trace_rx, client, whip)), async move |state| {
-        let (mut trace_rx, mut client, whip) = state?;
+    let stream = unfold(Some((trace_rx, client)), async move |state| {
+        let (mut trace_rx, mut client) = state?;
         tokio::select! {
             res = trace_rx.recv() => {
                 let trace = res?;
-                let body = format!("{trace}");
-                Some((Ok::<_, Box<dyn std::error::Error + Send + Sync>>(body), Some((trace_rx, client, whip))))
+                let body = format!("{trace}\n");
+                Some((Ok::<_, Box<dyn std::error::Error + Send + Sync>>(body), Some((trace_rx, client))))
             },
             mir = (&mut client) => {
                 let body = match mir {
-                    Ok(client) => {
-                        match whip.add_mirror(client).await {
-                            Ok(_) => "success".into(),
-                            Err(e) => format!("error: {e}")
-                        }
-                    },
-                    Err(e) => format!("error: {e}")
+                    Ok(_) => "success\n".into(),
+                    Err(e) => format!("{e}\n"),
                 };
                 Some((Ok::<_, Box<dyn std::error::Error + Send + Sync>>(body), None))
             },
@@ -232,18 +1356,713 @@ async fn create_mirror(whip: WHIP, payload: CreatePayload) -> Result<Response, S
     Ok(resp)
 }
 
-async fn delete_mirror(whip: WHIP, payload: DeletePayload) -> Result<Response, StatusCode> {
-    let Ok(_) = whip.remove_mirror(payload.id).await else {
+/// A mirror as described by a create request, ready to connect.
+enum MirrorBuilder {
+    Discord(DiscordLiveBuilder),
+    WHIPClient(WHIPClientBuilder),
+    Recorder(RecorderBuilder),
+}
+
+impl MirrorBuilder {
+    /// Recorders are kept below the recordings directory of `config`, and
+    /// refused if their path would leave it. WHIP clients gather their ICE
+    /// candidates as the streams' own endpoints do.
+    fn new(payload: CreatePayload, config: &StreamConfig) -> Result<Self, StatusCode> {
+        let builder = match payload {
+            CreatePayload::Discord(DiscordPayload {
+                token,
+                guild_id,
+                channel_id,
+                width,
+                height,
+                framerate,
+                bitrate,
+                codec,
+            }) => {
+                let mut builder = DiscordLiveBuilder::new(token, guild_id, channel_id);
+                if let Some(codec) = codec {
+                    let codec = codec.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
+                    builder = builder.codec(codec).map_err(|_| StatusCode::BAD_REQUEST)?;
+                }
+                match (width, height) {
+                    (Some(width), Some(height)) => builder = builder.resolution(width, height),
+                    (None, None) => {}
+                    _ => return Err(StatusCode::BAD_REQUEST),
+                }
+                if let Some(framerate) = framerate {
+                    builder = builder.framerate(framerate);
+                }
+                if let Some(bitrate) = bitrate {
+                    builder = builder.bitrate(bitrate);
+                }
+                Self::Discord(builder)
+            }
+            CreatePayload::WHIPClient(WHIPClientPayload { url, token, codec }) => {
+                let mut client = WHIPClientBuilder::new(url).ice(config.ice.clone());
+                if let Some(token) = token {
+                    client = client.token(token);
+                }
+                if let Some(codec) = codec {
+                    let codec: Codec = codec.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
+                    client = client.codec(codec).map_err(|_| StatusCode::BAD_REQUEST)?;
+                }
+                Self::WHIPClient(client)
+            }
+            CreatePayload::Recorder(RecorderPayload {
+                path,
+                max_duration,
+                max_size,
+            }) => {
+                if !RecorderBuilder::is_valid_path(&path) {
+                    return Err(StatusCode::BAD_REQUEST);
+                }
+                let mut recorder = RecorderBuilder::new(path).directory(&config.recordings);
+                if let Some(max_duration) = max_duration {
+                    recorder = recorder.max_duration(Duration::from_secs(max_duration));
+                }
+                if let Some(max_size) = max_size {
+                    recorder = recorder.max_size(max_size);
+                }
+                Self::Recorder(recorder)
+            }
+        };
+
+        Ok(builder)
+    }
+
+    /// Connect the mirror and add it to the hub, returning its ID or a line
+    /// telling what went wrong.
+    async fn connect(
+        self,
+        hub: &Hub,
+        trace_tx: Option<mpsc::UnboundedSender<DiscordLiveBuilderState>>,
+    ) -> Result<u64, String> {
+        let id = match self {
+            Self::Discord(builder) => {
+                let client = builder.connect(trace_tx).await;
+                hub.add_mirror(client.map_err(|e| format!("error: {e}"))?)
+                    .await
+            }
+            Self::WHIPClient(builder) => {
+                let client = builder.connect().await;
+                hub.add_mirror(client.map_err(|e| format!("error: {e}"))?)
+                    .await
+            }
+            Self::Recorder(builder) => {
+                let recorder = builder.connect().await;
+                hub.add_mirror(recorder.map_err(|e| format!("error: {e}"))?)
+                    .await
+            }
+        };
+
+        id.map_err(|e| error_message(&e))
+    }
+}
+
+/// Describe an error along with its source, which tells what went wrong.
+fn error_message(err: &dyn std::error::Error) -> String {
+    match err.source() {
+        Some(source) => format!("error: {err}: {source}"),
+        None => format!("error: {err}"),
+    }
+}
+
+async fn delete_mirror(hub: Hub, payload: DeletePayload) -> Result<Response, StatusCode> {
+    let Ok(found) = hub.remove_mirror(payload.id).await else {
         return Err(StatusCode::INTERNAL_SERVER_ERROR);
     };
+    if !found {
+        return Err(StatusCode::NOT_FOUND);
+    }
 
     Ok(().into_response())
 }
 
-fn build_cli() -> Command {
+/// The file given with `--config`. Command line flags take precedence over
+/// the settings it holds.
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Config {
+    server: ServerConfig,
+    whip: WhipConfig,
+    ice: IceSettings,
+    queue: QueueSettings,
+    log: LogConfig,
+    /// Settings of each stream by name.
+    streams: BTreeMap<String, StreamSettings>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ServerConfig {
+    host: Option<IpAddr>,
+    port: Option<u16>,
+    rtmp_port: Option<u16>,
+    state: Option<PathBuf>,
+    recordings: Option<PathBuf>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct WhipConfig {
+    token: Option<String>,
+    #[serde(deserialize_with = "from_str")]
+    takeover: Option<TakeoverPolicy>,
+    pli_interval: Option<u64>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct IceSettings {
+    nat_ips: Vec<IpAddr>,
+    udp_ports: Option<(u16, u16)>,
+    servers: Vec<String>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct QueueSettings {
+    size: Option<usize>,
+    #[serde(deserialize_with = "from_str")]
+    overflow_policy: Option<OverflowPolicy>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct LogConfig {
+    #[serde(deserialize_with = "from_str")]
+    verbosity: Option<LevelFilter>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct StreamSettings {
+    /// Token WHIP publishers of this stream must present instead.
+    whip_token: Option<String>,
+    /// Mirrors added as the stream is created, described as for
+    /// `/api/mirrors`.
+    mirrors: Vec<CreatePayload>,
+}
+
+impl Config {
+    fn load(path: &StdPath) -> Result<Self, Box<dyn std::error::Error>> {
+        let text =
+            fs::read_to_string(path).map_err(|e| format!("cannot read {}: {e}", path.display()))?;
+        Ok(Self::parse(&text).map_err(|e| format!("invalid {}: {e}", path.display()))?)
+    }
+
+    fn parse(text: &str) -> Result<Self, String> {
+        let config: Self = toml::from_str(text).map_err(|e| e.to_string())?;
+        if let Some(name) = config.streams.keys().find(|name| !is_stream_name(name)) {
+            return Err(format!("invalid stream name {name:?}"));
+        }
+        if let Some((port_min, port_max)) = config.ice.udp_ports
+            && port_min > port_max
+        {
+            return Err("invalid ICE UDP port range".into());
+        }
+
+        Ok(config)
+    }
+}
+
+/// Read a setting the way the command line parses it.
+fn from_str<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: Display,
+{
+    let Some(value) = Option::<String>::deserialize(deserializer)? else {
+        return Ok(None);
+    };
+    value.parse().map(Some).map_err(serde::de::Error::custom)
+}
+
+/// The value of a flag given on the command line, or else of the
+/// configuration file, or else the default of the flag.
+fn setting<T: Clone + Send + Sync + 'static>(
+    matches: &ArgMatches,
+    id: &str,
+    configured: Option<T>,
+) -> Option<T> {
+    if matches.value_source(id) == Some(ValueSource::CommandLine) {
+        return matches.get_one(id).cloned();
+    }
+    configured.or_else(|| matches.get_one(id).cloned())
+}
+
+/// A running instance, managed through its REST API by the `mirrors` and
+/// `streams` subcommands.
+struct Remote {
+    client: Client<HttpsConnector<HttpConnector>, Full<Bytes>>,
+    server: String,
+    json: bool,
+}
+
+impl Remote {
+    fn new(matches: &ArgMatches) -> Result<Self, Box<dyn std::error::Error>> {
+        let server: &String = matches.get_one("server").unwrap();
+        // Only HTTPS needs the CA bundle, which a server reached over plain
+        // HTTP, as the local one usually is, may well go without.
+        let is_https = server
+            .parse::<Uri>()
+            .is_ok_and(|uri| uri.scheme_str() == Some("https"));
+        let connector = if is_https {
+            HttpsConnectorBuilder::new().with_native_roots()?
+        } else {
+            HttpsConnectorBuilder::new().with_tls_config(
+                ClientConfig::builder()
+                    .with_root_certificates(RootCertStore::empty())
+                    .with_no_client_auth(),
+            )
+        };
+        let client = Client::builder(TokioExecutor::new())
+            .build(connector.https_or_http().enable_http1().build());
+
+        Ok(Self {
+            client,
+            server: server.trim_end_matches('/').to_owned(),
+            json: matches.get_flag("json"),
+        })
+    }
+
+    async fn run(
+        &self,
+        command: &str,
+        matches: &ArgMatches,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let Some((subcommand, args)) = matches.subcommand() else {
+            unreachable!("subcommands are required");
+        };
+        match (command, subcommand) {
+            ("streams", "list") => self.list_streams().await,
+            ("mirrors", "list") => self.list_mirrors(matches).await,
+            ("mirrors", "status") => {
+                self.mirror_status(matches, *args.get_one("id").unwrap())
+                    .await
+            }
+            ("mirrors", "add") => self.add_mirror(matches, args).await,
+            ("mirrors", "remove") => {
+                self.remove_mirror(matches, *args.get_one("id").unwrap())
+                    .await
+            }
+            _ => unreachable!("unknown subcommand {command} {subcommand}"),
+        }
+    }
+
+    async fn list_streams(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let streams = self.get("/api/streams").await?;
+        if self.json {
+            println!("{streams}");
+            return Ok(());
+        }
+        for name in streams.as_array().into_iter().flatten() {
+            println!("{}", name.as_str().unwrap_or_default());
+        }
+
+        Ok(())
+    }
+
+    async fn list_mirrors(&self, matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+        let mirrors = self.mirrors(matches).await?;
+        if self.json {
+            println!("{mirrors}");
+            return Ok(());
+        }
+        println!(
+            "{:>4}  {:<9}  {:<5}  {:<9}  TARGET",
+            "ID", "KIND", "STATE", "CONNECTED"
+        );
+        for mirror in mirrors.as_array().into_iter().flatten() {
+            println!(
+                "{:>4}  {:<9}  {:<5}  {:<9}  {}",
+                mirror["id"].as_u64().unwrap_or_default(),
+                mirror["kind"].as_str().unwrap_or_default(),
+                mirror["state"].as_str().unwrap_or_default(),
+                mirror["connected"].as_bool().unwrap_or_default(),
+                mirror["target"].as_str().unwrap_or_default(),
+            );
+        }
+
+        Ok(())
+    }
+
+    async fn mirror_status(
+        &self,
+        matches: &ArgMatches,
+        id: u64,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mirrors = self.mirrors(matches).await?;
+        let Some(mirror) = mirrors
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find(|mirror| mirror["id"] == id)
+        else {
+            return Err(format!("no mirror {id} in stream {}", stream_name(matches)).into());
+        };
+        if self.json {
+            println!("{mirror}");
+            return Ok(());
+        }
+        for (key, value) in mirror.as_object().into_iter().flatten() {
+            match value {
+                Value::String(value) => println!("{key:<20} {value}"),
+                Value::Array(values) => {
+                    let values: Vec<_> = values.iter().filter_map(Value::as_str).collect();
+                    println!("{key:<20} {}", values.join(", "));
+                }
+                value => println!("{key:<20} {value}"),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Create the mirror, printing the progress the server streams back
+    /// until it tells whether the mirror is in place.
+    async fn add_mirror(
+        &self,
+        matches: &ArgMatches,
+        args: &ArgMatches,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let payload = match args.subcommand() {
+            Some(("discord", args)) => CreatePayload::Discord(DiscordPayload {
+                token: discord_token()?,
+                guild_id: *args.get_one("guild-id").unwrap(),
+                channel_id: *args.get_one("channel-id").unwrap(),
+                width: args.get_one("width").copied(),
+                height: args.get_one("height").copied(),
+                framerate: args.get_one("framerate").copied(),
+                bitrate: args.get_one("bitrate").copied(),
+                codec: args.get_one("codec").cloned(),
+            }),
+            Some(("whip", args)) => CreatePayload::WHIPClient(WHIPClientPayload {
+                url: args.get_one::<String>("url").unwrap().clone(),
+                token: args.get_one("token").cloned(),
+                codec: args.get_one("codec").cloned(),
+            }),
+            Some(("recorder", args)) => CreatePayload::Recorder(RecorderPayload {
+                path: args.get_one::<String>("path").unwrap().clone(),
+                max_duration: args.get_one("max-duration").copied(),
+                max_size: args.get_one("max-size").copied(),
+            }),
+            _ => unreachable!("mirror kinds are required"),
+        };
+        let path = format!("{}?action=create", mirrors_path(matches));
+        let mut body = self.post(&path, &payload).await?.into_body();
+
+        // The response is made of lines, however it is cut into frames, the
+        // last one telling how it went.
+        let mut buf = Vec::new();
+        let mut last = String::new();
+        while let Some(frame) = body.frame().await {
+            let Ok(data) = frame?.into_data() else {
+                continue;
+            };
+            buf.extend_from_slice(&data);
+            while let Some(line) = take_line(&mut buf) {
+                if !last.is_empty() {
+                    self.progress(&last);
+                }
+                last = line;
+            }
+        }
+        if last != "success" {
+            return Err(last.trim_start_matches("error: ").into());
+        }
+        if self.json {
+            println!("{}", serde_json::json!({ "success": true }));
+        } else {
+            println!("mirror added to stream {}", stream_name(matches));
+        }
+
+        Ok(())
+    }
+
+    async fn remove_mirror(
+        &self,
+        matches: &ArgMatches,
+        id: u64,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let path = format!("{}?action=delete", mirrors_path(matches));
+        let resp = self.post(&path, &serde_json::json!({ "id": id })).await;
+        if let Err(e) = resp {
+            return match e.downcast_ref::<RemoteError>() {
+                Some(RemoteError(StatusCode::NOT_FOUND, _)) => {
+                    Err(format!("no mirror {id} in stream {}", stream_name(matches)).into())
+                }
+                _ => Err(e),
+            };
+        }
+        if self.json {
+            println!("{}", serde_json::json!({ "id": id, "removed": true }));
+        } else {
+            println!("mirror {id} removed from stream {}", stream_name(matches));
+        }
+
+        Ok(())
+    }
+
+    async fn mirrors(&self, matches: &ArgMatches) -> Result<Value, Box<dyn std::error::Error>> {
+        self.get(&mirrors_path(matches))
+            .await
+            .map_err(|e| match e.downcast_ref::<RemoteError>() {
+                Some(RemoteError(StatusCode::NOT_FOUND, _)) => {
+                    format!("no stream {}", stream_name(matches)).into()
+                }
+                _ => e,
+            })
+    }
+
+    fn progress(&self, line: &str) {
+        if self.json {
+            println!("{}", serde_json::json!({ "progress": line }));
+        } else {
+            println!("  - {line}");
+        }
+    }
+
+    async fn get(&self, path: &str) -> Result<Value, Box<dyn std::error::Error>> {
+        let req = hyper::Request::get(format!("{}{path}", self.server)).body(Full::default())?;
+        let resp = self.send(req).await?;
+        let body = resp.into_body().collect().await?.to_bytes();
+        Ok(serde_json::from_slice(&body)?)
+    }
+
+    async fn post<T: Serialize>(
+        &self,
+        path: &str,
+        payload: &T,
+    ) -> Result<hyper::Response<hyper::body::Incoming>, Box<dyn std::error::Error>> {
+        let req = hyper::Request::post(format!("{}{path}", self.server))
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Full::new(serde_json::to_vec(payload)?.into()))?;
+        self.send(req).await
+    }
+
+    async fn send(
+        &self,
+        req: hyper::Request<Full<Bytes>>,
+    ) -> Result<hyper::Response<hyper::body::Incoming>, Box<dyn std::error::Error>> {
+        let resp =
+            self.client
+                .request(req)
+                .await
+                .map_err(|e| match std::error::Error::source(&e) {
+                    Some(source) => format!("cannot reach {}: {source}", self.server),
+                    None => format!("cannot reach {}: {e}", self.server),
+                })?;
+        let status = resp.status();
+        if !status.is_success() {
+            let body = resp.into_body().collect().await?.to_bytes();
+            let body = String::from_utf8_lossy(&body).trim().to_owned();
+            return Err(RemoteError(status, body).into());
+        }
+
+        Ok(resp)
+    }
+}
+
+/// Environment variable `mirrors add discord` reads the token from, keeping
+/// it out of the process list and the shell history.
+const DISCORD_TOKEN_ENV: &str = "UTSURU_DISCORD_TOKEN";
+
+/// The Discord token from `DISCORD_TOKEN_ENV`, or else the first line of the
+/// standard input.
+fn discord_token() -> Result<String, Box<dyn std::error::Error>> {
+    if let Ok(token) = env::var(DISCORD_TOKEN_ENV)
+        && !token.is_empty()
+    {
+        return Ok(token);
+    }
+
+    let stdin = io::stdin();
+    if stdin.is_terminal() {
+        eprint!("Discord token: ");
+        io::stderr().flush()?;
+    }
+    let mut token = String::new();
+    stdin.read_line(&mut token)?;
+    match token.trim() {
+        "" => Err(format!("no Discord token, set {DISCORD_TOKEN_ENV} or pass it on stdin").into()),
+        token => Ok(token.to_owned()),
+    }
+}
+
+/// Take the next complete line out of `buf`, without its newline.
+fn take_line(buf: &mut Vec<u8>) -> Option<String> {
+    let end = buf.iter().position(|&b| b == b'\n')?;
+    let line: Vec<u8> = buf.drain(..=end).collect();
+    Some(String::from_utf8_lossy(&line[..end]).into_owned())
+}
+
+/// A request the server turned down.
+#[derive(Debug)]
+struct RemoteError(StatusCode, String);
+
+impl Display for RemoteError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.1.as_str() {
+            "" => write!(f, "server answered {}", self.0),
+            body => write!(f, "server answered {}: {body}", self.0),
+        }
+    }
+}
+
+impl std::error::Error for RemoteError {}
+
+fn stream_name(matches: &ArgMatches) -> &str {
+    matches.get_one::<String>("stream").unwrap()
+}
+
+fn mirrors_path(matches: &ArgMatches) -> String {
+    format!("/api/streams/{}/mirrors", stream_name(matches))
+}
+
+/// Flags of the subcommands talking to a running instance.
+fn remote_args(cmd: Command) -> Command {
+    cmd.subcommand_required(true)
+        .arg(
+            Arg::new("server")
+                .short('s')
+                .long("server")
+                .global(true)
+                .default_value("http://127.0.0.1:3000")
+                .help("URL of the running instance"),
+        )
+        .arg(
+            Arg::new("json")
+                .long("json")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .help("Print JSON instead of text"),
+        )
+}
+
+fn build_mirrors_cli() -> Command {
+    let id = Arg::new("id")
+        .required(true)
+        .value_parser(value_parser!(u64))
+        .help("ID of the mirror");
+    let codec = Arg::new("codec")
+        .long("codec")
+        .help("Video codec to send, such as h264, h265, vp8, vp9 or av1");
+
+    remote_args(Command::new("mirrors"))
+        .about("Manage the mirrors of a running instance")
+        .arg(
+            Arg::new("stream")
+                .long("stream")
+                .global(true)
+                .default_value(DEFAULT_STREAM)
+                .help("Stream the mirrors belong to"),
+        )
+        .subcommand(Command::new("list").about("List the mirrors"))
+        .subcommand(
+            Command::new("status")
+                .about("Print everything about a mirror")
+                .arg(id.clone()),
+        )
+        .subcommand(
+            Command::new("add")
+                .about("Add a mirror, printing its progress")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("discord")
+                        .about("Go live in a Discord voice channel")
+                        .after_help(
+                            "The Discord token is read from UTSURU_DISCORD_TOKEN, or else from the standard input.",
+                        )
+                        .arg(
+                            Arg::new("guild-id")
+                                .long("guild-id")
+                                .required(true)
+                                .value_parser(value_parser!(u64))
+                                .help("ID of the guild"),
+                        )
+                        .arg(
+                            Arg::new("channel-id")
+                                .long("channel-id")
+                                .required(true)
+                                .value_parser(value_parser!(u64))
+                                .help("ID of the voice channel"),
+                        )
+                        .arg(
+                            Arg::new("width")
+                                .long("width")
+                                .requires("height")
+                                .value_parser(value_parser!(u32))
+                                .help("Announced video width"),
+                        )
+                        .arg(
+                            Arg::new("height")
+                                .long("height")
+                                .requires("width")
+                                .value_parser(value_parser!(u32))
+                                .help("Announced video height"),
+                        )
+                        .arg(
+                            Arg::new("framerate")
+                                .long("framerate")
+                                .value_parser(value_parser!(u32))
+                                .help("Announced video framerate"),
+                        )
+                        .arg(
+                            Arg::new("bitrate")
+                                .long("bitrate")
+                                .value_parser(value_parser!(u64))
+                                .help("Announced video bitrate"),
+                        )
+                        .arg(codec.clone()),
+                )
+                .subcommand(
+                    Command::new("whip")
+                        .about("Publish to a WHIP server")
+                        .arg(Arg::new("url").required(true).help("WHIP endpoint"))
+                        .arg(
+                            Arg::new("token")
+                                .long("token")
+                                .help("Bearer token of the endpoint"),
+                        )
+                        .arg(codec),
+                )
+                .subcommand(
+                    Command::new("recorder")
+                        .about("Record to MP4 files")
+                        .arg(
+                            Arg::new("path")
+                                .required(true)
+                                .help("Where to write the recordings"),
+                        )
+                        .arg(
+                            Arg::new("max-duration")
+                                .long("max-duration")
+                                .value_parser(value_parser!(u64))
+                                .help("Start a new file every <max-duration> seconds"),
+                        )
+                        .arg(
+                            Arg::new("max-size")
+                                .long("max-size")
+                                .value_parser(value_parser!(u64))
+                                .help("Start a new file every <max-size> bytes"),
+                        ),
+                ),
+        )
+        .subcommand(Command::new("remove").about("Remove a mirror").arg(id))
+}
+
+fn build_cli() -> Command {
     Command::new(env!("CARGO_CRATE_NAME"))
         .version(env!("CARGO_PKG_VERSION"))
         .disable_help_flag(true)
+        .subcommand(build_mirrors_cli())
+        .subcommand(
+            remote_args(Command::new("streams"))
+                .about("Look at the streams of a running instance")
+                .subcommand(Command::new("list").about("List the streams")),
+        )
         .arg(
             Arg::new("host")
                 .short('h')
@@ -260,11 +2079,70 @@ fn build_cli() -> Command {
                 .default_value("3000")
                 .help("Specify port to listen on"),
         )
+        .arg(
+            Arg::new("rtmp-port")
+                .long("rtmp-port")
+                .value_parser(value_parser!(u16))
+                .help("Accept RTMP publishers on <rtmp-port>"),
+        )
+        .arg(
+            Arg::new("whip-token")
+                .long("whip-token")
+                .default_value(env!("CARGO_CRATE_NAME"))
+                .help("Bearer token WHIP publishers must present"),
+        )
+        .arg(
+            Arg::new("whip-takeover")
+                .long("whip-takeover")
+                .value_parser(value_parser!(TakeoverPolicy))
+                .default_value("reject")
+                .help("What a new WHIP publisher does to a live one, either reject or replace"),
+        )
+        .arg(
+            Arg::new("pli-interval")
+                .long("pli-interval")
+                .value_parser(value_parser!(u64).range(1..))
+                .help("Also ask WHIP publishers for a keyframe every <pli-interval> seconds"),
+        )
+        .arg(
+            Arg::new("queue-size")
+                .long("queue-size")
+                .value_parser(value_parser!(usize))
+                .default_value("256")
+                .help("Samples a mirror may lag behind before dropping"),
+        )
+        .arg(
+            Arg::new("overflow-policy")
+                .long("overflow-policy")
+                .value_parser(value_parser!(OverflowPolicy))
+                .default_value("drop-to-keyframe")
+                .help("What a lagging mirror drops, either drop-to-keyframe or drop-oldest"),
+        )
+        .arg(
+            Arg::new("config")
+                .short('c')
+                .long("config")
+                .value_parser(value_parser!(PathBuf))
+                .help("Read settings and mirrors from the TOML file <config>"),
+        )
+        .arg(
+            Arg::new("recordings")
+                .long("recordings")
+                .value_parser(value_parser!(PathBuf))
+                .default_value("recordings")
+                .help("Directory recorders write to, their paths being relative to it"),
+        )
+        .arg(
+            Arg::new("state")
+                .long("state")
+                .value_parser(value_parser!(PathBuf))
+                .help("Keep the mirrors in <state> and restore them on start"),
+        )
         .arg(
             Arg::new("verbosity")
                 .short('v')
                 .long("verbosity")
-                .value_parser(value_parser!(tracing::level_filters::LevelFilter))
+                .value_parser(value_parser!(LevelFilter))
                 .default_value("off")
                 .help("Log verbosity"),
         )
@@ -291,3 +2169,663 @@ fn print_completions<G: Generator>(generator: G, cmd: &mut Command) {
         &mut std::io::stdout(),
     );
 }
+
+/// The test fixtures of the library, which it only builds for its own
+/// tests.
+#[cfg(test)]
+#[path = "../utils/testing.rs"]
+mod testing;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::pin::Pin;
+    use tokio::sync::{Mutex, oneshot};
+    use utsuru::{
+        error::Error,
+        mirrors::{Mirror, MirrorStatus},
+        sources::{Source, SourceEvent},
+    };
+    use webrtc::{
+        media::Sample, rtp_transceiver::rtp_transceiver_direction::RTCRtpTransceiverDirection,
+    };
+
+    fn stream_config() -> StreamConfig {
+        StreamConfig {
+            host: IpAddr::from([127, 0, 0, 1]),
+            whip_token: "secret".into(),
+            whip_tokens: Arc::new(HashMap::from([("foo".to_owned(), "foosecret".into())])),
+            whip_takeover: TakeoverPolicy::Reject,
+            pli_interval: None,
+            queue: QueueConfig::default(),
+            ice: IceConfig::default(),
+            recordings: PathBuf::from("recordings"),
+        }
+    }
+
+    /// A source sending whatever the test pushes.
+    struct TestSource(Mutex<mpsc::UnboundedReceiver<SourceEvent>>);
+
+    impl Source for TestSource {
+        fn read_event<'a>(
+            &'a self,
+        ) -> Pin<Box<dyn Future<Output = Option<SourceEvent>> + Send + 'a>> {
+            Box::pin(async { self.0.lock().await.recv().await })
+        }
+    }
+
+    /// Add a source to the hub and take it live.
+    async fn go_live(hub: &Hub) -> mpsc::UnboundedSender<SourceEvent> {
+        let (events, events_rx) = mpsc::unbounded_channel();
+        hub.add_source(TestSource(Mutex::new(events_rx)))
+            .await
+            .unwrap();
+        let (live_tx, live_rx) = oneshot::channel();
+        events.send(SourceEvent::Connected(live_tx)).unwrap();
+        assert!(live_rx.await.unwrap());
+        events
+    }
+
+    /// A mirror reporting whatever status the test pushes.
+    struct TestMirror(Mutex<mpsc::UnboundedReceiver<MirrorStatus>>);
+
+    impl Mirror for TestMirror {
+        fn write_audio_sample<'a>(
+            &'a self,
+            _payload: &'a mut Sample,
+        ) -> Pin<Box<dyn Future<Output = Result<(), Error>> + Send + 'a>> {
+            Box::pin(async { Ok(()) })
+        }
+
+        fn write_video_sample<'a>(
+            &'a self,
+            _payload: &'a mut Sample,
+        ) -> Pin<Box<dyn Future<Output = Result<(), Error>> + Send + 'a>> {
+            Box::pin(async { Ok(()) })
+        }
+
+        fn kind(&self) -> &'static str {
+            "test"
+        }
+
+        fn target(&self) -> String {
+            "nowhere".to_owned()
+        }
+
+        fn read_status<'a>(&'a self) -> Pin<Box<dyn Future<Output = MirrorStatus> + Send + 'a>> {
+            Box::pin(async {
+                match self.0.lock().await.recv().await {
+                    Some(status) => status,
+                    None => std::future::pending().await,
+                }
+            })
+        }
+
+        fn close(&self) {}
+    }
+
+    /// The JSON of the next event of a server-sent event stream.
+    async fn next_event(body: &mut Body) -> Value {
+        let next = async {
+            loop {
+                let frame = body.frame().await.unwrap().unwrap();
+                let Ok(data) = frame.into_data() else {
+                    continue;
+                };
+                let text = String::from_utf8(data.to_vec()).unwrap();
+                if let Some(json) = text.strip_prefix("data: ") {
+                    return serde_json::from_str(json.trim_end()).unwrap();
+                }
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(5), next)
+            .await
+            .expect("no event")
+    }
+
+    /// An H.264 IDR slice in Annex B.
+    fn keyframe() -> Sample {
+        Sample {
+            data: Bytes::from_static(&[0, 0, 0, 1, 0x65, 0x88, 0x84]),
+            duration: Duration::from_millis(33),
+            ..Sample::default()
+        }
+    }
+
+    fn publish(stream: &str, token: &str, offer: &str) -> Request {
+        Request::post(format!("/whip/{stream}"))
+            .header(header::CONTENT_TYPE, "application/sdp")
+            .header(header::AUTHORIZATION, format!("Bearer {token}"))
+            .body(Body::from(offer.to_owned()))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn named_stream_takes_its_own_token() {
+        let streams = Streams::new(stream_config(), None).await.unwrap();
+        let offer = testing::offer(RTCRtpTransceiverDirection::Sendonly).await;
+
+        let resp = whip_post(
+            State(streams.clone()),
+            Path("foo".into()),
+            publish("foo", "secret", &offer),
+        )
+        .await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        assert!(streams.get("foo").await.is_none());
+
+        let resp = whip_post(
+            State(streams.clone()),
+            Path("foo".into()),
+            publish("foo", "foosecret", &offer),
+        )
+        .await;
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        assert!(streams.get("foo").await.is_some());
+    }
+
+    #[tokio::test]
+    async fn other_streams_take_the_default_token() {
+        let streams = Streams::new(stream_config(), None).await.unwrap();
+        let offer = testing::offer(RTCRtpTransceiverDirection::Sendonly).await;
+
+        let resp = whip_post(
+            State(streams.clone()),
+            Path("bar".into()),
+            publish("bar", "foosecret", &offer),
+        )
+        .await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        let resp = whip_post(
+            State(streams.clone()),
+            Path("bar".into()),
+            publish("bar", "secret", &offer),
+        )
+        .await;
+        assert_eq!(resp.status(), StatusCode::CREATED);
+    }
+
+    #[test]
+    fn metric_labels_are_escaped() {
+        let mut metrics = Metrics::default();
+        metrics.family("utsuru_test_total", "counter", "Things counted.");
+        metrics.sample(&[("stream", "live"), ("target", "a\\b\"c\nd")], 3);
+        metrics.sample(&[], 4);
+        assert_eq!(
+            metrics.body,
+            "# HELP utsuru_test_total Things counted.\n\
+             # TYPE utsuru_test_total counter\n\
+             utsuru_test_total{stream=\"live\",target=\"a\\\\b\\\"c\\nd\"} 3\n\
+             utsuru_test_total 4\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn metrics_cover_the_source_and_mirrors_of_every_stream() {
+        let streams = Streams::new(stream_config(), None).await.unwrap();
+        let hub = streams.default.hub.clone();
+        let events = go_live(&hub).await;
+        events
+            .send(SourceEvent::VideoSample(Codec::H264, keyframe()))
+            .unwrap();
+        let mirrors = hub.view_mirrors().await.unwrap();
+        let hls = mirrors.iter().find(|mirror| mirror.kind == "hls").unwrap();
+        for _ in 0..200 {
+            if hub
+                .view_mirrors()
+                .await
+                .unwrap()
+                .iter()
+                .any(|m| m.id == hls.id && m.written == 1)
+            {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        let resp = metrics_get(State(streams)).await.unwrap();
+        assert_eq!(
+            resp.headers()[header::CONTENT_TYPE],
+            "text/plain; version=0.0.4"
+        );
+        let body = resp.into_body().collect().await.unwrap().to_bytes();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        let lines: Vec<_> = body.lines().collect();
+
+        // Every family is described before its samples.
+        let mut family = "";
+        for pair in lines.windows(2) {
+            if let Some(help) = pair[0].strip_prefix("# HELP ") {
+                family = help.split(' ').next().unwrap();
+                assert_eq!(pair[1].split(' ').nth(2), Some(family));
+                assert!(pair[1].starts_with("# TYPE "));
+            } else if !pair[0].starts_with('#') {
+                assert!(pair[0].starts_with(&format!("{family}{{")));
+            }
+        }
+        for line in [
+            "# TYPE utsuru_source_connected gauge",
+            "# TYPE utsuru_source_frames_total counter",
+            "# TYPE utsuru_mirror_samples_written_total counter",
+            "# TYPE utsuru_mirror_queued_samples gauge",
+            "utsuru_source_connected{stream=\"live\"} 1",
+            "utsuru_source_keyframes_total{stream=\"live\"} 1",
+            "utsuru_source_frames_total{stream=\"live\",track=\"audio\"} 0",
+            "utsuru_source_frames_total{stream=\"live\",track=\"video\"} 1",
+            &format!(
+                "utsuru_mirror_samples_written_total{{stream=\"live\",id=\"{}\",kind=\"hls\"}} 1",
+                hls.id
+            ),
+            &format!(
+                "utsuru_mirror_live{{stream=\"live\",id=\"{}\",kind=\"hls\"}} 1",
+                hls.id
+            ),
+            &format!(
+                "utsuru_mirror_dropped_samples_total{{stream=\"live\",id=\"{}\",kind=\"hls\",track=\"video\"}} 0",
+                hls.id
+            ),
+        ] {
+            assert!(lines.contains(&line), "missing {line}");
+        }
+    }
+
+    #[tokio::test]
+    async fn events_tell_what_happens_in_every_stream() {
+        let streams = Streams::new(stream_config(), None).await.unwrap();
+        let mut body = events_get(State(streams.clone()))
+            .await
+            .into_response()
+            .into_body();
+        let hub = streams.default.hub.clone();
+
+        let events = go_live(&hub).await;
+        assert_eq!(
+            next_event(&mut body).await,
+            json!({"stream": "live", "event": "source_connected"})
+        );
+
+        let (status, status_rx) = mpsc::unbounded_channel();
+        let id = hub
+            .add_mirror(TestMirror(Mutex::new(status_rx)))
+            .await
+            .unwrap();
+        assert_eq!(
+            next_event(&mut body).await,
+            json!({
+                "stream": "live",
+                "event": "mirror_added",
+                "id": id,
+                "kind": "test",
+                "target": "nowhere",
+            })
+        );
+
+        status
+            .send(MirrorStatus::Reconnecting { attempt: 2 })
+            .unwrap();
+        assert_eq!(
+            next_event(&mut body).await,
+            json!({
+                "stream": "live",
+                "event": "mirror_status",
+                "id": id,
+                "status": "reconnecting",
+                "attempt": 2,
+            })
+        );
+
+        hub.remove_mirror(id).await.unwrap();
+        assert_eq!(
+            next_event(&mut body).await,
+            json!({"stream": "live", "event": "mirror_removed", "id": id})
+        );
+
+        events.send(SourceEvent::Disconnected).unwrap();
+        assert_eq!(
+            next_event(&mut body).await,
+            json!({"stream": "live", "event": "source_disconnected"})
+        );
+
+        // Streams created later are followed too, from their own mirrors on.
+        let studio = streams.get_or_create("studio").await.unwrap();
+        for (kind, target) in [("whep", "/whep/studio"), ("hls", "/hls/studio/index.m3u8")] {
+            let event = next_event(&mut body).await;
+            assert_eq!(event["stream"], "studio");
+            assert_eq!(event["event"], "mirror_added");
+            assert_eq!(event["kind"], kind);
+            assert_eq!(event["target"], target);
+        }
+        let _events = go_live(&studio.hub).await;
+        assert_eq!(
+            next_event(&mut body).await,
+            json!({"stream": "studio", "event": "source_connected"})
+        );
+    }
+
+    #[test]
+    fn config_parses_every_section() {
+        let config = Config::parse(
+            r#"
+            [server]
+            host = "0.0.0.0"
+            port = 8080
+            rtmp_port = 1935
+            state = "mirrors.json"
+            recordings = "/srv/recordings"
+
+            [whip]
+            token = "secret"
+            takeover = "replace"
+            pli_interval = 5
+
+            [ice]
+            nat_ips = ["203.0.113.7"]
+            udp_ports = [40000, 40100]
+            servers = ["stun:stun.l.google.com:19302"]
+
+            [queue]
+            size = 64
+            overflow_policy = "drop-oldest"
+
+            [log]
+            verbosity = "debug"
+
+            [streams.studio]
+            whip_token = "studio-secret"
+
+            [[streams.studio.mirrors]]
+            url = "https://example.com/whip"
+            token = "target"
+
+            [[streams.studio.mirrors]]
+            path = "studio"
+            max_duration = 3600
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.server.host, Some(IpAddr::from([0, 0, 0, 0])));
+        assert_eq!(config.server.port, Some(8080));
+        assert_eq!(config.server.rtmp_port, Some(1935));
+        assert_eq!(config.server.state, Some(PathBuf::from("mirrors.json")));
+        assert_eq!(
+            config.server.recordings,
+            Some(PathBuf::from("/srv/recordings"))
+        );
+        assert_eq!(config.whip.token.as_deref(), Some("secret"));
+        assert_eq!(config.whip.takeover, Some(TakeoverPolicy::Replace));
+        assert_eq!(config.whip.pli_interval, Some(5));
+        assert_eq!(config.ice.nat_ips, [IpAddr::from([203, 0, 113, 7])]);
+        assert_eq!(config.ice.udp_ports, Some((40000, 40100)));
+        assert_eq!(config.ice.servers, ["stun:stun.l.google.com:19302"]);
+        assert_eq!(config.queue.size, Some(64));
+        assert_eq!(
+            config.queue.overflow_policy,
+            Some(OverflowPolicy::DropOldest)
+        );
+        assert_eq!(config.log.verbosity, Some(LevelFilter::DEBUG));
+
+        let studio = &config.streams["studio"];
+        assert_eq!(studio.whip_token.as_deref(), Some("studio-secret"));
+        assert!(matches!(
+            &studio.mirrors[..],
+            [
+                CreatePayload::WHIPClient(WHIPClientPayload { url, .. }),
+                CreatePayload::Recorder(RecorderPayload {
+                    path,
+                    max_duration: Some(3600),
+                    ..
+                }),
+            ] if url == "https://example.com/whip" && path == "studio"
+        ));
+    }
+
+    #[test]
+    fn config_may_be_empty() {
+        let config = Config::parse("").unwrap();
+        assert!(config.server.port.is_none());
+        assert!(config.streams.is_empty());
+    }
+
+    #[test]
+    fn config_rejects_mistakes() {
+        assert!(Config::parse("[srv]").is_err());
+        assert!(Config::parse("[whip]\ntakeover = \"nope\"").is_err());
+        assert!(Config::parse("[log]\nverbosity = \"loud\"").is_err());
+        assert!(Config::parse("[streams.\"bad name\"]").is_err());
+        assert!(Config::parse("[ice]\nudp_ports = [5000, 4000]").is_err());
+        assert!(
+            Config::parse("[[streams.live.mirrors]]\npath = \"live.mp4\"\nmax_duraton = 60")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn command_line_overrides_config() {
+        let matches = build_cli().get_matches_from(["utsuru", "--port", "4000"]);
+        assert_eq!(setting(&matches, "port", Some(5000u16)), Some(4000));
+        assert_eq!(
+            setting(&matches, "host", Some(IpAddr::from([0, 0, 0, 0]))),
+            Some(IpAddr::from([0, 0, 0, 0]))
+        );
+        assert_eq!(
+            setting::<IpAddr>(&matches, "host", None),
+            Some(IpAddr::from([127, 0, 0, 1]))
+        );
+        assert_eq!(setting::<u16>(&matches, "rtmp-port", None), None);
+    }
+
+    #[test]
+    fn recorders_stay_in_the_recordings_directory() {
+        let recorder = |path: &str| {
+            let payload = CreatePayload::Recorder(RecorderPayload {
+                path: path.to_owned(),
+                max_duration: None,
+                max_size: None,
+            });
+            MirrorBuilder::new(payload, &stream_config()).map(|_| ())
+        };
+        assert_eq!(recorder("live/{date}.mp4"), Ok(()));
+        assert_eq!(recorder("/etc/passwd"), Err(StatusCode::BAD_REQUEST));
+        assert_eq!(recorder("../escape.mp4"), Err(StatusCode::BAD_REQUEST));
+        assert_eq!(
+            recorder("live/../../escape.mp4"),
+            Err(StatusCode::BAD_REQUEST)
+        );
+    }
+
+    fn discord(token: &str) -> CreatePayload {
+        CreatePayload::Discord(DiscordPayload {
+            token: token.to_owned(),
+            guild_id: 1,
+            channel_id: 2,
+            width: None,
+            height: None,
+            framerate: None,
+            bitrate: None,
+            codec: None,
+        })
+    }
+
+    #[test]
+    fn create_payloads_refuse_unknown_fields() {
+        let parse = serde_json::from_str::<CreatePayload>;
+        assert!(matches!(
+            parse(r#"{"path": "live.mp4", "max_duration": 60}"#),
+            Ok(CreatePayload::Recorder(_))
+        ));
+        assert!(parse(r#"{"path": "live.mp4", "max_duraton": 60}"#).is_err());
+        assert!(matches!(
+            parse(r#"{"url": "https://example.com/whip", "token": "target"}"#),
+            Ok(CreatePayload::WHIPClient(_))
+        ));
+        assert!(parse(r#"{"url": "https://example.com/whip", "tokne": "target"}"#).is_err());
+        assert!(matches!(
+            parse(r#"{"token": "t", "guild_id": 1, "channel_id": 2}"#),
+            Ok(CreatePayload::Discord(_))
+        ));
+        assert!(parse(r#"{"token": "t", "guild_id": 1, "channel_id": 2, "widht": 1280}"#).is_err());
+    }
+
+    #[tokio::test]
+    async fn create_requests_with_unknown_fields_are_refused() {
+        let req = Request::post("/api/mirrors?action=create")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(r#"{"path": "live.mp4", "max_duraton": 60}"#))
+            .unwrap();
+        let Err(resp) = Action::from_request(req, &()).await else {
+            panic!("the request was taken");
+        };
+        assert!(resp.status().is_client_error());
+    }
+
+    #[tokio::test]
+    async fn discord_resolutions_need_both_sides() {
+        let streams = Streams::new(stream_config(), None).await.unwrap();
+        let CreatePayload::Discord(payload) = discord("discord-token") else {
+            unreachable!();
+        };
+        for (width, height) in [(Some(1280), None), (None, Some(720))] {
+            let payload = CreatePayload::Discord(DiscordPayload {
+                width,
+                height,
+                ..payload.clone()
+            });
+            assert_eq!(
+                MirrorBuilder::new(payload.clone(), &stream_config()).err(),
+                Some(StatusCode::BAD_REQUEST)
+            );
+            let resp = mirrors_post(State(streams.clone()), Action::Create(payload)).await;
+            assert_eq!(resp.err(), Some(StatusCode::BAD_REQUEST));
+        }
+
+        let payload = CreatePayload::Discord(DiscordPayload {
+            width: Some(1280),
+            height: Some(720),
+            ..payload
+        });
+        assert!(MirrorBuilder::new(payload, &stream_config()).is_ok());
+    }
+
+    fn temp_state() -> PathBuf {
+        let directory = std::env::temp_dir().join(format!("utsuru-{}", Uuid::new_v4()));
+        fs::create_dir_all(&directory).unwrap();
+        directory.join("mirrors.json")
+    }
+
+    #[test]
+    fn stored_mirrors_come_back_with_their_tokens() {
+        let path = temp_state();
+        let (store, restored) = MirrorStore::open(path.clone()).unwrap();
+        assert!(restored.is_empty());
+        store.add("live", 1, &discord("discord-token"));
+        store.add("studio", 4, &discord("other-token"));
+        store.remove("studio", 4);
+
+        let state = fs::read_to_string(&path).unwrap();
+        assert!(!state.contains("discord-token"));
+
+        let (store, restored) = MirrorStore::open(path.clone()).unwrap();
+        let [StoredMirror { stream, mirror, .. }] = &restored[..] else {
+            panic!("expected a single mirror");
+        };
+        assert_eq!(stream, "live");
+        assert!(
+            matches!(mirror, CreatePayload::Discord(DiscordPayload { token, .. }) if token == "discord-token")
+        );
+        // Nothing runs until restored.
+        assert!(store.list().iter().all(|entry| entry.id.is_none()));
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn stored_mirrors_are_only_forgotten_on_request() {
+        let path = temp_state();
+        let (store, _) = MirrorStore::open(path.clone()).unwrap();
+        store.add("live", 1, &discord("discord-token"));
+        let key = store.list()[0].key.clone();
+
+        // Leaving the hub on its own, as a failed mirror does, keeps the
+        // mirror stored.
+        let (notifications, _) = broadcast::channel(NOTIFICATION_CAPACITY);
+        let follower = tokio::spawn(store.clone().follow(notifications.subscribe()));
+        notifications
+            .send(StreamNotification {
+                stream: "live".to_owned(),
+                notification: Notification::MirrorRemoved { id: 1 },
+            })
+            .ok()
+            .unwrap();
+        drop(notifications);
+        follower.await.unwrap();
+        assert_eq!(store.list()[0].id, None);
+        let (store, restored) = MirrorStore::open(path.clone()).unwrap();
+        assert_eq!(restored.len(), 1);
+
+        store.track("live", 7, key.clone());
+        assert_eq!(store.list()[0].id, Some(7));
+        assert_eq!(store.forget(&key), Some(("live".to_owned(), Some(7))));
+        assert_eq!(store.forget(&key), None);
+        let (_, restored) = MirrorStore::open(path.clone()).unwrap();
+        assert!(restored.is_empty());
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn secrets_are_private_on_every_write() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = temp_state();
+        let secrets = path.with_file_name("mirrors.json.secrets");
+        // A temporary file left behind by an earlier run.
+        let tmp = path.with_file_name("mirrors.json.secrets.tmp");
+        fs::write(&tmp, "{}").unwrap();
+        fs::set_permissions(&tmp, fs::Permissions::from_mode(0o644)).unwrap();
+
+        let (store, _) = MirrorStore::open(path.clone()).unwrap();
+        store.add("live", 1, &discord("discord-token"));
+        let mode = fs::metadata(&secrets).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn remotes_reach_servers_over_plain_http() {
+        let streams = Streams::new(stream_config(), None).await.unwrap();
+        let app = Router::new()
+            .route("/api/streams", get(streams_get))
+            .with_state(streams);
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let server = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let matches =
+            build_cli().get_matches_from(["utsuru", "streams", "--server", &server, "list"]);
+        let Some((_, matches)) = matches.subcommand() else {
+            unreachable!();
+        };
+        let remote = Remote::new(matches).unwrap();
+        assert_eq!(remote.get("/api/streams").await.unwrap(), json!(["live"]));
+    }
+
+    #[test]
+    fn progress_lines_are_split_however_frames_are_cut() {
+        let mut buf = b"connecting to the gateway\nconnec".to_vec();
+        assert_eq!(
+            take_line(&mut buf).as_deref(),
+            Some("connecting to the gateway")
+        );
+        assert_eq!(take_line(&mut buf), None);
+
+        buf.extend_from_slice(b"ted\nsuccess\n");
+        assert_eq!(take_line(&mut buf).as_deref(), Some("connected"));
+        assert_eq!(take_line(&mut buf).as_deref(), Some("success"));
+        assert!(buf.is_empty());
+    }
+}
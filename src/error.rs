@@ -28,11 +28,28 @@ where
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
         match self.kind {
             ErrorType::DiscordAuth => f.write_str("discord auth failed"),
+            ErrorType::DiscordCodec => f.write_str("discord cannot stream the codec"),
             ErrorType::DiscordIPC => f.write_str("discord client crashed"),
             ErrorType::DiscordGateway => f.write_str("discord gateway closed"),
             ErrorType::DiscordEndpoint => f.write_str("discord endpoint closed"),
             ErrorType::DiscordDAVE => f.write_str("discord dave closed"),
+            ErrorType::HLSIPC => f.write_str("hls service crashed"),
+            ErrorType::HubCodec => f.write_str("mirror does not accept the source codec"),
+            ErrorType::HubIPC => f.write_str("hub service crashed"),
+            ErrorType::HubMirror => f.write_str("mirror failed to start"),
+            ErrorType::RecorderFile => f.write_str("recorder file write failed"),
+            ErrorType::RecorderIPC => f.write_str("recorder service crashed"),
+            ErrorType::RecorderPath => f.write_str("recording path leaves the directory"),
+            ErrorType::RTMPProtocol => f.write_str("rtmp peer broke the protocol"),
+            ErrorType::RTMPSocket => f.write_str("rtmp socket closed"),
+            ErrorType::WHEPIPC => f.write_str("whep service crashed"),
+            ErrorType::WHEPOffer => f.write_str("whep offer rejected"),
+            ErrorType::WHEPPeer => f.write_str("whep rtc peer closed"),
+            ErrorType::WHIPClientCodec => f.write_str("whip client cannot send the codec"),
+            ErrorType::WHIPClientRequest => f.write_str("whip client request failed"),
+            ErrorType::WHIPClientPeer => f.write_str("whip client rtc peer closed"),
             ErrorType::WHIPIPC => f.write_str("whip service crashed"),
+            ErrorType::WHIPOffer => f.write_str("whip offer rejected"),
             ErrorType::WHIPPeer => f.write_str("whip rtc peer closed"),
         }
     }
@@ -49,10 +66,27 @@ impl StdError for Error<dyn StdError + Send + Sync> {
 #[derive(Debug)]
 pub enum ErrorType {
     DiscordAuth,
+    DiscordCodec,
     DiscordIPC,
     DiscordGateway,
     DiscordEndpoint,
     DiscordDAVE,
+    HLSIPC,
+    HubCodec,
+    HubIPC,
+    HubMirror,
+    RecorderFile,
+    RecorderIPC,
+    RecorderPath,
+    RTMPProtocol,
+    RTMPSocket,
+    WHEPIPC,
+    WHEPOffer,
+    WHEPPeer,
+    WHIPClientCodec,
+    WHIPClientRequest,
+    WHIPClientPeer,
     WHIPIPC,
+    WHIPOffer,
     WHIPPeer,
 }
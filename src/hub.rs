@@ -0,0 +1,1607 @@
+use bytes::Bytes;
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, HashSet, VecDeque},
+    error::Error as StdError,
+    fmt::{Display, Formatter, Result as FmtResult},
+    str::FromStr,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use tokio::sync::{
+    Notify, RwLock, broadcast,
+    mpsc::{self, error::SendError},
+    oneshot::{self, error::RecvError},
+};
+use tracing::{debug, info, warn};
+use webrtc::media::Sample;
+
+use crate::{
+    error::{Error, ErrorType},
+    mirrors::{Mirror, MirrorStatus},
+    sources::{Source, SourceEvent, SourceStats},
+    utils::codecs::{Codec, ParameterSets},
+};
+
+/// Keyframe requests closer together than this are answered by the same
+/// keyframe, so only the first one reaches the sources.
+const KEYFRAME_REQUEST_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Notifications a subscriber may fall behind by before missing some.
+const NOTIFICATION_CAPACITY: usize = 256;
+
+#[derive(Clone)]
+pub struct Hub {
+    inner_tx: mpsc::UnboundedSender<HubEvent>,
+    notifications: broadcast::Sender<Notification>,
+}
+
+impl Hub {
+    pub fn new() -> Self {
+        Self::with_queue_config(QueueConfig::default())
+    }
+
+    /// Create a hub whose mirrors are fed through queues set up from `config`.
+    pub fn with_queue_config(config: QueueConfig) -> Self {
+        let inner = mpsc::unbounded_channel();
+        let (inner_tx_a, inner_tx_b, mut inner_rx) = (inner.0.clone(), inner.0, inner.1);
+        let (notifications, _) = broadcast::channel(NOTIFICATION_CAPACITY);
+        let inner: Arc<HubInner> = Arc::new(HubInner::new(config, notifications.clone()));
+
+        let inner_tx = inner_tx_a;
+        tokio::spawn(async move {
+            let mut active = false;
+            let mut next_source = 0;
+
+            while let Some(payload) = inner_rx.recv().await {
+                match payload {
+                    HubEvent::NewSource(source, done_tx) => {
+                        let source: Arc<dyn Source + Send + Sync> = Arc::from(source);
+                        inner.add_source(source.clone()).await;
+                        let index = next_source;
+                        next_source += 1;
+                        let inner = inner.clone();
+                        let inner_tx = inner_tx.clone();
+                        tokio::spawn(async move {
+                            while let Some(event) = source.read_event().await {
+                                match event {
+                                    SourceEvent::Connected(live_tx) => {
+                                        // Samples following the request wait
+                                        // for the answer, not to be dropped.
+                                        let (answer_tx, answer_rx) = oneshot::channel();
+                                        let _ = inner_tx
+                                            .send(HubEvent::SourceConnected(index, answer_tx));
+                                        let _ = live_tx.send(answer_rx.await.unwrap_or(false));
+                                    }
+                                    SourceEvent::Disconnected => {
+                                        let _ = inner_tx.send(HubEvent::SourceDisconnected(index));
+                                    }
+                                    SourceEvent::IceConnectionState(state) => {
+                                        inner.notify(Notification::SourceIce {
+                                            state: state.to_string(),
+                                        });
+                                    }
+                                    SourceEvent::AudioSample(codec, payload)
+                                        if inner.is_live_source(index) =>
+                                    {
+                                        inner.write_audio_sample(codec, &payload).await;
+                                    }
+                                    SourceEvent::VideoSample(codec, payload)
+                                        if inner.is_live_source(index) =>
+                                    {
+                                        inner.write_video_sample(codec, &payload).await;
+                                    }
+                                    SourceEvent::AudioSample(..) | SourceEvent::VideoSample(..) => {
+                                    }
+                                }
+                            }
+                            let _ = inner_tx.send(HubEvent::SourceDisconnected(index));
+                            warn!("[Hub] source finished");
+                        });
+                        let _ = done_tx.send(());
+                    }
+                    HubEvent::SourceConnected(index, live_tx) => {
+                        let live_source = inner.live_source();
+                        if live_source.is_some_and(|live| live != index) {
+                            warn!("[Hub] refusing source, another one is live");
+                            let _ = live_tx.send(false);
+                            continue;
+                        }
+                        inner.set_live_source(Some(index));
+                        let _ = live_tx.send(true);
+                        if live_source.is_some() {
+                            continue;
+                        }
+                        info!("[Hub] source connected");
+                        inner.notify(Notification::SourceConnected);
+                        inner.set_state(MirrorState::Live).await;
+                        active = true;
+                    }
+                    HubEvent::SourceDisconnected(index) => {
+                        if inner.live_source().is_some_and(|live| live != index) {
+                            debug!("[Hub] ignoring the disconnection of an idle source");
+                            continue;
+                        }
+                        inner.set_live_source(None);
+                        if active {
+                            inner.notify(Notification::SourceDisconnected);
+                        }
+                        inner.set_state(MirrorState::Idle).await;
+                        active = false;
+                    }
+                    HubEvent::RetrieveSource(source_tx) => {
+                        let source = inner.view_source(active).await;
+                        let _ = source_tx.send(source);
+                    }
+                    HubEvent::RetrieveMirrors(mirrors_tx) => {
+                        let mirrors = inner.view_mirrors().await;
+                        let _ = mirrors_tx.send(mirrors);
+                    }
+                    HubEvent::NewMirror(mirror, id_tx) => {
+                        let id = inner.add_mirror(mirror, active).await;
+                        // A mirror joining mid-stream cannot start before the
+                        // next keyframe.
+                        if active && id.is_ok() {
+                            inner.request_keyframe().await;
+                        }
+                        let _ = id_tx.send(id);
+                        inner.announce_codecs().await;
+                    }
+                    HubEvent::EndMirror(id, found_tx) => {
+                        let found = inner.remove_mirror(id).await;
+                        let _ = found_tx.send(found);
+                        inner.announce_codecs().await;
+                    }
+                }
+            }
+
+            inner_rx.close();
+        });
+
+        let inner_tx = inner_tx_b;
+        Self {
+            inner_tx,
+            notifications,
+        }
+    }
+
+    /// Follow what happens to the sources and mirrors from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<Notification> {
+        self.notifications.subscribe()
+    }
+
+    pub async fn add_source<S: Source + Send + Sync + 'static>(
+        &self,
+        source: S,
+    ) -> Result<(), Error<dyn ErrorInner>> {
+        let (done_tx, done_rx) = oneshot::channel();
+        self.inner_tx
+            .send(HubEvent::NewSource(Box::new(source), done_tx))?;
+        done_rx.await.map_err(Into::into)
+    }
+
+    pub async fn view_source(&self) -> Result<SourceDescriptor, Error<dyn ErrorInner>> {
+        let (source_tx, source_rx) = oneshot::channel();
+        self.inner_tx.send(HubEvent::RetrieveSource(source_tx))?;
+        source_rx.await.map_err(Into::into)
+    }
+
+    pub async fn view_mirrors(&self) -> Result<Vec<MirrorDescriptor>, Error<dyn ErrorInner>> {
+        let (mirrors_tx, mirrors_rx) = oneshot::channel();
+        self.inner_tx.send(HubEvent::RetrieveMirrors(mirrors_tx))?;
+        mirrors_rx.await.map_err(Into::into)
+    }
+
+    /// Register a mirror, returning its ID. Fails, closing the mirror, if a
+    /// source is live with a video codec the mirror does not accept, or the
+    /// mirror fails to start on it. Audio it does not accept is dropped
+    /// instead.
+    pub async fn add_mirror<M: Mirror + Send + Sync + 'static>(
+        &self,
+        mirror: M,
+    ) -> Result<u64, Error<dyn ErrorInner>> {
+        let (id_tx, id_rx) = oneshot::channel();
+        self.inner_tx
+            .send(HubEvent::NewMirror(Box::new(mirror), id_tx))?;
+        id_rx.await?
+    }
+
+    /// Close and unregister a mirror. Returns `false` if no mirror has this ID.
+    pub async fn remove_mirror(&self, id: u64) -> Result<bool, Error<dyn ErrorInner>> {
+        let (found_tx, found_rx) = oneshot::channel();
+        self.inner_tx.send(HubEvent::EndMirror(id, found_tx))?;
+        found_rx.await.map_err(Into::into)
+    }
+}
+
+impl Default for Hub {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+enum HubEvent {
+    NewSource(Box<dyn Source + Send + Sync>, oneshot::Sender<()>),
+    /// A source asks to go live, by the order it was added in.
+    SourceConnected(usize, oneshot::Sender<bool>),
+    SourceDisconnected(usize),
+    RetrieveSource(oneshot::Sender<SourceDescriptor>),
+    RetrieveMirrors(oneshot::Sender<Vec<MirrorDescriptor>>),
+    NewMirror(
+        Box<dyn Mirror + Send + Sync>,
+        oneshot::Sender<Result<u64, Error<dyn ErrorInner>>>,
+    ),
+    EndMirror(u64, oneshot::Sender<bool>),
+}
+
+/// Something that happened to the sources or mirrors of a hub.
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Notification {
+    SourceConnected,
+    SourceDisconnected,
+    /// The ICE connection of the publisher moved to `state`.
+    SourceIce {
+        state: String,
+    },
+    MirrorAdded {
+        id: u64,
+        kind: &'static str,
+        target: String,
+    },
+    MirrorRemoved {
+        id: u64,
+    },
+    /// The mirror stopped taking samples and is about to be removed.
+    MirrorFailed {
+        id: u64,
+        /// The kind of error, such as `DiscordEndpoint`.
+        error_kind: String,
+        error: String,
+    },
+    MirrorStatus {
+        id: u64,
+        #[serde(flatten)]
+        status: MirrorStatus,
+    },
+}
+
+impl Notification {
+    fn mirror_failed(id: u64, err: &Error) -> Self {
+        Self::MirrorFailed {
+            id,
+            error_kind: format!("{:?}", err.kind()),
+            error: err.to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MirrorState {
+    /// Waiting for a source to connect.
+    Idle,
+    /// Receiving samples from a connected source.
+    Live,
+}
+
+/// Describes a mirror registered with the hub.
+#[derive(Clone, Debug, Serialize)]
+pub struct MirrorDescriptor {
+    /// Stays the same for the lifetime of the mirror and is never reused.
+    pub id: u64,
+    pub kind: &'static str,
+    pub target: String,
+    /// Codecs the mirror accepts.
+    pub codecs: Vec<String>,
+    pub state: MirrorState,
+    /// Seconds since the unix epoch.
+    pub created: u64,
+    /// Samples waiting in the mirror's queue.
+    pub queued: usize,
+    /// Audio samples dropped because the mirror fell behind.
+    pub dropped_audio: u64,
+    /// Video samples dropped because the mirror fell behind.
+    pub dropped_video: u64,
+    /// Samples the mirror took.
+    pub written: u64,
+    /// Samples the mirror failed to take.
+    pub write_errors: u64,
+    /// Samples that went out unencrypted.
+    pub encryption_failures: u64,
+    pub connected: bool,
+}
+
+/// Describes what the hub has received from its sources.
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct SourceDescriptor {
+    pub connected: bool,
+    /// Audio samples passed on to the mirrors.
+    pub audio_frames: u64,
+    /// Video samples passed on to the mirrors.
+    pub video_frames: u64,
+    pub keyframes: u64,
+    #[serde(flatten)]
+    pub stats: SourceStats,
+}
+
+/// What to drop once a mirror's queue is full.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum OverflowPolicy {
+    /// Discard the queued video and skip video until the next keyframe, so
+    /// the mirror never decodes a frame whose references were dropped.
+    #[default]
+    DropToKeyframe,
+    /// Discard the oldest queued sample.
+    DropOldest,
+}
+
+impl FromStr for OverflowPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "drop-to-keyframe" => Ok(Self::DropToKeyframe),
+            "drop-oldest" => Ok(Self::DropOldest),
+            _ => Err(format!(
+                "unknown overflow policy `{s}`, expected `drop-to-keyframe` or `drop-oldest`"
+            )),
+        }
+    }
+}
+
+impl Display for OverflowPolicy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::DropToKeyframe => f.write_str("drop-to-keyframe"),
+            Self::DropOldest => f.write_str("drop-oldest"),
+        }
+    }
+}
+
+/// Every mirror is fed from its own task through a bounded queue, so a slow
+/// mirror only ever delays itself.
+#[derive(Clone, Copy, Debug)]
+pub struct QueueConfig {
+    /// Samples a mirror may lag behind before the overflow policy applies.
+    pub capacity: usize,
+    pub policy: OverflowPolicy,
+}
+
+impl Default for QueueConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 256,
+            policy: OverflowPolicy::default(),
+        }
+    }
+}
+
+struct MirrorEntry {
+    descriptor: MirrorDescriptor,
+    mirror: Arc<dyn Mirror + Send + Sync>,
+    queue: Arc<MirrorQueue>,
+}
+
+impl MirrorEntry {
+    fn close(&self) {
+        self.queue.close();
+        self.mirror.close();
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Track {
+    Audio,
+    Video,
+}
+
+struct QueuedSample {
+    track: Track,
+    sample: Sample,
+}
+
+/// What became of a sample handed to a mirror queue.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Pushed {
+    Queued,
+    /// The queue overflowed into dropping video, the mirror cannot go on
+    /// before the next keyframe.
+    NeedsKeyframe,
+    Closed,
+}
+
+#[derive(Default)]
+struct QueueState {
+    samples: VecDeque<QueuedSample>,
+    awaiting_keyframe: bool,
+    /// Video made it into the queue, the mirror has started decoding.
+    started: bool,
+    lagging: bool,
+}
+
+struct MirrorQueue {
+    id: u64,
+    config: QueueConfig,
+    state: Mutex<QueueState>,
+    notify: Notify,
+    is_closed: AtomicBool,
+    dropped_audio: AtomicU64,
+    dropped_video: AtomicU64,
+    written: AtomicU64,
+    write_errors: AtomicU64,
+}
+
+impl MirrorQueue {
+    fn new(id: u64, config: QueueConfig) -> Self {
+        Self {
+            id,
+            config,
+            // Video before the first keyframe cannot be decoded.
+            state: Mutex::new(QueueState {
+                awaiting_keyframe: true,
+                ..QueueState::default()
+            }),
+            notify: Notify::new(),
+            is_closed: AtomicBool::new(false),
+            dropped_audio: AtomicU64::new(0),
+            dropped_video: AtomicU64::new(0),
+            written: AtomicU64::new(0),
+            write_errors: AtomicU64::new(0),
+        }
+    }
+
+    /// Queue a sample without waiting on the mirror.
+    ///
+    /// Video keyframes come along with their data led by the parameter sets
+    /// they depend on, which mirrors starting on them are given instead.
+    fn push(&self, track: Track, keyframe: Option<&Bytes>, mut sample: Sample) -> Pushed {
+        if self.is_closed.load(Ordering::Relaxed) {
+            return Pushed::Closed;
+        }
+        let Ok(mut state) = self.state.lock() else {
+            return Pushed::Closed;
+        };
+
+        if track == Track::Video && state.awaiting_keyframe {
+            let Some(keyframe) = keyframe else {
+                if state.started {
+                    self.count_dropped(track, 1);
+                }
+                return Pushed::Queued;
+            };
+            state.awaiting_keyframe = false;
+            state.started = true;
+            sample.data = keyframe.clone();
+        }
+
+        let mut pushed = Pushed::Queued;
+        if state.samples.len() >= self.config.capacity.max(1) {
+            if !state.lagging {
+                warn!(
+                    "[Hub] mirror {} is falling behind, dropping samples ({})",
+                    self.id, self.config.policy
+                );
+                state.lagging = true;
+            }
+            match self.config.policy {
+                OverflowPolicy::DropOldest => {
+                    if let Some(oldest) = state.samples.pop_front() {
+                        self.count_dropped(oldest.track, 1);
+                    }
+                }
+                OverflowPolicy::DropToKeyframe => {
+                    let before = state.samples.len();
+                    state.samples.retain(|queued| queued.track == Track::Audio);
+                    let dropped = (before - state.samples.len()) as u64;
+                    self.count_dropped(Track::Video, dropped);
+                    // Video after the hole would reference dropped frames,
+                    // whichever track overflowed the queue.
+                    let is_video = track == Track::Video;
+                    if (dropped > 0 || is_video) && keyframe.is_none() {
+                        state.awaiting_keyframe = true;
+                        pushed = Pushed::NeedsKeyframe;
+                    }
+                    if is_video && keyframe.is_none() {
+                        self.count_dropped(track, 1);
+                        return pushed;
+                    }
+                    if state.samples.len() >= self.config.capacity.max(1)
+                        && let Some(oldest) = state.samples.pop_front()
+                    {
+                        self.count_dropped(oldest.track, 1);
+                    }
+                }
+            }
+        }
+
+        state.samples.push_back(QueuedSample { track, sample });
+        drop(state);
+        self.notify.notify_one();
+        pushed
+    }
+
+    async fn pop(&self) -> Option<QueuedSample> {
+        loop {
+            if self.is_closed.load(Ordering::Relaxed) {
+                return None;
+            }
+            if let Ok(mut state) = self.state.lock()
+                && let Some(queued) = state.samples.pop_front()
+            {
+                if state.samples.is_empty() {
+                    state.lagging = false;
+                }
+                return Some(queued);
+            }
+            self.notify.notified().await;
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.state
+            .lock()
+            .map(|state| state.samples.len())
+            .unwrap_or_default()
+    }
+
+    fn count_dropped(&self, track: Track, count: u64) {
+        let dropped = match track {
+            Track::Audio => &self.dropped_audio,
+            Track::Video => &self.dropped_video,
+        };
+        dropped.fetch_add(count, Ordering::Relaxed);
+    }
+
+    fn close(&self) {
+        self.is_closed.store(true, Ordering::Relaxed);
+        self.notify.notify_one();
+    }
+}
+
+/// Feed queued samples to the mirror until either side closes, passing the
+/// keyframe requests of the mirror on to the sources and its status changes
+/// on to the subscribers meanwhile.
+async fn drive_mirror(
+    mirror: Arc<dyn Mirror + Send + Sync>,
+    queue: Arc<MirrorQueue>,
+    hub: Arc<HubInner>,
+) {
+    let samples = async {
+        while let Some(mut queued) = queue.pop().await {
+            let result = match queued.track {
+                Track::Audio => mirror.write_audio_sample(&mut queued.sample).await,
+                Track::Video => mirror.write_video_sample(&mut queued.sample).await,
+            };
+            if let Err(err) = result {
+                queue.write_errors.fetch_add(1, Ordering::Relaxed);
+                hub.notify(Notification::mirror_failed(queue.id, &err));
+                queue.close();
+                break;
+            }
+            queue.written.fetch_add(1, Ordering::Relaxed);
+        }
+    };
+    let keyframe_requests = async {
+        loop {
+            mirror.read_keyframe_request().await;
+            debug!("[Hub] mirror {} requested a keyframe", queue.id);
+            hub.request_keyframe().await;
+        }
+    };
+    let statuses = async {
+        loop {
+            let status = mirror.read_status().await;
+            hub.notify(Notification::MirrorStatus {
+                id: queue.id,
+                status,
+            });
+        }
+    };
+
+    tokio::select! {
+        _ = samples => {}
+        _ = keyframe_requests => {}
+        _ = statuses => {}
+    }
+}
+
+/// The codecs a live source is sending, as seen in its latest samples.
+#[derive(Clone, Copy, Default)]
+struct SourceCodecs {
+    audio: Option<Codec>,
+    video: Option<Codec>,
+}
+
+struct HubInner {
+    queue_config: QueueConfig,
+    next_id: AtomicU64,
+    sources: RwLock<Vec<Arc<dyn Source + Send + Sync>>>,
+    /// The one source feeding the mirrors, by the order sources were added
+    /// in. Only it may take the hub back to idle, so a source whose publisher
+    /// leaves cannot end the stream of another.
+    live_source: Mutex<Option<usize>>,
+    source_codecs: Mutex<SourceCodecs>,
+    parameter_sets: Mutex<ParameterSets>,
+    mirrors: RwLock<BTreeMap<u64, MirrorEntry>>,
+    rejected: RwLock<HashSet<(u64, Codec)>>,
+    last_keyframe_request: Mutex<Option<Instant>>,
+    audio_frames: AtomicU64,
+    video_frames: AtomicU64,
+    keyframes: AtomicU64,
+    notifications: broadcast::Sender<Notification>,
+}
+
+impl HubInner {
+    fn new(queue_config: QueueConfig, notifications: broadcast::Sender<Notification>) -> Self {
+        Self {
+            queue_config,
+            next_id: AtomicU64::new(0),
+            sources: RwLock::new(Vec::new()),
+            live_source: Mutex::new(None),
+            source_codecs: Mutex::new(SourceCodecs::default()),
+            parameter_sets: Mutex::new(ParameterSets::default()),
+            mirrors: RwLock::new(BTreeMap::new()),
+            rejected: RwLock::new(HashSet::new()),
+            last_keyframe_request: Mutex::new(None),
+            audio_frames: AtomicU64::new(0),
+            video_frames: AtomicU64::new(0),
+            keyframes: AtomicU64::new(0),
+            notifications,
+        }
+    }
+
+    fn notify(&self, notification: Notification) {
+        // Nobody may be listening.
+        let _ = self.notifications.send(notification);
+    }
+
+    fn live_source(&self) -> Option<usize> {
+        self.live_source.lock().ok().and_then(|live| *live)
+    }
+
+    fn set_live_source(&self, index: Option<usize>) {
+        if let Ok(mut live) = self.live_source.lock() {
+            *live = index;
+        }
+    }
+
+    fn is_live_source(&self, index: usize) -> bool {
+        self.live_source() == Some(index)
+    }
+
+    async fn add_source(&self, source: Arc<dyn Source + Send + Sync>) {
+        source.set_accepted_codecs(&self.accepted_codecs().await);
+        self.sources.write().await.push(source);
+    }
+
+    /// The codecs accepted by at least one mirror.
+    async fn accepted_codecs(&self) -> Vec<Codec> {
+        let mut codecs = Vec::new();
+        for entry in self.mirrors.read().await.values() {
+            for &codec in entry.mirror.codecs() {
+                if !codecs.contains(&codec) {
+                    codecs.push(codec);
+                }
+            }
+        }
+        codecs
+    }
+
+    /// Let the sources know what the mirrors accept, for them to negotiate.
+    async fn announce_codecs(&self) {
+        let codecs = self.accepted_codecs().await;
+        for source in self.sources.read().await.iter() {
+            source.set_accepted_codecs(&codecs);
+        }
+    }
+
+    /// Ask the sources for a keyframe, unless they were asked moments ago.
+    async fn request_keyframe(&self) {
+        let is_due = self.last_keyframe_request.lock().is_ok_and(|mut last| {
+            let now = Instant::now();
+            if last.is_some_and(|last| now.duration_since(last) < KEYFRAME_REQUEST_INTERVAL) {
+                return false;
+            }
+            *last = Some(now);
+            true
+        });
+        if !is_due {
+            return;
+        }
+
+        for source in self.sources.read().await.iter() {
+            source.request_keyframe();
+        }
+    }
+
+    async fn view_source(&self, connected: bool) -> SourceDescriptor {
+        let mut stats = SourceStats::default();
+        for source in self.sources.read().await.iter() {
+            let source = source.stats();
+            stats.rtp_packets += source.rtp_packets;
+            stats.rtp_bytes += source.rtp_bytes;
+            stats.dropped_packets += source.dropped_packets;
+            stats.audio_jitter = stats.audio_jitter.max(source.audio_jitter);
+            stats.video_jitter = stats.video_jitter.max(source.video_jitter);
+        }
+        SourceDescriptor {
+            connected,
+            audio_frames: self.audio_frames.load(Ordering::Relaxed),
+            video_frames: self.video_frames.load(Ordering::Relaxed),
+            keyframes: self.keyframes.load(Ordering::Relaxed),
+            stats,
+        }
+    }
+
+    async fn view_mirrors(&self) -> Vec<MirrorDescriptor> {
+        self.mirrors
+            .read()
+            .await
+            .values()
+            .map(|entry| MirrorDescriptor {
+                queued: entry.queue.len(),
+                dropped_audio: entry.queue.dropped_audio.load(Ordering::Relaxed),
+                dropped_video: entry.queue.dropped_video.load(Ordering::Relaxed),
+                written: entry.queue.written.load(Ordering::Relaxed),
+                write_errors: entry.queue.write_errors.load(Ordering::Relaxed),
+                encryption_failures: entry.mirror.encryption_failures(),
+                connected: entry.mirror.is_connected(),
+                ..entry.descriptor.clone()
+            })
+            .collect()
+    }
+
+    async fn add_mirror(
+        self: &Arc<Self>,
+        mirror: Box<dyn Mirror + Send + Sync>,
+        active: bool,
+    ) -> Result<u64, Error<dyn ErrorInner>> {
+        // Audio the mirror does not accept is only dropped, see `accepts`,
+        // as the mirror may still be watched without it.
+        if active
+            && let Some(codec) = self
+                .source_codecs
+                .lock()
+                .ok()
+                .and_then(|codecs| codecs.video)
+            && !mirror.codecs().contains(&codec)
+        {
+            let err = UnsupportedCodec {
+                codec,
+                accepted: mirror.codecs().to_vec(),
+            };
+            warn!(
+                "[Hub] refusing {} {}: {err}",
+                mirror.kind(),
+                mirror.target()
+            );
+            mirror.close();
+            return Err(Error {
+                kind: ErrorType::HubCodec,
+                source: Some(Box::new(err)),
+            });
+        }
+
+        if active && let Err(err) = mirror.call_connected_callback() {
+            warn!(
+                "[Hub] refusing {} {}: {err}",
+                mirror.kind(),
+                mirror.target()
+            );
+            mirror.close();
+            return Err(Error {
+                kind: ErrorType::HubMirror,
+                source: Some(Box::new(err)),
+            });
+        }
+
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let created = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        let descriptor = MirrorDescriptor {
+            id,
+            kind: mirror.kind(),
+            target: mirror.target(),
+            codecs: mirror.codecs().iter().map(ToString::to_string).collect(),
+            state: if active {
+                MirrorState::Live
+            } else {
+                MirrorState::Idle
+            },
+            created,
+            queued: 0,
+            dropped_audio: 0,
+            dropped_video: 0,
+            written: 0,
+            write_errors: 0,
+            encryption_failures: 0,
+            connected: false,
+        };
+        info!(
+            "[Hub] mirror {id} added: {} {}",
+            descriptor.kind, descriptor.target
+        );
+
+        self.notify(Notification::MirrorAdded {
+            id,
+            kind: descriptor.kind,
+            target: descriptor.target.clone(),
+        });
+
+        let mirror: Arc<dyn Mirror + Send + Sync> = Arc::from(mirror);
+        let queue = Arc::new(MirrorQueue::new(id, self.queue_config));
+        tokio::spawn(drive_mirror(mirror.clone(), queue.clone(), self.clone()));
+
+        self.mirrors.write().await.insert(
+            id,
+            MirrorEntry {
+                descriptor,
+                mirror,
+                queue,
+            },
+        );
+        Ok(id)
+    }
+
+    async fn remove_mirror(&self, id: u64) -> bool {
+        let Some(entry) = self.mirrors.write().await.remove(&id) else {
+            return false;
+        };
+        entry.close();
+        self.forget_rejections(id).await;
+        info!("[Hub] mirror {id} removed");
+        self.notify(Notification::MirrorRemoved { id });
+        true
+    }
+
+    async fn write_audio_sample(&self, codec: Codec, payload: &Sample) {
+        self.write_sample(Track::Audio, codec, payload).await;
+    }
+
+    async fn write_video_sample(&self, codec: Codec, payload: &Sample) {
+        self.write_sample(Track::Video, codec, payload).await;
+    }
+
+    async fn write_sample(&self, track: Track, codec: Codec, payload: &Sample) {
+        if let Ok(mut source_codecs) = self.source_codecs.lock() {
+            match track {
+                Track::Audio => source_codecs.audio = Some(codec),
+                Track::Video => source_codecs.video = Some(codec),
+            }
+        }
+        let keyframe = match track {
+            Track::Video => self.parameter_sets.lock().ok().and_then(|mut sets| {
+                sets.update(codec, &payload.data);
+                codec
+                    .is_keyframe(&payload.data)
+                    .then(|| sets.prepend(codec, &payload.data))
+            }),
+            Track::Audio => None,
+        };
+        let frames = match track {
+            Track::Audio => &self.audio_frames,
+            Track::Video => &self.video_frames,
+        };
+        frames.fetch_add(1, Ordering::Relaxed);
+        if keyframe.is_some() {
+            self.keyframes.fetch_add(1, Ordering::Relaxed);
+        }
+
+        let mut closed = Vec::new();
+        let mut needs_keyframe = false;
+        for (&id, entry) in self.mirrors.read().await.iter() {
+            if !self.accepts(id, entry.mirror.as_ref(), codec).await {
+                continue;
+            }
+            let payload = Sample {
+                data: payload.data.clone(),
+                ..*payload
+            };
+            match entry.queue.push(track, keyframe.as_ref(), payload) {
+                Pushed::Queued => {}
+                Pushed::NeedsKeyframe => needs_keyframe = true,
+                Pushed::Closed => closed.push(id),
+            }
+        }
+
+        // The PLI timer of the source may be off, so a mirror that dropped
+        // video would otherwise wait for the encoder's next keyframe.
+        if needs_keyframe {
+            self.request_keyframe().await;
+        }
+
+        if !closed.is_empty() {
+            self.drop_closed(&mut *self.mirrors.write().await, closed)
+                .await;
+            self.announce_codecs().await;
+        }
+    }
+
+    async fn accepts(&self, id: u64, mirror: &(dyn Mirror + Send + Sync), codec: Codec) -> bool {
+        if mirror.codecs().contains(&codec) {
+            return true;
+        }
+
+        // Only the first sample of the codec needs to be written down.
+        if self.rejected.read().await.contains(&(id, codec)) {
+            return false;
+        }
+        if self.rejected.write().await.insert((id, codec)) {
+            warn!("[Hub] mirror {id} does not accept {codec}, dropping its samples");
+        }
+        false
+    }
+
+    async fn forget_rejections(&self, id: u64) {
+        self.rejected
+            .write()
+            .await
+            .retain(|&(rejected, _)| rejected != id);
+    }
+
+    async fn set_state(&self, state: MirrorState) {
+        if state == MirrorState::Idle
+            && let Ok(mut source_codecs) = self.source_codecs.lock()
+        {
+            *source_codecs = SourceCodecs::default();
+        }
+        if state == MirrorState::Idle
+            && let Ok(mut parameter_sets) = self.parameter_sets.lock()
+        {
+            *parameter_sets = ParameterSets::default();
+        }
+        // The next publisher may send other codecs.
+        if state == MirrorState::Idle {
+            self.rejected.write().await.clear();
+        }
+
+        let mut mirrors = self.mirrors.write().await;
+
+        let mut closed = Vec::new();
+        for (&id, entry) in mirrors.iter_mut() {
+            entry.descriptor.state = state;
+            if state != MirrorState::Live {
+                continue;
+            }
+            if let Err(err) = entry.mirror.call_connected_callback() {
+                self.notify(Notification::mirror_failed(id, &err));
+                closed.push(id);
+            }
+        }
+        if !closed.is_empty() {
+            self.drop_closed(&mut mirrors, closed).await;
+            drop(mirrors);
+            self.announce_codecs().await;
+        }
+    }
+
+    async fn drop_closed(&self, mirrors: &mut BTreeMap<u64, MirrorEntry>, closed: Vec<u64>) {
+        for id in closed {
+            if let Some(entry) = mirrors.remove(&id) {
+                entry.close();
+                self.forget_rejections(id).await;
+                warn!("[Hub] mirror {id} closed");
+                self.notify(Notification::MirrorRemoved { id });
+            }
+        }
+    }
+}
+
+/// A mirror was refused for not accepting the video codec of the live
+/// source.
+#[derive(Debug)]
+pub struct UnsupportedCodec {
+    pub codec: Codec,
+    pub accepted: Vec<Codec>,
+}
+
+impl Display for UnsupportedCodec {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(
+            f,
+            "the source sends {}, but the mirror only accepts ",
+            self.codec
+        )?;
+        for (i, codec) in self.accepted.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{codec}")?;
+        }
+        Ok(())
+    }
+}
+
+impl StdError for UnsupportedCodec {}
+
+pub trait ErrorInner: StdError + Send + Sync {}
+
+impl<T: StdError + Send + Sync> ErrorInner for T {}
+
+impl StdError for Error<dyn ErrorInner> {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        self.source
+            .as_ref()
+            .map(|source| &**source as &(dyn StdError + 'static))
+    }
+}
+
+impl From<SendError<HubEvent>> for Error<dyn ErrorInner> {
+    fn from(err: SendError<HubEvent>) -> Self {
+        Self {
+            kind: ErrorType::HubIPC,
+            source: Some(Box::new(err)),
+        }
+    }
+}
+
+impl From<RecvError> for Error<dyn ErrorInner> {
+    fn from(err: RecvError) -> Self {
+        Self {
+            kind: ErrorType::HubIPC,
+            source: Some(Box::new(err)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::{BufMut, BytesMut};
+    use std::{pin::Pin, sync::atomic::AtomicUsize};
+    use tokio::{sync::Mutex as AsyncMutex, time::sleep};
+
+    /// A source sending whatever the test pushes, remembering the codecs it
+    /// was told the mirrors accept and counting the keyframes asked of it.
+    struct TestSource {
+        events: AsyncMutex<mpsc::UnboundedReceiver<SourceEvent>>,
+        accepted: Arc<Mutex<Vec<Codec>>>,
+        keyframe_requests: Arc<AtomicUsize>,
+    }
+
+    impl Source for TestSource {
+        fn read_event<'a>(
+            &'a self,
+        ) -> Pin<Box<dyn Future<Output = Option<SourceEvent>> + Send + 'a>> {
+            Box::pin(async { self.events.lock().await.recv().await })
+        }
+
+        fn set_accepted_codecs(&self, codecs: &[Codec]) {
+            *self.accepted.lock().unwrap() = codecs.to_vec();
+        }
+
+        fn request_keyframe(&self) {
+            self.keyframe_requests.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// A mirror counting the samples it is given and keeping the video ones,
+    /// whose viewers ask for a keyframe whenever the test notifies `pli`.
+    #[derive(Clone, Default)]
+    struct TestMirror {
+        codecs: Vec<Codec>,
+        fails_to_start: bool,
+        /// Writes never complete, as with a mirror that cannot keep up.
+        stalls: bool,
+        pli: Arc<Notify>,
+        audio: Arc<AtomicUsize>,
+        video: Arc<AtomicUsize>,
+        frames: Arc<Mutex<Vec<Bytes>>>,
+        closed: Arc<AtomicBool>,
+    }
+
+    impl TestMirror {
+        fn new(codecs: &[Codec]) -> Self {
+            Self {
+                codecs: codecs.to_vec(),
+                ..Self::default()
+            }
+        }
+
+        fn write(&self) -> Pin<Box<dyn Future<Output = Result<(), Error>> + Send + 'static>> {
+            if self.stalls {
+                return Box::pin(std::future::pending());
+            }
+            Box::pin(async { Ok(()) })
+        }
+    }
+
+    impl Mirror for TestMirror {
+        fn write_audio_sample<'a>(
+            &'a self,
+            _payload: &'a mut Sample,
+        ) -> Pin<Box<dyn Future<Output = Result<(), Error>> + Send + 'a>> {
+            self.audio.fetch_add(1, Ordering::Relaxed);
+            self.write()
+        }
+
+        fn write_video_sample<'a>(
+            &'a self,
+            payload: &'a mut Sample,
+        ) -> Pin<Box<dyn Future<Output = Result<(), Error>> + Send + 'a>> {
+            self.video.fetch_add(1, Ordering::Relaxed);
+            self.frames.lock().unwrap().push(payload.data.clone());
+            self.write()
+        }
+
+        fn call_connected_callback(&self) -> Result<(), Error> {
+            if self.fails_to_start {
+                return Err(Error {
+                    kind: ErrorType::WHEPPeer,
+                    source: None,
+                });
+            }
+            Ok(())
+        }
+
+        fn read_keyframe_request<'a>(&'a self) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+            Box::pin(self.pli.notified())
+        }
+
+        fn kind(&self) -> &'static str {
+            "test"
+        }
+
+        fn target(&self) -> String {
+            String::new()
+        }
+
+        fn codecs(&self) -> &[Codec] {
+            &self.codecs
+        }
+
+        fn close(&self) {
+            self.closed.store(true, Ordering::Relaxed);
+        }
+    }
+
+    struct Setup {
+        hub: Hub,
+        events: mpsc::UnboundedSender<SourceEvent>,
+        accepted: Arc<Mutex<Vec<Codec>>>,
+        keyframe_requests: Arc<AtomicUsize>,
+    }
+
+    async fn setup() -> Setup {
+        setup_with(QueueConfig::default()).await
+    }
+
+    async fn setup_with(config: QueueConfig) -> Setup {
+        let hub = Hub::with_queue_config(config);
+        let (events, events_rx) = mpsc::unbounded_channel();
+        let accepted = Arc::new(Mutex::new(Vec::new()));
+        let keyframe_requests = Arc::new(AtomicUsize::new(0));
+        hub.add_source(TestSource {
+            events: AsyncMutex::new(events_rx),
+            accepted: accepted.clone(),
+            keyframe_requests: keyframe_requests.clone(),
+        })
+        .await
+        .unwrap();
+        Setup {
+            hub,
+            events,
+            accepted,
+            keyframe_requests,
+        }
+    }
+
+    fn sample(data: &'static [u8]) -> Sample {
+        Sample {
+            data: Bytes::from_static(data),
+            duration: Duration::from_millis(20),
+            ..Sample::default()
+        }
+    }
+
+    /// An H.264 IDR slice in Annex B.
+    const H264_KEYFRAME: &[u8] = &[0, 0, 0, 1, 0x65, 0x88, 0x84];
+    /// An H.264 non-IDR slice in Annex B.
+    const H264_DELTA: &[u8] = &[0, 0, 0, 1, 0x41, 0x9a, 0x02];
+
+    /// Ask the hub to take the source sending `events` live.
+    async fn connect(events: &mpsc::UnboundedSender<SourceEvent>) -> bool {
+        let (live_tx, live_rx) = oneshot::channel();
+        events.send(SourceEvent::Connected(live_tx)).unwrap();
+        live_rx.await.unwrap()
+    }
+
+    /// Publish AAC audio with H.264 video and wait for the hub to see both.
+    async fn go_live(setup: &Setup) {
+        assert!(connect(&setup.events).await);
+        setup
+            .events
+            .send(SourceEvent::AudioSample(Codec::AAC, sample(&[0xff, 0xf1])))
+            .unwrap();
+        setup
+            .events
+            .send(SourceEvent::VideoSample(Codec::H264, sample(H264_KEYFRAME)))
+            .unwrap();
+        eventually(async || {
+            let source = setup.hub.view_source().await.unwrap();
+            source.connected && source.audio_frames == 1 && source.video_frames == 1
+        })
+        .await;
+    }
+
+    async fn eventually(mut check: impl AsyncFnMut() -> bool) {
+        for _ in 0..200 {
+            if check().await {
+                return;
+            }
+            sleep(Duration::from_millis(10)).await;
+        }
+        panic!("condition never held");
+    }
+
+    #[tokio::test]
+    async fn sources_are_told_the_accepted_codecs() {
+        let setup = setup().await;
+        setup
+            .hub
+            .add_mirror(TestMirror::new(&[Codec::Opus, Codec::H264]))
+            .await
+            .unwrap();
+        let vp8 = setup
+            .hub
+            .add_mirror(TestMirror::new(&[Codec::Opus, Codec::VP8]))
+            .await
+            .unwrap();
+        assert_eq!(
+            *setup.accepted.lock().unwrap(),
+            [Codec::Opus, Codec::H264, Codec::VP8]
+        );
+
+        setup.hub.remove_mirror(vp8).await.unwrap();
+        assert_eq!(*setup.accepted.lock().unwrap(), [Codec::Opus, Codec::H264]);
+    }
+
+    #[tokio::test]
+    async fn mirrors_without_the_source_audio_codec_are_kept() {
+        let setup = setup().await;
+        go_live(&setup).await;
+
+        let mirror = TestMirror::new(&[Codec::Opus, Codec::H264]);
+        let id = setup.hub.add_mirror(mirror.clone()).await.unwrap();
+        setup
+            .events
+            .send(SourceEvent::AudioSample(Codec::AAC, sample(&[0xff, 0xf1])))
+            .unwrap();
+        setup
+            .events
+            .send(SourceEvent::VideoSample(Codec::H264, sample(H264_KEYFRAME)))
+            .unwrap();
+        eventually(async || mirror.video.load(Ordering::Relaxed) == 1).await;
+
+        assert_eq!(mirror.audio.load(Ordering::Relaxed), 0);
+        assert!(!mirror.closed.load(Ordering::Relaxed));
+        let mirrors = setup.hub.view_mirrors().await.unwrap();
+        assert!(mirrors.iter().any(|mirror| mirror.id == id));
+    }
+
+    #[tokio::test]
+    async fn mirrors_without_the_source_video_codec_are_refused() {
+        let setup = setup().await;
+        go_live(&setup).await;
+
+        let mirror = TestMirror::new(&[Codec::Opus, Codec::VP8]);
+        let err = setup.hub.add_mirror(mirror.clone()).await.unwrap_err();
+        assert!(matches!(err.kind(), ErrorType::HubCodec));
+        assert!(mirror.closed.load(Ordering::Relaxed));
+        assert!(setup.hub.view_mirrors().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn mirrors_failing_to_start_are_refused() {
+        let setup = setup().await;
+        go_live(&setup).await;
+
+        let mirror = TestMirror {
+            fails_to_start: true,
+            ..TestMirror::new(&[Codec::Opus, Codec::H264])
+        };
+        let err = setup.hub.add_mirror(mirror.clone()).await.unwrap_err();
+        assert!(matches!(err.kind(), ErrorType::HubMirror));
+        assert!(mirror.closed.load(Ordering::Relaxed));
+        assert!(setup.hub.view_mirrors().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn idle_sources_cannot_end_the_stream() {
+        let setup = setup().await;
+        let (other, other_rx) = mpsc::unbounded_channel();
+        setup
+            .hub
+            .add_source(TestSource {
+                events: AsyncMutex::new(other_rx),
+                accepted: Arc::default(),
+                keyframe_requests: Arc::default(),
+            })
+            .await
+            .unwrap();
+        go_live(&setup).await;
+
+        other.send(SourceEvent::Disconnected).unwrap();
+        drop(other);
+        sleep(Duration::from_millis(50)).await;
+        assert!(setup.hub.view_source().await.unwrap().connected);
+
+        setup.events.send(SourceEvent::Disconnected).unwrap();
+        eventually(async || !setup.hub.view_source().await.unwrap().connected).await;
+    }
+
+    #[tokio::test]
+    async fn mirrors_joining_mid_stream_ask_for_a_keyframe() {
+        let setup = setup().await;
+        let codecs = [Codec::Opus, Codec::H264];
+        setup
+            .hub
+            .add_mirror(TestMirror::new(&codecs))
+            .await
+            .unwrap();
+        assert_eq!(setup.keyframe_requests.load(Ordering::Relaxed), 0);
+
+        go_live(&setup).await;
+        setup
+            .hub
+            .add_mirror(TestMirror::new(&codecs))
+            .await
+            .unwrap();
+        assert_eq!(setup.keyframe_requests.load(Ordering::Relaxed), 1);
+    }
+
+    #[tokio::test]
+    async fn late_mirrors_start_on_a_keyframe_led_by_the_parameter_sets() {
+        let setup = setup().await;
+        const SPS: &[u8] = &[0x67, 0x42, 0xc0, 0x1f, 0xda, 0x01, 0x40, 0x16, 0xe4];
+        const PPS: &[u8] = &[0x68, 0xce, 0x3c, 0x80];
+        const IDR: &[u8] = &[0x65, 0x88, 0x84];
+        let annexb = |nalus: &[&[u8]]| {
+            let mut data = BytesMut::new();
+            for nalu in nalus {
+                data.put_slice(&[0, 0, 0, 1]);
+                data.put_slice(nalu);
+            }
+            data.freeze()
+        };
+        let send = |data: Bytes| {
+            let payload = Sample {
+                data,
+                duration: Duration::from_millis(33),
+                ..Sample::default()
+            };
+            setup
+                .events
+                .send(SourceEvent::VideoSample(Codec::H264, payload))
+                .unwrap();
+        };
+
+        assert!(connect(&setup.events).await);
+        let codecs = [Codec::Opus, Codec::H264];
+        let early = TestMirror::new(&codecs);
+        setup.hub.add_mirror(early.clone()).await.unwrap();
+
+        // The parameter sets only come with the first keyframe.
+        send(annexb(&[SPS, PPS, IDR]));
+        send(Bytes::from_static(H264_DELTA));
+        eventually(async || early.video.load(Ordering::Relaxed) == 2).await;
+
+        let late = TestMirror::new(&codecs);
+        setup.hub.add_mirror(late.clone()).await.unwrap();
+        send(Bytes::from_static(H264_DELTA));
+        send(Bytes::from_static(H264_DELTA));
+        eventually(async || early.video.load(Ordering::Relaxed) == 4).await;
+        assert!(late.frames.lock().unwrap().is_empty());
+
+        send(annexb(&[IDR]));
+        send(Bytes::from_static(H264_DELTA));
+        eventually(async || late.video.load(Ordering::Relaxed) == 2).await;
+
+        assert_eq!(
+            *late.frames.lock().unwrap(),
+            [annexb(&[SPS, PPS, IDR]), Bytes::from_static(H264_DELTA)]
+        );
+        // Mirrors already decoding get the keyframe as it was sent.
+        assert_eq!(early.frames.lock().unwrap()[4], annexb(&[IDR]));
+    }
+
+    #[tokio::test]
+    async fn keyframe_requests_of_mirrors_are_rate_limited() {
+        let setup = setup().await;
+        go_live(&setup).await;
+        let mirror = TestMirror::new(&[Codec::Opus, Codec::H264]);
+        setup.hub.add_mirror(mirror.clone()).await.unwrap();
+        assert_eq!(setup.keyframe_requests.load(Ordering::Relaxed), 1);
+
+        // Asked right after joining, the source already has a request.
+        mirror.pli.notify_one();
+        sleep(Duration::from_millis(50)).await;
+        assert_eq!(setup.keyframe_requests.load(Ordering::Relaxed), 1);
+
+        sleep(KEYFRAME_REQUEST_INTERVAL).await;
+        mirror.pli.notify_one();
+        eventually(async || setup.keyframe_requests.load(Ordering::Relaxed) == 2).await;
+    }
+
+    #[tokio::test]
+    async fn only_the_live_source_feeds_the_mirrors() {
+        let setup = setup().await;
+        let (other, other_rx) = mpsc::unbounded_channel();
+        setup
+            .hub
+            .add_source(TestSource {
+                events: AsyncMutex::new(other_rx),
+                accepted: Arc::default(),
+                keyframe_requests: Arc::default(),
+            })
+            .await
+            .unwrap();
+        let mirror = TestMirror::new(&[Codec::AAC, Codec::H264]);
+        setup.hub.add_mirror(mirror.clone()).await.unwrap();
+        go_live(&setup).await;
+
+        assert!(!connect(&other).await);
+        other
+            .send(SourceEvent::AudioSample(Codec::AAC, sample(&[0xff, 0xf1])))
+            .unwrap();
+        other
+            .send(SourceEvent::VideoSample(Codec::H264, sample(H264_KEYFRAME)))
+            .unwrap();
+        setup
+            .events
+            .send(SourceEvent::VideoSample(Codec::H264, sample(H264_DELTA)))
+            .unwrap();
+        eventually(async || mirror.video.load(Ordering::Relaxed) == 2).await;
+        sleep(Duration::from_millis(50)).await;
+        assert_eq!(mirror.audio.load(Ordering::Relaxed), 1);
+        assert_eq!(mirror.video.load(Ordering::Relaxed), 2);
+
+        // Once the live source leaves, the other one may go live.
+        setup.events.send(SourceEvent::Disconnected).unwrap();
+        eventually(async || !setup.hub.view_source().await.unwrap().connected).await;
+        assert!(connect(&other).await);
+        assert!(setup.hub.view_source().await.unwrap().connected);
+    }
+
+    #[tokio::test]
+    async fn rejections_are_forgotten_with_the_mirror_or_the_publisher() {
+        let (notifications, _) = broadcast::channel(NOTIFICATION_CAPACITY);
+        let inner = Arc::new(HubInner::new(QueueConfig::default(), notifications));
+        let mirror = TestMirror::new(&[Codec::Opus, Codec::H264]);
+        let id = inner
+            .add_mirror(Box::new(mirror.clone()), false)
+            .await
+            .unwrap();
+
+        assert!(!inner.accepts(id, &mirror, Codec::VP8).await);
+        assert!(!inner.accepts(id, &mirror, Codec::VP8).await);
+        assert_eq!(
+            *inner.rejected.read().await,
+            HashSet::from([(id, Codec::VP8)])
+        );
+        inner.set_state(MirrorState::Idle).await;
+        assert!(inner.rejected.read().await.is_empty());
+
+        assert!(!inner.accepts(id, &mirror, Codec::AV1).await);
+        assert!(inner.remove_mirror(id).await);
+        assert!(inner.rejected.read().await.is_empty());
+    }
+
+    fn queue(capacity: usize, policy: OverflowPolicy) -> MirrorQueue {
+        MirrorQueue::new(0, QueueConfig { capacity, policy })
+    }
+
+    fn tracks(queue: &MirrorQueue) -> Vec<Track> {
+        let state = queue.state.lock().unwrap();
+        state.samples.iter().map(|queued| queued.track).collect()
+    }
+
+    fn dropped(queue: &MirrorQueue) -> (u64, u64) {
+        (
+            queue.dropped_audio.load(Ordering::Relaxed),
+            queue.dropped_video.load(Ordering::Relaxed),
+        )
+    }
+
+    #[test]
+    fn overflowing_queues_drop_the_oldest_sample() {
+        let queue = queue(2, OverflowPolicy::DropOldest);
+        let keyframe = Bytes::from_static(H264_KEYFRAME);
+        queue.push(Track::Video, Some(&keyframe), sample(H264_KEYFRAME));
+        queue.push(Track::Audio, None, sample(&[0xff, 0xf1]));
+
+        let pushed = queue.push(Track::Video, None, sample(H264_DELTA));
+        assert_eq!(pushed, Pushed::Queued);
+        assert_eq!(tracks(&queue), [Track::Audio, Track::Video]);
+        assert_eq!(dropped(&queue), (0, 1));
+
+        queue.push(Track::Audio, None, sample(&[0xff, 0xf1]));
+        assert_eq!(tracks(&queue), [Track::Video, Track::Audio]);
+        assert_eq!(dropped(&queue), (1, 1));
+    }
+
+    #[test]
+    fn overflowing_video_skips_to_the_next_keyframe() {
+        let queue = queue(2, OverflowPolicy::DropToKeyframe);
+        let keyframe = Bytes::from_static(H264_KEYFRAME);
+        queue.push(Track::Video, Some(&keyframe), sample(H264_KEYFRAME));
+        queue.push(Track::Video, None, sample(H264_DELTA));
+
+        let pushed = queue.push(Track::Video, None, sample(H264_DELTA));
+        assert_eq!(pushed, Pushed::NeedsKeyframe);
+        assert!(tracks(&queue).is_empty());
+        assert_eq!(dropped(&queue), (0, 3));
+
+        queue.push(Track::Video, None, sample(H264_DELTA));
+        assert!(tracks(&queue).is_empty());
+        assert_eq!(dropped(&queue), (0, 4));
+
+        let pushed = queue.push(Track::Video, Some(&keyframe), sample(H264_KEYFRAME));
+        assert_eq!(pushed, Pushed::Queued);
+        assert_eq!(tracks(&queue), [Track::Video]);
+    }
+
+    #[test]
+    fn overflowing_audio_skips_video_to_the_next_keyframe() {
+        let queue = queue(3, OverflowPolicy::DropToKeyframe);
+        let keyframe = Bytes::from_static(H264_KEYFRAME);
+        queue.push(Track::Video, Some(&keyframe), sample(H264_KEYFRAME));
+        queue.push(Track::Video, None, sample(H264_DELTA));
+        queue.push(Track::Audio, None, sample(&[0xff, 0xf1]));
+
+        let pushed = queue.push(Track::Audio, None, sample(&[0xff, 0xf1]));
+        assert_eq!(pushed, Pushed::NeedsKeyframe);
+        assert_eq!(tracks(&queue), [Track::Audio, Track::Audio]);
+        assert_eq!(dropped(&queue), (0, 2));
+
+        // Video resumes at the keyframe, not at the delta frame after the hole.
+        queue.push(Track::Video, None, sample(H264_DELTA));
+        assert_eq!(tracks(&queue), [Track::Audio, Track::Audio]);
+        assert_eq!(dropped(&queue), (0, 3));
+        queue.push(Track::Video, Some(&keyframe), sample(H264_KEYFRAME));
+        assert_eq!(tracks(&queue), [Track::Audio, Track::Audio, Track::Video]);
+    }
+
+    #[test]
+    fn overflowing_audio_alone_drops_the_oldest_audio() {
+        let queue = queue(2, OverflowPolicy::DropToKeyframe);
+        for _ in 0..3 {
+            let pushed = queue.push(Track::Audio, None, sample(&[0xff, 0xf1]));
+            assert_eq!(pushed, Pushed::Queued);
+        }
+        assert_eq!(tracks(&queue), [Track::Audio, Track::Audio]);
+        assert_eq!(dropped(&queue), (1, 0));
+    }
+
+    #[tokio::test]
+    async fn mirrors_dropping_video_ask_for_a_keyframe() {
+        let setup = setup_with(QueueConfig {
+            capacity: 2,
+            policy: OverflowPolicy::DropToKeyframe,
+        })
+        .await;
+        let mirror = TestMirror {
+            stalls: true,
+            ..TestMirror::new(&[Codec::AAC, Codec::H264])
+        };
+        setup.hub.add_mirror(mirror).await.unwrap();
+        go_live(&setup).await;
+        assert_eq!(setup.keyframe_requests.load(Ordering::Relaxed), 0);
+
+        for _ in 0..3 {
+            setup
+                .events
+                .send(SourceEvent::VideoSample(Codec::H264, sample(H264_DELTA)))
+                .unwrap();
+        }
+        eventually(async || setup.keyframe_requests.load(Ordering::Relaxed) == 1).await;
+        let mirrors = setup.hub.view_mirrors().await.unwrap();
+        assert!(mirrors[0].dropped_video > 0);
+    }
+}
@@ -1,4 +1,5 @@
 pub mod error;
+pub mod hub;
 pub mod mirrors;
 pub mod sources;
 pub mod utils;
@@ -21,7 +21,10 @@ use tokio_websockets::Message as WebSocketMessage;
 use tracing::warn;
 
 use super::{DAVEInstance, DAVEPayload, Notifier};
-use crate::error::{Error, ErrorType};
+use crate::{
+    error::{Error, ErrorType},
+    mirrors::{MirrorStatus, StatusUpdates},
+};
 
 pub const DAVE_TRANSITION_READY: u8 = 23;
 pub const MLS_EXTERNAL_SENDER: u8 = 25;
@@ -37,6 +40,7 @@ pub async fn handle(
     egress_tx: &mpsc::UnboundedSender<WebSocketMessage>,
     mut dave_rx: mpsc::UnboundedReceiver<DAVEPayload>,
     mut instance_tx: Option<oneshot::Sender<Arc<RwLock<DAVEInstance>>>>,
+    statuses: StatusUpdates,
 ) -> Result<JoinHandle<Result<(), Error<dyn ErrorInner>>>, Error<dyn ErrorInner>> {
     let notifier = notify.clone();
     let egress_tx = egress_tx.clone();
@@ -192,11 +196,16 @@ pub async fn handle(
                     let Some(session) = session else {
                         continue;
                     };
+                    statuses.push(MirrorStatus::Dave {
+                        protocol_version: dave_protocol_version,
+                    });
                     let inst = Arc::new(RwLock::new(DAVEInstance {
                         session,
                         dave_protocol_version,
                         local_audio_track,
                         local_video_track,
+                        encryption_failures: 0,
+                        statuses: statuses.clone(),
                     }));
                     if let Some(instance_tx) = instance_tx.take() {
                         let _ = instance_tx.send(inst.clone());
@@ -256,21 +265,18 @@ pub async fn handle(
                     )
                     .await;
                 }
-                (DAVEPayload::OpCode24(protocol_version, epoch), Some(dave_instance)) => {
-                    if epoch == 1 {
-                        let mut instance = dave_instance.write().await;
-                        dave_protocol_version =
-                            instance.set_dave_protocol_version(protocol_version);
-                        let Ok(_) = reinit_dave_session(
-                            &egress_tx,
-                            Some(&mut instance),
-                            dave_protocol_version,
-                            user_id,
-                            channel_id,
-                        ) else {
-                            break;
-                        };
-                    }
+                (DAVEPayload::OpCode24(protocol_version, 1), Some(dave_instance)) => {
+                    let mut instance = dave_instance.write().await;
+                    dave_protocol_version = instance.set_dave_protocol_version(protocol_version);
+                    let Ok(_) = reinit_dave_session(
+                        &egress_tx,
+                        Some(&mut instance),
+                        dave_protocol_version,
+                        user_id,
+                        channel_id,
+                    ) else {
+                        break;
+                    };
                 }
                 _ => {}
             }
@@ -23,7 +23,10 @@ use webrtc::{
     api::{
         APIBuilder,
         interceptor_registry::register_default_interceptors,
-        media_engine::{MIME_TYPE_H264, MIME_TYPE_OPUS, MediaEngine},
+        media_engine::{
+            MIME_TYPE_AV1, MIME_TYPE_H264, MIME_TYPE_HEVC, MIME_TYPE_OPUS, MIME_TYPE_VP8,
+            MIME_TYPE_VP9, MediaEngine,
+        },
         setting_engine::SettingEngine,
     },
     ice_transport::ice_connection_state::RTCIceConnectionState,
@@ -43,7 +46,11 @@ use webrtc::{
 };
 
 use super::{DAVEPayload, Notifier};
-use crate::error::{Error, ErrorType};
+use crate::{
+    error::{Error, ErrorType},
+    mirrors::{MirrorStatus, StatusUpdates},
+    utils::rtcp::{KeyframeRequests, read_keyframe_requests},
+};
 
 #[allow(clippy::too_many_arguments, clippy::type_complexity)]
 pub async fn handle(
@@ -72,6 +79,8 @@ pub async fn handle(
     nonce_tx: mpsc::UnboundedSender<u64>,
     mut heartbeat_tx: Option<oneshot::Sender<u64>>,
     dave_tx: &mpsc::UnboundedSender<DAVEPayload>,
+    keyframe_requests: &KeyframeRequests,
+    statuses: &StatusUpdates,
 ) -> Result<JoinHandle<Result<(), Error<dyn ErrorInner>>>, Error<dyn ErrorInner>> {
     let uri = format!("wss://{}/?v=9", endpoint);
     let tls = Arc::new(Connector::new()?);
@@ -113,6 +122,8 @@ pub async fn handle(
         video_rtxpayload,
         nego_tx,
         connected_tx,
+        keyframe_requests.clone(),
+        statuses.clone(),
     )
     .await?;
     let mut feed = Some((feed_tx, peer_connection, audio_rtp_sender, video_rtp_sender));
@@ -261,6 +272,7 @@ pub async fn handle(
     }))
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn init_feed(
     audio_payload: u8,
     audio_codec: &str,
@@ -269,6 +281,8 @@ async fn init_feed(
     video_rtxpayload: u8,
     mut nego_tx: Option<oneshot::Sender<()>>,
     mut connected_tx: Option<oneshot::Sender<()>>,
+    keyframe_requests: KeyframeRequests,
+    statuses: StatusUpdates,
 ) -> Result<(Arc<RTCPeerConnection>, Arc<RTCRtpSender>, Arc<RTCRtpSender>), Error<dyn ErrorInner>> {
     let mut m = MediaEngine::default();
     m.register_codec(
@@ -276,6 +290,10 @@ async fn init_feed(
             capability: RTCRtpCodecCapability {
                 mime_type: match video_codec {
                     "H264" => MIME_TYPE_H264.to_owned(),
+                    "H265" => MIME_TYPE_HEVC.to_owned(),
+                    "VP8" => MIME_TYPE_VP8.to_owned(),
+                    "VP9" => MIME_TYPE_VP9.to_owned(),
+                    "AV1" => MIME_TYPE_AV1.to_owned(),
                     _ => format!("video/{video_codec}"),
                 },
                 clock_rate: 90000,
@@ -352,6 +370,9 @@ async fn init_feed(
                 "[WebRTC] ICE connection state changed to: {}",
                 connection_state
             );
+            statuses.push(MirrorStatus::Ice {
+                state: connection_state.to_string(),
+            });
             let (connected_tx, pc) = match connection_state {
                 RTCIceConnectionState::Connected => (connected_tx.take(), None),
                 RTCIceConnectionState::Failed => (None, pc.take()),
@@ -393,13 +414,10 @@ async fn init_feed(
         .add_transceiver_from_kind(RTPCodecType::Video, None)
         .await?;
     let video_rtp_sender = video_rtp_transceiver.sender().await;
-    let sender = video_rtp_sender.clone();
-    tokio::spawn(async move {
-        let mut rtcp_buf = vec![0u8; 1500];
-        while let Ok((_, _)) = sender.read(&mut rtcp_buf).await {}
-        debug!("[WebRTC] video rtp_sender.read loop exit");
-        Ok::<(), ()>(())
-    });
+    tokio::spawn(read_keyframe_requests(
+        video_rtp_sender.clone(),
+        keyframe_requests,
+    ));
 
     Ok((peer_connection, audio_rtp_sender, video_rtp_sender))
 }
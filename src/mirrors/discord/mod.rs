@@ -8,8 +8,8 @@ use std::{
     num::ParseIntError,
     pin::Pin,
     sync::{
-        Arc,
-        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+        atomic::{AtomicBool, AtomicU64, Ordering},
     },
     time::Duration,
 };
@@ -22,7 +22,7 @@ use tokio::{
     time::sleep,
 };
 use tokio_websockets::{Message as WebSocketMessage, Payload};
-use tracing::debug;
+use tracing::{debug, info, warn};
 use twilight_gateway::{Intents, Shard, ShardId};
 use twilight_model::id::{
     Id,
@@ -30,16 +30,24 @@ use twilight_model::id::{
 };
 use uuid::Uuid;
 use webrtc::{
-    api::media_engine::{MIME_TYPE_H264, MIME_TYPE_OPUS},
+    api::media_engine::MIME_TYPE_OPUS,
     media::Sample,
     peer_connection::sdp::{sdp_type::RTCSdpType, session_description::RTCSessionDescription},
     rtp_transceiver::rtp_codec::RTCRtpCodecCapability,
     track::track_local::{TrackLocal, track_local_static_sample::TrackLocalStaticSample},
 };
 
-use super::Mirror;
+use super::{Mirror, MirrorStatus, StatusUpdates};
 use crate::error::{Error, ErrorType};
-use crate::utils::{h264_parser::parse_sps, h264_synthesizer::synthesize_sps};
+use crate::utils::{
+    av1_parser::{OBU_SEQUENCE_HEADER, SequenceHeader, parse_sequence_header, split_obus},
+    codecs::{self, H265_SPS_NALU_TYPE, NALU_TYPE_BITMASK, SPS_NALU_TYPE, split_annexb},
+    h264_parser::{Level, Sps, parse_sps},
+    h264_synthesizer::synthesize_sps,
+    h265_parser,
+    rtcp::KeyframeRequests,
+    vpx_parser::{parse_vp8_frame_header, parse_vp9_frame_header},
+};
 
 mod dave;
 mod endpoint;
@@ -51,10 +59,23 @@ const START_CODE_HIGHEST_POSSIBLE_VALUE: u8 = 1;
 const START_CODE_END_BYTE_VALUE: u8 = 1;
 const START_CODE_LEADING_BYTES_VALUE: u8 = 0;
 
+/// How long the source is measured for before its bitrate is updated.
+const PROBE_WINDOW: Duration = Duration::from_secs(5);
+const PROBE_BITRATE_STEP: u64 = 100_000;
+
+const RECONNECT_ATTEMPTS: u32 = 10;
+const RECONNECT_BACKOFF_MIN: Duration = Duration::from_secs(1);
+const RECONNECT_BACKOFF_MAX: Duration = Duration::from_secs(60);
+
+#[derive(Clone)]
 pub struct DiscordLiveBuilder {
     token: Box<str>,
     guild_id: Id<GuildMarker>,
     channel_id: Id<ChannelMarker>,
+    resolution: Option<(u32, u32)>,
+    framerate: Option<u32>,
+    bitrate: Option<u64>,
+    codec: codecs::Codec,
 }
 
 impl DiscordLiveBuilder {
@@ -63,13 +84,124 @@ impl DiscordLiveBuilder {
             token: token.as_ref().into(),
             guild_id: Id::new(guild_id),
             channel_id: Id::new(channel_id),
+            resolution: None,
+            framerate: None,
+            bitrate: None,
+            codec: codecs::Codec::H264,
+        }
+    }
+
+    /// Announce this resolution instead of the one found in the source's SPS.
+    pub fn resolution(mut self, width: u32, height: u32) -> Self {
+        self.resolution = Some((width, height));
+        self
+    }
+
+    /// Announce this framerate instead of the one found in the source's VUI
+    /// or measured from its samples.
+    pub fn framerate(mut self, framerate: u32) -> Self {
+        self.framerate = Some(framerate);
+        self
+    }
+
+    /// Announce this bitrate, in bits per second, instead of the peak bitrate
+    /// measured from the source.
+    pub fn bitrate(mut self, bitrate: u64) -> Self {
+        self.bitrate = Some(bitrate);
+        self
+    }
+
+    /// Stream video encoded with this codec, either H264, the default, H265,
+    /// VP8, VP9 or AV1. The mirror only receives samples from sources sending
+    /// it. Fails for any other codec.
+    pub fn codec(mut self, codec: codecs::Codec) -> Result<Self, Error<dyn ErrorInner>> {
+        if !matches!(
+            codec,
+            codecs::Codec::H264
+                | codecs::Codec::H265
+                | codecs::Codec::VP8
+                | codecs::Codec::VP9
+                | codecs::Codec::AV1
+        ) {
+            return Err(Error {
+                kind: ErrorType::DiscordCodec,
+                source: None,
+            });
         }
+        self.codec = codec;
+        Ok(self)
     }
 
+    fn parameters(&self, probe: &SourceProbe) -> StreamParameters {
+        let default = StreamParameters::default();
+        let default_profile_level_id = match self.codec {
+            // Main profile, main tier, level 3.1.
+            codecs::Codec::H265 => 0x01005d,
+            // Main profile, main tier, level 3.1.
+            codecs::Codec::AV1 => 0x000005,
+            _ => default.profile_level_id,
+        };
+        let (width, height) = self
+            .resolution
+            .or(probe.resolution)
+            .unwrap_or((default.width, default.height));
+        StreamParameters {
+            width,
+            height,
+            framerate: self
+                .framerate
+                .or(probe.framerate)
+                .unwrap_or(default.framerate),
+            bitrate: self.bitrate.or(probe.bitrate).unwrap_or(default.bitrate),
+            profile_level_id: probe.profile_level_id.unwrap_or(default_profile_level_id),
+        }
+    }
+
+    /// Join the channel and start streaming. Should the connection drop later
+    /// on, the mirror reconnects on its own and reports its attempts on
+    /// `trace_tx`.
     pub async fn connect(
         self,
         trace_tx: Option<mpsc::UnboundedSender<DiscordLiveBuilderState>>,
     ) -> Result<DiscordLive, Error<dyn ErrorInner>> {
+        let target = format!("{}/{}", self.guild_id, self.channel_id);
+        let probe = SourceProbe::default();
+        let parameters = self.parameters(&probe);
+        let keyframe_requests = KeyframeRequests::default();
+        let statuses = StatusUpdates::default();
+        let session = self
+            .handshake(parameters, &keyframe_requests, &statuses, &trace_tx)
+            .await?;
+
+        let inner = Arc::new(DiscordLiveInner {
+            builder: self,
+            session: RwLock::new(session),
+            probe: Mutex::new(SourceProbe {
+                announced: parameters,
+                ..probe
+            }),
+            is_live: AtomicBool::new(false),
+            is_closed: AtomicBool::new(false),
+            keyframe_requests,
+            statuses,
+            encryption_failures: AtomicU64::new(0),
+        });
+        tokio::spawn(supervise(inner.clone(), trace_tx));
+
+        Ok(DiscordLive {
+            target,
+            codecs: [codecs::Codec::Opus, inner.builder.codec],
+            inner,
+        })
+    }
+
+    async fn handshake(
+        &self,
+        parameters: StreamParameters,
+        keyframe_requests: &KeyframeRequests,
+        statuses: &StatusUpdates,
+        trace_tx: &Option<mpsc::UnboundedSender<DiscordLiveBuilderState>>,
+    ) -> Result<Session, Error<dyn ErrorInner>> {
         let _ = rustls::crypto::ring::default_provider().install_default();
 
         if self.token.len() < 4 {
@@ -121,7 +253,14 @@ impl DiscordLiveBuilder {
         let mut audio_mid: u8 = 0;
         let mut audio_ssrc: u32 = 0;
         let video_payload = 102;
-        let video_codec = "H264";
+        let video_codec = match self.codec {
+            codecs::Codec::H265 => "H265",
+            codecs::Codec::VP8 => "VP8",
+            codecs::Codec::VP9 => "VP9",
+            codecs::Codec::AV1 => "AV1",
+            _ => "H264",
+        };
+        let video_mime_type = self.codec.mime_type();
         let video_rtxpayload = 103;
         let mut video_mid: u8 = 1;
         let mut video_ssrc: u32 = 0;
@@ -129,7 +268,8 @@ impl DiscordLiveBuilder {
 
         let notify = Arc::new(Notifier::new());
 
-        if let Err(e) = gateway::handle(&notify, self, shard, voice_tx, rtcsrv_tx, wsconn_tx).await
+        if let Err(e) =
+            gateway::handle(&notify, self.clone(), shard, voice_tx, rtcsrv_tx, wsconn_tx).await
         {
             notify.close();
             return Err(Error {
@@ -172,6 +312,8 @@ impl DiscordLiveBuilder {
             nonce_tx,
             heartbeat_tx,
             &dave_tx,
+            keyframe_requests,
+            statuses,
         )
         .await
         {
@@ -200,7 +342,9 @@ impl DiscordLiveBuilder {
             .as_ref()
             .map(|tx| tx.send(DiscordLiveBuilderState::EndpointRTCNegotiation));
         nego_rx.await?;
-        if let Err(e) = dave::handle(&notify, &egress_tx, dave_rx, instance_tx).await {
+        if let Err(e) =
+            dave::handle(&notify, &egress_tx, dave_rx, instance_tx, statuses.clone()).await
+        {
             notify.close();
             return Err(Error {
                 kind: e.kind,
@@ -217,6 +361,8 @@ impl DiscordLiveBuilder {
             source: None,
         })?;
 
+        let video_fmtp = video_fmtp(self.codec, &parameters);
+
         let sdp = local_desc.unmarshal()?;
         let mut attributes = HashSet::new();
         for attribute in sdp.attributes {
@@ -349,7 +495,7 @@ impl DiscordLiveBuilder {
         let remote_sdp = format!(
             "v=0\r\no=- 1420070400000 0 IN IP4 127.0.0.1\r\ns=-\r\nt=0 0\r\na=msid-semantic: WMS *\r\na=group:BUNDLE 0 1\r\n\
             m=audio {port} UDP/TLS/RTP/SAVPF {audio_payload}\r\na=rtpmap:{audio_payload} {audio_codec}/48000/2\r\na=fmtp:{audio_payload} minptime=10;useinbandfec=1;usedtx=0\r\na=rtcp-fb:{audio_payload} transport-cc\r\na=extmap:1 urn:ietf:params:rtp-hdrext:ssrc-audio-level\r\na=extmap:3 http://www.ietf.org/id/draft-holmer-rmcat-transport-wide-cc-extensions-01\r\na=setup:{setup}\r\na=mid:{audio_mid}\r\na=maxptime:60\r\na={direction}\r\na=rtcp-mux\r\n\
-            m=video {port} UDP/TLS/RTP/SAVPF {video_payload} {video_rtxpayload}\r\na=rtpmap:{video_payload} {video_codec}/90000\r\na=rtpmap:{video_rtxpayload} rtx/90000\r\na=fmtp:{video_payload} x-google-max-bitrate=2500;level-asymmetry-allowed=1;packetization-mode=1;profile-level-id=42e01f\r\na=fmtp:{video_rtxpayload} apt={video_payload}\r\na=rtcp-fb:{video_payload} ccm fir\r\na=rtcp-fb:{video_payload} nack\r\na=rtcp-fb:{video_payload} nack pli\r\na=rtcp-fb:{video_payload} goog-remb\r\na=rtcp-fb:{video_payload} transport-cc\r\na=extmap:2 http://www.webrtc.org/experiments/rtp-hdrext/abs-send-time\r\na=extmap:3 http://www.ietf.org/id/draft-holmer-rmcat-transport-wide-cc-extensions-01\r\na=extmap:14 urn:ietf:params:rtp-hdrext:toffset\r\na=extmap:13 urn:3gpp:video-orientation\r\na=extmap:5 http://www.webrtc.org/experiments/rtp-hdrext/playout-delay\r\na=setup:{setup}\r\na=mid:{video_mid}\r\na={direction}\r\na=rtcp-mux\r\n"
+            m=video {port} UDP/TLS/RTP/SAVPF {video_payload} {video_rtxpayload}\r\na=rtpmap:{video_payload} {video_codec}/90000\r\na=rtpmap:{video_rtxpayload} rtx/90000\r\na=fmtp:{video_payload} {video_fmtp}\r\na=fmtp:{video_rtxpayload} apt={video_payload}\r\na=rtcp-fb:{video_payload} ccm fir\r\na=rtcp-fb:{video_payload} nack\r\na=rtcp-fb:{video_payload} nack pli\r\na=rtcp-fb:{video_payload} goog-remb\r\na=rtcp-fb:{video_payload} transport-cc\r\na=extmap:2 http://www.webrtc.org/experiments/rtp-hdrext/abs-send-time\r\na=extmap:3 http://www.ietf.org/id/draft-holmer-rmcat-transport-wide-cc-extensions-01\r\na=extmap:14 urn:ietf:params:rtp-hdrext:toffset\r\na=extmap:13 urn:3gpp:video-orientation\r\na=extmap:5 http://www.webrtc.org/experiments/rtp-hdrext/playout-delay\r\na=setup:{setup}\r\na=mid:{video_mid}\r\na={direction}\r\na=rtcp-mux\r\n"
         );
         answer.sdp = remote_sdp;
 
@@ -367,7 +513,7 @@ impl DiscordLiveBuilder {
         let remote_sdp = format!(
             "v=0\r\no=- 1420070400000 0 IN IP4 127.0.0.1\r\ns=-\r\nt=0 0\r\na=msid-semantic: WMS *\r\na=group:BUNDLE 0 1\r\n\
             m=audio {port} UDP/TLS/RTP/SAVPF {audio_payload}\r\na=rtpmap:{audio_payload} {audio_codec}/48000/2\r\na=fmtp:{audio_payload} minptime=10;useinbandfec=1;usedtx=0\r\na=rtcp-fb:{audio_payload} transport-cc\r\na=extmap:1 urn:ietf:params:rtp-hdrext:ssrc-audio-level\r\na=extmap:3 http://www.ietf.org/id/draft-holmer-rmcat-transport-wide-cc-extensions-01\r\na=setup:{setup}\r\na=mid:{audio_mid}\r\na=maxptime:60\r\na={direction}\r\na=rtcp-mux\r\n\
-            m=video {port} UDP/TLS/RTP/SAVPF {video_payload} {video_rtxpayload}\r\na=rtpmap:{video_payload} {video_codec}/90000\r\na=rtpmap:{video_rtxpayload} rtx/90000\r\na=fmtp:{video_payload} x-google-max-bitrate=2500;level-asymmetry-allowed=1;packetization-mode=1;profile-level-id=42e01f\r\na=fmtp:{video_rtxpayload} apt={video_payload}\r\na=rtcp-fb:{video_payload} ccm fir\r\na=rtcp-fb:{video_payload} nack\r\na=rtcp-fb:{video_payload} nack pli\r\na=rtcp-fb:{video_payload} goog-remb\r\na=rtcp-fb:{video_payload} transport-cc\r\na=extmap:2 http://www.webrtc.org/experiments/rtp-hdrext/abs-send-time\r\na=extmap:3 http://www.ietf.org/id/draft-holmer-rmcat-transport-wide-cc-extensions-01\r\na=extmap:14 urn:ietf:params:rtp-hdrext:toffset\r\na=extmap:13 urn:3gpp:video-orientation\r\na=extmap:5 http://www.webrtc.org/experiments/rtp-hdrext/playout-delay\r\na=setup:{setup}\r\na=mid:{video_mid}\r\na={direction}\r\na=rtcp-mux\r\n"
+            m=video {port} UDP/TLS/RTP/SAVPF {video_payload} {video_rtxpayload}\r\na=rtpmap:{video_payload} {video_codec}/90000\r\na=rtpmap:{video_rtxpayload} rtx/90000\r\na=fmtp:{video_payload} {video_fmtp}\r\na=fmtp:{video_rtxpayload} apt={video_payload}\r\na=rtcp-fb:{video_payload} ccm fir\r\na=rtcp-fb:{video_payload} nack\r\na=rtcp-fb:{video_payload} nack pli\r\na=rtcp-fb:{video_payload} goog-remb\r\na=rtcp-fb:{video_payload} transport-cc\r\na=extmap:2 http://www.webrtc.org/experiments/rtp-hdrext/abs-send-time\r\na=extmap:3 http://www.ietf.org/id/draft-holmer-rmcat-transport-wide-cc-extensions-01\r\na=extmap:14 urn:ietf:params:rtp-hdrext:toffset\r\na=extmap:13 urn:3gpp:video-orientation\r\na=extmap:5 http://www.webrtc.org/experiments/rtp-hdrext/playout-delay\r\na=setup:{setup}\r\na=mid:{video_mid}\r\na={direction}\r\na=rtcp-mux\r\n"
         );
         answer.sdp = remote_sdp;
 
@@ -406,7 +552,7 @@ impl DiscordLiveBuilder {
 
         let local_video_track = Arc::new(TrackLocalStaticSample::new(
             RTCRtpCodecCapability {
-                mime_type: MIME_TYPE_H264.to_owned(),
+                mime_type: video_mime_type.to_owned(),
                 ..Default::default()
             },
             "video".to_owned(),
@@ -443,54 +589,8 @@ impl DiscordLiveBuilder {
         });
         egress_tx.send(WebSocketMessage::text(payload.to_string()))?;
 
-        let payload = json!({
-            "op": 12,
-            "d": {
-                "audio_ssrc": audio_ssrc,
-                "video_ssrc": video_ssrc,
-                "rtx_ssrc": video_rtxssrc,
-                "streams": [{
-                    "type": "video",
-                    "rid": "100",
-                    "ssrc": video_ssrc,
-                    "active": true,
-                    "quality": 100,
-                    "rtx_ssrc": video_rtxssrc,
-                    "max_bitrate": 3500000,
-                    "max_framerate": 30,
-                    "max_resolution": {
-                        "type": "fixed",
-                        "width": 1280,
-                        "height": 720
-                    }
-                }]
-            }
-        });
-        let active = payload.to_string();
-        let payload = json!({
-            "op": 12,
-            "d": {
-                "audio_ssrc": 0,
-                "video_ssrc": streams[0].ssrc,
-                "rtx_ssrc": streams[0].rtx_ssrc,
-                "streams": [{
-                    "type": "video",
-                    "rid": "100",
-                    "ssrc": streams[0].ssrc,
-                    "active": false,
-                    "quality": 100,
-                    "rtx_ssrc": streams[0].rtx_ssrc,
-                    "max_bitrate": 3500000,
-                    "max_framerate": 30,
-                    "max_resolution": {
-                        "type": "fixed",
-                        "width": 1280,
-                        "height": 720
-                    }
-                }]
-            }
-        });
-        let inactive = payload.to_string();
+        let inactive =
+            video_payload_op12(0, streams[0].ssrc, streams[0].rtx_ssrc, false, &parameters);
         egress_tx.send(WebSocketMessage::text(inactive))?;
 
         let instance_lock = dave_instance.clone();
@@ -530,7 +630,7 @@ impl DiscordLiveBuilder {
 
                 let local_video_track = Arc::new(TrackLocalStaticSample::new(
                     RTCRtpCodecCapability {
-                        mime_type: MIME_TYPE_H264.to_owned(),
+                        mime_type: video_mime_type.to_owned(),
                         ..Default::default()
                     },
                     "video".to_owned(),
@@ -560,9 +660,11 @@ impl DiscordLiveBuilder {
             }
         });
 
-        Ok(DiscordLive {
+        Ok(Session {
             notify,
-            active,
+            audio_ssrc,
+            video_ssrc,
+            video_rtxssrc,
             dave_instance,
             egress_tx,
         })
@@ -570,33 +672,353 @@ impl DiscordLiveBuilder {
 }
 
 pub struct DiscordLive {
+    target: String,
+    codecs: [codecs::Codec; 2],
+    inner: Arc<DiscordLiveInner>,
+}
+
+struct DiscordLiveInner {
+    builder: DiscordLiveBuilder,
+    session: RwLock<Session>,
+    probe: Mutex<SourceProbe>,
+    /// The source went live at some point, new sessions have to activate the
+    /// stream right away.
+    is_live: AtomicBool,
+    is_closed: AtomicBool,
+    /// Outlives the sessions, so requests keep coming through reconnects.
+    keyframe_requests: KeyframeRequests,
+    statuses: StatusUpdates,
+    encryption_failures: AtomicU64,
+}
+
+/// One connection to the gateway and the live stream endpoint.
+struct Session {
     notify: Arc<Notifier>,
-    active: String,
+    audio_ssrc: u32,
+    video_ssrc: u32,
+    video_rtxssrc: u32,
     dave_instance: Arc<RwLock<DAVEInstance>>,
     egress_tx: mpsc::UnboundedSender<WebSocketMessage>,
 }
 
+impl Session {
+    fn activate(&self, parameters: &StreamParameters) -> Result<(), SendError<WebSocketMessage>> {
+        let payload = video_payload_op12(
+            self.audio_ssrc,
+            self.video_ssrc,
+            self.video_rtxssrc,
+            true,
+            parameters,
+        );
+        self.egress_tx.send(WebSocketMessage::text(payload))
+    }
+}
+
+impl DiscordLiveInner {
+    fn parameters(&self) -> StreamParameters {
+        self.probe
+            .lock()
+            .map(|probe| probe.announced)
+            .unwrap_or_default()
+    }
+
+    /// Learn about the source from a video sample. Returns the parameters to
+    /// announce when they changed.
+    fn observe(&self, payload: &Sample) -> Option<StreamParameters> {
+        let mut probe = self.probe.lock().ok()?;
+        probe.push_video(self.builder.codec, payload);
+        let parameters = self.builder.parameters(&probe);
+        if parameters == probe.announced {
+            return None;
+        }
+        debug!("[Discord] announcing {parameters:?}");
+        probe.announced = parameters;
+        Some(parameters)
+    }
+
+    /// Move the failures of the last write over to the running count.
+    fn count_encryption_failures(&self, dave_instance: &mut DAVEInstance) {
+        let failures = std::mem::take(&mut dave_instance.encryption_failures);
+        self.encryption_failures
+            .fetch_add(failures, Ordering::Relaxed);
+    }
+}
+
+/// What the live stream is announced as.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct StreamParameters {
+    width: u32,
+    height: u32,
+    framerate: u32,
+    /// Bits per second.
+    bitrate: u64,
+    /// `profile_idc`, the constraint flags and `level_idc`, as in RFC 6184.
+    /// For H265, `general_profile_idc`, `general_tier_flag` and
+    /// `general_level_idc` packed the same way, and for AV1, `seq_profile`,
+    /// `seq_tier` and `seq_level_idx`.
+    profile_level_id: u32,
+}
+
+impl Default for StreamParameters {
+    /// Used until the source tells otherwise.
+    fn default() -> Self {
+        Self {
+            width: 1280,
+            height: 720,
+            framerate: 30,
+            bitrate: 3_500_000,
+            profile_level_id: 0x42e01f,
+        }
+    }
+}
+
+/// Collects the stream parameters of the source from its video samples.
+#[derive(Default)]
+struct SourceProbe {
+    resolution: Option<(u32, u32)>,
+    /// From the VUI timing info, or measured when the SPS lacks it.
+    framerate: Option<u32>,
+    vui_framerate: bool,
+    /// Peak bitrate over a measurement window, rounded up.
+    bitrate: Option<u64>,
+    profile_level_id: Option<u32>,
+    window_bytes: u64,
+    window_frames: u32,
+    window_duration: Duration,
+    announced: StreamParameters,
+}
+
+impl SourceProbe {
+    fn push_video(&mut self, codec: codecs::Codec, payload: &Sample) {
+        match codec {
+            // VP8 and VP9 keyframes carry their size in the frame header.
+            codecs::Codec::VP8 => {
+                if let Ok(header) = parse_vp8_frame_header(&payload.data)
+                    && header.key_frame
+                {
+                    self.resolution = Some((header.width.into(), header.height.into()));
+                }
+            }
+            codecs::Codec::VP9 => {
+                if let Ok(header) = parse_vp9_frame_header(&payload.data)
+                    && header.key_frame
+                {
+                    self.resolution = Some((header.width, header.height));
+                }
+            }
+            codecs::Codec::AV1 => {
+                for obu in split_obus(&payload.data) {
+                    if obu.obu_type == OBU_SEQUENCE_HEADER
+                        && let Ok((sequence_header, _)) = parse_sequence_header(&obu.payload)
+                    {
+                        self.push_av1_sequence_header(&sequence_header);
+                    }
+                }
+            }
+            _ if codec.is_keyframe(&payload.data) => {
+                for nalu in split_annexb(&payload.data) {
+                    match codec {
+                        codecs::Codec::H265 => {
+                            if nalu.first().map(|b| codecs::h265_nalu_type(*b))
+                                == Some(H265_SPS_NALU_TYPE)
+                                && let Some(rbsp) = nalu.get(2..)
+                                && let Ok((sps, _)) = h265_parser::parse_sps(rbsp)
+                            {
+                                self.push_h265_sps(&sps);
+                            }
+                        }
+                        _ => {
+                            if nalu.first().map(|b| b & NALU_TYPE_BITMASK) == Some(SPS_NALU_TYPE)
+                                && let Ok((sps, _)) = parse_sps(&nalu[1..])
+                            {
+                                self.push_sps(&sps);
+                            }
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        self.window_bytes += payload.data.len() as u64;
+        self.window_frames += 1;
+        self.window_duration += payload.duration;
+        if self.window_duration < PROBE_WINDOW {
+            return;
+        }
+
+        let secs = self.window_duration.as_secs_f64();
+        let bitrate = (self.window_bytes * 8) as f64 / secs;
+        let bitrate = (bitrate / PROBE_BITRATE_STEP as f64).ceil() as u64 * PROBE_BITRATE_STEP;
+        self.bitrate = Some(self.bitrate.unwrap_or_default().max(bitrate));
+        if !self.vui_framerate {
+            self.framerate = Some((self.window_frames as f64 / secs).round() as u32);
+        }
+        self.window_bytes = 0;
+        self.window_frames = 0;
+        self.window_duration = Duration::ZERO;
+    }
+
+    fn push_sps(&mut self, sps: &Sps) {
+        let size = sps.visible_rectangle().max;
+        self.resolution = Some((size.x, size.y));
+
+        let constraints = [
+            sps.constraint_set0_flag,
+            sps.constraint_set1_flag,
+            sps.constraint_set2_flag,
+            sps.constraint_set3_flag,
+            sps.constraint_set4_flag,
+            sps.constraint_set5_flag,
+        ]
+        .iter()
+        .enumerate()
+        .fold(0, |flags, (i, &set)| flags | (u32::from(set) << (7 - i)));
+        let level = match sps.level_idc {
+            Level::L1B => 11,
+            level => level as u32,
+        };
+        self.profile_level_id =
+            Some((u32::from(sps.profile_idc) << 16) | (constraints << 8) | level);
+
+        let vui = &sps.vui_parameters;
+        if sps.vui_parameters_present_flag
+            && vui.timing_info_present_flag
+            && vui.num_units_in_tick > 0
+        {
+            let framerate = vui.time_scale as f64 / (2 * vui.num_units_in_tick) as f64;
+            self.framerate = Some(framerate.round() as u32);
+            self.vui_framerate = true;
+        }
+    }
+
+    fn push_h265_sps(&mut self, sps: &h265_parser::Sps) {
+        let size = sps.visible_rectangle().max;
+        self.resolution = Some((size.x, size.y));
+
+        let ptl = &sps.profile_tier_level;
+        self.profile_level_id = Some(
+            (u32::from(ptl.general_profile_idc) << 16)
+                | (u32::from(ptl.general_tier_flag) << 8)
+                | u32::from(ptl.general_level_idc),
+        );
+
+        // Unlike H264, a clock tick spans a whole picture.
+        let vui = &sps.vui_parameters;
+        if sps.vui_parameters_present_flag
+            && vui.timing_info_present_flag
+            && vui.num_units_in_tick > 0
+        {
+            let framerate = vui.time_scale as f64 / vui.num_units_in_tick as f64;
+            self.framerate = Some(framerate.round() as u32);
+            self.vui_framerate = true;
+        }
+    }
+
+    fn push_av1_sequence_header(&mut self, sequence_header: &SequenceHeader) {
+        self.resolution = Some((sequence_header.width(), sequence_header.height()));
+        self.profile_level_id = Some(
+            (u32::from(sequence_header.seq_profile) << 16)
+                | (u32::from(sequence_header.seq_tier) << 8)
+                | u32::from(sequence_header.seq_level_idx),
+        );
+
+        // Only a constant picture interval gives the framerate away.
+        if sequence_header.timing_info_present_flag
+            && sequence_header.equal_picture_interval
+            && sequence_header.num_units_in_display_tick > 0
+        {
+            let ticks_per_picture = u64::from(sequence_header.num_ticks_per_picture_minus_1) + 1;
+            let framerate = sequence_header.time_scale as f64
+                / (u64::from(sequence_header.num_units_in_display_tick) * ticks_per_picture) as f64;
+            self.framerate = Some(framerate.round() as u32);
+            self.vui_framerate = true;
+        }
+    }
+}
+
+/// The format parameters of the video codec, as announced in the SDP.
+fn video_fmtp(codec: codecs::Codec, parameters: &StreamParameters) -> String {
+    let max_bitrate = parameters.bitrate / 1000;
+    let profile_level_id = parameters.profile_level_id;
+    match codec {
+        codecs::Codec::H265 => format!(
+            "x-google-max-bitrate={max_bitrate};level-id={};profile-id={};tier-flag={};tx-mode=SRST",
+            profile_level_id & 0xff,
+            profile_level_id >> 16,
+            (profile_level_id >> 8) & 1,
+        ),
+        codecs::Codec::VP8 => format!("x-google-max-bitrate={max_bitrate}"),
+        codecs::Codec::VP9 => format!("x-google-max-bitrate={max_bitrate};profile-id=0"),
+        codecs::Codec::AV1 => format!(
+            "x-google-max-bitrate={max_bitrate};level-idx={};profile={};tier={}",
+            profile_level_id & 0xff,
+            profile_level_id >> 16,
+            (profile_level_id >> 8) & 1,
+        ),
+        _ => format!(
+            "x-google-max-bitrate={max_bitrate};level-asymmetry-allowed=1;packetization-mode=1;profile-level-id={profile_level_id:06x}"
+        ),
+    }
+}
+
+fn video_payload_op12(
+    audio_ssrc: u32,
+    video_ssrc: u32,
+    video_rtxssrc: u32,
+    active: bool,
+    parameters: &StreamParameters,
+) -> String {
+    let payload = json!({
+        "op": 12,
+        "d": {
+            "audio_ssrc": audio_ssrc,
+            "video_ssrc": video_ssrc,
+            "rtx_ssrc": video_rtxssrc,
+            "streams": [{
+                "type": "video",
+                "rid": "100",
+                "ssrc": video_ssrc,
+                "active": active,
+                "quality": 100,
+                "rtx_ssrc": video_rtxssrc,
+                "max_bitrate": parameters.bitrate,
+                "max_framerate": parameters.framerate,
+                "max_resolution": {
+                    "type": "fixed",
+                    "width": parameters.width,
+                    "height": parameters.height
+                }
+            }]
+        }
+    });
+    payload.to_string()
+}
+
 impl Mirror for DiscordLive {
     fn write_audio_sample<'a>(
         &'a self,
         payload: &'a mut Sample,
     ) -> Pin<Box<dyn Future<Output = Result<(), Error>> + Send + 'a>> {
         Box::pin(async {
-            if self.notify.is_closed() {
+            if self.inner.is_closed.load(Ordering::Relaxed) {
                 return Err(Error {
                     kind: ErrorType::DiscordEndpoint,
                     source: None,
                 });
             }
-            self.dave_instance
-                .write()
-                .await
-                .write_audio_sample(payload)
-                .await
-                .map_err(|err| Error {
-                    kind: ErrorType::DiscordEndpoint,
-                    source: Some(err.into()),
-                })
+            let session = self.inner.session.read().await;
+            if session.notify.is_closed() {
+                // Reconnecting, drop the sample.
+                return Ok(());
+            }
+            let mut dave_instance = session.dave_instance.write().await;
+            let result = dave_instance.write_audio_sample(payload).await;
+            self.inner.count_encryption_failures(&mut dave_instance);
+            result.map_err(|err| Error {
+                kind: ErrorType::DiscordEndpoint,
+                source: Some(err.into()),
+            })
         })
     }
 
@@ -605,41 +1027,200 @@ impl Mirror for DiscordLive {
         payload: &'a mut Sample,
     ) -> Pin<Box<dyn Future<Output = Result<(), Error>> + Send + 'a>> {
         Box::pin(async {
-            if self.notify.is_closed() {
+            if self.inner.is_closed.load(Ordering::Relaxed) {
                 return Err(Error {
                     kind: ErrorType::DiscordEndpoint,
                     source: None,
                 });
             }
-            self.dave_instance
-                .write()
-                .await
-                .write_video_sample(payload)
-                .await
-                .map_err(|err| Error {
+            let parameters = self.inner.observe(payload);
+            let session = self.inner.session.read().await;
+            if session.notify.is_closed() {
+                // Reconnecting, drop the sample.
+                return Ok(());
+            }
+            if let Some(parameters) = parameters
+                && self.inner.is_live.load(Ordering::Relaxed)
+            {
+                session.activate(&parameters).map_err(|err| Error {
                     kind: ErrorType::DiscordEndpoint,
                     source: Some(err.into()),
-                })
+                })?;
+            }
+            let mut dave_instance = session.dave_instance.write().await;
+            let result = dave_instance
+                .write_video_sample(payload, self.inner.builder.codec)
+                .await;
+            self.inner.count_encryption_failures(&mut dave_instance);
+            result.map_err(|err| Error {
+                kind: ErrorType::DiscordEndpoint,
+                source: Some(err.into()),
+            })
         })
     }
 
     fn call_connected_callback(&self) -> Result<(), Error> {
-        if self.notify.is_closed() {
+        if self.inner.is_closed.load(Ordering::Relaxed) {
             return Err(Error {
                 kind: ErrorType::DiscordEndpoint,
                 source: None,
             });
         }
-        self.egress_tx
-            .send(WebSocketMessage::text(self.active.clone()))
+        self.inner.is_live.store(true, Ordering::SeqCst);
+        // The supervisor only takes the write lock to swap in a new session,
+        // and activates it once the lock is released if the stream went live
+        // by then. While it reconnects, the session here is the closed one it
+        // is replacing, so both cases are left to it.
+        let Ok(session) = self.inner.session.try_read() else {
+            return Ok(());
+        };
+        if session.notify.is_closed() {
+            return Ok(());
+        }
+        session
+            .activate(&self.inner.parameters())
             .map_err(|err| Error {
                 kind: ErrorType::DiscordEndpoint,
                 source: Some(err.into()),
             })
     }
 
+    fn kind(&self) -> &'static str {
+        "discord"
+    }
+
+    fn target(&self) -> String {
+        self.target.clone()
+    }
+
+    fn codecs(&self) -> &[codecs::Codec] {
+        &self.codecs
+    }
+
+    fn read_keyframe_request<'a>(&'a self) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+        Box::pin(self.inner.keyframe_requests.read())
+    }
+
+    fn encryption_failures(&self) -> u64 {
+        self.inner.encryption_failures.load(Ordering::Relaxed)
+    }
+
+    fn read_status<'a>(&'a self) -> Pin<Box<dyn Future<Output = MirrorStatus> + Send + 'a>> {
+        Box::pin(self.inner.statuses.read())
+    }
+
+    fn is_connected(&self) -> bool {
+        if self.inner.is_closed.load(Ordering::Relaxed) {
+            return false;
+        }
+        // While the supervisor reconnects, the session is the closed one it
+        // is replacing.
+        self.inner
+            .session
+            .try_read()
+            .is_ok_and(|session| !session.notify.is_closed())
+    }
+
     fn close(&self) {
-        self.notify.close()
+        self.inner.is_closed.store(true, Ordering::Relaxed);
+        // If the supervisor holds the write lock it is swapping in a new
+        // session, and closes it itself once it sees the flag. While it
+        // reconnects, the session is closed already and the flag stops it
+        // before the next attempt.
+        if let Ok(session) = self.inner.session.try_read() {
+            session.notify.close();
+        }
+    }
+}
+
+/// Wait for the session to drop and replace it with a new one, backing off
+/// between attempts. Gives up after `RECONNECT_ATTEMPTS` failed attempts or
+/// when the token is rejected.
+async fn supervise(
+    inner: Arc<DiscordLiveInner>,
+    trace_tx: Option<mpsc::UnboundedSender<DiscordLiveBuilderState>>,
+) {
+    loop {
+        let notify = inner.session.read().await.notify.clone();
+        notify.wait_closed().await;
+        if inner.is_closed.load(Ordering::Relaxed) {
+            break;
+        }
+        warn!("[Discord] connection lost, reconnecting");
+
+        let mut backoff = RECONNECT_BACKOFF_MIN;
+        let mut session = None;
+        for attempt in 1..=RECONNECT_ATTEMPTS {
+            sleep(backoff).await;
+            if inner.is_closed.load(Ordering::Relaxed) {
+                return;
+            }
+            trace_tx
+                .as_ref()
+                .map(|tx| tx.send(DiscordLiveBuilderState::Reconnecting(attempt)));
+            inner.statuses.push(MirrorStatus::Reconnecting { attempt });
+            match inner
+                .builder
+                .handshake(
+                    inner.parameters(),
+                    &inner.keyframe_requests,
+                    &inner.statuses,
+                    &trace_tx,
+                )
+                .await
+            {
+                Ok(new_session) => {
+                    session = Some(new_session);
+                    break;
+                }
+                Err(e) => {
+                    warn!("[Discord] reconnect attempt {attempt} failed: {e}");
+                    let rejected = matches!(e.kind, ErrorType::DiscordAuth);
+                    inner.statuses.push(MirrorStatus::ReconnectFailed {
+                        attempt,
+                        error: e.to_string(),
+                    });
+                    trace_tx.as_ref().map(|tx| {
+                        tx.send(DiscordLiveBuilderState::ReconnectFailed(
+                            attempt,
+                            e.to_string(),
+                        ))
+                    });
+                    if rejected {
+                        break;
+                    }
+                    backoff = (backoff * 2).min(RECONNECT_BACKOFF_MAX);
+                }
+            }
+        }
+
+        let Some(session) = session else {
+            warn!("[Discord] giving up on reconnecting");
+            trace_tx
+                .as_ref()
+                .map(|tx| tx.send(DiscordLiveBuilderState::Disconnected));
+            inner.statuses.push(MirrorStatus::Disconnected);
+            inner.is_closed.store(true, Ordering::Relaxed);
+            break;
+        };
+        *inner.session.write().await = session;
+        let session = inner.session.read().await;
+        if inner.is_closed.load(Ordering::Relaxed) {
+            session.notify.close();
+            break;
+        }
+        // Checked once the new session is in place, so a stream going live
+        // meanwhile is activated either here or by the connected callback.
+        if inner.is_live.load(Ordering::SeqCst) {
+            let _ = session.activate(&inner.parameters());
+        }
+        drop(session);
+
+        info!("[Discord] reconnected");
+        trace_tx
+            .as_ref()
+            .map(|tx| tx.send(DiscordLiveBuilderState::Reconnected));
+        inner.statuses.push(MirrorStatus::Reconnected);
     }
 }
 
@@ -648,6 +1229,10 @@ struct DAVEInstance {
     dave_protocol_version: u16,
     local_audio_track: Arc<TrackLocalStaticSample>,
     local_video_track: Arc<TrackLocalStaticSample>,
+    /// Samples sent in the clear since the last write, as the session failed
+    /// to encrypt them.
+    encryption_failures: u64,
+    statuses: StatusUpdates,
 }
 
 impl DAVEInstance {
@@ -656,6 +1241,11 @@ impl DAVEInstance {
     }
 
     fn set_dave_protocol_version(&mut self, version: u16) -> u16 {
+        if version != self.dave_protocol_version {
+            self.statuses.push(MirrorStatus::Dave {
+                protocol_version: version,
+            });
+        }
         self.dave_protocol_version = version;
         self.dave_protocol_version
     }
@@ -677,6 +1267,7 @@ impl DAVEInstance {
             .session
             .encrypt(MediaType::AUDIO, Codec::OPUS, &payload.data)
         else {
+            self.encryption_failures += 1;
             return self.local_audio_track.write_sample(payload).await;
         };
         payload.data = Bytes::copy_from_slice(&data);
@@ -684,11 +1275,32 @@ impl DAVEInstance {
         self.local_audio_track.write_sample(payload).await
     }
 
-    async fn write_video_sample(&mut self, payload: &mut Sample) -> Result<(), webrtc::Error> {
+    async fn write_video_sample(
+        &mut self,
+        payload: &mut Sample,
+        codec: codecs::Codec,
+    ) -> Result<(), webrtc::Error> {
         if self.dave_protocol_version == 0 || !self.session.is_ready() {
             return self.local_video_track.write_sample(payload).await;
         }
 
+        // VP8, VP9 and AV1 frames are encrypted whole, DAVE leaves their
+        // headers readable on its own.
+        if let codecs::Codec::VP8 | codecs::Codec::VP9 | codecs::Codec::AV1 = codec {
+            let codec = match codec {
+                codecs::Codec::VP8 => Codec::VP8,
+                codecs::Codec::VP9 => Codec::VP9,
+                _ => Codec::AV1,
+            };
+            let Ok(data) = self.session.encrypt(MediaType::VIDEO, codec, &payload.data) else {
+                self.encryption_failures += 1;
+                return self.local_video_track.write_sample(payload).await;
+            };
+            payload.data = Bytes::copy_from_slice(&data);
+
+            return self.local_video_track.write_sample(payload).await;
+        }
+
         let mut data = Vec::new();
         let mut nalu_indexes = Vec::new();
         let mut i = 0;
@@ -717,6 +1329,14 @@ impl DAVEInstance {
                 .get(pos + 1)
                 .map(|v| v.0)
                 .unwrap_or(payload.data.len());
+            if codec == codecs::Codec::H265 {
+                // Keep the slices and parameter sets, the decoder does
+                // without access unit delimiters and SEI.
+                if let 0..=34 = codecs::h265_nalu_type(payload.data[nalu + start_size]) {
+                    data.extend_from_slice(&payload.data[nalu..next_nalu]);
+                }
+                continue;
+            }
             match payload.data[nalu + start_size] & 0x1F {
                 1 | 5 | 8 => {
                     data.extend_from_slice(&payload.data[nalu..next_nalu]);
@@ -741,7 +1361,12 @@ impl DAVEInstance {
             }
         }
 
-        let Ok(data) = self.session.encrypt(MediaType::VIDEO, Codec::H264, &data) else {
+        let codec = match codec {
+            codecs::Codec::H265 => Codec::H265,
+            _ => Codec::H264,
+        };
+        let Ok(data) = self.session.encrypt(MediaType::VIDEO, codec, &data) else {
+            self.encryption_failures += 1;
             return self.local_video_track.write_sample(payload).await;
         };
         payload.data = Bytes::copy_from_slice(&data);
@@ -768,6 +1393,7 @@ enum DAVEPayload {
 
 pub(super) struct Notifier {
     is_closed: AtomicBool,
+    closed: Notify,
     gateway: Arc<Notify>,
     endpoint: Arc<Notify>,
     heartbeat: Arc<Notify>,
@@ -778,6 +1404,7 @@ impl Notifier {
     fn new() -> Self {
         Self {
             is_closed: AtomicBool::new(false),
+            closed: Notify::new(),
             gateway: Arc::new(Notify::new()),
             endpoint: Arc::new(Notify::new()),
             heartbeat: Arc::new(Notify::new()),
@@ -791,11 +1418,22 @@ impl Notifier {
         self.heartbeat.notify_one();
         self.dave.notify_one();
         self.is_closed.store(true, Ordering::Relaxed);
+        self.closed.notify_waiters();
     }
 
     fn is_closed(&self) -> bool {
         self.is_closed.load(Ordering::Relaxed)
     }
+
+    async fn wait_closed(&self) {
+        let closed = self.closed.notified();
+        let mut closed = std::pin::pin!(closed);
+        closed.as_mut().enable();
+        if self.is_closed() {
+            return;
+        }
+        closed.await;
+    }
 }
 
 pub enum DiscordLiveBuilderState {
@@ -807,6 +1445,10 @@ pub enum DiscordLiveBuilderState {
     EndpointRTCNegotiation,
     EndpointRTCConnecting,
     EndpointDAVECreating,
+    Reconnecting(u32),
+    ReconnectFailed(u32, String),
+    Reconnected,
+    Disconnected,
 }
 
 impl Display for DiscordLiveBuilderState {
@@ -832,6 +1474,16 @@ impl Display for DiscordLiveBuilderState {
             DiscordLiveBuilderState::EndpointDAVECreating => {
                 f.write_str("creating new dave session")
             }
+            DiscordLiveBuilderState::Reconnecting(attempt) => {
+                write!(f, "connection lost, reconnecting (attempt {attempt})")
+            }
+            DiscordLiveBuilderState::ReconnectFailed(attempt, e) => {
+                write!(f, "reconnect attempt {attempt} failed: {e}")
+            }
+            DiscordLiveBuilderState::Reconnected => f.write_str("reconnected"),
+            DiscordLiveBuilderState::Disconnected => {
+                f.write_str("gave up on reconnecting, mirror closed")
+            }
         }
     }
 }
@@ -892,3 +1544,47 @@ impl From<SendError<WebSocketMessage>> for Error<dyn ErrorInner> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(data: &'static [u8]) -> Sample {
+        Sample {
+            data: Bytes::from_static(data),
+            duration: Duration::from_millis(33),
+            ..Sample::default()
+        }
+    }
+
+    #[test]
+    fn probe_skips_truncated_h265_sps() {
+        let mut probe = SourceProbe::default();
+        // An IDR picture followed by an SPS cut short after its first byte.
+        probe.push_video(
+            codecs::Codec::H265,
+            &sample(&[0, 0, 1, 0x26, 0x01, 0, 0, 1, 0x42]),
+        );
+        assert_eq!(probe.resolution, None);
+    }
+
+    #[test]
+    fn probe_skips_truncated_h264_sps() {
+        let mut probe = SourceProbe::default();
+        probe.push_video(
+            codecs::Codec::H264,
+            &sample(&[0, 0, 1, 0x65, 0x88, 0, 0, 1, 0x67]),
+        );
+        assert_eq!(probe.resolution, None);
+    }
+
+    #[test]
+    fn unsupported_codecs_are_refused() {
+        let builder = || DiscordLiveBuilder::new("token", 1, 2);
+        let err = builder().codec(codecs::Codec::Opus).err().unwrap();
+        assert!(matches!(err.kind(), ErrorType::DiscordCodec));
+
+        let builder = builder().codec(codecs::Codec::AV1).ok().unwrap();
+        assert_eq!(builder.codec, codecs::Codec::AV1);
+    }
+}
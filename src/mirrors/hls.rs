@@ -0,0 +1,522 @@
+use bytes::{Bytes, BytesMut};
+use http::{
+    Request, Response, StatusCode,
+    header::{ACCESS_CONTROL_ALLOW_ORIGIN, CACHE_CONTROL, CONTENT_TYPE},
+};
+use http_body_util::Full;
+use std::{
+    collections::VecDeque,
+    convert::Infallible,
+    error::Error as StdError,
+    fmt::Write,
+    pin::Pin,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+use tokio::sync::{
+    mpsc::{self, error::SendError},
+    oneshot::{self, error::RecvError},
+};
+use tracing::info;
+use webrtc::media::Sample;
+
+use super::Mirror;
+use crate::{
+    error::{Error, ErrorType},
+    utils::mp4::{Fragmenter, Gop, Timeline},
+};
+
+/// Number of segments listed in the live playlist.
+const PLAYLIST_WINDOW: usize = 6;
+
+#[derive(Clone)]
+pub struct HLS {
+    stream: Box<str>,
+    inner_tx: mpsc::UnboundedSender<HLSEvent>,
+    is_closed: Arc<AtomicBool>,
+}
+
+impl HLS {
+    /// Serve the stream under `/hls/<stream>/`, with the playlist at
+    /// `/hls/<stream>/index.m3u8`.
+    pub fn new<T: Into<Box<str>>>(stream: T) -> Self {
+        let (inner_tx, mut inner_rx) = mpsc::unbounded_channel();
+        let stream: Box<str> = stream.into();
+
+        let stream_a = stream.clone();
+        tokio::spawn(async move {
+            let stream = stream_a;
+            let mut packager = Packager::default();
+
+            while let Some(payload) = inner_rx.recv().await {
+                match payload {
+                    HLSEvent::AudioSample(sample) => {
+                        packager
+                            .fragmenter
+                            .push_audio(&sample.data, sample.duration);
+                    }
+                    HLSEvent::VideoSample(sample) => {
+                        if let Some(gop) = packager
+                            .fragmenter
+                            .push_video(&sample.data, sample.duration)
+                        {
+                            packager.push_gop(gop);
+                        }
+                    }
+                    HLSEvent::NewRequest(path, resp_tx) => {
+                        let file = path
+                            .strip_prefix("/hls/")
+                            .and_then(|path| path.split_once('/'))
+                            .filter(|(name, _)| *name == &*stream)
+                            .map(|(_, file)| file);
+                        let resp = match file {
+                            Some(file) => packager.serve(file),
+                            None => Err(StatusCode::NOT_FOUND),
+                        };
+                        let _ = resp_tx.send(resp);
+                    }
+                    HLSEvent::Close => break,
+                }
+            }
+
+            inner_rx.close();
+        });
+
+        Self {
+            stream,
+            inner_tx,
+            is_closed: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    async fn add_request(
+        &self,
+        path: String,
+    ) -> Result<Result<Response<Full<Bytes>>, StatusCode>, Error<dyn ErrorInner>> {
+        let (resp_tx, resp_rx) = oneshot::channel();
+        self.inner_tx.send(HLSEvent::NewRequest(path, resp_tx))?;
+        resp_rx.await.map_err(Into::into)
+    }
+
+    fn send(&self, event: HLSEvent) -> Result<(), Error> {
+        if self.is_closed.load(Ordering::Relaxed) {
+            return Err(Error {
+                kind: ErrorType::HLSIPC,
+                source: None,
+            });
+        }
+        self.inner_tx.send(event).map_err(|_| Error {
+            kind: ErrorType::HLSIPC,
+            source: None,
+        })
+    }
+
+    #[allow(clippy::type_complexity)]
+    pub fn into_closure<ReqBody>(
+        &self,
+    ) -> impl FnMut(
+        Request<ReqBody>,
+    ) -> Pin<
+        Box<
+            dyn Future<Output = Result<Result<Response<Full<Bytes>>, StatusCode>, Infallible>>
+                + Send,
+        >,
+    > + Clone
+    + use<ReqBody>
+    where
+        ReqBody: Send + 'static,
+    {
+        let mut hls = Some(self.clone());
+        move |req: Request<ReqBody>| {
+            let hls = hls.take().unwrap();
+            Box::pin(async move {
+                let path = req.uri().path().to_owned();
+                let res = hls
+                    .add_request(path)
+                    .await
+                    .unwrap_or(Err(StatusCode::SERVICE_UNAVAILABLE));
+                Ok(res)
+            })
+        }
+    }
+}
+
+impl Mirror for HLS {
+    fn write_audio_sample<'a>(
+        &'a self,
+        payload: &'a mut Sample,
+    ) -> Pin<Box<dyn Future<Output = Result<(), Error>> + Send + 'a>> {
+        Box::pin(async { self.send(HLSEvent::AudioSample(std::mem::take(payload))) })
+    }
+
+    fn write_video_sample<'a>(
+        &'a self,
+        payload: &'a mut Sample,
+    ) -> Pin<Box<dyn Future<Output = Result<(), Error>> + Send + 'a>> {
+        Box::pin(async { self.send(HLSEvent::VideoSample(std::mem::take(payload))) })
+    }
+
+    fn kind(&self) -> &'static str {
+        "hls"
+    }
+
+    fn target(&self) -> String {
+        format!("/hls/{}/index.m3u8", self.stream)
+    }
+
+    fn close(&self) {
+        self.is_closed.store(true, Ordering::Relaxed);
+        let _ = self.inner_tx.send(HLSEvent::Close);
+    }
+}
+
+enum HLSEvent {
+    AudioSample(Sample),
+    VideoSample(Sample),
+    NewRequest(
+        String,
+        oneshot::Sender<Result<Response<Full<Bytes>>, StatusCode>>,
+    ),
+    Close,
+}
+
+struct Segment {
+    sequence: u64,
+    init: u64,
+    discontinuity: bool,
+    duration: f64,
+    data: Bytes,
+}
+
+#[derive(Default)]
+struct Packager {
+    fragmenter: Fragmenter,
+    timeline: Timeline,
+    inits: VecDeque<(u64, Bytes)>,
+    segments: VecDeque<Segment>,
+    next_sequence: u64,
+    discontinuity_sequence: u64,
+    target_duration: u64,
+}
+
+impl Packager {
+    fn push_gop(&mut self, gop: Gop) {
+        let mut discontinuity = false;
+        let init = match self.inits.back() {
+            Some((id, init)) if *init == gop.init => *id,
+            last => {
+                // The parameter sets changed, players have to reinitialize
+                // their decoders from the new initialization segment.
+                let id = last.map(|(id, _)| id + 1).unwrap_or_default();
+                discontinuity = last.is_some();
+                self.inits.push_back((id, gop.init.clone()));
+                self.timeline = Timeline::new();
+                id
+            }
+        };
+
+        let mut data = BytesMut::new();
+        self.timeline.write_fragment(&gop, &mut data);
+        let duration = gop.duration().as_secs_f64();
+        self.target_duration = self.target_duration.max(duration.ceil() as u64);
+
+        if self.next_sequence == 0 {
+            info!("[HLS] first segment ready");
+        }
+        self.segments.push_back(Segment {
+            sequence: self.next_sequence,
+            init,
+            discontinuity,
+            duration,
+            data: data.freeze(),
+        });
+        self.next_sequence += 1;
+
+        while self.segments.len() > PLAYLIST_WINDOW {
+            let Some(segment) = self.segments.pop_front() else {
+                break;
+            };
+            if segment.discontinuity {
+                self.discontinuity_sequence += 1;
+            }
+        }
+        let oldest = self.segments.front().map(|s| s.init).unwrap_or_default();
+        self.inits.retain(|(id, _)| *id >= oldest);
+    }
+
+    fn serve(&self, file: &str) -> Result<Response<Full<Bytes>>, StatusCode> {
+        if file == "index.m3u8" {
+            let playlist = self.playlist().ok_or(StatusCode::NOT_FOUND)?;
+            return respond("application/vnd.apple.mpegurl", Bytes::from(playlist));
+        }
+
+        if let Some(id) = file
+            .strip_prefix("init")
+            .and_then(|file| file.strip_suffix(".mp4"))
+            .and_then(|id| id.parse::<u64>().ok())
+        {
+            let (_, init) = self
+                .inits
+                .iter()
+                .find(|(i, _)| *i == id)
+                .ok_or(StatusCode::NOT_FOUND)?;
+            return respond("video/mp4", init.clone());
+        }
+
+        if let Some(sequence) = file
+            .strip_prefix("segment")
+            .and_then(|file| file.strip_suffix(".m4s"))
+            .and_then(|sequence| sequence.parse::<u64>().ok())
+        {
+            let segment = self
+                .segments
+                .iter()
+                .find(|s| s.sequence == sequence)
+                .ok_or(StatusCode::NOT_FOUND)?;
+            return respond("video/iso.segment", segment.data.clone());
+        }
+
+        Err(StatusCode::NOT_FOUND)
+    }
+
+    fn playlist(&self) -> Option<String> {
+        let first = self.segments.front()?;
+
+        let mut playlist = String::new();
+        let _ = writeln!(playlist, "#EXTM3U");
+        let _ = writeln!(playlist, "#EXT-X-VERSION:7");
+        let _ = writeln!(playlist, "#EXT-X-TARGETDURATION:{}", self.target_duration);
+        let _ = writeln!(playlist, "#EXT-X-MEDIA-SEQUENCE:{}", first.sequence);
+        // The tag of the first segment is left out, its discontinuity counts
+        // as one that was removed.
+        let _ = writeln!(
+            playlist,
+            "#EXT-X-DISCONTINUITY-SEQUENCE:{}",
+            self.discontinuity_sequence + u64::from(first.discontinuity)
+        );
+
+        let mut init = None;
+        for segment in &self.segments {
+            if init != Some(segment.init) {
+                if init.is_some() && segment.discontinuity {
+                    let _ = writeln!(playlist, "#EXT-X-DISCONTINUITY");
+                }
+                let _ = writeln!(playlist, "#EXT-X-MAP:URI=\"init{}.mp4\"", segment.init);
+                init = Some(segment.init);
+            }
+            let _ = writeln!(playlist, "#EXTINF:{:.3},", segment.duration);
+            let _ = writeln!(playlist, "segment{}.m4s", segment.sequence);
+        }
+
+        Some(playlist)
+    }
+}
+
+fn respond(content_type: &str, body: Bytes) -> Result<Response<Full<Bytes>>, StatusCode> {
+    Response::builder()
+        .header(CONTENT_TYPE, content_type)
+        .header(CACHE_CONTROL, "no-cache")
+        .header(ACCESS_CONTROL_ALLOW_ORIGIN, "*")
+        .body(Full::new(body))
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+pub trait ErrorInner: StdError + Send + Sync {}
+
+impl<T: StdError + Send + Sync> ErrorInner for T {}
+
+impl StdError for Error<dyn ErrorInner> {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        self.source
+            .as_ref()
+            .map(|source| &**source as &(dyn StdError + 'static))
+    }
+}
+
+impl From<SendError<HLSEvent>> for Error<dyn ErrorInner> {
+    fn from(err: SendError<HLSEvent>) -> Self {
+        Self {
+            kind: ErrorType::HLSIPC,
+            source: Some(Box::new(err)),
+        }
+    }
+}
+
+impl From<RecvError> for Error<dyn ErrorInner> {
+    fn from(err: RecvError) -> Self {
+        Self {
+            kind: ErrorType::HLSIPC,
+            source: Some(Box::new(err)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::mp4::{FragmentSample, VIDEO_TIMESCALE};
+    use http_body_util::BodyExt;
+
+    /// A GOP of a single keyframe lasting `seconds`.
+    fn gop(init: &'static [u8], seconds: u32) -> Gop {
+        Gop {
+            init: Bytes::from_static(init),
+            video: vec![FragmentSample {
+                duration: seconds * VIDEO_TIMESCALE,
+                keyframe: true,
+                data: Bytes::from_static(&[0, 0, 0, 1, 0x65]),
+            }],
+            audio: Vec::new(),
+        }
+    }
+
+    fn playlist(packager: &Packager) -> Vec<String> {
+        packager
+            .playlist()
+            .unwrap()
+            .lines()
+            .map(str::to_owned)
+            .collect()
+    }
+
+    async fn body(resp: Response<Full<Bytes>>) -> Bytes {
+        resp.into_body().collect().await.unwrap().to_bytes()
+    }
+
+    #[test]
+    fn playlists_wait_for_the_first_segment() {
+        let packager = Packager::default();
+        assert!(packager.playlist().is_none());
+        assert_eq!(
+            packager.serve("index.m3u8").err(),
+            Some(StatusCode::NOT_FOUND)
+        );
+    }
+
+    #[test]
+    fn playlists_list_a_window_of_segments() {
+        let mut packager = Packager::default();
+        for seconds in [2, 2, 3, 2, 2, 2, 2, 2] {
+            packager.push_gop(gop(b"init", seconds));
+        }
+
+        let mut expected = vec![
+            "#EXTM3U".to_owned(),
+            "#EXT-X-VERSION:7".to_owned(),
+            "#EXT-X-TARGETDURATION:3".to_owned(),
+            "#EXT-X-MEDIA-SEQUENCE:2".to_owned(),
+            "#EXT-X-DISCONTINUITY-SEQUENCE:0".to_owned(),
+            "#EXT-X-MAP:URI=\"init0.mp4\"".to_owned(),
+        ];
+        for (sequence, seconds) in (2..8).zip([3, 2, 2, 2, 2, 2]) {
+            expected.push(format!("#EXTINF:{seconds}.000,"));
+            expected.push(format!("segment{sequence}.m4s"));
+        }
+        assert_eq!(playlist(&packager), expected);
+        assert_eq!(packager.segments.len(), PLAYLIST_WINDOW);
+    }
+
+    #[tokio::test]
+    async fn segments_leaving_the_window_are_gone() {
+        let mut packager = Packager::default();
+        for _ in 0..PLAYLIST_WINDOW + 2 {
+            packager.push_gop(gop(b"init", 2));
+        }
+
+        for file in ["segment0.m4s", "segment1.m4s", "segment8.m4s", "init1.mp4"] {
+            assert_eq!(packager.serve(file).err(), Some(StatusCode::NOT_FOUND));
+        }
+        for file in ["segment", "segmentx.m4s", "index.m3u", "../index.m3u8"] {
+            assert_eq!(packager.serve(file).err(), Some(StatusCode::NOT_FOUND));
+        }
+
+        let resp = packager.serve("segment2.m4s").unwrap();
+        assert_eq!(resp.headers()[CONTENT_TYPE], "video/iso.segment");
+        let segment = body(resp).await;
+        assert_eq!(&segment[4..8], b"moof");
+        assert_eq!(segment, packager.segments[0].data);
+
+        let resp = packager.serve("init0.mp4").unwrap();
+        assert_eq!(resp.headers()[CONTENT_TYPE], "video/mp4");
+        assert_eq!(body(resp).await, &b"init"[..]);
+    }
+
+    #[test]
+    fn new_init_segments_start_a_discontinuity() {
+        let mut packager = Packager::default();
+        packager.push_gop(gop(b"first", 2));
+        packager.push_gop(gop(b"first", 2));
+        packager.push_gop(gop(b"second", 2));
+        packager.push_gop(gop(b"second", 2));
+
+        let lines = playlist(&packager);
+        assert_eq!(lines[4], "#EXT-X-DISCONTINUITY-SEQUENCE:0");
+        assert_eq!(
+            lines[5..],
+            [
+                "#EXT-X-MAP:URI=\"init0.mp4\"",
+                "#EXTINF:2.000,",
+                "segment0.m4s",
+                "#EXTINF:2.000,",
+                "segment1.m4s",
+                "#EXT-X-DISCONTINUITY",
+                "#EXT-X-MAP:URI=\"init1.mp4\"",
+                "#EXTINF:2.000,",
+                "segment2.m4s",
+                "#EXTINF:2.000,",
+                "segment3.m4s",
+            ]
+        );
+        assert!(packager.serve("init0.mp4").is_ok());
+        assert!(packager.serve("init1.mp4").is_ok());
+
+        // Each new initialization segment restarts the timeline.
+        assert_eq!(packager.segments[2].data, packager.segments[0].data);
+    }
+
+    #[test]
+    fn init_segments_are_dropped_with_their_last_segment() {
+        let mut packager = Packager::default();
+        packager.push_gop(gop(b"first", 2));
+        packager.push_gop(gop(b"first", 2));
+        for _ in 0..PLAYLIST_WINDOW - 1 {
+            packager.push_gop(gop(b"second", 2));
+        }
+
+        let lines = playlist(&packager);
+        assert_eq!(lines[3], "#EXT-X-MEDIA-SEQUENCE:1");
+        assert_eq!(lines[4], "#EXT-X-DISCONTINUITY-SEQUENCE:0");
+        assert!(packager.serve("init0.mp4").is_ok());
+
+        // The window now starts right after the discontinuity, which counts
+        // as removed along with its tag.
+        packager.push_gop(gop(b"second", 2));
+        let lines = playlist(&packager);
+        assert_eq!(lines[3], "#EXT-X-MEDIA-SEQUENCE:2");
+        assert_eq!(lines[4], "#EXT-X-DISCONTINUITY-SEQUENCE:1");
+        assert_eq!(lines[5], "#EXT-X-MAP:URI=\"init1.mp4\"");
+        assert!(!lines.iter().any(|line| line == "#EXT-X-DISCONTINUITY"));
+        assert_eq!(packager.inits.len(), 1);
+        assert_eq!(
+            packager.serve("init0.mp4").err(),
+            Some(StatusCode::NOT_FOUND)
+        );
+
+        packager.push_gop(gop(b"second", 2));
+        let lines = playlist(&packager);
+        assert_eq!(lines[4], "#EXT-X-DISCONTINUITY-SEQUENCE:1");
+    }
+
+    #[tokio::test]
+    async fn requests_for_other_streams_are_not_found() {
+        let hls = HLS::new("live");
+        let resp = hls.add_request("/hls/other/index.m3u8".to_owned()).await;
+        assert_eq!(resp.unwrap().err(), Some(StatusCode::NOT_FOUND));
+        // Nothing was received yet.
+        let resp = hls.add_request("/hls/live/index.m3u8".to_owned()).await;
+        assert_eq!(resp.unwrap().err(), Some(StatusCode::NOT_FOUND));
+        hls.close();
+    }
+}
@@ -1,11 +1,21 @@
-use std::pin::Pin;
+use serde::Serialize;
+use std::{pin::Pin, sync::Arc};
+use tokio::sync::{Mutex, mpsc};
 use webrtc::media::Sample;
 
-use crate::error::Error;
+use crate::{error::Error, utils::codecs::Codec};
 
 mod discord;
+mod hls;
+mod record;
+mod whep;
+mod whip;
 
-pub use discord::DiscordLiveBuilder;
+pub use discord::{DiscordLiveBuilder, DiscordLiveBuilderState};
+pub use hls::HLS;
+pub use record::RecorderBuilder;
+pub use whep::WHEP;
+pub use whip::WHIPClientBuilder;
 
 pub trait Mirror {
     fn write_audio_sample<'a>(
@@ -22,5 +32,92 @@ pub trait Mirror {
         Ok(())
     }
 
+    /// Short name of the kind of mirror, such as `discord` or `whep`.
+    fn kind(&self) -> &'static str;
+
+    /// Where the mirror delivers the stream, for display.
+    fn target(&self) -> String;
+
+    fn codecs(&self) -> &[Codec] {
+        &[Codec::Opus, Codec::H264]
+    }
+
+    /// Resolves whenever a viewer of the mirror asks for a keyframe, such as
+    /// through a PLI or FIR. Mirrors that cannot tell never resolve.
+    fn read_keyframe_request<'a>(&'a self) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+        Box::pin(std::future::pending())
+    }
+
+    /// Samples that had to go out unencrypted, for mirrors that encrypt.
+    fn encryption_failures(&self) -> u64 {
+        0
+    }
+
+    /// Whether the mirror currently has a connection to deliver over.
+    fn is_connected(&self) -> bool {
+        true
+    }
+
+    /// Resolves with every change the mirror reports about its connection.
+    /// Mirrors without any never resolve.
+    fn read_status<'a>(&'a self) -> Pin<Box<dyn Future<Output = MirrorStatus> + Send + 'a>> {
+        Box::pin(std::future::pending())
+    }
+
     fn close(&self);
 }
+
+/// A change in the connection of a mirror.
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum MirrorStatus {
+    /// The ICE connection moved to `state`.
+    Ice {
+        state: String,
+    },
+    /// DAVE switched protocol version, `0` meaning unencrypted.
+    Dave {
+        protocol_version: u16,
+    },
+    Reconnecting {
+        attempt: u32,
+    },
+    ReconnectFailed {
+        attempt: u32,
+        error: String,
+    },
+    Reconnected,
+    /// The mirror gave up on reconnecting.
+    Disconnected,
+}
+
+/// Status changes of a mirror, held until the hub reads them.
+#[derive(Clone)]
+pub(crate) struct StatusUpdates {
+    tx: mpsc::UnboundedSender<MirrorStatus>,
+    rx: Arc<Mutex<mpsc::UnboundedReceiver<MirrorStatus>>>,
+}
+
+impl Default for StatusUpdates {
+    fn default() -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        Self {
+            tx,
+            rx: Arc::new(Mutex::new(rx)),
+        }
+    }
+}
+
+impl StatusUpdates {
+    pub(crate) fn push(&self, status: MirrorStatus) {
+        let _ = self.tx.send(status);
+    }
+
+    pub(crate) async fn read(&self) -> MirrorStatus {
+        // The sender is kept alongside, so the channel never ends.
+        match self.rx.lock().await.recv().await {
+            Some(status) => status,
+            None => std::future::pending().await,
+        }
+    }
+}
@@ -0,0 +1,394 @@
+use bytes::{Bytes, BytesMut};
+use std::{
+    error::Error as StdError,
+    io,
+    path::{Component, Path, PathBuf},
+    pin::Pin,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::{
+    fs::{self, File},
+    io::AsyncWriteExt,
+    sync::mpsc,
+};
+use tracing::{info, warn};
+use webrtc::media::Sample;
+
+use super::Mirror;
+use crate::{
+    error::{Error, ErrorType},
+    utils::mp4::{Fragmenter, Gop, Timeline},
+};
+
+pub struct RecorderBuilder {
+    path: Box<str>,
+    directory: PathBuf,
+    max_duration: Option<Duration>,
+    max_size: Option<u64>,
+}
+
+impl RecorderBuilder {
+    /// The path may contain `{date}`, `{time}`, `{timestamp}` and `{index}`,
+    /// which are filled in whenever a new file is started. Dates and times
+    /// are in UTC. It is taken relative to the directory, see
+    /// [`Self::is_valid_path`].
+    pub fn new<T: Into<Box<str>>>(path: T) -> Self {
+        Self {
+            path: path.into(),
+            directory: PathBuf::from("."),
+            max_duration: None,
+            max_size: None,
+        }
+    }
+
+    /// Keep the recordings below this directory. Defaults to the working
+    /// directory.
+    pub fn directory(mut self, directory: impl Into<PathBuf>) -> Self {
+        self.directory = directory.into();
+        self
+    }
+
+    /// Whether the path stays within the directory once filled in: it has to
+    /// be relative, without any `..`.
+    pub fn is_valid_path(path: &str) -> bool {
+        !path.is_empty()
+            && Path::new(path)
+                .components()
+                .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+    }
+
+    /// Start a new file once the current one would grow past this duration.
+    pub fn max_duration(mut self, max_duration: Duration) -> Self {
+        self.max_duration = Some(max_duration);
+        self
+    }
+
+    /// Start a new file once the current one would grow past this many bytes.
+    pub fn max_size(mut self, max_size: u64) -> Self {
+        self.max_size = Some(max_size);
+        self
+    }
+
+    pub async fn connect(self) -> Result<Recorder, Error<dyn ErrorInner>> {
+        if !Self::is_valid_path(&self.path) {
+            return Err(Error {
+                kind: ErrorType::RecorderPath,
+                source: None,
+            });
+        }
+        let path = self
+            .directory
+            .join(render_path(&self.path, 0, SystemTime::now()));
+        if let Some(parent) = path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            fs::create_dir_all(parent).await?;
+        }
+
+        let (inner_tx, mut inner_rx) = mpsc::unbounded_channel();
+        let is_closed = Arc::new(AtomicBool::new(false));
+        let template = self.path.clone();
+
+        let mut recording = Recording {
+            builder: self,
+            fragmenter: Fragmenter::new(),
+            segment: None,
+            index: 0,
+        };
+        let is_closed_a = is_closed.clone();
+        tokio::spawn(async move {
+            while let Some(payload) = inner_rx.recv().await {
+                let result = match payload {
+                    RecorderEvent::AudioSample(sample) => {
+                        recording
+                            .fragmenter
+                            .push_audio(&sample.data, sample.duration);
+                        Ok(())
+                    }
+                    RecorderEvent::VideoSample(sample) => {
+                        match recording
+                            .fragmenter
+                            .push_video(&sample.data, sample.duration)
+                        {
+                            Some(gop) => recording.write_gop(gop).await,
+                            None => Ok(()),
+                        }
+                    }
+                    RecorderEvent::Close => break,
+                };
+
+                if let Err(e) = result {
+                    warn!("[Recorder] failed to write recording: {e}");
+                    is_closed_a.store(true, Ordering::Relaxed);
+                    break;
+                }
+            }
+
+            if let Some(gop) = recording.fragmenter.flush()
+                && let Err(e) = recording.write_gop(gop).await
+            {
+                warn!("[Recorder] failed to write recording: {e}");
+            }
+            if let Some(segment) = recording.segment.take() {
+                info!("[Recorder] finished {}", segment.path);
+            }
+
+            inner_rx.close();
+        });
+
+        Ok(Recorder {
+            path: template,
+            inner_tx,
+            is_closed,
+        })
+    }
+}
+
+pub struct Recorder {
+    path: Box<str>,
+    inner_tx: mpsc::UnboundedSender<RecorderEvent>,
+    is_closed: Arc<AtomicBool>,
+}
+
+impl Recorder {
+    fn send(&self, event: RecorderEvent) -> Result<(), Error> {
+        if self.is_closed.load(Ordering::Relaxed) {
+            return Err(Error {
+                kind: ErrorType::RecorderFile,
+                source: None,
+            });
+        }
+        self.inner_tx.send(event).map_err(|_| Error {
+            kind: ErrorType::RecorderIPC,
+            source: None,
+        })
+    }
+}
+
+impl Mirror for Recorder {
+    fn write_audio_sample<'a>(
+        &'a self,
+        payload: &'a mut Sample,
+    ) -> Pin<Box<dyn Future<Output = Result<(), Error>> + Send + 'a>> {
+        Box::pin(async { self.send(RecorderEvent::AudioSample(std::mem::take(payload))) })
+    }
+
+    fn write_video_sample<'a>(
+        &'a self,
+        payload: &'a mut Sample,
+    ) -> Pin<Box<dyn Future<Output = Result<(), Error>> + Send + 'a>> {
+        Box::pin(async { self.send(RecorderEvent::VideoSample(std::mem::take(payload))) })
+    }
+
+    fn kind(&self) -> &'static str {
+        "recorder"
+    }
+
+    fn target(&self) -> String {
+        self.path.to_string()
+    }
+
+    fn close(&self) {
+        self.is_closed.store(true, Ordering::Relaxed);
+        let _ = self.inner_tx.send(RecorderEvent::Close);
+    }
+}
+
+enum RecorderEvent {
+    AudioSample(Sample),
+    VideoSample(Sample),
+    Close,
+}
+
+struct Recording {
+    builder: RecorderBuilder,
+    fragmenter: Fragmenter,
+    segment: Option<Segment>,
+    index: u32,
+}
+
+struct Segment {
+    file: File,
+    path: String,
+    init: Bytes,
+    timeline: Timeline,
+    duration: Duration,
+    size: u64,
+}
+
+impl Recording {
+    async fn write_gop(&mut self, gop: Gop) -> Result<(), io::Error> {
+        let mut buf = BytesMut::new();
+
+        let rollover = match &self.segment {
+            Some(segment) => {
+                segment.init != gop.init
+                    || self
+                        .builder
+                        .max_duration
+                        .is_some_and(|max| segment.duration + gop.duration() > max)
+                    || self
+                        .builder
+                        .max_size
+                        .is_some_and(|max| segment.size + gop.size() as u64 > max)
+            }
+            None => true,
+        };
+        if rollover {
+            buf.extend_from_slice(&gop.init);
+            self.open(gop.init.clone()).await?;
+        }
+        let Some(segment) = self.segment.as_mut() else {
+            return Ok(());
+        };
+
+        segment.timeline.write_fragment(&gop, &mut buf);
+        segment.file.write_all(&buf).await?;
+        segment.file.flush().await?;
+        segment.duration += gop.duration();
+        segment.size += buf.len() as u64;
+
+        Ok(())
+    }
+
+    async fn open(&mut self, init: Bytes) -> Result<(), io::Error> {
+        let previous = self.segment.take().map(|segment| segment.path);
+        if let Some(previous) = &previous {
+            info!("[Recorder] finished {previous}");
+        }
+
+        let path = render_path(&self.builder.path, self.index, SystemTime::now());
+        let mut path = self.builder.directory.join(path).display().to_string();
+        if previous.as_ref() == Some(&path) {
+            // The template does not tell files apart, avoid overwriting the
+            // previous one.
+            path = match path.rsplit_once('.') {
+                Some((stem, ext)) if !ext.contains('/') => format!("{stem}_{}.{ext}", self.index),
+                _ => format!("{path}_{}", self.index),
+            };
+        }
+        self.index += 1;
+
+        if let Some(parent) = Path::new(&path).parent()
+            && !parent.as_os_str().is_empty()
+        {
+            fs::create_dir_all(parent).await?;
+        }
+        let file = File::create(&path).await?;
+        info!("[Recorder] recording to {path}");
+
+        self.segment = Some(Segment {
+            file,
+            path,
+            init,
+            timeline: Timeline::new(),
+            duration: Duration::ZERO,
+            size: 0,
+        });
+        Ok(())
+    }
+}
+
+fn render_path(template: &str, index: u32, now: SystemTime) -> String {
+    let secs = now
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let (year, month, day) = civil_from_days((secs / 86400) as i64);
+    let time = secs % 86400;
+
+    template
+        .replace("{date}", &format!("{year:04}-{month:02}-{day:02}"))
+        .replace(
+            "{time}",
+            &format!("{:02}-{:02}-{:02}", time / 3600, time / 60 % 60, time % 60),
+        )
+        .replace("{timestamp}", &secs.to_string())
+        .replace("{index}", &index.to_string())
+}
+
+/// Convert days since the unix epoch to a proleptic Gregorian date.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+pub trait ErrorInner: StdError + Send + Sync {}
+
+impl<T: StdError + Send + Sync> ErrorInner for T {}
+
+impl StdError for Error<dyn ErrorInner> {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        self.source
+            .as_ref()
+            .map(|source| &**source as &(dyn StdError + 'static))
+    }
+}
+
+impl From<io::Error> for Error<dyn ErrorInner> {
+    fn from(err: io::Error) -> Self {
+        Self {
+            kind: ErrorType::RecorderFile,
+            source: Some(Box::new(err)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paths_must_stay_within_the_directory() {
+        assert!(RecorderBuilder::is_valid_path("live.mp4"));
+        assert!(RecorderBuilder::is_valid_path("./live/{date}_{time}.mp4"));
+        assert!(!RecorderBuilder::is_valid_path(""));
+        assert!(!RecorderBuilder::is_valid_path("/tmp/live.mp4"));
+        assert!(!RecorderBuilder::is_valid_path("../live.mp4"));
+        assert!(!RecorderBuilder::is_valid_path("live/../../live.mp4"));
+    }
+
+    #[tokio::test]
+    async fn recordings_are_written_below_the_directory() {
+        let directory = std::env::temp_dir().join(format!("utsuru-{}", uuid::Uuid::new_v4()));
+        let recorder = RecorderBuilder::new("live/{index}.mp4")
+            .directory(&directory)
+            .connect()
+            .await
+            .unwrap();
+        recorder.close();
+        assert!(directory.join("live").is_dir());
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[tokio::test]
+    async fn recorders_leaving_the_directory_are_refused() {
+        let err = RecorderBuilder::new("../live.mp4")
+            .connect()
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err.kind(), ErrorType::RecorderPath));
+    }
+
+    #[test]
+    fn dates_are_rendered_in_utc() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        assert_eq!(
+            render_path("{date}_{time}_{timestamp}_{index}", 3, now),
+            "2023-11-14_22-13-20_1700000000_3"
+        );
+    }
+}
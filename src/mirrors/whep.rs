@@ -0,0 +1,610 @@
+use http::{
+    Method, Request, Response, StatusCode,
+    header::{CONTENT_TYPE, LOCATION},
+};
+use http_body::Body;
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    error::Error as StdError,
+    net::IpAddr,
+    pin::Pin,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+use tokio::{
+    sync::{
+        mpsc::{self, error::SendError},
+        oneshot::{self, error::RecvError},
+    },
+    time::sleep,
+};
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+use webrtc::{
+    api::{
+        APIBuilder,
+        interceptor_registry::register_default_interceptors,
+        media_engine::{MIME_TYPE_H264, MIME_TYPE_OPUS, MediaEngine},
+        setting_engine::SettingEngine,
+    },
+    ice_transport::ice_connection_state::RTCIceConnectionState,
+    interceptor::registry::Registry,
+    media::Sample,
+    peer_connection::{
+        RTCPeerConnection,
+        configuration::RTCConfiguration,
+        policy::{
+            bundle_policy::RTCBundlePolicy, ice_transport_policy::RTCIceTransportPolicy,
+            rtcp_mux_policy::RTCRtcpMuxPolicy,
+        },
+        sdp::session_description::RTCSessionDescription,
+    },
+    rtp_transceiver::{
+        RTCRtpTransceiverInit, rtp_codec::RTCRtpCodecCapability,
+        rtp_transceiver_direction::RTCRtpTransceiverDirection,
+    },
+    track::track_local::{TrackLocal, track_local_static_sample::TrackLocalStaticSample},
+};
+
+use super::Mirror;
+use crate::{
+    error::{Error, ErrorType},
+    utils::{
+        ice::{ICE_DISCONNECTED_TIMEOUT, IceConfig},
+        request::{SDP_CONTENT_TYPE, has_content_type, read_body},
+        rtcp::{KeyframeRequests, read_keyframe_requests},
+    },
+};
+
+#[derive(Clone)]
+pub struct WHEP {
+    path: Box<str>,
+    inner_tx: mpsc::UnboundedSender<WHEPEvent>,
+    is_closed: Arc<AtomicBool>,
+    local_audio_track: Arc<TrackLocalStaticSample>,
+    local_video_track: Arc<TrackLocalStaticSample>,
+    keyframe_requests: KeyframeRequests,
+    ice: Arc<IceConfig>,
+}
+
+impl WHEP {
+    pub fn new(host: IpAddr) -> Self {
+        let inner = mpsc::unbounded_channel();
+        let (inner_tx_a, inner_tx_b, mut inner_rx) = (inner.0.clone(), inner.0, inner.1);
+
+        let local_audio_track = Arc::new(TrackLocalStaticSample::new(
+            RTCRtpCodecCapability {
+                mime_type: MIME_TYPE_OPUS.to_owned(),
+                clock_rate: 48000,
+                channels: 2,
+                ..Default::default()
+            },
+            "audio".to_owned(),
+            "utsuru".to_owned(),
+        ));
+        let local_video_track = Arc::new(TrackLocalStaticSample::new(
+            RTCRtpCodecCapability {
+                mime_type: MIME_TYPE_H264.to_owned(),
+                clock_rate: 90000,
+                ..Default::default()
+            },
+            "video".to_owned(),
+            "utsuru".to_owned(),
+        ));
+
+        let keyframe_requests = KeyframeRequests::default();
+
+        let inner_tx = inner_tx_a;
+        let requests = keyframe_requests.clone();
+        let audio_track = local_audio_track.clone();
+        let video_track = local_video_track.clone();
+        tokio::spawn(async move {
+            let mut viewers: HashMap<Uuid, Arc<RTCPeerConnection>> = HashMap::new();
+
+            while let Some(payload) = inner_rx.recv().await {
+                match payload {
+                    WHEPEvent::NewRequest(offer, path, ice, resp_tx) => {
+                        let id = Uuid::new_v4();
+                        let peer = init_peer(
+                            host,
+                            &ice,
+                            id,
+                            offer,
+                            &audio_track,
+                            &video_track,
+                            &requests,
+                            inner_tx.clone(),
+                        )
+                        .await;
+                        let (sdp, peer_connection) = match peer {
+                            Ok(peer) => peer,
+                            Err(err) if matches!(err.kind(), ErrorType::WHEPOffer) => {
+                                warn!("[WHEP] refusing offer: {err}");
+                                let _ = resp_tx.send(Err(StatusCode::BAD_REQUEST));
+                                continue;
+                            }
+                            Err(err) => {
+                                warn!("[WHEP] failed to answer offer: {err}");
+                                let _ = resp_tx.send(Err(StatusCode::INTERNAL_SERVER_ERROR));
+                                continue;
+                            }
+                        };
+
+                        // Viewers live at the path of the endpoint followed by
+                        // their ID, where they may end their session.
+                        let resp = Response::builder()
+                            .header(
+                                LOCATION,
+                                format!("{}/{}", path.trim_end_matches('/'), id.simple()),
+                            )
+                            .header(CONTENT_TYPE, SDP_CONTENT_TYPE)
+                            .status(StatusCode::CREATED)
+                            .body(sdp)
+                            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR);
+                        let _ = resp_tx.send(resp);
+
+                        viewers.insert(id, peer_connection);
+                        info!("[WHEP] viewer {id} joined, {} watching", viewers.len());
+                    }
+                    WHEPEvent::DeleteRequest(id, resp_tx) => {
+                        let Some(peer_connection) = viewers.remove(&id) else {
+                            let _ = resp_tx.send(Err(StatusCode::NOT_FOUND));
+                            continue;
+                        };
+                        let _ = peer_connection.close().await;
+                        info!("[WHEP] viewer {id} ended, {} watching", viewers.len());
+                        let resp = Response::builder()
+                            .status(StatusCode::OK)
+                            .body(String::new())
+                            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR);
+                        let _ = resp_tx.send(resp);
+                    }
+                    WHEPEvent::EndRequest(id) => {
+                        let Some(peer_connection) = viewers.remove(&id) else {
+                            continue;
+                        };
+                        let _ = peer_connection.close().await;
+                        info!("[WHEP] viewer {id} left, {} watching", viewers.len());
+                    }
+                    WHEPEvent::Close => {
+                        for (_, peer_connection) in viewers.drain() {
+                            let _ = peer_connection.close().await;
+                        }
+                        break;
+                    }
+                }
+            }
+
+            inner_rx.close();
+        });
+
+        let inner_tx = inner_tx_b;
+        Self {
+            path: "/whep".into(),
+            inner_tx,
+            is_closed: Arc::new(AtomicBool::new(false)),
+            local_audio_track,
+            local_video_track,
+            keyframe_requests,
+            ice: Arc::default(),
+        }
+    }
+
+    /// The path the endpoint is served at, shown as the target of the
+    /// mirror. Defaults to `/whep`.
+    pub fn path(mut self, path: impl AsRef<str>) -> Self {
+        self.path = path.as_ref().into();
+        self
+    }
+
+    /// How the viewers' connections gather their ICE candidates.
+    pub fn ice(mut self, ice: IceConfig) -> Self {
+        self.ice = Arc::new(ice);
+        self
+    }
+
+    async fn add_request(
+        &self,
+        offer: String,
+        path: String,
+    ) -> Result<Result<Response<String>, StatusCode>, Error<dyn ErrorInner>> {
+        let (resp_tx, resp_rx) = oneshot::channel();
+        self.inner_tx.send(WHEPEvent::NewRequest(
+            offer,
+            path,
+            self.ice.clone(),
+            resp_tx,
+        ))?;
+        resp_rx.await.map_err(Into::into)
+    }
+
+    async fn delete_request(
+        &self,
+        id: Uuid,
+    ) -> Result<Result<Response<String>, StatusCode>, Error<dyn ErrorInner>> {
+        let (resp_tx, resp_rx) = oneshot::channel();
+        self.inner_tx.send(WHEPEvent::DeleteRequest(id, resp_tx))?;
+        resp_rx.await.map_err(Into::into)
+    }
+
+    #[allow(clippy::type_complexity)]
+    pub fn into_closure<ReqBody>(
+        &self,
+    ) -> impl FnMut(
+        Request<ReqBody>,
+    ) -> Pin<
+        Box<dyn Future<Output = Result<Result<Response<String>, StatusCode>, Infallible>> + Send>,
+    > + Clone
+    + use<ReqBody>
+    where
+        ReqBody: Body + Send + 'static,
+        <ReqBody as Body>::Data: std::marker::Send,
+        <ReqBody as Body>::Error: std::fmt::Debug,
+    {
+        let mut whep = Some(self.clone());
+        move |req: Request<ReqBody>| {
+            let whep = whep.take().unwrap();
+            Box::pin(async move {
+                let path = req.uri().path().to_owned();
+                match req.method().clone() {
+                    Method::POST => {
+                        if !has_content_type(&req, SDP_CONTENT_TYPE) {
+                            return Ok(Err(StatusCode::UNSUPPORTED_MEDIA_TYPE));
+                        }
+                        let Some(offer) = read_body(req).await else {
+                            return Ok(Err(StatusCode::BAD_REQUEST));
+                        };
+                        let res = whep
+                            .add_request(offer, path)
+                            .await
+                            .unwrap_or(Err(StatusCode::INTERNAL_SERVER_ERROR));
+                        Ok(res)
+                    }
+                    Method::DELETE => {
+                        let session_id = path.rsplit('/').next().unwrap_or_default();
+                        let Ok(id) = Uuid::try_parse(session_id) else {
+                            return Ok(Err(StatusCode::NOT_FOUND));
+                        };
+                        let res = whep
+                            .delete_request(id)
+                            .await
+                            .unwrap_or(Err(StatusCode::INTERNAL_SERVER_ERROR));
+                        Ok(res)
+                    }
+                    _ => Ok(Err(StatusCode::METHOD_NOT_ALLOWED)),
+                }
+            })
+        }
+    }
+}
+
+impl Mirror for WHEP {
+    fn write_audio_sample<'a>(
+        &'a self,
+        payload: &'a mut Sample,
+    ) -> Pin<Box<dyn Future<Output = Result<(), Error>> + Send + 'a>> {
+        Box::pin(async {
+            if self.is_closed.load(Ordering::Relaxed) {
+                return Err(Error {
+                    kind: ErrorType::WHEPPeer,
+                    source: None,
+                });
+            }
+            if let Err(e) = self.local_audio_track.write_sample(payload).await {
+                debug!("[WHEP] audio sample dropped: {e}");
+            }
+            Ok(())
+        })
+    }
+
+    fn write_video_sample<'a>(
+        &'a self,
+        payload: &'a mut Sample,
+    ) -> Pin<Box<dyn Future<Output = Result<(), Error>> + Send + 'a>> {
+        Box::pin(async {
+            if self.is_closed.load(Ordering::Relaxed) {
+                return Err(Error {
+                    kind: ErrorType::WHEPPeer,
+                    source: None,
+                });
+            }
+            if let Err(e) = self.local_video_track.write_sample(payload).await {
+                debug!("[WHEP] video sample dropped: {e}");
+            }
+            Ok(())
+        })
+    }
+
+    fn kind(&self) -> &'static str {
+        "whep"
+    }
+
+    fn target(&self) -> String {
+        self.path.to_string()
+    }
+
+    fn read_keyframe_request<'a>(&'a self) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+        Box::pin(self.keyframe_requests.read())
+    }
+
+    fn close(&self) {
+        self.is_closed.store(true, Ordering::Relaxed);
+        let _ = self.inner_tx.send(WHEPEvent::Close);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn init_peer(
+    host: IpAddr,
+    ice: &IceConfig,
+    id: Uuid,
+    offer: String,
+    local_audio_track: &Arc<TrackLocalStaticSample>,
+    local_video_track: &Arc<TrackLocalStaticSample>,
+    keyframe_requests: &KeyframeRequests,
+    inner_tx: mpsc::UnboundedSender<WHEPEvent>,
+) -> Result<(String, Arc<RTCPeerConnection>), Error<dyn ErrorInner>> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+
+    let mut registry = Registry::new();
+    registry = register_default_interceptors(registry, &mut m)?;
+
+    let mut s = SettingEngine::default();
+    s.set_include_loopback_candidate(true);
+    if !host.is_unspecified() {
+        let ip_filter = Box::new(move |ipaddr| ipaddr == host);
+        s.set_ip_filter(ip_filter);
+    }
+    ice.apply(&mut s)?;
+
+    let api = APIBuilder::new()
+        .with_media_engine(m)
+        .with_interceptor_registry(registry)
+        .with_setting_engine(s)
+        .build();
+
+    let config = RTCConfiguration {
+        ice_servers: ice.ice_servers(),
+        ice_transport_policy: RTCIceTransportPolicy::All,
+        bundle_policy: RTCBundlePolicy::MaxBundle,
+        rtcp_mux_policy: RTCRtcpMuxPolicy::Require,
+        ..Default::default()
+    };
+    let peer_connection = Arc::new(api.new_peer_connection(config).await?);
+
+    for (track, keyframe_requests) in [
+        (local_audio_track, None),
+        (local_video_track, Some(keyframe_requests.clone())),
+    ] {
+        let rtp_transceiver = peer_connection
+            .add_transceiver_from_track(
+                Arc::clone(track) as Arc<dyn TrackLocal + Send + Sync>,
+                Some(RTCRtpTransceiverInit {
+                    direction: RTCRtpTransceiverDirection::Sendonly,
+                    send_encodings: vec![],
+                }),
+            )
+            .await?;
+        let sender = rtp_transceiver.sender().await;
+        if let Some(keyframe_requests) = keyframe_requests {
+            tokio::spawn(read_keyframe_requests(sender, keyframe_requests));
+            continue;
+        }
+        tokio::spawn(async move {
+            let mut rtcp_buf = vec![0u8; 1500];
+            while let Ok((_, _)) = sender.read(&mut rtcp_buf).await {}
+            debug!("[WHEP] rtp_sender.read loop exit");
+        });
+    }
+
+    // A disconnected viewer often comes back on its own, it only ends once
+    // ICE fails or stays disconnected for too long.
+    let pc = Arc::downgrade(&peer_connection);
+    peer_connection.on_ice_connection_state_change(Box::new(
+        move |connection_state: RTCIceConnectionState| {
+            info!(
+                "[WHEP] ICE connection state changed to: {}",
+                connection_state
+            );
+            match connection_state {
+                RTCIceConnectionState::Disconnected => {
+                    let pc = pc.clone();
+                    let inner_tx = inner_tx.clone();
+                    tokio::spawn(async move {
+                        sleep(ICE_DISCONNECTED_TIMEOUT).await;
+                        let still_disconnected = pc.upgrade().is_some_and(|pc| {
+                            pc.ice_connection_state() == RTCIceConnectionState::Disconnected
+                        });
+                        if still_disconnected {
+                            warn!("[WHEP] viewer {id} stayed disconnected, closing peer");
+                            let _ = inner_tx.send(WHEPEvent::EndRequest(id));
+                        }
+                    });
+                }
+                RTCIceConnectionState::Failed => {
+                    warn!("[WHEP] closing peer");
+                    let _ = inner_tx.send(WHEPEvent::EndRequest(id));
+                }
+                _ => {}
+            }
+            Box::pin(async {})
+        },
+    ));
+
+    let offer = RTCSessionDescription::offer(offer).map_err(offer_error)?;
+    peer_connection
+        .set_remote_description(offer)
+        .await
+        .map_err(offer_error)?;
+    let answer = peer_connection.create_answer(None).await?;
+    let mut gather_complete = peer_connection.gathering_complete_promise().await;
+    peer_connection.set_local_description(answer).await?;
+    let _ = gather_complete.recv().await;
+    let local_desc = peer_connection.local_description().await.ok_or(Error {
+        kind: ErrorType::WHEPPeer,
+        source: None,
+    })?;
+
+    Ok((local_desc.sdp, peer_connection))
+}
+
+/// The offer could not be parsed or taken by the peer.
+fn offer_error(err: webrtc::Error) -> Error<dyn ErrorInner> {
+    Error {
+        kind: ErrorType::WHEPOffer,
+        source: Some(Box::new(err)),
+    }
+}
+
+enum WHEPEvent {
+    NewRequest(
+        String,
+        String,
+        Arc<IceConfig>,
+        oneshot::Sender<Result<Response<String>, StatusCode>>,
+    ),
+    DeleteRequest(Uuid, oneshot::Sender<Result<Response<String>, StatusCode>>),
+    EndRequest(Uuid),
+    Close,
+}
+
+pub trait ErrorInner: StdError + Send + Sync {}
+
+impl<T: StdError + Send + Sync> ErrorInner for T {}
+
+impl StdError for Error<dyn ErrorInner> {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        self.source
+            .as_ref()
+            .map(|source| &**source as &(dyn StdError + 'static))
+    }
+}
+
+impl From<SendError<WHEPEvent>> for Error<dyn ErrorInner> {
+    fn from(err: SendError<WHEPEvent>) -> Self {
+        Self {
+            kind: ErrorType::WHEPIPC,
+            source: Some(Box::new(err)),
+        }
+    }
+}
+
+impl From<RecvError> for Error<dyn ErrorInner> {
+    fn from(err: RecvError) -> Self {
+        Self {
+            kind: ErrorType::WHEPIPC,
+            source: Some(Box::new(err)),
+        }
+    }
+}
+
+impl From<webrtc::Error> for Error<dyn ErrorInner> {
+    fn from(err: webrtc::Error) -> Self {
+        Self {
+            kind: ErrorType::WHEPPeer,
+            source: Some(Box::new(err)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing;
+    use bytes::Bytes;
+    use http::header::CONTENT_TYPE;
+    use http_body_util::Full;
+
+    fn whep() -> WHEP {
+        WHEP::new(IpAddr::from([127, 0, 0, 1]))
+    }
+
+    async fn send(whep: &WHEP, req: Request<Full<Bytes>>) -> Result<Response<String>, StatusCode> {
+        let Ok(res) = whep.into_closure()(req).await;
+        res
+    }
+
+    fn post(content_type: &str, body: impl Into<Bytes>) -> Request<Full<Bytes>> {
+        Request::post("/whep/live")
+            .header(CONTENT_TYPE, content_type)
+            .body(Full::new(body.into()))
+            .unwrap()
+    }
+
+    fn delete(path: &str) -> Request<Full<Bytes>> {
+        Request::delete(path).body(Full::default()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn viewers_get_a_session_they_can_end() {
+        let whep = whep();
+        let resp = send(
+            &whep,
+            post(
+                "application/sdp",
+                testing::offer(RTCRtpTransceiverDirection::Recvonly).await,
+            ),
+        )
+        .await
+        .unwrap();
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        let location = resp.headers()[LOCATION].to_str().unwrap().to_owned();
+        assert!(location.starts_with("/whep/live/"));
+        assert_ne!(location, "/whep/live/");
+
+        let resp = send(&whep, delete(&location)).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let resp = send(&whep, delete(&location)).await;
+        assert_eq!(resp.unwrap_err(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn offers_need_the_sdp_content_type() {
+        let resp = send(
+            &whep(),
+            post(
+                "text/plain",
+                testing::offer(RTCRtpTransceiverDirection::Recvonly).await,
+            ),
+        )
+        .await;
+        assert_eq!(resp.unwrap_err(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    }
+
+    #[tokio::test]
+    async fn offers_that_are_not_text_are_refused() {
+        let resp = send(&whep(), post("application/sdp", &[0xff, 0xfe][..])).await;
+        assert_eq!(resp.unwrap_err(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn malformed_offers_are_refused() {
+        let resp = send(&whep(), post("application/sdp", "v=0")).await;
+        assert_eq!(resp.unwrap_err(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn closed_endpoints_answer_with_an_error() {
+        let whep = whep();
+        whep.close();
+        let resp = send(
+            &whep,
+            post(
+                "application/sdp",
+                testing::offer(RTCRtpTransceiverDirection::Recvonly).await,
+            ),
+        )
+        .await;
+        assert_eq!(resp.unwrap_err(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[tokio::test]
+    async fn unknown_sessions_are_not_found() {
+        let resp = send(&whep(), delete("/whep/live/nope")).await;
+        assert_eq!(resp.unwrap_err(), StatusCode::NOT_FOUND);
+    }
+}
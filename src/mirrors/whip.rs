@@ -0,0 +1,664 @@
+use bytes::Bytes;
+use http::{
+    Method, Request, StatusCode, Uri,
+    header::{AUTHORIZATION, CONTENT_TYPE, LOCATION},
+    uri::InvalidUri,
+};
+use http_body_util::{BodyExt, Full};
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
+use hyper_util::{
+    client::legacy::{Client, connect::HttpConnector},
+    rt::TokioExecutor,
+};
+use std::{
+    error::Error as StdError,
+    pin::Pin,
+    sync::{
+        Arc, Weak,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+use tokio::time::sleep;
+use tracing::{debug, info, warn};
+use webrtc::{
+    api::{
+        APIBuilder,
+        interceptor_registry::register_default_interceptors,
+        media_engine::{MIME_TYPE_OPUS, MediaEngine},
+        setting_engine::SettingEngine,
+    },
+    ice_transport::ice_connection_state::RTCIceConnectionState,
+    interceptor::registry::Registry,
+    media::Sample,
+    peer_connection::{
+        RTCPeerConnection,
+        configuration::RTCConfiguration,
+        policy::{
+            bundle_policy::RTCBundlePolicy, ice_transport_policy::RTCIceTransportPolicy,
+            rtcp_mux_policy::RTCRtcpMuxPolicy,
+        },
+        sdp::session_description::RTCSessionDescription,
+    },
+    rtp_transceiver::{
+        RTCRtpTransceiverInit,
+        rtp_codec::{RTCRtpCodecCapability, RTCRtpCodecParameters, RTPCodecType},
+        rtp_transceiver_direction::RTCRtpTransceiverDirection,
+    },
+    track::track_local::{TrackLocal, track_local_static_sample::TrackLocalStaticSample},
+};
+
+use super::{Mirror, MirrorStatus, StatusUpdates};
+use crate::{
+    error::{Error, ErrorType},
+    utils::{
+        codecs::Codec,
+        ice::{ICE_DISCONNECTED_TIMEOUT, IceConfig},
+        rtcp::{KeyframeRequests, read_keyframe_requests},
+    },
+};
+
+pub struct WHIPClientBuilder {
+    url: Box<str>,
+    token: Option<Box<str>>,
+    codec: Codec,
+    ice: IceConfig,
+}
+
+impl WHIPClientBuilder {
+    pub fn new(url: impl AsRef<str>) -> Self {
+        Self {
+            url: url.as_ref().into(),
+            token: None,
+            codec: Codec::H264,
+            ice: IceConfig::default(),
+        }
+    }
+
+    pub fn token(mut self, token: impl AsRef<str>) -> Self {
+        self.token = Some(token.as_ref().into());
+        self
+    }
+
+    /// Send video encoded with this codec, either H264, the default, H265,
+    /// VP8, VP9 or AV1. The mirror only receives samples from sources sending
+    /// it. Fails for any other codec.
+    pub fn codec(mut self, codec: Codec) -> Result<Self, Error<dyn ErrorInner>> {
+        if !matches!(
+            codec,
+            Codec::H264 | Codec::H265 | Codec::VP8 | Codec::VP9 | Codec::AV1
+        ) {
+            return Err(Error {
+                kind: ErrorType::WHIPClientCodec,
+                source: None,
+            });
+        }
+        self.codec = codec;
+        Ok(self)
+    }
+
+    /// How the connection to the server gathers its ICE candidates.
+    pub fn ice(mut self, ice: IceConfig) -> Self {
+        self.ice = ice;
+        self
+    }
+
+    pub async fn connect(self) -> Result<WHIPClient, Error<dyn ErrorInner>> {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+
+        let audio_payload = 111;
+        let audio_codec = "opus";
+        let video_payload = 102;
+        let video_fmtp = match self.codec {
+            Codec::H265 => "level-id=93;profile-id=1;tier-flag=0;tx-mode=SRST",
+            Codec::VP8 => "",
+            Codec::VP9 => "profile-id=0",
+            Codec::AV1 => "level-idx=5;profile=0;tier=0",
+            _ => "level-asymmetry-allowed=1;packetization-mode=1;profile-level-id=42e01f",
+        };
+        let video_rtxpayload = 103;
+
+        let url: Uri = self.url.parse()?;
+        let client = Client::builder(TokioExecutor::new()).build(
+            HttpsConnectorBuilder::new()
+                .with_native_roots()?
+                .https_or_http()
+                .enable_http1()
+                .build(),
+        );
+
+        let mut m = MediaEngine::default();
+        m.register_codec(
+            RTCRtpCodecParameters {
+                capability: RTCRtpCodecCapability {
+                    mime_type: self.codec.mime_type().to_owned(),
+                    clock_rate: 90000,
+                    channels: 0,
+                    sdp_fmtp_line: video_fmtp.to_owned(),
+                    rtcp_feedback: vec![],
+                },
+                payload_type: video_payload,
+                ..Default::default()
+            },
+            RTPCodecType::Video,
+        )?;
+        m.register_codec(
+            RTCRtpCodecParameters {
+                capability: RTCRtpCodecCapability {
+                    mime_type: "video/rtx".to_owned(),
+                    clock_rate: 90000,
+                    channels: 0,
+                    sdp_fmtp_line: format!("apt={video_payload}"),
+                    rtcp_feedback: vec![],
+                },
+                payload_type: video_rtxpayload,
+                ..Default::default()
+            },
+            RTPCodecType::Video,
+        )?;
+        m.register_codec(
+            RTCRtpCodecParameters {
+                capability: RTCRtpCodecCapability {
+                    mime_type: match audio_codec {
+                        "opus" => MIME_TYPE_OPUS.to_owned(),
+                        _ => format!("audio/{audio_codec}"),
+                    },
+                    clock_rate: 48000,
+                    channels: 2,
+                    sdp_fmtp_line: "minptime=10;useinbandfec=1".to_owned(),
+                    rtcp_feedback: vec![],
+                },
+                payload_type: audio_payload,
+                ..Default::default()
+            },
+            RTPCodecType::Audio,
+        )?;
+
+        let mut registry = Registry::new();
+        registry = register_default_interceptors(registry, &mut m)?;
+
+        let mut s = SettingEngine::default();
+        self.ice.apply(&mut s)?;
+
+        let api = APIBuilder::new()
+            .with_media_engine(m)
+            .with_interceptor_registry(registry)
+            .with_setting_engine(s)
+            .build();
+
+        let config = RTCConfiguration {
+            ice_servers: self.ice.ice_servers(),
+            ice_transport_policy: RTCIceTransportPolicy::All,
+            bundle_policy: RTCBundlePolicy::MaxBundle,
+            rtcp_mux_policy: RTCRtcpMuxPolicy::Require,
+            ..Default::default()
+        };
+        let peer_connection = Arc::new(api.new_peer_connection(config).await?);
+
+        let local_audio_track = Arc::new(TrackLocalStaticSample::new(
+            RTCRtpCodecCapability {
+                mime_type: MIME_TYPE_OPUS.to_owned(),
+                ..Default::default()
+            },
+            "audio".to_owned(),
+            "utsuru".to_owned(),
+        ));
+        let local_video_track = Arc::new(TrackLocalStaticSample::new(
+            RTCRtpCodecCapability {
+                mime_type: self.codec.mime_type().to_owned(),
+                ..Default::default()
+            },
+            "video".to_owned(),
+            "utsuru".to_owned(),
+        ));
+        let keyframe_requests = KeyframeRequests::default();
+        for (track, keyframe_requests) in [
+            (&local_audio_track, None),
+            (&local_video_track, Some(keyframe_requests.clone())),
+        ] {
+            let rtp_transceiver = peer_connection
+                .add_transceiver_from_track(
+                    Arc::clone(track) as Arc<dyn TrackLocal + Send + Sync>,
+                    Some(RTCRtpTransceiverInit {
+                        direction: RTCRtpTransceiverDirection::Sendonly,
+                        send_encodings: vec![],
+                    }),
+                )
+                .await?;
+            let sender = rtp_transceiver.sender().await;
+            if let Some(keyframe_requests) = keyframe_requests {
+                tokio::spawn(read_keyframe_requests(sender, keyframe_requests));
+                continue;
+            }
+            tokio::spawn(async move {
+                let mut rtcp_buf = vec![0u8; 1500];
+                while let Ok((_, _)) = sender.read(&mut rtcp_buf).await {}
+                debug!("[WHIP] rtp_sender.read loop exit");
+            });
+        }
+
+        let is_closed = Arc::new(AtomicBool::new(false));
+        let is_closed_ice = is_closed.clone();
+        let statuses = StatusUpdates::default();
+        let statuses_ice = statuses.clone();
+        let pc = Arc::downgrade(&peer_connection);
+        peer_connection.on_ice_connection_state_change(Box::new(
+            move |connection_state: RTCIceConnectionState| {
+                info!(
+                    "[WHIP] ICE connection state changed to: {}",
+                    connection_state
+                );
+                statuses_ice.push(MirrorStatus::Ice {
+                    state: connection_state.to_string(),
+                });
+                match connection_state {
+                    RTCIceConnectionState::Failed => {
+                        tokio::spawn(close_peer(pc.clone(), is_closed_ice.clone(), None));
+                    }
+                    RTCIceConnectionState::Disconnected => {
+                        tokio::spawn(close_peer(
+                            pc.clone(),
+                            is_closed_ice.clone(),
+                            Some(ICE_DISCONNECTED_TIMEOUT),
+                        ));
+                    }
+                    _ => {}
+                }
+                Box::pin(async {})
+            },
+        ));
+
+        let offer = peer_connection.create_offer(None).await?;
+        let mut gather_complete = peer_connection.gathering_complete_promise().await;
+        peer_connection.set_local_description(offer).await?;
+        let _ = gather_complete.recv().await;
+        let local_desc = peer_connection.local_description().await.ok_or(Error {
+            kind: ErrorType::WHIPClientPeer,
+            source: None,
+        })?;
+
+        debug!("[WHIP] sending offer to {url}");
+        let mut req = Request::builder()
+            .method(Method::POST)
+            .uri(url.clone())
+            .header(CONTENT_TYPE, "application/sdp");
+        if let Some(token) = &self.token {
+            req = req.header(AUTHORIZATION, format!("Bearer {token}"));
+        }
+        let req = req.body(Full::new(Bytes::from(local_desc.sdp)))?;
+        let resp = client.request(req).await?;
+
+        let status = resp.status();
+        if status != StatusCode::CREATED {
+            warn!("[WHIP] server answered with {status}");
+            let _ = peer_connection.close().await;
+            return Err(Error {
+                kind: ErrorType::WHIPClientRequest,
+                source: None,
+            });
+        }
+        let resource = resp
+            .headers()
+            .get(LOCATION)
+            .and_then(|location| location.to_str().ok())
+            .and_then(|location| resolve_location(&url, location));
+        let answer = resp.into_body().collect().await?.to_bytes();
+        let answer = String::from_utf8(answer.into())?;
+
+        let answer = RTCSessionDescription::answer(answer)?;
+        peer_connection.set_remote_description(answer).await?;
+        debug!("[WHIP] answer received, resource at {resource:?}");
+
+        Ok(WHIPClient {
+            url: self.url,
+            codecs: [Codec::Opus, self.codec],
+            client,
+            resource,
+            token: self.token,
+            is_closed,
+            peer_connection,
+            local_audio_track,
+            local_video_track,
+            keyframe_requests,
+            statuses,
+        })
+    }
+}
+
+pub struct WHIPClient {
+    url: Box<str>,
+    codecs: [Codec; 2],
+    client: Client<HttpsConnector<HttpConnector>, Full<Bytes>>,
+    resource: Option<Uri>,
+    token: Option<Box<str>>,
+    is_closed: Arc<AtomicBool>,
+    peer_connection: Arc<RTCPeerConnection>,
+    local_audio_track: Arc<TrackLocalStaticSample>,
+    local_video_track: Arc<TrackLocalStaticSample>,
+    keyframe_requests: KeyframeRequests,
+    statuses: StatusUpdates,
+}
+
+impl Mirror for WHIPClient {
+    fn write_audio_sample<'a>(
+        &'a self,
+        payload: &'a mut Sample,
+    ) -> Pin<Box<dyn Future<Output = Result<(), Error>> + Send + 'a>> {
+        Box::pin(async {
+            if self.is_closed.load(Ordering::Relaxed) {
+                return Err(Error {
+                    kind: ErrorType::WHIPClientPeer,
+                    source: None,
+                });
+            }
+            self.local_audio_track
+                .write_sample(payload)
+                .await
+                .map_err(|err| Error {
+                    kind: ErrorType::WHIPClientPeer,
+                    source: Some(err.into()),
+                })
+        })
+    }
+
+    fn write_video_sample<'a>(
+        &'a self,
+        payload: &'a mut Sample,
+    ) -> Pin<Box<dyn Future<Output = Result<(), Error>> + Send + 'a>> {
+        Box::pin(async {
+            if self.is_closed.load(Ordering::Relaxed) {
+                return Err(Error {
+                    kind: ErrorType::WHIPClientPeer,
+                    source: None,
+                });
+            }
+            self.local_video_track
+                .write_sample(payload)
+                .await
+                .map_err(|err| Error {
+                    kind: ErrorType::WHIPClientPeer,
+                    source: Some(err.into()),
+                })
+        })
+    }
+
+    fn kind(&self) -> &'static str {
+        "whip"
+    }
+
+    fn target(&self) -> String {
+        self.url.to_string()
+    }
+
+    fn codecs(&self) -> &[Codec] {
+        &self.codecs
+    }
+
+    fn read_keyframe_request<'a>(&'a self) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+        Box::pin(self.keyframe_requests.read())
+    }
+
+    fn is_connected(&self) -> bool {
+        !self.is_closed.load(Ordering::Relaxed)
+    }
+
+    fn read_status<'a>(&'a self) -> Pin<Box<dyn Future<Output = MirrorStatus> + Send + 'a>> {
+        Box::pin(self.statuses.read())
+    }
+
+    fn close(&self) {
+        self.is_closed.store(true, Ordering::Relaxed);
+
+        let client = self.client.clone();
+        let resource = self.resource.clone();
+        let token = self.token.clone();
+        let peer_connection = self.peer_connection.clone();
+        tokio::spawn(async move {
+            let _ = peer_connection.close().await;
+
+            let Some(resource) = resource else {
+                return;
+            };
+            let mut req = Request::builder().method(Method::DELETE).uri(resource);
+            if let Some(token) = token {
+                req = req.header(AUTHORIZATION, format!("Bearer {token}"));
+            }
+            let Ok(req) = req.body(Full::new(Bytes::new())) else {
+                return;
+            };
+            if let Err(e) = client.request(req).await {
+                debug!("[WHIP] failed to delete session resource: {e}");
+            }
+        });
+    }
+}
+
+/// Close the peer once ICE failed, or after `timeout` if it is still
+/// disconnected by then.
+async fn close_peer(
+    pc: Weak<RTCPeerConnection>,
+    is_closed: Arc<AtomicBool>,
+    timeout: Option<Duration>,
+) {
+    if let Some(timeout) = timeout {
+        sleep(timeout).await;
+    }
+    let Some(pc) = pc.upgrade() else {
+        return;
+    };
+    if timeout.is_some() && pc.ice_connection_state() != RTCIceConnectionState::Disconnected {
+        return;
+    }
+
+    is_closed.store(true, Ordering::Relaxed);
+    let _ = pc.close().await;
+    warn!("[WHIP] closing peer");
+}
+
+fn resolve_location(url: &Uri, location: &str) -> Option<Uri> {
+    let location: Uri = location.parse().ok()?;
+    if location.scheme().is_some() {
+        return Some(location);
+    }
+
+    let mut parts = url.clone().into_parts();
+    parts.path_and_query = location.into_parts().path_and_query;
+    Uri::from_parts(parts).ok()
+}
+
+pub trait ErrorInner: StdError + Send + Sync {}
+
+impl<T: StdError + Send + Sync> ErrorInner for T {}
+
+impl StdError for Error<dyn ErrorInner> {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        self.source
+            .as_ref()
+            .map(|source| &**source as &(dyn StdError + 'static))
+    }
+}
+
+impl From<InvalidUri> for Error<dyn ErrorInner> {
+    fn from(err: InvalidUri) -> Self {
+        Self {
+            kind: ErrorType::WHIPClientRequest,
+            source: Some(Box::new(err)),
+        }
+    }
+}
+
+impl From<std::io::Error> for Error<dyn ErrorInner> {
+    fn from(err: std::io::Error) -> Self {
+        Self {
+            kind: ErrorType::WHIPClientRequest,
+            source: Some(Box::new(err)),
+        }
+    }
+}
+
+impl From<http::Error> for Error<dyn ErrorInner> {
+    fn from(err: http::Error) -> Self {
+        Self {
+            kind: ErrorType::WHIPClientRequest,
+            source: Some(Box::new(err)),
+        }
+    }
+}
+
+impl From<hyper::Error> for Error<dyn ErrorInner> {
+    fn from(err: hyper::Error) -> Self {
+        Self {
+            kind: ErrorType::WHIPClientRequest,
+            source: Some(Box::new(err)),
+        }
+    }
+}
+
+impl From<hyper_util::client::legacy::Error> for Error<dyn ErrorInner> {
+    fn from(err: hyper_util::client::legacy::Error) -> Self {
+        Self {
+            kind: ErrorType::WHIPClientRequest,
+            source: Some(Box::new(err)),
+        }
+    }
+}
+
+impl From<std::string::FromUtf8Error> for Error<dyn ErrorInner> {
+    fn from(err: std::string::FromUtf8Error) -> Self {
+        Self {
+            kind: ErrorType::WHIPClientRequest,
+            source: Some(Box::new(err)),
+        }
+    }
+}
+
+impl From<webrtc::Error> for Error<dyn ErrorInner> {
+    fn from(err: webrtc::Error) -> Self {
+        Self {
+            kind: ErrorType::WHIPClientPeer,
+            source: Some(Box::new(err)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::{server::conn::http1, service::service_fn};
+    use hyper_util::rt::TokioIo;
+    use std::net::{IpAddr, SocketAddr};
+    use tokio::net::TcpListener;
+
+    use crate::{
+        hub::Hub,
+        sources::{TakeoverPolicy, WHIP},
+    };
+
+    /// Serve a WHIP endpoint taking one publisher at a time at `/whip`,
+    /// returning its URL.
+    async fn serve(token: &str) -> String {
+        let whip = WHIP::new(IpAddr::from([127, 0, 0, 1]))
+            .token(token)
+            .takeover(TakeoverPolicy::Reject);
+        Hub::new().add_source(whip.clone()).await.unwrap();
+        let listener = TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], 0)))
+            .await
+            .unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let whip = whip.clone();
+                let service = service_fn(move |req| {
+                    let mut serve = whip.into_closure();
+                    async move {
+                        let resp = match serve(req).await.unwrap() {
+                            Ok(resp) => resp,
+                            Err(status) => http::Response::builder()
+                                .status(status)
+                                .body(String::new())
+                                .unwrap(),
+                        };
+                        Ok::<_, std::convert::Infallible>(resp)
+                    }
+                });
+                tokio::spawn(http1::Builder::new().serve_connection(TokioIo::new(stream), service));
+            }
+        });
+
+        format!("http://{addr}/whip")
+    }
+
+    #[test]
+    fn unsupported_codecs_are_refused() {
+        let err = WHIPClientBuilder::new("http://127.0.0.1/whip")
+            .codec(Codec::Opus)
+            .err()
+            .unwrap();
+        assert!(matches!(err.kind(), ErrorType::WHIPClientCodec));
+
+        let builder = WHIPClientBuilder::new("http://127.0.0.1/whip")
+            .codec(Codec::VP8)
+            .ok()
+            .unwrap();
+        assert_eq!(builder.codec, Codec::VP8);
+    }
+
+    #[tokio::test]
+    async fn publishes_to_the_endpoint_and_ends_the_session_on_close() {
+        let url = serve("secret").await;
+        let client = WHIPClientBuilder::new(&url)
+            .token("secret")
+            .ice(IceConfig::default())
+            .connect()
+            .await
+            .ok()
+            .unwrap();
+        let resource = client.resource.clone().unwrap();
+        assert!(resource.path().starts_with("/whip/"));
+        assert_eq!(client.codecs(), [Codec::Opus, Codec::H264]);
+
+        // The endpoint takes one publisher at a time, so another one only
+        // gets in once the session of the first is deleted.
+        let second = WHIPClientBuilder::new(&url).token("secret").connect().await;
+        assert!(matches!(
+            second.err().unwrap().kind(),
+            ErrorType::WHIPClientRequest
+        ));
+
+        client.close();
+        for _ in 0..100 {
+            if let Ok(second) = WHIPClientBuilder::new(&url).token("secret").connect().await {
+                second.close();
+                return;
+            }
+            sleep(Duration::from_millis(50)).await;
+        }
+        panic!("the session of the closed client was never deleted");
+    }
+
+    #[tokio::test]
+    async fn wrong_tokens_fail_to_connect() {
+        let url = serve("secret").await;
+        let err = WHIPClientBuilder::new(&url)
+            .token("guess")
+            .connect()
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err.kind(), ErrorType::WHIPClientRequest));
+    }
+
+    #[test]
+    fn relative_locations_resolve_against_the_endpoint() {
+        let url: Uri = "https://example.com/whip".parse().unwrap();
+        assert_eq!(
+            resolve_location(&url, "/whip/abc"),
+            Some("https://example.com/whip/abc".parse().unwrap())
+        );
+        assert_eq!(
+            resolve_location(&url, "https://other.example/session"),
+            Some("https://other.example/session".parse().unwrap())
+        );
+    }
+}
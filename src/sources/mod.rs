@@ -1,3 +1,70 @@
+use serde::Serialize;
+use std::pin::Pin;
+use tokio::sync::{mpsc, oneshot};
+use webrtc::{ice_transport::ice_connection_state::RTCIceConnectionState, media::Sample};
+
+use crate::utils::codecs::Codec;
+
+mod rtmp;
 mod whip;
 
-pub use whip::WHIP;
+pub use rtmp::{PublishRequest, RTMP, RTMPServer};
+pub use whip::{TakeoverPolicy, WHIP, WHIPRejection, has_bearer_token};
+
+pub trait Source {
+    fn read_event<'a>(&'a self) -> Pin<Box<dyn Future<Output = Option<SourceEvent>> + Send + 'a>>;
+
+    /// Told the codecs the registered mirrors accept whenever they change.
+    /// Sources able to negotiate pick from these.
+    fn set_accepted_codecs(&self, _codecs: &[Codec]) {}
+
+    /// Asked for a keyframe on behalf of the mirrors. Sources able to reach
+    /// the encoder pass the request on.
+    fn request_keyframe(&self) {}
+
+    /// What the source has received so far, for metrics.
+    fn stats(&self) -> SourceStats {
+        SourceStats::default()
+    }
+}
+
+/// Counts kept by sources that receive RTP. Other sources leave them at zero.
+#[derive(Clone, Copy, Default, Debug, Serialize)]
+pub struct SourceStats {
+    pub rtp_packets: u64,
+    /// Payload bytes of the RTP packets.
+    pub rtp_bytes: u64,
+    /// RTP packets the sample builders threw away, being late, duplicated or
+    /// undecodable.
+    pub dropped_packets: u64,
+    /// Interarrival jitter of the audio track in seconds, see RFC 3550.
+    pub audio_jitter: f64,
+    /// Interarrival jitter of the video track in seconds.
+    pub video_jitter: f64,
+}
+
+pub enum SourceEvent {
+    /// A publisher wants to go live. The hub answers whether it took the
+    /// source live, refusing while another of its sources is, and drops the
+    /// samples of sources that are not.
+    Connected(oneshot::Sender<bool>),
+    Disconnected,
+    /// The ICE connection of a WebRTC publisher changed state.
+    IceConnectionState(RTCIceConnectionState),
+    AudioSample(Codec, Sample),
+    VideoSample(Codec, Sample),
+}
+
+/// Ask the hub reading `source_tx` to take the source live, returning its
+/// answer.
+pub(crate) async fn go_live(source_tx: &mpsc::Sender<SourceEvent>) -> bool {
+    let (live_tx, live_rx) = oneshot::channel();
+    if source_tx
+        .send(SourceEvent::Connected(live_tx))
+        .await
+        .is_err()
+    {
+        return false;
+    }
+    live_rx.await.unwrap_or(false)
+}
@@ -0,0 +1,1070 @@
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use std::{
+    collections::HashMap,
+    error::Error as StdError,
+    io,
+    net::SocketAddr,
+    pin::Pin,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, SystemTime},
+};
+use subtle::ConstantTimeEq;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{
+        TcpListener, TcpStream,
+        tcp::{OwnedReadHalf, OwnedWriteHalf},
+    },
+    sync::{Mutex, mpsc, oneshot},
+    time::sleep,
+};
+use tracing::{debug, info, warn};
+use webrtc::media::Sample;
+
+use super::{Source, SourceEvent, go_live};
+use crate::{
+    error::{Error, ErrorType},
+    utils::{
+        amf::{self, Amf0Value},
+        codecs::Codec,
+        flv::{self, AudioSpecificConfig, AudioTag, AvcDecoderConfig, VideoTag},
+    },
+};
+
+const HANDSHAKE_SIZE: usize = 1536;
+const DEFAULT_CHUNK_SIZE: usize = 128;
+const OUT_CHUNK_SIZE: usize = 4096;
+const WINDOW_ACK_SIZE: u32 = 2_500_000;
+
+const MSG_SET_CHUNK_SIZE: u8 = 1;
+const MSG_ABORT: u8 = 2;
+const MSG_ACK: u8 = 3;
+const MSG_USER_CONTROL: u8 = 4;
+const MSG_WINDOW_ACK_SIZE: u8 = 5;
+const MSG_SET_PEER_BANDWIDTH: u8 = 6;
+const MSG_AUDIO: u8 = 8;
+const MSG_VIDEO: u8 = 9;
+const MSG_DATA_AMF3: u8 = 15;
+const MSG_COMMAND_AMF3: u8 = 17;
+const MSG_DATA_AMF0: u8 = 18;
+const MSG_COMMAND_AMF0: u8 = 20;
+
+const CSID_CONTROL: u8 = 2;
+const CSID_COMMAND: u8 = 3;
+const CSID_STATUS: u8 = 5;
+const STREAM_ID: u32 = 1;
+
+/// Chunk streams a peer may interleave messages on. Encoders use a handful.
+const MAX_CHUNK_STREAMS: usize = 64;
+/// Bytes of unfinished messages a peer may have buffered across all its chunk
+/// streams, enough for the largest message the header can announce.
+const MAX_BUFFERED: usize = 16 * 1024 * 1024;
+
+const VIDEO_FALLBACK_DURATION: Duration = Duration::from_millis(33);
+const OPUS_FALLBACK_DURATION: Duration = Duration::from_millis(20);
+
+/// Accepts RTMP publishers, handing each over to the stream its key names.
+/// Keys take the form `<stream>?token=<token>`, and every publisher waits on
+/// a [`PublishRequest`] for the caller to let it in or turn it away.
+#[derive(Clone)]
+pub struct RTMPServer {
+    local_addr: SocketAddr,
+    request_rx: Arc<Mutex<mpsc::Receiver<PublishRequest>>>,
+}
+
+impl RTMPServer {
+    pub async fn bind(addr: SocketAddr) -> Result<Self, Error<dyn ErrorInner>> {
+        let listener = TcpListener::bind(addr).await?;
+        let local_addr = listener.local_addr()?;
+        let (request_tx, request_rx) = mpsc::channel(16);
+
+        tokio::spawn(async move {
+            loop {
+                let (stream, peer) = match listener.accept().await {
+                    Ok(conn) => conn,
+                    Err(e) => {
+                        warn!("[RTMP] failed to accept connection: {e}");
+                        sleep(Duration::from_millis(100)).await;
+                        continue;
+                    }
+                };
+
+                let request_tx = request_tx.clone();
+                tokio::spawn(async move {
+                    debug!("[RTMP] connection from {peer}");
+                    let mut session = Session::new(stream, request_tx);
+                    if let Err(e) = session.run().await {
+                        debug!("[RTMP] connection from {peer} ended: {e}");
+                    }
+                    session.finish().await;
+                });
+            }
+        });
+
+        let request_rx = Arc::new(Mutex::new(request_rx));
+        Ok(Self {
+            local_addr,
+            request_rx,
+        })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// The next publisher asking to publish. Returns `None` once the server
+    /// is gone.
+    pub async fn next_request(&self) -> Option<PublishRequest> {
+        self.request_rx.lock().await.recv().await
+    }
+}
+
+/// A publisher asking to publish on a stream. Dropping the request turns the
+/// publisher away.
+pub struct PublishRequest {
+    stream: String,
+    token: Option<String>,
+    reply_tx: oneshot::Sender<RTMP>,
+}
+
+impl PublishRequest {
+    /// The stream named by the key.
+    pub fn stream(&self) -> &str {
+        &self.stream
+    }
+
+    /// Whether the key carries `token`, compared in constant time so the
+    /// token cannot be guessed from response times.
+    pub fn has_token(&self, token: &str) -> bool {
+        self.token
+            .as_ref()
+            .is_some_and(|presented| bool::from(presented.as_bytes().ct_eq(token.as_bytes())))
+    }
+
+    /// Let the publisher in, its media going to `source`. It is still turned
+    /// away if another publisher feeds `source`, or if the hub of `source`
+    /// has another source live.
+    pub fn accept(self, source: &RTMP) {
+        let _ = self.reply_tx.send(source.clone());
+    }
+
+    pub fn reject(self) {}
+}
+
+/// The source of a stream fed by the RTMP publishers let in through
+/// [`PublishRequest::accept`], one at a time.
+#[derive(Clone)]
+pub struct RTMP {
+    source_tx: mpsc::Sender<SourceEvent>,
+    source_rx: Arc<Mutex<mpsc::Receiver<SourceEvent>>>,
+    active: Arc<AtomicBool>,
+}
+
+impl RTMP {
+    pub fn new() -> Self {
+        let (source_tx, source_rx) = mpsc::channel(64);
+        Self {
+            source_tx,
+            source_rx: Arc::new(Mutex::new(source_rx)),
+            active: Arc::default(),
+        }
+    }
+}
+
+impl Default for RTMP {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Source for RTMP {
+    fn read_event<'a>(&'a self) -> Pin<Box<dyn Future<Output = Option<SourceEvent>> + Send + 'a>> {
+        Box::pin(async move { self.source_rx.lock().await.recv().await })
+    }
+}
+
+struct Session {
+    reader: BufReader<OwnedReadHalf>,
+    writer: OwnedWriteHalf,
+    request_tx: mpsc::Sender<PublishRequest>,
+    /// The source this connection publishes to, once let in.
+    source: Option<RTMP>,
+    chunk_size: usize,
+    out_chunk_size: usize,
+    chunks: HashMap<u32, ChunkState>,
+    window_ack_size: u32,
+    bytes_read: u64,
+    bytes_acked: u64,
+    avc_config: Option<AvcDecoderConfig>,
+    aac_config: Option<AudioSpecificConfig>,
+    audio: Track,
+    video: Track,
+    last_error: Option<String>,
+}
+
+#[derive(Default)]
+struct ChunkState {
+    timestamp: u32,
+    delta: u32,
+    length: usize,
+    type_id: u8,
+    stream_id: u32,
+    extended: bool,
+    payload: BytesMut,
+}
+
+struct Message {
+    type_id: u8,
+    timestamp: u32,
+    payload: Bytes,
+}
+
+impl Session {
+    fn new(stream: TcpStream, request_tx: mpsc::Sender<PublishRequest>) -> Self {
+        let _ = stream.set_nodelay(true);
+        let (reader, writer) = stream.into_split();
+
+        Self {
+            reader: BufReader::new(reader),
+            writer,
+            request_tx,
+            source: None,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            out_chunk_size: DEFAULT_CHUNK_SIZE,
+            chunks: HashMap::new(),
+            window_ack_size: 0,
+            bytes_read: 0,
+            bytes_acked: 0,
+            avc_config: None,
+            aac_config: None,
+            audio: Track::default(),
+            video: Track::default(),
+            last_error: None,
+        }
+    }
+
+    async fn run(&mut self) -> Result<(), Error<dyn ErrorInner>> {
+        self.handshake().await?;
+
+        loop {
+            let message = self.read_message().await?;
+            self.acknowledge().await?;
+
+            match message.type_id {
+                MSG_SET_CHUNK_SIZE => {
+                    if let Some(size) = read_u32(&message.payload) {
+                        self.chunk_size = (size & 0x7FFF_FFFF).max(1) as usize;
+                    }
+                }
+                MSG_ABORT => {
+                    if let Some(csid) = read_u32(&message.payload)
+                        && let Some(state) = self.chunks.get_mut(&csid)
+                    {
+                        state.payload.clear();
+                    }
+                }
+                MSG_WINDOW_ACK_SIZE => {
+                    if let Some(size) = read_u32(&message.payload) {
+                        self.window_ack_size = size;
+                    }
+                }
+                MSG_AUDIO => self.handle_audio(message).await,
+                MSG_VIDEO => self.handle_video(message).await,
+                MSG_COMMAND_AMF0 | MSG_COMMAND_AMF3 => {
+                    let mut payload = &message.payload[..];
+                    if message.type_id == MSG_COMMAND_AMF3 && payload.has_remaining() {
+                        payload.advance(1);
+                    }
+                    if !self.handle_command(payload).await? {
+                        return Ok(());
+                    }
+                }
+                MSG_ACK
+                | MSG_USER_CONTROL
+                | MSG_SET_PEER_BANDWIDTH
+                | MSG_DATA_AMF0
+                | MSG_DATA_AMF3 => {}
+                type_id => debug!("[RTMP] ignoring message type {type_id}"),
+            }
+        }
+    }
+
+    /// Flush the pending samples and hand the source back, if this
+    /// connection was publishing to it.
+    async fn finish(&mut self) {
+        if let Some((codec, sample)) = self.audio.flush() {
+            self.send(SourceEvent::AudioSample(codec, sample)).await;
+        }
+        if let Some((codec, sample)) = self.video.flush() {
+            self.send(SourceEvent::VideoSample(codec, sample)).await;
+        }
+
+        let Some(source) = self.source.take() else {
+            return;
+        };
+        source.active.store(false, Ordering::SeqCst);
+        let _ = source.source_tx.send(SourceEvent::Disconnected).await;
+        info!("[RTMP] publisher disconnected");
+    }
+
+    async fn send(&self, event: SourceEvent) {
+        if let Some(source) = &self.source {
+            let _ = source.source_tx.send(event).await;
+        }
+    }
+
+    async fn handshake(&mut self) -> Result<(), Error<dyn ErrorInner>> {
+        let mut c0c1 = [0; 1 + HANDSHAKE_SIZE];
+        self.read_exact(&mut c0c1).await?;
+        if c0c1[0] != 3 {
+            debug!("[RTMP] client requested protocol version {}", c0c1[0]);
+        }
+
+        let mut s1 = [0; HANDSHAKE_SIZE];
+        rand::fill(&mut s1[8..]);
+
+        let mut s0s1s2 = Vec::with_capacity(1 + 2 * HANDSHAKE_SIZE);
+        s0s1s2.put_u8(3);
+        s0s1s2.put_slice(&s1);
+        s0s1s2.put_slice(&c0c1[1..]);
+        self.writer.write_all(&s0s1s2).await?;
+
+        let mut c2 = [0; HANDSHAKE_SIZE];
+        self.read_exact(&mut c2).await?;
+
+        Ok(())
+    }
+
+    async fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), Error<dyn ErrorInner>> {
+        self.reader.read_exact(buf).await?;
+        self.bytes_read += buf.len() as u64;
+        Ok(())
+    }
+
+    async fn read_message(&mut self) -> Result<Message, Error<dyn ErrorInner>> {
+        loop {
+            let mut basic = [0; 1];
+            self.read_exact(&mut basic).await?;
+            let fmt = basic[0] >> 6;
+            let csid = match basic[0] & 0x3F {
+                0 => {
+                    let mut ext = [0; 1];
+                    self.read_exact(&mut ext).await?;
+                    64 + ext[0] as u32
+                }
+                1 => {
+                    let mut ext = [0; 2];
+                    self.read_exact(&mut ext).await?;
+                    64 + ext[0] as u32 + ((ext[1] as u32) << 8)
+                }
+                csid => csid as u32,
+            };
+
+            let mut header = [0; 11];
+            let header_len = [11, 7, 3, 0][fmt as usize];
+            self.read_exact(&mut header[..header_len]).await?;
+
+            if self.chunks.len() >= MAX_CHUNK_STREAMS && !self.chunks.contains_key(&csid) {
+                return Err(protocol_error());
+            }
+            let mut state = self.chunks.remove(&csid).unwrap_or_default();
+            let new_message = state.payload.is_empty();
+
+            let mut field = 0;
+            if fmt <= 2 {
+                field = read_u24(&header[0..3]);
+                state.extended = field == 0xFF_FFFF;
+            }
+            if fmt <= 1 {
+                state.length = read_u24(&header[3..6]) as usize;
+                state.type_id = header[6];
+            }
+            if fmt == 0 {
+                state.stream_id = u32::from_le_bytes([header[7], header[8], header[9], header[10]]);
+            }
+            if state.extended {
+                let mut ext = [0; 4];
+                self.read_exact(&mut ext).await?;
+                field = u32::from_be_bytes(ext);
+            }
+
+            match fmt {
+                0 => {
+                    state.timestamp = field;
+                    state.delta = 0;
+                }
+                1 | 2 => {
+                    state.delta = field;
+                    state.timestamp = state.timestamp.wrapping_add(field);
+                }
+                _ if new_message => {
+                    state.timestamp = state.timestamp.wrapping_add(state.delta);
+                }
+                _ => {}
+            }
+
+            let start = state.payload.len();
+            let size = self.chunk_size.min(state.length.saturating_sub(start));
+            let buffered: usize = self.chunks.values().map(|state| state.payload.len()).sum();
+            if buffered + start + size > MAX_BUFFERED {
+                return Err(protocol_error());
+            }
+            state.payload.resize(start + size, 0);
+            self.read_exact(&mut state.payload[start..]).await?;
+
+            if state.payload.len() >= state.length {
+                let message = Message {
+                    type_id: state.type_id,
+                    timestamp: state.timestamp,
+                    payload: state.payload.split().freeze(),
+                };
+                self.chunks.insert(csid, state);
+                return Ok(message);
+            }
+            self.chunks.insert(csid, state);
+        }
+    }
+
+    async fn acknowledge(&mut self) -> Result<(), Error<dyn ErrorInner>> {
+        if self.window_ack_size == 0
+            || self.bytes_read - self.bytes_acked < self.window_ack_size as u64
+        {
+            return Ok(());
+        }
+
+        self.bytes_acked = self.bytes_read;
+        let sequence = (self.bytes_read as u32).to_be_bytes();
+        self.write_message(CSID_CONTROL, MSG_ACK, 0, &sequence)
+            .await
+    }
+
+    async fn write_message(
+        &mut self,
+        csid: u8,
+        type_id: u8,
+        stream_id: u32,
+        payload: &[u8],
+    ) -> Result<(), Error<dyn ErrorInner>> {
+        let mut buf = Vec::with_capacity(payload.len() + 16);
+        buf.put_u8(csid);
+        buf.put_uint(0, 3);
+        buf.put_uint(payload.len() as u64, 3);
+        buf.put_u8(type_id);
+        buf.put_u32_le(stream_id);
+        for (i, chunk) in payload.chunks(self.out_chunk_size).enumerate() {
+            if i > 0 {
+                buf.put_u8(0xC0 | csid);
+            }
+            buf.put_slice(chunk);
+        }
+
+        self.writer.write_all(&buf).await?;
+        Ok(())
+    }
+
+    async fn write_command(
+        &mut self,
+        csid: u8,
+        stream_id: u32,
+        values: &[Amf0Value],
+    ) -> Result<(), Error<dyn ErrorInner>> {
+        let mut payload = Vec::new();
+        for value in values {
+            amf::encode(value, &mut payload);
+        }
+        self.write_message(csid, MSG_COMMAND_AMF0, stream_id, &payload)
+            .await
+    }
+
+    /// Returns `false` once the client asked to stop publishing.
+    async fn handle_command(&mut self, payload: &[u8]) -> Result<bool, Error<dyn ErrorInner>> {
+        let values = match amf::decode_all(payload) {
+            Ok(values) => values,
+            Err(e) => {
+                warn!("[RTMP] malformed command: {e}");
+                return Ok(true);
+            }
+        };
+        let Some(name) = values.first().and_then(Amf0Value::as_str) else {
+            return Ok(true);
+        };
+        let txid = values.get(1).and_then(Amf0Value::as_number).unwrap_or(0.0);
+
+        match name {
+            "connect" => {
+                let app = values
+                    .get(2)
+                    .and_then(|object| object.get("app"))
+                    .and_then(Amf0Value::as_str)
+                    .unwrap_or_default();
+                debug!("[RTMP] connect to app {app}");
+
+                self.write_message(
+                    CSID_CONTROL,
+                    MSG_WINDOW_ACK_SIZE,
+                    0,
+                    &WINDOW_ACK_SIZE.to_be_bytes(),
+                )
+                .await?;
+                let mut bandwidth = WINDOW_ACK_SIZE.to_be_bytes().to_vec();
+                bandwidth.push(2);
+                self.write_message(CSID_CONTROL, MSG_SET_PEER_BANDWIDTH, 0, &bandwidth)
+                    .await?;
+                self.write_message(
+                    CSID_CONTROL,
+                    MSG_SET_CHUNK_SIZE,
+                    0,
+                    &(OUT_CHUNK_SIZE as u32).to_be_bytes(),
+                )
+                .await?;
+                self.out_chunk_size = OUT_CHUNK_SIZE;
+
+                let properties = Amf0Value::Object(vec![
+                    ("fmsVer".into(), Amf0Value::String("FMS/3,0,1,123".into())),
+                    ("capabilities".into(), Amf0Value::Number(31.0)),
+                ]);
+                let information = Amf0Value::Object(vec![
+                    ("level".into(), Amf0Value::String("status".into())),
+                    (
+                        "code".into(),
+                        Amf0Value::String("NetConnection.Connect.Success".into()),
+                    ),
+                    (
+                        "description".into(),
+                        Amf0Value::String("Connection succeeded.".into()),
+                    ),
+                    ("objectEncoding".into(), Amf0Value::Number(0.0)),
+                ]);
+                self.write_command(
+                    CSID_COMMAND,
+                    0,
+                    &[
+                        Amf0Value::String("_result".into()),
+                        Amf0Value::Number(txid),
+                        properties,
+                        information,
+                    ],
+                )
+                .await?;
+            }
+            "releaseStream" | "FCPublish" => {
+                self.write_command(
+                    CSID_COMMAND,
+                    0,
+                    &[
+                        Amf0Value::String("_result".into()),
+                        Amf0Value::Number(txid),
+                        Amf0Value::Null,
+                        Amf0Value::Undefined,
+                    ],
+                )
+                .await?;
+            }
+            "createStream" => {
+                self.write_command(
+                    CSID_COMMAND,
+                    0,
+                    &[
+                        Amf0Value::String("_result".into()),
+                        Amf0Value::Number(txid),
+                        Amf0Value::Null,
+                        Amf0Value::Number(STREAM_ID as f64),
+                    ],
+                )
+                .await?;
+            }
+            "publish" => {
+                let key = values
+                    .get(3)
+                    .and_then(Amf0Value::as_str)
+                    .unwrap_or_default();
+                let (stream, token) = parse_key(key);
+
+                let (reply_tx, reply_rx) = oneshot::channel();
+                let request = PublishRequest {
+                    stream: stream.clone(),
+                    token,
+                    reply_tx,
+                };
+                let source = match self.request_tx.send(request).await {
+                    Ok(()) => reply_rx.await.ok(),
+                    Err(_) => None,
+                };
+                let Some(source) =
+                    source.filter(|source| !source.active.swap(true, Ordering::SeqCst))
+                else {
+                    warn!("[RTMP] rejecting publish of {stream}");
+                    return self.refuse_publish().await;
+                };
+                // The hub of the stream may have another source live.
+                if !go_live(&source.source_tx).await {
+                    source.active.store(false, Ordering::SeqCst);
+                    warn!("[RTMP] rejecting publish of {stream}, the stream is live");
+                    return self.refuse_publish().await;
+                }
+                self.source = Some(source);
+
+                let mut stream_begin = vec![0, 0];
+                stream_begin.put_u32(STREAM_ID);
+                self.write_message(CSID_CONTROL, MSG_USER_CONTROL, 0, &stream_begin)
+                    .await?;
+                self.write_command(
+                    CSID_STATUS,
+                    STREAM_ID,
+                    &on_status(
+                        "status",
+                        "NetStream.Publish.Start",
+                        &format!("{stream} is now published."),
+                    ),
+                )
+                .await?;
+
+                info!("[RTMP] publisher connected to stream {stream}");
+            }
+            "FCUnpublish" | "deleteStream" | "closeStream" => return Ok(false),
+            name => debug!("[RTMP] ignoring command {name}"),
+        }
+
+        Ok(true)
+    }
+
+    /// Tell the publisher it may not publish, ending the connection.
+    async fn refuse_publish(&mut self) -> Result<bool, Error<dyn ErrorInner>> {
+        self.write_command(
+            CSID_STATUS,
+            STREAM_ID,
+            &on_status(
+                "error",
+                "NetStream.Publish.BadName",
+                "Stream may not be published.",
+            ),
+        )
+        .await?;
+        Ok(false)
+    }
+
+    async fn handle_video(&mut self, message: Message) {
+        if self.source.is_none() {
+            return;
+        }
+
+        let tag = match flv::parse_video_tag(message.payload) {
+            Ok(Some(tag)) => tag,
+            Ok(None) => return,
+            Err(e) => return self.drop_media(e),
+        };
+
+        match tag {
+            VideoTag::SequenceStart { config, .. } => match AvcDecoderConfig::parse(config) {
+                Ok(config) => self.avc_config = Some(config),
+                Err(e) => self.drop_media(e),
+            },
+            VideoTag::CodedFrames { codec, data, .. } => {
+                let Some(config) = &self.avc_config else {
+                    return self.drop_media("video frame arrived before its decoder configuration");
+                };
+                let data = match flv::avcc_to_annexb(&data, config) {
+                    Ok(data) => data,
+                    Err(e) => return self.drop_media(e),
+                };
+
+                let next = PendingSample {
+                    codec,
+                    timestamp: message.timestamp,
+                    fallback: VIDEO_FALLBACK_DURATION,
+                    data,
+                };
+                if let Some((codec, sample)) = self.video.push(next) {
+                    self.send(SourceEvent::VideoSample(codec, sample)).await;
+                }
+            }
+            VideoTag::SequenceEnd { .. } => {}
+        }
+    }
+
+    async fn handle_audio(&mut self, message: Message) {
+        if self.source.is_none() {
+            return;
+        }
+
+        let tag = match flv::parse_audio_tag(message.payload) {
+            Ok(Some(tag)) => tag,
+            Ok(None) => return,
+            Err(e) => return self.drop_media(e),
+        };
+
+        let next = match tag {
+            AudioTag::SequenceStart {
+                codec: Codec::AAC,
+                config,
+            } => {
+                match AudioSpecificConfig::parse(&config) {
+                    Ok(config) => {
+                        warn!(
+                            "[RTMP] publisher sends aac audio, mirrors which only accept opus will not receive audio"
+                        );
+                        self.aac_config = Some(config);
+                    }
+                    Err(e) => self.drop_media(e),
+                }
+                return;
+            }
+            AudioTag::CodedFrames {
+                codec: Codec::AAC,
+                data,
+            } => {
+                let Some(config) = self.aac_config else {
+                    return self.drop_media("aac frame arrived before its decoder configuration");
+                };
+                PendingSample {
+                    codec: Codec::AAC,
+                    timestamp: message.timestamp,
+                    fallback: Duration::from_secs_f64(1024.0 / config.sample_rate() as f64),
+                    data: config.to_adts(&data),
+                }
+            }
+            AudioTag::CodedFrames { codec, data } => PendingSample {
+                codec,
+                timestamp: message.timestamp,
+                fallback: OPUS_FALLBACK_DURATION,
+                data,
+            },
+            AudioTag::SequenceStart { .. } | AudioTag::SequenceEnd { .. } => return,
+        };
+
+        if let Some((codec, sample)) = self.audio.push(next) {
+            self.send(SourceEvent::AudioSample(codec, sample)).await;
+        }
+    }
+
+    fn drop_media(&mut self, reason: impl Into<String>) {
+        let reason = reason.into();
+        if self.last_error.as_ref() == Some(&reason) {
+            debug!("[RTMP] dropping media: {reason}");
+            return;
+        }
+        warn!("[RTMP] dropping media: {reason}");
+        self.last_error = Some(reason);
+    }
+}
+
+struct PendingSample {
+    codec: Codec,
+    timestamp: u32,
+    fallback: Duration,
+    data: Bytes,
+}
+
+/// Holds back the latest sample of a track until the next one arrives, as
+/// FLV tags only carry a timestamp and the duration is the distance to the
+/// following tag.
+#[derive(Default)]
+struct Track {
+    pending: Option<PendingSample>,
+}
+
+impl Track {
+    fn push(&mut self, next: PendingSample) -> Option<(Codec, Sample)> {
+        let delta = self
+            .pending
+            .as_ref()
+            .map(|prev| next.timestamp.wrapping_sub(prev.timestamp));
+        let prev = self.pending.replace(next)?;
+
+        let duration = match delta {
+            Some(delta) if delta > 0 && delta < 1000 => Duration::from_millis(delta as u64),
+            _ => prev.fallback,
+        };
+        Some(into_sample(prev, duration))
+    }
+
+    fn flush(&mut self) -> Option<(Codec, Sample)> {
+        let prev = self.pending.take()?;
+        let duration = prev.fallback;
+        Some(into_sample(prev, duration))
+    }
+}
+
+fn into_sample(pending: PendingSample, duration: Duration) -> (Codec, Sample) {
+    let sample = Sample {
+        data: pending.data,
+        timestamp: SystemTime::now(),
+        duration,
+        ..Default::default()
+    };
+    (pending.codec, sample)
+}
+
+/// Split a stream key of the form `<stream>?token=<token>` into the stream
+/// name and the token.
+fn parse_key(key: &str) -> (String, Option<String>) {
+    let (stream, query) = key.split_once('?').unwrap_or((key, ""));
+    let token = query
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("token="))
+        .map(str::to_owned);
+    (stream.to_owned(), token)
+}
+
+fn on_status(level: &str, code: &str, description: &str) -> [Amf0Value; 4] {
+    [
+        Amf0Value::String("onStatus".into()),
+        Amf0Value::Number(0.0),
+        Amf0Value::Null,
+        Amf0Value::Object(vec![
+            ("level".into(), Amf0Value::String(level.into())),
+            ("code".into(), Amf0Value::String(code.into())),
+            ("description".into(), Amf0Value::String(description.into())),
+        ]),
+    ]
+}
+
+fn read_u24(data: &[u8]) -> u32 {
+    ((data[0] as u32) << 16) | ((data[1] as u32) << 8) | data[2] as u32
+}
+
+fn read_u32(mut data: &[u8]) -> Option<u32> {
+    (data.remaining() >= 4).then(|| data.get_u32())
+}
+
+fn protocol_error() -> Error<dyn ErrorInner> {
+    Error {
+        kind: ErrorType::RTMPProtocol,
+        source: None,
+    }
+}
+
+pub trait ErrorInner: StdError + Send + Sync {}
+
+impl<T: StdError + Send + Sync> ErrorInner for T {}
+
+impl StdError for Error<dyn ErrorInner> {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        self.source
+            .as_ref()
+            .map(|source| &**source as &(dyn StdError + 'static))
+    }
+}
+
+impl From<io::Error> for Error<dyn ErrorInner> {
+    fn from(err: io::Error) -> Self {
+        Self {
+            kind: ErrorType::RTMPSocket,
+            source: Some(Box::new(err)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::time::timeout;
+
+    /// A publisher speaking just enough RTMP to reach the server.
+    struct Client {
+        stream: TcpStream,
+    }
+
+    impl Client {
+        async fn connect(server: &RTMPServer) -> Self {
+            let mut stream = TcpStream::connect(server.local_addr()).await.unwrap();
+            let mut c0c1 = vec![3];
+            c0c1.resize(1 + HANDSHAKE_SIZE, 0);
+            stream.write_all(&c0c1).await.unwrap();
+            let mut s0s1s2 = vec![0; 1 + 2 * HANDSHAKE_SIZE];
+            stream.read_exact(&mut s0s1s2).await.unwrap();
+            stream.write_all(&[0; HANDSHAKE_SIZE]).await.unwrap();
+            Self { stream }
+        }
+
+        /// Start a message of `length` bytes on `csid`, sending `data` of it.
+        async fn chunk(&mut self, csid: u32, type_id: u8, length: usize, data: &[u8]) {
+            let mut buf = Vec::new();
+            if csid < 64 {
+                buf.put_u8(csid as u8);
+            } else {
+                buf.put_u8(0);
+                buf.put_u8((csid - 64) as u8);
+            }
+            buf.put_uint(0, 3);
+            buf.put_uint(length as u64, 3);
+            buf.put_u8(type_id);
+            buf.put_u32_le(STREAM_ID);
+            buf.put_slice(data);
+            // The server may already have hung up.
+            let _ = self.stream.write_all(&buf).await;
+        }
+
+        async fn publish(&mut self, key: &str) {
+            let mut payload = Vec::new();
+            for value in [
+                Amf0Value::String("publish".into()),
+                Amf0Value::Number(5.0),
+                Amf0Value::Null,
+                Amf0Value::String(key.into()),
+                Amf0Value::String("live".into()),
+            ] {
+                amf::encode(&value, &mut payload);
+            }
+            self.chunk(
+                CSID_COMMAND as u32,
+                MSG_COMMAND_AMF0,
+                payload.len(),
+                &payload,
+            )
+            .await;
+        }
+
+        /// Wait for the server to hang up.
+        async fn closed(mut self) {
+            let mut rest = Vec::new();
+            timeout(Duration::from_secs(5), self.stream.read_to_end(&mut rest))
+                .await
+                .expect("connection stayed open")
+                .ok();
+        }
+    }
+
+    async fn server() -> RTMPServer {
+        RTMPServer::bind(SocketAddr::from(([127, 0, 0, 1], 0)))
+            .await
+            .unwrap()
+    }
+
+    /// Answer the request of the publisher of `source` to go live, as its hub
+    /// would.
+    async fn answer(source: &RTMP, live: bool) {
+        let Some(SourceEvent::Connected(live_tx)) = source.read_event().await else {
+            panic!("the publisher did not ask to go live");
+        };
+        live_tx.send(live).unwrap();
+    }
+
+    #[test]
+    fn keys_name_a_stream_and_a_token() {
+        assert_eq!(
+            parse_key("live?token=secret"),
+            ("live".into(), Some("secret".into()))
+        );
+        assert_eq!(
+            parse_key("studio?foo=1&token=x"),
+            ("studio".into(), Some("x".into()))
+        );
+        assert_eq!(parse_key("live"), ("live".into(), None));
+    }
+
+    #[tokio::test]
+    async fn publishers_feed_the_source_they_are_given() {
+        let server = server().await;
+        let mut client = Client::connect(&server).await;
+        client.publish("studio?token=secret").await;
+
+        let request = server.next_request().await.unwrap();
+        assert_eq!(request.stream(), "studio");
+        assert!(request.has_token("secret"));
+        assert!(!request.has_token("secreT"));
+        let source = RTMP::new();
+        request.accept(&source);
+        answer(&source, true).await;
+
+        drop(client);
+        assert!(matches!(
+            source.read_event().await,
+            Some(SourceEvent::Disconnected)
+        ));
+    }
+
+    #[tokio::test]
+    async fn rejected_publishers_are_disconnected() {
+        let server = server().await;
+        let mut client = Client::connect(&server).await;
+        client.publish("live").await;
+
+        let request = server.next_request().await.unwrap();
+        assert!(!request.has_token(""));
+        request.reject();
+        client.closed().await;
+    }
+
+    #[tokio::test]
+    async fn publishers_the_hub_refuses_are_disconnected() {
+        let server = server().await;
+        let source = RTMP::new();
+        let mut first = Client::connect(&server).await;
+        first.publish("live").await;
+        server.next_request().await.unwrap().accept(&source);
+        answer(&source, false).await;
+        first.closed().await;
+
+        // The source is free for the next publisher.
+        let mut second = Client::connect(&server).await;
+        second.publish("live").await;
+        server.next_request().await.unwrap().accept(&source);
+        answer(&source, true).await;
+    }
+
+    #[tokio::test]
+    async fn sources_take_one_publisher_at_a_time() {
+        let server = server().await;
+        let source = RTMP::new();
+        let mut first = Client::connect(&server).await;
+        first.publish("live").await;
+        server.next_request().await.unwrap().accept(&source);
+        answer(&source, true).await;
+
+        let mut second = Client::connect(&server).await;
+        second.publish("live").await;
+        server.next_request().await.unwrap().accept(&source);
+        second.closed().await;
+
+        // The turned away publisher leaves the first one live.
+        drop(first);
+        assert!(matches!(
+            source.read_event().await,
+            Some(SourceEvent::Disconnected)
+        ));
+    }
+
+    #[tokio::test]
+    async fn too_many_chunk_streams_end_the_connection() {
+        let server = server().await;
+        let mut client = Client::connect(&server).await;
+        for csid in 64..64 + MAX_CHUNK_STREAMS as u32 + 1 {
+            client
+                .chunk(
+                    csid,
+                    MSG_VIDEO,
+                    2 * DEFAULT_CHUNK_SIZE,
+                    &[0; DEFAULT_CHUNK_SIZE],
+                )
+                .await;
+        }
+        client.closed().await;
+    }
+
+    #[tokio::test]
+    async fn too_much_buffered_data_ends_the_connection() {
+        let server = server().await;
+        let mut client = Client::connect(&server).await;
+        let chunk_size = MAX_BUFFERED / 2;
+        client
+            .chunk(
+                CSID_CONTROL as u32,
+                MSG_SET_CHUNK_SIZE,
+                4,
+                &(chunk_size as u32).to_be_bytes(),
+            )
+            .await;
+
+        // Each message is cut after its first chunk, half the limit.
+        let length = 0xFF_FFFF;
+        let data = vec![0; chunk_size];
+        client.chunk(4, MSG_VIDEO, length, &data).await;
+        client.chunk(6, MSG_VIDEO, length, &data).await;
+        client.chunk(4, MSG_VIDEO, length, &data).await;
+        client.closed().await;
+    }
+}
@@ -1,30 +1,45 @@
-use http::{Request, Response, StatusCode, header::LOCATION};
+use http::{
+    Method, Request, Response, StatusCode,
+    header::{AUTHORIZATION, CONTENT_TYPE, ETAG, IF_MATCH, LOCATION, WWW_AUTHENTICATE},
+};
 use http_body::Body;
-use http_body_util::BodyExt;
 use std::{
-    collections::VecDeque, convert::Infallible, error::Error as StdError, net::IpAddr, pin::Pin,
-    sync::Arc, time::Duration,
+    convert::Infallible,
+    error::Error as StdError,
+    fmt::{Display, Formatter, Result as FmtResult},
+    net::IpAddr,
+    pin::Pin,
+    str::FromStr,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
 };
+use subtle::ConstantTimeEq;
 use tokio::{
     sync::{
-        RwLock,
+        Mutex,
         mpsc::{self, error::SendError},
         oneshot::{self, error::RecvError},
     },
     time::sleep,
 };
 use tracing::{debug, info, warn};
+use uuid::Uuid;
 use webrtc::{
     api::{
         APIBuilder,
         interceptor_registry::register_default_interceptors,
-        media_engine::{MIME_TYPE_H264, MIME_TYPE_OPUS, MediaEngine},
+        media_engine::{MIME_TYPE_OPUS, MediaEngine},
         setting_engine::SettingEngine,
     },
-    ice_transport::ice_connection_state::RTCIceConnectionState,
+    ice_transport::{
+        ice_candidate::RTCIceCandidateInit, ice_connection_state::RTCIceConnectionState,
+    },
     interceptor::registry::Registry,
-    media::Sample,
     peer_connection::{
+        RTCPeerConnection,
         configuration::RTCConfiguration,
         policy::{
             bundle_policy::RTCBundlePolicy, ice_transport_policy::RTCIceTransportPolicy,
@@ -33,73 +48,165 @@ use webrtc::{
         sdp::session_description::RTCSessionDescription,
     },
     rtcp::payload_feedbacks::picture_loss_indication::PictureLossIndication,
-    rtp::codecs::opus::OpusPacket,
+    rtp::{
+        codecs::{opus::OpusPacket, vp8::Vp8Packet, vp9::Vp9Packet},
+        packetizer::Depacketizer,
+    },
     rtp_transceiver::{
         RTCRtpTransceiverInit,
         rtp_codec::{RTCRtpCodecCapability, RTCRtpCodecParameters, RTPCodecType},
         rtp_transceiver_direction::RTCRtpTransceiverDirection,
     },
+    track::track_remote::TrackRemote,
 };
 
+use super::{Source, SourceEvent, SourceStats, go_live};
 use crate::{
     error::{Error, ErrorType},
-    mirrors::Mirror,
-    utils::{codecs::H264Packet, io::SampleBuilder},
+    utils::{
+        codecs::{Av1Packet, Codec, H264Packet, H265Packet},
+        ice::{ICE_DISCONNECTED_TIMEOUT, IceConfig},
+        io::SampleBuilder,
+        request::{SDP_CONTENT_TYPE, has_content_type, read_body},
+    },
 };
 
 #[derive(Clone)]
 pub struct WHIP {
     inner_tx: mpsc::UnboundedSender<WHIPEvent>,
+    source_rx: Arc<Mutex<mpsc::Receiver<SourceEvent>>>,
+    token: Option<Arc<str>>,
+    policy: Policy,
+    ice: Arc<IceConfig>,
+    counters: Arc<Counters>,
 }
 
 impl WHIP {
     pub fn new(host: IpAddr) -> Self {
         let inner = mpsc::unbounded_channel();
         let (inner_tx_a, inner_tx_b, mut inner_rx) = (inner.0.clone(), inner.0, inner.1);
-        let inner: Arc<WHIPInner> = Arc::new(WHIPInner::default());
+        let (source_tx, source_rx) = mpsc::channel(64);
+        let counters = Arc::new(Counters::default());
 
         let inner_tx = inner_tx_a;
+        let session_counters = counters.clone();
         tokio::spawn(async move {
-            let mut active = false;
+            let mut session: Option<Session> = None;
+            let mut accepted_codecs = Vec::new();
 
             while let Some(payload) = inner_rx.recv().await {
                 match payload {
-                    WHIPEvent::NewRequest(offer, path, resp_tx) => {
-                        if active {
+                    WHIPEvent::NewRequest(offer, path, policy, ice, resp_tx) => {
+                        if let Some(live) = &session
+                            && policy.takeover == TakeoverPolicy::Reject
+                        {
+                            info!("[WebRTC] refusing publisher, session {} is live", live.id);
+                            let _ = resp_tx.send(WHIPRejection::Busy.response());
                             continue;
                         }
 
-                        let Ok(sdp) = init_peer(host, &inner, offer, inner_tx.clone()).await else {
-                            let _ = resp_tx.send(Err(StatusCode::INTERNAL_SERVER_ERROR));
-                            continue;
+                        let id = Uuid::new_v4().simple().to_string();
+                        let peer = init_peer(
+                            host,
+                            &ice,
+                            offer,
+                            &id,
+                            &accepted_codecs,
+                            policy.pli_interval,
+                            source_tx.clone(),
+                            inner_tx.clone(),
+                            session_counters.clone(),
+                        )
+                        .await;
+                        let (sdp, peer) = match peer {
+                            Ok(peer) => peer,
+                            Err(err) if matches!(err.kind(), ErrorType::WHIPOffer) => {
+                                warn!("[WebRTC] refusing offer: {err}");
+                                let _ = resp_tx.send(WHIPRejection::MalformedOffer.response());
+                                continue;
+                            }
+                            Err(err) => {
+                                warn!("[WebRTC] failed to answer offer: {err}");
+                                let _ = resp_tx.send(Err(StatusCode::INTERNAL_SERVER_ERROR));
+                                continue;
+                            }
                         };
 
+                        // Only a publisher whose offer was taken may end the
+                        // live session.
+                        if let Some(live) = session.take() {
+                            info!("[WebRTC] session {} taken over", live.id);
+                            live.end(&source_tx).await;
+                        }
+                        // Another source of the hub may be live.
+                        if !go_live(&source_tx).await {
+                            info!("[WebRTC] refusing publisher, another source is live");
+                            let _ = peer.close().await;
+                            let _ = resp_tx.send(WHIPRejection::Busy.response());
+                            continue;
+                        }
+
+                        let new_session = Session {
+                            id,
+                            etag: new_etag(),
+                            peer,
+                        };
                         let resp = Response::builder()
-                            .header(LOCATION, path)
+                            .header(
+                                LOCATION,
+                                format!("{}/{}", path.trim_end_matches('/'), new_session.id),
+                            )
+                            .header(CONTENT_TYPE, SDP_CONTENT_TYPE)
+                            .header(ETAG, &new_session.etag)
                             .status(StatusCode::CREATED)
                             .body(sdp)
                             .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR);
                         let _ = resp_tx.send(resp);
 
-                        active = true;
+                        info!("[WebRTC] session {} started", new_session.id);
+                        session = Some(new_session);
+                    }
+                    WHIPEvent::PatchRequest(id, if_match, fragment, resp_tx) => {
+                        let Some(session) = session.as_mut().filter(|session| session.id == id)
+                        else {
+                            let _ = resp_tx.send(Err(StatusCode::NOT_FOUND));
+                            continue;
+                        };
+                        if let Some(if_match) = if_match
+                            && if_match != "*"
+                            && if_match != session.etag
+                        {
+                            let _ = resp_tx.send(Err(StatusCode::PRECONDITION_FAILED));
+                            continue;
+                        }
+
+                        let resp = session.patch(&fragment).await;
+                        let _ = resp_tx.send(resp);
                     }
-                    WHIPEvent::EndRequest => {
-                        active = false;
+                    WHIPEvent::DeleteRequest(id, resp_tx) => {
+                        let Some(ended) = session.take_if(|session| session.id == id) else {
+                            let _ = resp_tx.send(Err(StatusCode::NOT_FOUND));
+                            continue;
+                        };
+                        ended.end(&source_tx).await;
+                        let resp = Response::builder()
+                            .status(StatusCode::OK)
+                            .body(String::new())
+                            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR);
+                        let _ = resp_tx.send(resp);
                     }
-                    WHIPEvent::RetrieveMirrors(mirrors_tx) => {
-                        let mirrors = inner.view_mirrors().await;
-                        let _ = mirrors_tx.send(mirrors);
+                    WHIPEvent::EndRequest(id) => {
+                        if let Some(ended) = session.take_if(|session| session.id == id) {
+                            ended.end(&source_tx).await;
+                        }
                     }
-                    WHIPEvent::NewMirror(mirror, done_tx) => {
-                        inner.add_mirror(mirror).await;
-                        if active {
-                            inner.call_connected_callback().await;
+                    WHIPEvent::RequestKeyframe => {
+                        if let Some(session) = &session {
+                            session.request_keyframe().await;
                         }
-                        let _ = done_tx.send(());
                     }
-                    WHIPEvent::EndMirror(id, done_tx) => {
-                        inner.remove_mirror(id).await;
-                        let _ = done_tx.send(());
+                    WHIPEvent::AcceptedCodecs(codecs) => {
+                        accepted_codecs = codecs;
                     }
                 }
             }
@@ -108,7 +215,50 @@ impl WHIP {
         });
 
         let inner_tx = inner_tx_b;
-        Self { inner_tx }
+        let source_rx = Arc::new(Mutex::new(source_rx));
+        Self {
+            inner_tx,
+            source_rx,
+            token: None,
+            policy: Policy::default(),
+            ice: Arc::default(),
+            counters,
+        }
+    }
+
+    /// Only let publishers presenting this bearer token in. Without one,
+    /// anyone able to reach the endpoint may publish.
+    pub fn token(mut self, token: impl AsRef<str>) -> Self {
+        self.token = Some(token.as_ref().into());
+        self
+    }
+
+    /// What to do with a new publisher while another one is live. Defaults
+    /// to turning it away.
+    pub fn takeover(mut self, takeover: TakeoverPolicy) -> Self {
+        self.policy.takeover = takeover;
+        self
+    }
+
+    /// Also ask publishers for a keyframe every `interval`, on top of the
+    /// requests of the mirrors. For mirrors unable to ask on their own.
+    pub fn pli_interval(mut self, interval: Duration) -> Self {
+        self.policy.pli_interval = Some(interval);
+        self
+    }
+
+    /// How the sessions gather their ICE candidates.
+    pub fn ice(mut self, ice: IceConfig) -> Self {
+        self.ice = Arc::new(ice);
+        self
+    }
+
+    /// Whether the request carries the bearer token, if the endpoint takes
+    /// one.
+    pub fn is_authorized<ReqBody>(&self, req: &Request<ReqBody>) -> bool {
+        self.token
+            .as_deref()
+            .is_none_or(|token| has_bearer_token(req, token))
     }
 
     async fn add_request(
@@ -117,31 +267,35 @@ impl WHIP {
         path: String,
     ) -> Result<Result<Response<String>, StatusCode>, Error<dyn ErrorInner>> {
         let (resp_tx, resp_rx) = oneshot::channel();
-        self.inner_tx
-            .send(WHIPEvent::NewRequest(offer, path, resp_tx))?;
+        self.inner_tx.send(WHIPEvent::NewRequest(
+            offer,
+            path,
+            self.policy,
+            self.ice.clone(),
+            resp_tx,
+        ))?;
         resp_rx.await.map_err(Into::into)
     }
 
-    pub async fn view_mirrors(&self) -> Result<Vec<bool>, Error<dyn ErrorInner>> {
-        let (mirrors_tx, mirrors_rx) = oneshot::channel();
-        self.inner_tx.send(WHIPEvent::RetrieveMirrors(mirrors_tx))?;
-        mirrors_rx.await.map_err(Into::into)
-    }
-
-    pub async fn add_mirror<M: Mirror + Send + Sync + 'static>(
+    async fn patch_request(
         &self,
-        mirror: M,
-    ) -> Result<(), Error<dyn ErrorInner>> {
-        let (done_tx, done_rx) = oneshot::channel();
+        id: String,
+        if_match: Option<String>,
+        fragment: String,
+    ) -> Result<Result<Response<String>, StatusCode>, Error<dyn ErrorInner>> {
+        let (resp_tx, resp_rx) = oneshot::channel();
         self.inner_tx
-            .send(WHIPEvent::NewMirror(Box::new(mirror), done_tx))?;
-        done_rx.await.map_err(Into::into)
+            .send(WHIPEvent::PatchRequest(id, if_match, fragment, resp_tx))?;
+        resp_rx.await.map_err(Into::into)
     }
 
-    pub async fn remove_mirror(&self, id: usize) -> Result<(), Error<dyn ErrorInner>> {
-        let (done_tx, done_rx) = oneshot::channel();
-        self.inner_tx.send(WHIPEvent::EndMirror(id, done_tx))?;
-        done_rx.await.map_err(Into::into)
+    async fn delete_request(
+        &self,
+        id: String,
+    ) -> Result<Result<Response<String>, StatusCode>, Error<dyn ErrorInner>> {
+        let (resp_tx, resp_rx) = oneshot::channel();
+        self.inner_tx.send(WHIPEvent::DeleteRequest(id, resp_tx))?;
+        resp_rx.await.map_err(Into::into)
     }
 
     #[allow(clippy::type_complexity)]
@@ -162,61 +316,538 @@ impl WHIP {
         move |req: Request<ReqBody>| {
             let whip = whip.take().unwrap();
             Box::pin(async move {
+                if !whip.is_authorized(&req) {
+                    warn!("[WebRTC] rejecting publisher without a valid bearer token");
+                    return Ok(WHIPRejection::Unauthorized.response());
+                }
+
+                // Sessions live at the path of the endpoint followed by their ID.
                 let path = req.uri().path().to_owned();
-                let offer =
-                    String::from_utf8(req.into_body().collect().await.unwrap().to_bytes().into())
-                        .unwrap();
-                let res = whip.add_request(offer, path).await.unwrap();
-                Ok(res)
+                let session_id = path.rsplit('/').next().unwrap_or_default().to_owned();
+                match req.method().clone() {
+                    Method::POST => {
+                        if !has_content_type(&req, SDP_CONTENT_TYPE) {
+                            return Ok(WHIPRejection::UnsupportedMediaType.response());
+                        }
+                        let Some(offer) = read_body(req).await else {
+                            return Ok(WHIPRejection::MalformedOffer.response());
+                        };
+                        let res = whip
+                            .add_request(offer, path)
+                            .await
+                            .unwrap_or(Err(StatusCode::INTERNAL_SERVER_ERROR));
+                        Ok(res)
+                    }
+                    Method::PATCH => {
+                        if !has_content_type(&req, SDPFRAG_CONTENT_TYPE) {
+                            return Ok(WHIPRejection::UnsupportedMediaType.response());
+                        }
+                        let if_match = req
+                            .headers()
+                            .get(IF_MATCH)
+                            .and_then(|value| value.to_str().ok())
+                            .map(ToOwned::to_owned);
+                        let Some(fragment) = read_body(req).await else {
+                            return Ok(WHIPRejection::MalformedOffer.response());
+                        };
+                        let res = whip
+                            .patch_request(session_id, if_match, fragment)
+                            .await
+                            .unwrap_or(Err(StatusCode::INTERNAL_SERVER_ERROR));
+                        Ok(res)
+                    }
+                    Method::DELETE => {
+                        let res = whip
+                            .delete_request(session_id)
+                            .await
+                            .unwrap_or(Err(StatusCode::INTERNAL_SERVER_ERROR));
+                        Ok(res)
+                    }
+                    _ => Ok(Err(StatusCode::METHOD_NOT_ALLOWED)),
+                }
             })
         }
     }
 }
 
+impl Source for WHIP {
+    fn read_event<'a>(&'a self) -> Pin<Box<dyn Future<Output = Option<SourceEvent>> + Send + 'a>> {
+        Box::pin(async { self.source_rx.lock().await.recv().await })
+    }
+
+    fn set_accepted_codecs(&self, codecs: &[Codec]) {
+        let _ = self
+            .inner_tx
+            .send(WHIPEvent::AcceptedCodecs(codecs.to_vec()));
+    }
+
+    fn request_keyframe(&self) {
+        let _ = self.inner_tx.send(WHIPEvent::RequestKeyframe);
+    }
+
+    fn stats(&self) -> SourceStats {
+        self.counters.stats()
+    }
+}
+
+/// What the publishers have sent so far, shared with the track readers.
+#[derive(Default)]
+struct Counters {
+    rtp_packets: AtomicU64,
+    rtp_bytes: AtomicU64,
+    dropped_packets: AtomicU64,
+    /// Latest jitter estimates, as the bits of an `f64` in seconds.
+    audio_jitter: AtomicU64,
+    video_jitter: AtomicU64,
+}
+
+impl Counters {
+    fn stats(&self) -> SourceStats {
+        SourceStats {
+            rtp_packets: self.rtp_packets.load(Ordering::Relaxed),
+            rtp_bytes: self.rtp_bytes.load(Ordering::Relaxed),
+            dropped_packets: self.dropped_packets.load(Ordering::Relaxed),
+            audio_jitter: f64::from_bits(self.audio_jitter.load(Ordering::Relaxed)),
+            video_jitter: f64::from_bits(self.video_jitter.load(Ordering::Relaxed)),
+        }
+    }
+
+    fn jitter(&self, kind: RTPCodecType) -> &AtomicU64 {
+        match kind {
+            RTPCodecType::Audio => &self.audio_jitter,
+            _ => &self.video_jitter,
+        }
+    }
+}
+
+/// Interarrival jitter estimate of RFC 3550, section 6.4.1.
+struct Jitter {
+    clock_rate: f64,
+    last: Option<(Instant, u32)>,
+    jitter: f64,
+}
+
+impl Jitter {
+    fn new(clock_rate: u32) -> Self {
+        Self {
+            clock_rate: clock_rate as f64,
+            last: None,
+            jitter: 0.0,
+        }
+    }
+
+    /// Account for a packet stamped `timestamp` arriving now, returning the
+    /// estimate in seconds.
+    fn update(&mut self, timestamp: u32) -> f64 {
+        let now = Instant::now();
+        if let Some((arrival, last_timestamp)) = self.last {
+            let elapsed = now.duration_since(arrival).as_secs_f64() * self.clock_rate;
+            let stamped = timestamp.wrapping_sub(last_timestamp) as i32 as f64;
+            self.jitter += ((elapsed - stamped).abs() - self.jitter) / 16.0;
+        }
+        self.last = Some((now, timestamp));
+        self.jitter / self.clock_rate
+    }
+}
+
+/// Video codecs the source can receive, along with their payload type and the
+/// payload type of their retransmissions.
+const VIDEO_CODECS: [(Codec, u8, u8); 5] = [
+    (Codec::H264, 102, 103),
+    (Codec::H265, 104, 105),
+    (Codec::VP8, 96, 97),
+    (Codec::VP9, 98, 99),
+    (Codec::AV1, 45, 46),
+];
+
+/// Pick the video codecs the answer may take from the offer, those some
+/// mirror accepts. Falls back to every codec when no mirror accepts any, so a
+/// publisher can still connect.
+fn video_codecs(accepted_codecs: &[Codec]) -> Vec<(Codec, u8, u8)> {
+    let video_codecs: Vec<_> = VIDEO_CODECS
+        .into_iter()
+        .filter(|(codec, ..)| accepted_codecs.contains(codec))
+        .collect();
+    if video_codecs.is_empty() {
+        return VIDEO_CODECS.to_vec();
+    }
+    video_codecs
+}
+
+const SDPFRAG_CONTENT_TYPE: &str = "application/trickle-ice-sdpfrag";
+
+/// Whether the request carries the bearer `token`, compared in constant time
+/// so the token cannot be guessed from response times.
+pub fn has_bearer_token<ReqBody>(req: &Request<ReqBody>, token: &str) -> bool {
+    let Some(credentials) = req
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+    else {
+        return false;
+    };
+    let Some((scheme, presented)) = credentials.split_once(' ') else {
+        return false;
+    };
+
+    scheme.eq_ignore_ascii_case("bearer")
+        && bool::from(presented.trim().as_bytes().ct_eq(token.as_bytes()))
+}
+
+/// How the endpoint treats publishers, as set through the builder methods.
+#[derive(Clone, Copy, Default)]
+struct Policy {
+    takeover: TakeoverPolicy,
+    pli_interval: Option<Duration>,
+}
+
+/// What the endpoint does with a new publisher while another one is live.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum TakeoverPolicy {
+    /// Turn the new publisher away with `409 Conflict`.
+    #[default]
+    Reject,
+    /// End the live session and let the new publisher in.
+    Replace,
+}
+
+impl FromStr for TakeoverPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "reject" => Ok(Self::Reject),
+            "replace" => Ok(Self::Replace),
+            _ => Err(format!(
+                "unknown takeover policy `{s}`, expected `reject` or `replace`"
+            )),
+        }
+    }
+}
+
+impl Display for TakeoverPolicy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Reject => f.write_str("reject"),
+            Self::Replace => f.write_str("replace"),
+        }
+    }
+}
+
+/// Why the endpoint turned a request away.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WHIPRejection {
+    /// The request lacks the bearer token of the endpoint.
+    Unauthorized,
+    /// Another publisher is live and may not be taken over, or another
+    /// source of the hub is live.
+    Busy,
+    /// The body is not a session description, or fragment, the endpoint can
+    /// take.
+    MalformedOffer,
+    /// The body is not of the media type the method expects.
+    UnsupportedMediaType,
+}
+
+impl WHIPRejection {
+    pub const fn status(&self) -> StatusCode {
+        match self {
+            Self::Unauthorized => StatusCode::UNAUTHORIZED,
+            Self::Busy => StatusCode::CONFLICT,
+            Self::MalformedOffer => StatusCode::BAD_REQUEST,
+            Self::UnsupportedMediaType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+        }
+    }
+
+    /// Answer with the status, explained in the body.
+    pub fn response(self) -> Result<Response<String>, StatusCode> {
+        let mut resp = Response::builder()
+            .status(self.status())
+            .header(CONTENT_TYPE, "text/plain; charset=utf-8");
+        if self == Self::Unauthorized {
+            resp = resp.header(WWW_AUTHENTICATE, r#"Bearer realm="whip""#);
+        }
+        resp.body(self.to_string())
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+    }
+}
+
+impl Display for WHIPRejection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Unauthorized => f.write_str("a valid bearer token is required"),
+            Self::Busy => f.write_str("another publisher is live on this endpoint"),
+            Self::MalformedOffer => f.write_str("the body could not be parsed"),
+            Self::UnsupportedMediaType => f.write_str("the body has the wrong content type"),
+        }
+    }
+}
+
+impl StdError for WHIPRejection {}
+
+/// A publisher connected through the endpoint, addressed by its ID.
+struct Session {
+    id: String,
+    /// Changes with every ICE restart, see section 4.4 of RFC 9725.
+    etag: String,
+    peer: Arc<RTCPeerConnection>,
+}
+
+impl Session {
+    /// Apply a trickle-ice-sdpfrag: trickle its candidates in, or restart ICE
+    /// when it carries new ICE credentials.
+    async fn patch(&mut self, fragment: &str) -> Result<Response<String>, StatusCode> {
+        let fragment = SdpFragment::parse(fragment);
+        let remote_ufrag = self
+            .peer
+            .remote_description()
+            .await
+            .and_then(|desc| SdpFragment::parse(&desc.sdp).ufrag);
+
+        if fragment.ufrag.is_some() && fragment.ufrag != remote_ufrag {
+            info!("[WebRTC] session {} restarting ice", self.id);
+            let sdpfrag = match restart_ice(&self.peer, &fragment).await {
+                Ok(sdpfrag) => sdpfrag,
+                Err(err) => {
+                    warn!("[WebRTC] session {} ice restart failed: {err}", self.id);
+                    return Err(StatusCode::INTERNAL_SERVER_ERROR);
+                }
+            };
+            self.etag = new_etag();
+            return Response::builder()
+                .status(StatusCode::OK)
+                .header(CONTENT_TYPE, SDPFRAG_CONTENT_TYPE)
+                .header(ETAG, &self.etag)
+                .body(sdpfrag)
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR);
+        }
+
+        add_candidates(&self.peer, &fragment)
+            .await
+            .map_err(|_| StatusCode::BAD_REQUEST)?;
+        Response::builder()
+            .status(StatusCode::NO_CONTENT)
+            .body(String::new())
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+    }
+
+    /// Send the publisher a PLI for its video track.
+    async fn request_keyframe(&self) {
+        for receiver in self.peer.get_receivers().await {
+            for track in receiver.tracks().await {
+                if track.kind() != RTPCodecType::Video {
+                    continue;
+                }
+                debug!("[WebRTC] session {} requesting keyframe", self.id);
+                let _ = self
+                    .peer
+                    .write_rtcp(&[Box::new(PictureLossIndication {
+                        sender_ssrc: 0,
+                        media_ssrc: track.ssrc(),
+                    })])
+                    .await;
+            }
+        }
+    }
+
+    async fn end(self, source_tx: &mpsc::Sender<SourceEvent>) {
+        let _ = self.peer.close().await;
+        warn!("[WebRTC] session {} ended, closing peer", self.id);
+        let _ = source_tx.send(SourceEvent::Disconnected).await;
+    }
+}
+
+fn new_etag() -> String {
+    format!("\"{}\"", Uuid::new_v4().simple())
+}
+
+/// The ICE details of a trickle-ice-sdpfrag body, see RFC 8840. Also reads
+/// them out of whole session descriptions.
+#[derive(Default)]
+struct SdpFragment {
+    ufrag: Option<String>,
+    pwd: Option<String>,
+    /// Along with the mid and index of the media section they belong to.
+    candidates: Vec<(Option<String>, u16, String)>,
+}
+
+impl SdpFragment {
+    fn parse(fragment: &str) -> Self {
+        let mut parsed = Self::default();
+        let mut mid = None;
+        let mut mline_index: Option<u16> = None;
+        for line in fragment.lines().map(str::trim) {
+            if let Some(ufrag) = line.strip_prefix("a=ice-ufrag:") {
+                parsed.ufrag.get_or_insert_with(|| ufrag.to_owned());
+            } else if let Some(pwd) = line.strip_prefix("a=ice-pwd:") {
+                parsed.pwd.get_or_insert_with(|| pwd.to_owned());
+            } else if line.starts_with("m=") {
+                mline_index = Some(mline_index.map_or(0, |index| index + 1));
+                mid = None;
+            } else if let Some(value) = line.strip_prefix("a=mid:") {
+                mid = Some(value.to_owned());
+            } else if let Some(candidate) = line.strip_prefix("a=")
+                && candidate.starts_with("candidate:")
+            {
+                parsed.candidates.push((
+                    mid.clone(),
+                    mline_index.unwrap_or_default(),
+                    candidate.to_owned(),
+                ));
+            }
+        }
+        parsed
+    }
+}
+
+/// Cut the ICE credentials and candidates out of a session description, as
+/// the body of a response to an ICE restart.
+fn local_sdpfrag(sdp: &str) -> String {
+    let mut ufrag = None;
+    let mut pwd = None;
+    let mut media = String::new();
+    for line in sdp.lines().map(str::trim) {
+        if line.starts_with("a=ice-ufrag:") {
+            ufrag.get_or_insert(line);
+        } else if line.starts_with("a=ice-pwd:") {
+            pwd.get_or_insert(line);
+        } else if line.starts_with("m=")
+            || line.starts_with("a=mid:")
+            || line.starts_with("a=candidate:")
+            || line.starts_with("a=end-of-candidates")
+        {
+            media.push_str(line);
+            media.push_str("\r\n");
+        }
+    }
+
+    let mut sdpfrag = String::new();
+    for line in [ufrag, pwd].into_iter().flatten() {
+        sdpfrag.push_str(line);
+        sdpfrag.push_str("\r\n");
+    }
+    sdpfrag.push_str(&media);
+    sdpfrag
+}
+
+async fn add_candidates(
+    peer: &RTCPeerConnection,
+    fragment: &SdpFragment,
+) -> Result<(), Error<dyn ErrorInner>> {
+    for (sdp_mid, sdp_mline_index, candidate) in &fragment.candidates {
+        peer.add_ice_candidate(RTCIceCandidateInit {
+            candidate: candidate.clone(),
+            sdp_mid: sdp_mid.clone(),
+            sdp_mline_index: Some(*sdp_mline_index),
+            username_fragment: fragment.ufrag.clone(),
+        })
+        .await?;
+    }
+    Ok(())
+}
+
+/// Restart ICE with the credentials of the publisher, by renegotiating its
+/// original offer with them. Returns the new local credentials and
+/// candidates as a trickle-ice-sdpfrag.
+async fn restart_ice(
+    peer: &RTCPeerConnection,
+    fragment: &SdpFragment,
+) -> Result<String, Error<dyn ErrorInner>> {
+    let (Some(ufrag), Some(pwd), Some(remote_desc)) = (
+        &fragment.ufrag,
+        &fragment.pwd,
+        peer.remote_description().await,
+    ) else {
+        return Err(Error {
+            kind: ErrorType::WHIPPeer,
+            source: None,
+        });
+    };
+
+    let mut sdp = String::new();
+    for line in remote_desc.sdp.lines().map(str::trim) {
+        if line.starts_with("a=ice-ufrag:") {
+            sdp.push_str(&format!("a=ice-ufrag:{ufrag}"));
+        } else if line.starts_with("a=ice-pwd:") {
+            sdp.push_str(&format!("a=ice-pwd:{pwd}"));
+        } else if line.starts_with("a=candidate:") || line.starts_with("a=end-of-candidates") {
+            continue;
+        } else {
+            sdp.push_str(line);
+        }
+        sdp.push_str("\r\n");
+    }
+
+    // An offer with new credentials restarts ICE on its own.
+    let offer = RTCSessionDescription::offer(sdp)?;
+    peer.set_remote_description(offer).await?;
+    let answer = peer.create_answer(None).await?;
+    let mut gather_complete = peer.gathering_complete_promise().await;
+    peer.set_local_description(answer).await?;
+    let _ = gather_complete.recv().await;
+    add_candidates(peer, fragment).await?;
+
+    let local_desc = peer.local_description().await.ok_or(Error {
+        kind: ErrorType::WHIPPeer,
+        source: None,
+    })?;
+    Ok(local_sdpfrag(&local_desc.sdp))
+}
+
+#[allow(clippy::too_many_arguments)]
 async fn init_peer(
     host: IpAddr,
-    inner: &Arc<WHIPInner>,
+    ice: &IceConfig,
     offer: String,
+    id: &str,
+    accepted_codecs: &[Codec],
+    pli_interval: Option<Duration>,
+    source_tx: mpsc::Sender<SourceEvent>,
     inner_tx: mpsc::UnboundedSender<WHIPEvent>,
-) -> Result<String, Error<dyn ErrorInner>> {
+    counters: Arc<Counters>,
+) -> Result<(String, Arc<RTCPeerConnection>), Error<dyn ErrorInner>> {
     let audio_payload = 111;
     let audio_codec = "opus";
-    let video_payload = 102;
-    let video_codec = "H264";
-    let video_rtxpayload = 103;
+    let video_codecs = video_codecs(accepted_codecs);
+    debug!(
+        "[WebRTC] answering with {:?}",
+        video_codecs
+            .iter()
+            .map(|(codec, ..)| codec.to_string())
+            .collect::<Vec<_>>()
+    );
 
     let mut m = MediaEngine::default();
-    m.register_codec(
-        RTCRtpCodecParameters {
-            capability: RTCRtpCodecCapability {
-                mime_type: match video_codec {
-                    "H264" => MIME_TYPE_H264.to_owned(),
-                    _ => format!("video/{video_codec}"),
+    for (video_codec, video_payload, video_rtxpayload) in video_codecs {
+        m.register_codec(
+            RTCRtpCodecParameters {
+                capability: RTCRtpCodecCapability {
+                    mime_type: video_codec.mime_type().to_owned(),
+                    clock_rate: 90000,
+                    channels: 0,
+                    sdp_fmtp_line: "".to_owned(),
+                    rtcp_feedback: vec![],
                 },
-                clock_rate: 90000,
-                channels: 0,
-                sdp_fmtp_line: "".to_owned(),
-                rtcp_feedback: vec![],
+                payload_type: video_payload,
+                ..Default::default()
             },
-            payload_type: video_payload,
-            ..Default::default()
-        },
-        RTPCodecType::Video,
-    )?;
-    m.register_codec(
-        RTCRtpCodecParameters {
-            capability: RTCRtpCodecCapability {
-                mime_type: "video/rtx".to_owned(),
-                clock_rate: 90000,
-                channels: 0,
-                sdp_fmtp_line: format!("apt={video_payload}"),
-                rtcp_feedback: vec![],
+            RTPCodecType::Video,
+        )?;
+        m.register_codec(
+            RTCRtpCodecParameters {
+                capability: RTCRtpCodecCapability {
+                    mime_type: "video/rtx".to_owned(),
+                    clock_rate: 90000,
+                    channels: 0,
+                    sdp_fmtp_line: format!("apt={video_payload}"),
+                    rtcp_feedback: vec![],
+                },
+                payload_type: video_rtxpayload,
+                ..Default::default()
             },
-            payload_type: video_rtxpayload,
-            ..Default::default()
-        },
-        RTPCodecType::Video,
-    )?;
+            RTPCodecType::Video,
+        )?;
+    }
     m.register_codec(
         RTCRtpCodecParameters {
             capability: RTCRtpCodecCapability {
@@ -245,6 +876,7 @@ async fn init_peer(
         let ip_filter = Box::new(move |ipaddr| ipaddr == host);
         s.set_ip_filter(ip_filter);
     }
+    ice.apply(&mut s)?;
 
     let api = APIBuilder::new()
         .with_media_engine(m)
@@ -253,7 +885,7 @@ async fn init_peer(
         .build();
 
     let config = RTCConfiguration {
-        ice_servers: vec![],
+        ice_servers: ice.ice_servers(),
         ice_transport_policy: RTCIceTransportPolicy::All,
         bundle_policy: RTCBundlePolicy::MaxBundle,
         rtcp_mux_policy: RTCRtcpMuxPolicy::Require,
@@ -280,37 +912,37 @@ async fn init_peer(
         )
         .await?;
 
-    let inner_track = inner.clone();
+    let source_track = source_tx.clone();
     let pc = Arc::downgrade(&peer_connection);
     peer_connection.on_track(Box::new(move |track, _, _| {
         let media_ssrc = track.ssrc();
 
-        if track.kind() == RTPCodecType::Video {
+        if let Some(pli_interval) = pli_interval
+            && track.kind() == RTPCodecType::Video
+        {
             let pc2 = pc.clone();
             tokio::spawn(async move {
-                let mut result = Result::<usize, _>::Ok(0);
-                while result.is_ok() {
-                    let timeout = sleep(Duration::from_secs(3));
-                    tokio::pin!(timeout);
-
-                    tokio::select! {
-                        _ = timeout.as_mut() => {
-                            if let Some(pc) = pc2.upgrade() {
-                                result = pc.write_rtcp(&[Box::new(PictureLossIndication {
-                                    sender_ssrc: 0,
-                                    media_ssrc,
-                                })]).await;
-                            } else {
-                                break;
-                            }
-                        }
+                loop {
+                    sleep(pli_interval).await;
+                    let Some(pc) = pc2.upgrade() else {
+                        break;
                     };
+                    let result = pc
+                        .write_rtcp(&[Box::new(PictureLossIndication {
+                            sender_ssrc: 0,
+                            media_ssrc,
+                        })])
+                        .await;
+                    if result.is_err() {
+                        break;
+                    }
                 }
                 debug!("[WebRTC] closing video pli thread");
             });
         }
 
-        let inner_track = inner_track.clone();
+        let source_track = source_track.clone();
+        let counters = counters.clone();
 
         tokio::spawn(async move {
             info!(
@@ -321,28 +953,60 @@ async fn init_peer(
 
             match track.kind() {
                 RTPCodecType::Audio => {
-                    let mut s = SampleBuilder::new(OpusPacket, 15, 48000);
-
-                    while let Ok((rtp, _)) = track.read_rtp().await {
-                        let is_emit = s.push(rtp);
-                        if !is_emit {
-                            s = SampleBuilder::new(OpusPacket, 15, 48000);
-                        }
-                        while let Some(mut payload) = s.pop() {
-                            inner_track.write_audio_sample(&mut payload).await;
-                        }
-                    }
+                    read_track(&track, OpusPacket, Codec::Opus, &source_track, &counters).await;
                 }
                 RTPCodecType::Video => {
-                    let mut s = SampleBuilder::new(H264Packet::default(), 30, 90000);
-
-                    while let Ok((rtp, _)) = track.read_rtp().await {
-                        let is_emit = s.push(rtp);
-                        if !is_emit {
-                            s = SampleBuilder::new(H264Packet::default(), 30, 90000);
+                    let codec = Codec::from_mime_type(&track.codec().capability.mime_type);
+                    match codec {
+                        Some(Codec::H265) => {
+                            read_track(
+                                &track,
+                                H265Packet::default(),
+                                Codec::H265,
+                                &source_track,
+                                &counters,
+                            )
+                            .await;
                         }
-                        while let Some(mut payload) = s.pop() {
-                            inner_track.write_video_sample(&mut payload).await;
+                        Some(Codec::VP8) => {
+                            read_track(
+                                &track,
+                                Vp8Packet::default(),
+                                Codec::VP8,
+                                &source_track,
+                                &counters,
+                            )
+                            .await;
+                        }
+                        Some(Codec::VP9) => {
+                            read_track(
+                                &track,
+                                Vp9Packet::default(),
+                                Codec::VP9,
+                                &source_track,
+                                &counters,
+                            )
+                            .await;
+                        }
+                        Some(Codec::AV1) => {
+                            read_track(
+                                &track,
+                                Av1Packet::default(),
+                                Codec::AV1,
+                                &source_track,
+                                &counters,
+                            )
+                            .await;
+                        }
+                        _ => {
+                            read_track(
+                                &track,
+                                H264Packet::default(),
+                                Codec::H264,
+                                &source_track,
+                                &counters,
+                            )
+                            .await;
                         }
                     }
                 }
@@ -359,39 +1023,53 @@ async fn init_peer(
         Box::pin(async {})
     }));
 
-    let mut inner_tx = Some(inner_tx);
-    let mut inner_ice = Some(inner.clone());
-    let mut pc = Some(peer_connection.clone());
+    // A disconnected session may still come back on its own or through an
+    // ICE restart, it only ends once ICE fails, stays disconnected for too
+    // long or the publisher deletes it.
+    let id = id.to_owned();
+    let pc = Arc::downgrade(&peer_connection);
     peer_connection.on_ice_connection_state_change(Box::new(
         move |connection_state: RTCIceConnectionState| {
             info!(
                 "[WebRTC] ICE connection state changed to: {}",
                 connection_state
             );
-            let (inner_tx, inner_ice, pc) = match connection_state {
-                RTCIceConnectionState::Connected => (None, inner_ice.take(), None),
-                RTCIceConnectionState::Disconnected => (inner_tx.take(), None, None),
-                RTCIceConnectionState::Failed => (None, None, pc.take()),
-                _ => (None, None, None),
-            };
+            let source_tx = source_tx.clone();
+            let inner_tx = inner_tx.clone();
+            let id = id.clone();
+            let pc = pc.clone();
             Box::pin(async move {
-                if let Some(inner_ice) = inner_ice {
-                    inner_ice.call_connected_callback().await;
-                }
-                if let Some(inner_tx) = inner_tx {
-                    let _ = inner_tx.send(WHIPEvent::EndRequest);
-                }
-                if let Some(pc) = pc {
-                    let _ = pc.close().await;
-                    warn!("[WebRTC] closing peer");
+                let _ = source_tx
+                    .send(SourceEvent::IceConnectionState(connection_state))
+                    .await;
+                match connection_state {
+                    RTCIceConnectionState::Disconnected => {
+                        tokio::spawn(async move {
+                            sleep(ICE_DISCONNECTED_TIMEOUT).await;
+                            let still_disconnected = pc.upgrade().is_some_and(|pc| {
+                                pc.ice_connection_state() == RTCIceConnectionState::Disconnected
+                            });
+                            if still_disconnected {
+                                warn!("[WebRTC] session {id} stayed disconnected, ending it");
+                                let _ = inner_tx.send(WHIPEvent::EndRequest(id));
+                            }
+                        });
+                    }
+                    RTCIceConnectionState::Failed => {
+                        let _ = inner_tx.send(WHIPEvent::EndRequest(id));
+                    }
+                    _ => {}
                 }
             })
         },
     ));
 
     debug!("[WebRTC] waiting for offer");
-    let offer = RTCSessionDescription::offer(offer)?;
-    peer_connection.set_remote_description(offer).await?;
+    let offer = RTCSessionDescription::offer(offer).map_err(offer_error)?;
+    peer_connection
+        .set_remote_description(offer)
+        .await
+        .map_err(offer_error)?;
     let answer = peer_connection.create_answer(None).await?;
     let mut gather_complete = peer_connection.gathering_complete_promise().await;
     peer_connection.set_local_description(answer).await?;
@@ -402,114 +1080,85 @@ async fn init_peer(
         source: None,
     })?;
 
-    Ok(local_desc.sdp)
-}
-
-enum WHIPEvent {
-    NewRequest(
-        String,
-        String,
-        oneshot::Sender<Result<Response<String>, StatusCode>>,
-    ),
-    EndRequest,
-    RetrieveMirrors(oneshot::Sender<Vec<bool>>),
-    NewMirror(Box<dyn Mirror + Send + Sync>, oneshot::Sender<()>),
-    EndMirror(usize, oneshot::Sender<()>),
-}
-
-#[derive(Default)]
-struct WHIPInner {
-    map: RwLock<Vec<Option<usize>>>,
-    mirrors: RwLock<VecDeque<(usize, Box<dyn Mirror + Send + Sync>)>>,
+    Ok((local_desc.sdp, peer_connection))
 }
 
-impl WHIPInner {
-    async fn view_mirrors(&self) -> Vec<bool> {
-        self.map.read().await.iter().map(|&x| x.is_some()).collect()
-    }
-
-    async fn add_mirror(&self, mirror: Box<dyn Mirror + Send + Sync>) {
-        let mut map = self.map.write().await;
-        let mut deque = self.mirrors.write().await;
-
-        let seq = deque.len();
-        deque.push_back((map.len(), mirror));
-        map.push(Some(seq));
+/// The offer could not be parsed or taken by the peer.
+fn offer_error(err: webrtc::Error) -> Error<dyn ErrorInner> {
+    Error {
+        kind: ErrorType::WHIPOffer,
+        source: Some(Box::new(err)),
     }
+}
 
-    async fn remove_mirror(&self, id: usize) {
-        let mut map = self.map.write().await;
-        let mut deque = self.mirrors.write().await;
-
-        let Some(pos) = map.get_mut(id) else {
-            return;
-        };
-        let Some(seq) = pos else {
-            return;
-        };
-        let Some((_, mirror)) = deque.remove(*seq) else {
-            return;
-        };
-        mirror.close();
-        *pos = None;
-    }
+/// Reassemble the track into samples until it ends, keeping count of what
+/// arrives.
+async fn read_track<T: Depacketizer + Clone>(
+    track: &TrackRemote,
+    depacketizer: T,
+    codec: Codec,
+    source_tx: &mpsc::Sender<SourceEvent>,
+    counters: &Counters,
+) {
+    let kind = track.kind();
+    let (max_late, sample_rate) = match kind {
+        RTPCodecType::Audio => (15, 48000),
+        _ => (30, 90000),
+    };
+    let mut s = SampleBuilder::new(depacketizer.clone(), max_late, sample_rate);
+    let mut jitter = Jitter::new(sample_rate);
 
-    async fn write_audio_sample(&self, payload: &mut Sample) {
-        let mut map = self.map.write().await;
-        let mut deque = self.mirrors.write().await;
+    while let Ok((rtp, _)) = track.read_rtp().await {
+        counters.rtp_packets.fetch_add(1, Ordering::Relaxed);
+        counters
+            .rtp_bytes
+            .fetch_add(rtp.payload.len() as u64, Ordering::Relaxed);
+        let estimate = jitter.update(rtp.header.timestamp);
+        counters
+            .jitter(kind)
+            .store(estimate.to_bits(), Ordering::Relaxed);
 
-        let len = deque.len();
-        for seq in 0..len {
-            let Some((id, mirror)) = deque.pop_front() else {
-                continue;
-            };
-            let pos = map.get_mut(id).unwrap();
-            let Ok(_) = mirror.write_audio_sample(payload).await else {
-                *pos = None;
-                continue;
-            };
-            *pos = Some(seq);
-            deque.push_back((id, mirror));
+        let is_emit = s.push(rtp);
+        if !is_emit {
+            counters
+                .dropped_packets
+                .fetch_add(s.take_dropped(), Ordering::Relaxed);
+            s = SampleBuilder::new(depacketizer.clone(), max_late, sample_rate);
         }
-    }
-
-    async fn write_video_sample(&self, payload: &mut Sample) {
-        let mut map = self.map.write().await;
-        let mut deque = self.mirrors.write().await;
-
-        let len = deque.len();
-        for seq in 0..len {
-            let Some((id, mirror)) = deque.pop_front() else {
-                continue;
-            };
-            let pos = map.get_mut(id).unwrap();
-            let Ok(_) = mirror.write_video_sample(payload).await else {
-                *pos = None;
-                continue;
+        while let Some(payload) = s.pop() {
+            let event = match kind {
+                RTPCodecType::Audio => SourceEvent::AudioSample(codec, payload),
+                _ => SourceEvent::VideoSample(codec, payload),
             };
-            *pos = Some(seq);
-            deque.push_back((id, mirror));
+            let _ = source_tx.send(event).await;
         }
+        counters
+            .dropped_packets
+            .fetch_add(s.take_dropped(), Ordering::Relaxed);
     }
+}
 
-    async fn call_connected_callback(&self) {
-        let mut map = self.map.write().await;
-        let mut deque = self.mirrors.write().await;
-
-        let len = deque.len();
-        for seq in 0..len {
-            let Some((id, mirror)) = deque.pop_front() else {
-                continue;
-            };
-            let pos = map.get_mut(id).unwrap();
-            let Ok(_) = mirror.call_connected_callback() else {
-                *pos = None;
-                continue;
-            };
-            *pos = Some(seq);
-            deque.push_back((id, mirror));
-        }
-    }
+enum WHIPEvent {
+    NewRequest(
+        String,
+        String,
+        Policy,
+        Arc<IceConfig>,
+        oneshot::Sender<Result<Response<String>, StatusCode>>,
+    ),
+    PatchRequest(
+        String,
+        Option<String>,
+        String,
+        oneshot::Sender<Result<Response<String>, StatusCode>>,
+    ),
+    DeleteRequest(
+        String,
+        oneshot::Sender<Result<Response<String>, StatusCode>>,
+    ),
+    EndRequest(String),
+    RequestKeyframe,
+    AcceptedCodecs(Vec<Codec>),
 }
 
 pub trait ErrorInner: StdError + Send + Sync {}
@@ -550,3 +1199,272 @@ impl From<webrtc::Error> for Error<dyn ErrorInner> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hub::Hub;
+    use crate::utils::testing;
+    use bytes::Bytes;
+    use http_body_util::Full;
+
+    fn whip() -> WHIP {
+        fed(WHIP::new(IpAddr::from([127, 0, 0, 1])).token("secret"))
+    }
+
+    /// Add the endpoint to a hub, for its publishers to go live.
+    fn fed(whip: WHIP) -> WHIP {
+        let hub = Hub::new();
+        let source = whip.clone();
+        tokio::spawn(async move { hub.add_source(source).await });
+        whip
+    }
+
+    /// Send the request, answering rejections by their status.
+    async fn send(whip: &WHIP, req: Request<Full<Bytes>>) -> StatusCode {
+        let Ok(res) = whip.into_closure()(req).await;
+        res.map_or_else(|status| status, |resp| resp.status())
+    }
+
+    fn post(content_type: &str, body: impl Into<Bytes>) -> Request<Full<Bytes>> {
+        Request::post("/whip/live")
+            .header(AUTHORIZATION, "Bearer secret")
+            .header(CONTENT_TYPE, content_type)
+            .body(Full::new(body.into()))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn publishers_need_the_bearer_token() {
+        let whip = whip();
+        for authorization in [None, Some("Bearer guess"), Some("Basic secret")] {
+            let mut req = post(
+                SDP_CONTENT_TYPE,
+                testing::offer(RTCRtpTransceiverDirection::Sendonly).await,
+            );
+            req.headers_mut().remove(AUTHORIZATION);
+            if let Some(authorization) = authorization {
+                req.headers_mut()
+                    .insert(AUTHORIZATION, authorization.parse().unwrap());
+            }
+            let Ok(res) = whip.into_closure()(req).await;
+            let resp = res.unwrap();
+            assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+            assert_eq!(resp.headers()[WWW_AUTHENTICATE], r#"Bearer realm="whip""#);
+        }
+
+        let status = send(
+            &whip,
+            post(
+                SDP_CONTENT_TYPE,
+                testing::offer(RTCRtpTransceiverDirection::Sendonly).await,
+            ),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+    }
+
+    #[tokio::test]
+    async fn endpoints_without_a_token_take_anyone() {
+        let whip = fed(WHIP::new(IpAddr::from([127, 0, 0, 1])));
+        let mut req = post(
+            SDP_CONTENT_TYPE,
+            testing::offer(RTCRtpTransceiverDirection::Sendonly).await,
+        );
+        req.headers_mut().remove(AUTHORIZATION);
+        assert_eq!(send(&whip, req).await, StatusCode::CREATED);
+    }
+
+    #[tokio::test]
+    async fn bearer_scheme_is_case_insensitive() {
+        let req = Request::post("/whip")
+            .header(AUTHORIZATION, "bearer  secret")
+            .body(())
+            .unwrap();
+        assert!(whip().is_authorized(&req));
+        assert!(!whip().token("secre").is_authorized(&req));
+    }
+
+    fn patch(location: &str, body: &str) -> Request<Full<Bytes>> {
+        Request::patch(location)
+            .header(AUTHORIZATION, "Bearer secret")
+            .header(CONTENT_TYPE, SDPFRAG_CONTENT_TYPE)
+            .body(Full::new(Bytes::copy_from_slice(body.as_bytes())))
+            .unwrap()
+    }
+
+    fn delete(location: &str) -> Request<Full<Bytes>> {
+        Request::delete(location)
+            .header(AUTHORIZATION, "Bearer secret")
+            .body(Full::default())
+            .unwrap()
+    }
+
+    /// Start a session, returning its location and ETag.
+    async fn start(whip: &WHIP) -> (String, String) {
+        let Ok(res) = whip.into_closure()(post(
+            SDP_CONTENT_TYPE,
+            testing::offer(RTCRtpTransceiverDirection::Sendonly).await,
+        ))
+        .await;
+        let resp = res.unwrap();
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        let header = |name| resp.headers()[name].to_str().unwrap().to_owned();
+        (header(LOCATION), header(ETAG))
+    }
+
+    const CANDIDATE: &str =
+        "a=mid:0\r\na=candidate:1 1 udp 2130706431 127.0.0.1 50000 typ host\r\n";
+
+    #[tokio::test]
+    async fn sessions_end_on_delete() {
+        let whip = whip();
+        let (location, _) = start(&whip).await;
+        assert!(location.starts_with("/whip/live/"));
+
+        assert_eq!(send(&whip, delete(&location)).await, StatusCode::OK);
+        assert_eq!(send(&whip, delete(&location)).await, StatusCode::NOT_FOUND);
+        assert_eq!(
+            send(&whip, patch(&location, CANDIDATE)).await,
+            StatusCode::NOT_FOUND
+        );
+    }
+
+    #[tokio::test]
+    async fn sessions_take_trickled_candidates() {
+        let whip = whip();
+        let (location, etag) = start(&whip).await;
+
+        assert_eq!(
+            send(&whip, patch(&location, CANDIDATE)).await,
+            StatusCode::NO_CONTENT
+        );
+        let mut req = patch(&location, CANDIDATE);
+        req.headers_mut().insert(IF_MATCH, etag.parse().unwrap());
+        assert_eq!(send(&whip, req).await, StatusCode::NO_CONTENT);
+        let mut req = patch(&location, CANDIDATE);
+        req.headers_mut()
+            .insert(IF_MATCH, new_etag().parse().unwrap());
+        assert_eq!(send(&whip, req).await, StatusCode::PRECONDITION_FAILED);
+    }
+
+    #[tokio::test]
+    async fn new_credentials_restart_ice() {
+        let whip = whip();
+        let (location, etag) = start(&whip).await;
+
+        let fragment = "a=ice-ufrag:restart\r\na=ice-pwd:restartrestartrestartrest\r\n";
+        let Ok(res) = whip.into_closure()(patch(&location, fragment)).await;
+        let resp = res.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers()[CONTENT_TYPE], SDPFRAG_CONTENT_TYPE);
+        assert_ne!(resp.headers()[ETAG], etag.as_str());
+        assert!(resp.body().contains("a=ice-ufrag:"));
+    }
+
+    #[tokio::test]
+    async fn patches_need_the_sdpfrag_content_type() {
+        let whip = whip();
+        let (location, _) = start(&whip).await;
+        let mut req = patch(&location, CANDIDATE);
+        req.headers_mut()
+            .insert(CONTENT_TYPE, SDP_CONTENT_TYPE.parse().unwrap());
+        assert_eq!(send(&whip, req).await, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    }
+
+    #[tokio::test]
+    async fn other_methods_are_not_allowed() {
+        let req = Request::get("/whip/live")
+            .header(AUTHORIZATION, "Bearer secret")
+            .body(Full::default())
+            .unwrap();
+        assert_eq!(send(&whip(), req).await, StatusCode::METHOD_NOT_ALLOWED);
+    }
+
+    #[tokio::test]
+    async fn publishers_can_take_over() {
+        let whip = whip().takeover(TakeoverPolicy::Replace);
+        let (first, _) = start(&whip).await;
+        let (second, _) = start(&whip).await;
+        assert_ne!(first, second);
+
+        assert_eq!(send(&whip, delete(&first)).await, StatusCode::NOT_FOUND);
+        assert_eq!(send(&whip, delete(&second)).await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn live_sessions_refuse_takeovers_by_default() {
+        let whip = whip();
+        let (location, _) = start(&whip).await;
+
+        let req = post(
+            SDP_CONTENT_TYPE,
+            testing::offer(RTCRtpTransceiverDirection::Sendonly).await,
+        );
+        assert_eq!(send(&whip, req).await, StatusCode::CONFLICT);
+        assert_eq!(send(&whip, delete(&location)).await, StatusCode::OK);
+        start(&whip).await;
+    }
+
+    #[tokio::test]
+    async fn malformed_offers_are_bad_requests() {
+        let whip = whip();
+        for body in [
+            Bytes::from_static(b"not sdp"),
+            Bytes::from_static(&[0xff, 0xfe]),
+        ] {
+            let req = post(SDP_CONTENT_TYPE, body);
+            assert_eq!(send(&whip, req).await, StatusCode::BAD_REQUEST);
+        }
+    }
+
+    #[tokio::test]
+    async fn offers_need_the_sdp_content_type() {
+        let whip = whip();
+        for content_type in ["text/plain", SDPFRAG_CONTENT_TYPE] {
+            let req = post(
+                content_type,
+                testing::offer(RTCRtpTransceiverDirection::Sendonly).await,
+            );
+            assert_eq!(send(&whip, req).await, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        }
+        let mut req = post(
+            SDP_CONTENT_TYPE,
+            testing::offer(RTCRtpTransceiverDirection::Sendonly).await,
+        );
+        req.headers_mut().remove(CONTENT_TYPE);
+        assert_eq!(send(&whip, req).await, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    }
+
+    #[tokio::test]
+    async fn malformed_offers_leave_the_live_session_alone() {
+        let whip = whip().takeover(TakeoverPolicy::Replace);
+        let (location, _) = start(&whip).await;
+
+        let req = post(SDP_CONTENT_TYPE, "not sdp");
+        assert_eq!(send(&whip, req).await, StatusCode::BAD_REQUEST);
+        assert_eq!(send(&whip, delete(&location)).await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn publishers_wait_for_the_other_sources_of_the_hub() {
+        let hub = Hub::new();
+        let first = WHIP::new(IpAddr::from([127, 0, 0, 1])).token("secret");
+        // Taking over only ever ends sessions of the same endpoint.
+        let second = WHIP::new(IpAddr::from([127, 0, 0, 1]))
+            .token("secret")
+            .takeover(TakeoverPolicy::Replace);
+        hub.add_source(first.clone()).await.unwrap();
+        hub.add_source(second.clone()).await.unwrap();
+        let (location, _) = start(&first).await;
+
+        let req = post(
+            SDP_CONTENT_TYPE,
+            testing::offer(RTCRtpTransceiverDirection::Sendonly).await,
+        );
+        assert_eq!(send(&second, req).await, StatusCode::CONFLICT);
+
+        assert_eq!(send(&first, delete(&location)).await, StatusCode::OK);
+        start(&second).await;
+    }
+}
@@ -0,0 +1,218 @@
+use bytes::{Buf, BufMut};
+
+const NUMBER_MARKER: u8 = 0x00;
+const BOOLEAN_MARKER: u8 = 0x01;
+const STRING_MARKER: u8 = 0x02;
+const OBJECT_MARKER: u8 = 0x03;
+const NULL_MARKER: u8 = 0x05;
+const UNDEFINED_MARKER: u8 = 0x06;
+const ECMA_ARRAY_MARKER: u8 = 0x08;
+const OBJECT_END_MARKER: u8 = 0x09;
+const STRICT_ARRAY_MARKER: u8 = 0x0A;
+const DATE_MARKER: u8 = 0x0B;
+const LONG_STRING_MARKER: u8 = 0x0C;
+
+/// A value of the Action Message Format version 0, as used by RTMP commands
+/// and FLV script data.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Amf0Value {
+    Number(f64),
+    Boolean(bool),
+    String(String),
+    Object(Vec<(String, Amf0Value)>),
+    Null,
+    Undefined,
+    EcmaArray(Vec<(String, Amf0Value)>),
+    StrictArray(Vec<Amf0Value>),
+    Date(f64),
+}
+
+impl Amf0Value {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Amf0Value::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            Amf0Value::Number(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Look up a property of an object or ECMA array.
+    pub fn get(&self, key: &str) -> Option<&Amf0Value> {
+        match self {
+            Amf0Value::Object(properties) | Amf0Value::EcmaArray(properties) => properties
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+}
+
+/// Decode every value contained in `data`.
+pub fn decode_all(mut data: &[u8]) -> Result<Vec<Amf0Value>, String> {
+    let mut values = Vec::new();
+    while data.has_remaining() {
+        values.push(decode(&mut data)?);
+    }
+    Ok(values)
+}
+
+/// Decode a single value, advancing `data` past it.
+pub fn decode(data: &mut &[u8]) -> Result<Amf0Value, String> {
+    if !data.has_remaining() {
+        return Err("amf0 value is missing its marker".into());
+    }
+
+    let value = match data.get_u8() {
+        NUMBER_MARKER => Amf0Value::Number(read_f64(data)?),
+        BOOLEAN_MARKER => {
+            if !data.has_remaining() {
+                return Err("amf0 boolean is truncated".into());
+            }
+            Amf0Value::Boolean(data.get_u8() != 0)
+        }
+        STRING_MARKER => Amf0Value::String(read_string(data)?),
+        OBJECT_MARKER => Amf0Value::Object(read_properties(data)?),
+        NULL_MARKER => Amf0Value::Null,
+        UNDEFINED_MARKER => Amf0Value::Undefined,
+        ECMA_ARRAY_MARKER => {
+            if data.remaining() < 4 {
+                return Err("amf0 ecma array is truncated".into());
+            }
+            // The associative count is only a hint, the list is still
+            // terminated by an object end marker.
+            data.advance(4);
+            Amf0Value::EcmaArray(read_properties(data)?)
+        }
+        STRICT_ARRAY_MARKER => {
+            if data.remaining() < 4 {
+                return Err("amf0 strict array is truncated".into());
+            }
+            let len = data.get_u32();
+            let mut values = Vec::new();
+            for _ in 0..len {
+                values.push(decode(data)?);
+            }
+            Amf0Value::StrictArray(values)
+        }
+        DATE_MARKER => {
+            let value = read_f64(data)?;
+            if data.remaining() < 2 {
+                return Err("amf0 date is truncated".into());
+            }
+            // Time zone, reserved and always zero.
+            data.advance(2);
+            Amf0Value::Date(value)
+        }
+        LONG_STRING_MARKER => {
+            if data.remaining() < 4 {
+                return Err("amf0 long string is truncated".into());
+            }
+            let len = data.get_u32() as usize;
+            Amf0Value::String(read_utf8(data, len)?)
+        }
+        marker => return Err(format!("amf0 marker {marker:#04x} is not supported")),
+    };
+
+    Ok(value)
+}
+
+/// Encode `value` at the end of `buf`.
+pub fn encode(value: &Amf0Value, buf: &mut Vec<u8>) {
+    match value {
+        Amf0Value::Number(value) => {
+            buf.put_u8(NUMBER_MARKER);
+            buf.put_f64(*value);
+        }
+        Amf0Value::Boolean(value) => {
+            buf.put_u8(BOOLEAN_MARKER);
+            buf.put_u8(*value as u8);
+        }
+        Amf0Value::String(value) => {
+            if value.len() > u16::MAX as usize {
+                buf.put_u8(LONG_STRING_MARKER);
+                buf.put_u32(value.len() as u32);
+            } else {
+                buf.put_u8(STRING_MARKER);
+                buf.put_u16(value.len() as u16);
+            }
+            buf.put_slice(value.as_bytes());
+        }
+        Amf0Value::Object(properties) => {
+            buf.put_u8(OBJECT_MARKER);
+            write_properties(properties, buf);
+        }
+        Amf0Value::Null => buf.put_u8(NULL_MARKER),
+        Amf0Value::Undefined => buf.put_u8(UNDEFINED_MARKER),
+        Amf0Value::EcmaArray(properties) => {
+            buf.put_u8(ECMA_ARRAY_MARKER);
+            buf.put_u32(properties.len() as u32);
+            write_properties(properties, buf);
+        }
+        Amf0Value::StrictArray(values) => {
+            buf.put_u8(STRICT_ARRAY_MARKER);
+            buf.put_u32(values.len() as u32);
+            for value in values {
+                encode(value, buf);
+            }
+        }
+        Amf0Value::Date(value) => {
+            buf.put_u8(DATE_MARKER);
+            buf.put_f64(*value);
+            buf.put_u16(0);
+        }
+    }
+}
+
+fn read_f64(data: &mut &[u8]) -> Result<f64, String> {
+    if data.remaining() < 8 {
+        return Err("amf0 number is truncated".into());
+    }
+    Ok(data.get_f64())
+}
+
+fn read_string(data: &mut &[u8]) -> Result<String, String> {
+    if data.remaining() < 2 {
+        return Err("amf0 string is truncated".into());
+    }
+    let len = data.get_u16() as usize;
+    read_utf8(data, len)
+}
+
+fn read_utf8(data: &mut &[u8], len: usize) -> Result<String, String> {
+    if data.remaining() < len {
+        return Err("amf0 string is truncated".into());
+    }
+    let value = String::from_utf8_lossy(&data[..len]).into_owned();
+    data.advance(len);
+    Ok(value)
+}
+
+fn read_properties(data: &mut &[u8]) -> Result<Vec<(String, Amf0Value)>, String> {
+    let mut properties = Vec::new();
+    loop {
+        let key = read_string(data)?;
+        if key.is_empty() && data.first() == Some(&OBJECT_END_MARKER) {
+            data.advance(1);
+            return Ok(properties);
+        }
+        let value = decode(data)?;
+        properties.push((key, value));
+    }
+}
+
+fn write_properties(properties: &[(String, Amf0Value)], buf: &mut Vec<u8>) {
+    for (key, value) in properties {
+        buf.put_u16(key.len() as u16);
+        buf.put_slice(key.as_bytes());
+        encode(value, buf);
+    }
+    buf.put_u16(0);
+    buf.put_u8(OBJECT_END_MARKER);
+}
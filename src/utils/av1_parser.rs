@@ -0,0 +1,451 @@
+use bytes::{BufMut, Bytes, BytesMut};
+
+use super::bitstream::BitReader;
+
+pub const OBU_SEQUENCE_HEADER: u8 = 1;
+pub const OBU_TEMPORAL_DELIMITER: u8 = 2;
+pub const OBU_FRAME_HEADER: u8 = 3;
+pub const OBU_TILE_GROUP: u8 = 4;
+pub const OBU_METADATA: u8 = 5;
+pub const OBU_FRAME: u8 = 6;
+pub const OBU_REDUNDANT_FRAME_HEADER: u8 = 7;
+pub const OBU_TILE_LIST: u8 = 8;
+pub const OBU_PADDING: u8 = 15;
+
+pub const OBU_TYPE_BITMASK: u8 = 0x78;
+pub const OBU_EXTENSION_FLAG_BITMASK: u8 = 0x04;
+pub const OBU_HAS_SIZE_FIELD_BITMASK: u8 = 0x02;
+
+/// `frame_type` of key frames. See 6.8.2.
+pub const KEY_FRAME: u8 = 0;
+
+/// The number of operating points a sequence may describe, as per 5.5.1.
+const MAX_OPERATING_POINTS: usize = 32;
+
+/// `seq_force_screen_content_tools` and `seq_force_integer_mv` value telling
+/// the frame headers to choose. See 6.4.
+const SELECT_SCREEN_CONTENT_TOOLS: u8 = 2;
+const SELECT_INTEGER_MV: u8 = 2;
+
+/// An open bitstream unit.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Obu {
+    pub obu_type: u8,
+    /// The OBU header, with its extension if any.
+    pub header: Bytes,
+    pub payload: Bytes,
+}
+
+/// A AV1 Sequence Header, up to and including the bit depth of the color
+/// config.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SequenceHeader {
+    /// Specifies the features that can be used in the coded video sequence
+    pub seq_profile: u8,
+    /// Whether the coded video sequence contains only one coded frame
+    pub still_picture: bool,
+    /// Retains the same meaning as in the specification. See 6.4
+    pub reduced_still_picture_header: bool,
+    pub timing_info_present_flag: bool,
+    /// Number of time units of a clock operating at the frequency time_scale
+    /// Hz that corresponds to one increment of a clock tick
+    pub num_units_in_display_tick: u32,
+    /// Number of time units that pass in one second
+    pub time_scale: u32,
+    /// Whether pictures are displayed at a constant interval
+    pub equal_picture_interval: bool,
+    /// Number of clock ticks corresponding to the display time of each
+    /// picture, minus 1
+    pub num_ticks_per_picture_minus_1: u32,
+    /// Level of the first operating point, as in table A.1
+    pub seq_level_idx: u8,
+    /// Tier of the first operating point
+    pub seq_tier: bool,
+    /// Retains the same meaning as in the specification. See 6.4
+    pub max_frame_width_minus_1: u32,
+    /// Retains the same meaning as in the specification. See 6.4
+    pub max_frame_height_minus_1: u32,
+    /// Whether frame ids are coded in the frame headers
+    pub frame_id_numbers_present_flag: bool,
+    /// Retains the same meaning as in the specification. See 6.4
+    pub use_128x128_superblock: bool,
+    /// Whether order hints are coded in the frame headers
+    pub enable_order_hint: bool,
+    /// Retains the same meaning as in the specification. See 6.4
+    pub order_hint_bits: u8,
+    /// Retains the same meaning as in the specification. See 6.4
+    pub enable_superres: bool,
+    /// Retains the same meaning as in the specification. See 6.4
+    pub enable_cdef: bool,
+    /// Retains the same meaning as in the specification. See 6.4
+    pub enable_restoration: bool,
+    /// Bits per sample
+    pub bit_depth: u8,
+}
+
+impl SequenceHeader {
+    /// Returns the maximum width of the frames in the sequence.
+    pub const fn width(&self) -> u32 {
+        self.max_frame_width_minus_1 + 1
+    }
+
+    /// Returns the maximum height of the frames in the sequence.
+    pub const fn height(&self) -> u32 {
+        self.max_frame_height_minus_1 + 1
+    }
+}
+
+/// The leading syntax elements of an uncompressed frame header. See 5.9.2.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FrameHeader {
+    /// The frame is a copy of an already decoded one, nothing else is coded
+    pub show_existing_frame: bool,
+    /// Specifies the type of the frame, only known when not showing an
+    /// existing frame
+    pub frame_type: u8,
+    /// Whether the frame is meant to be displayed
+    pub show_frame: bool,
+}
+
+/// Read an unsigned integer coded as leb128. Returns the value and the number
+/// of bytes it took. See 4.10.5.
+pub fn read_leb128(data: &[u8]) -> Option<(usize, usize)> {
+    let mut value = 0;
+    for (i, &b) in data.iter().take(8).enumerate() {
+        value |= usize::from(b & 0x7f) << (i * 7);
+        if b & 0x80 == 0 {
+            return Some((value, i + 1));
+        }
+    }
+    None
+}
+
+/// Write an unsigned integer coded as leb128.
+pub fn put_leb128(buf: &mut BytesMut, mut value: usize) {
+    loop {
+        let b = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            buf.put_u8(b);
+            return;
+        }
+        buf.put_u8(b | 0x80);
+    }
+}
+
+/// Split a temporal unit in the low overhead bitstream format into its OBUs.
+/// A malformed OBU ends the split.
+pub fn split_obus(data: &Bytes) -> Vec<Obu> {
+    let mut obus = Vec::new();
+    let mut offset = 0;
+    while offset < data.len() {
+        let b0 = data[offset];
+        let header_size = if b0 & OBU_EXTENSION_FLAG_BITMASK != 0 {
+            2
+        } else {
+            1
+        };
+        if offset + header_size > data.len() {
+            break;
+        }
+        let header = data.slice(offset..offset + header_size);
+        offset += header_size;
+
+        let size = if b0 & OBU_HAS_SIZE_FIELD_BITMASK != 0 {
+            let Some((size, leb128_size)) = read_leb128(&data[offset..]) else {
+                break;
+            };
+            offset += leb128_size;
+            size
+        } else {
+            data.len() - offset
+        };
+        if offset + size > data.len() {
+            break;
+        }
+
+        obus.push(Obu {
+            obu_type: (b0 & OBU_TYPE_BITMASK) >> 3,
+            header,
+            payload: data.slice(offset..offset + size),
+        });
+        offset += size;
+    }
+    obus
+}
+
+/// Reads a variable length unsigned integer. See 4.10.3.
+fn read_uvlc(r: &mut BitReader) -> Result<u32, String> {
+    let mut leading_zeros = 0;
+    while !r.read_bit()? {
+        leading_zeros += 1;
+        if leading_zeros >= 32 {
+            return Ok(u32::MAX);
+        }
+    }
+    if leading_zeros == 0 {
+        return Ok(0);
+    }
+
+    let value = r.read_bits::<u32>(leading_zeros)?;
+    Ok(value + ((1 << leading_zeros) - 1))
+}
+
+fn read_u32(r: &mut BitReader) -> Result<u32, String> {
+    let high = r.read_bits::<u32>(16)?;
+    let low = r.read_bits::<u32>(16)?;
+    Ok((high << 16) | low)
+}
+
+/// Parse the payload of a sequence header OBU.
+pub fn parse_sequence_header(data: &[u8]) -> Result<(SequenceHeader, usize), String> {
+    let mut r = BitReader::new(data, false);
+    let mut seq = SequenceHeader {
+        seq_profile: r.read_bits(3)?,
+        still_picture: r.read_bit()?,
+        reduced_still_picture_header: r.read_bit()?,
+        ..Default::default()
+    };
+    if seq.seq_profile > 2 {
+        return Err(format!("Invalid seq_profile {}", seq.seq_profile));
+    }
+
+    if seq.reduced_still_picture_header {
+        seq.seq_level_idx = r.read_bits(5)?;
+    } else {
+        seq.timing_info_present_flag = r.read_bit()?;
+        let mut decoder_model_info_present_flag = false;
+        let mut buffer_delay_length = 0;
+        if seq.timing_info_present_flag {
+            seq.num_units_in_display_tick = read_u32(&mut r)?;
+            seq.time_scale = read_u32(&mut r)?;
+            if seq.num_units_in_display_tick == 0 || seq.time_scale == 0 {
+                return Err("Invalid timing info, which is not allowed by 6.4.3".into());
+            }
+            seq.equal_picture_interval = r.read_bit()?;
+            if seq.equal_picture_interval {
+                seq.num_ticks_per_picture_minus_1 = read_uvlc(&mut r)?;
+            }
+
+            decoder_model_info_present_flag = r.read_bit()?;
+            if decoder_model_info_present_flag {
+                buffer_delay_length = r.read_bits::<usize>(5)? + 1;
+                // num_units_in_decoding_tick
+                r.skip_bits(32)?;
+                // buffer_removal_time_length_minus_1 and
+                // frame_presentation_time_length_minus_1
+                r.skip_bits(10)?;
+            }
+        }
+
+        let initial_display_delay_present_flag = r.read_bit()?;
+        let operating_points_cnt = r.read_bits::<usize>(5)? + 1;
+        if operating_points_cnt > MAX_OPERATING_POINTS {
+            return Err(format!(
+                "Invalid operating_points_cnt {operating_points_cnt}"
+            ));
+        }
+        for i in 0..operating_points_cnt {
+            // operating_point_idc
+            r.skip_bits(12)?;
+            let seq_level_idx: u8 = r.read_bits(5)?;
+            let seq_tier = seq_level_idx > 7 && r.read_bit()?;
+            if i == 0 {
+                seq.seq_level_idx = seq_level_idx;
+                seq.seq_tier = seq_tier;
+            }
+
+            if decoder_model_info_present_flag {
+                let decoder_model_present_for_this_op = r.read_bit()?;
+                if decoder_model_present_for_this_op {
+                    // decoder_buffer_delay, encoder_buffer_delay and
+                    // low_delay_mode_flag
+                    r.skip_bits(2 * buffer_delay_length + 1)?;
+                }
+            }
+            if initial_display_delay_present_flag {
+                let initial_display_delay_present_for_this_op = r.read_bit()?;
+                if initial_display_delay_present_for_this_op {
+                    // initial_display_delay_minus_1
+                    r.skip_bits(4)?;
+                }
+            }
+        }
+    }
+
+    let frame_width_bits = r.read_bits::<usize>(4)? + 1;
+    let frame_height_bits = r.read_bits::<usize>(4)? + 1;
+    seq.max_frame_width_minus_1 = r.read_bits(frame_width_bits)?;
+    seq.max_frame_height_minus_1 = r.read_bits(frame_height_bits)?;
+
+    if !seq.reduced_still_picture_header {
+        seq.frame_id_numbers_present_flag = r.read_bit()?;
+    }
+    if seq.frame_id_numbers_present_flag {
+        // delta_frame_id_length_minus_2 and additional_frame_id_length_minus_1
+        r.skip_bits(7)?;
+    }
+
+    seq.use_128x128_superblock = r.read_bit()?;
+    // enable_filter_intra and enable_intra_edge_filter
+    r.skip_bits(2)?;
+
+    if !seq.reduced_still_picture_header {
+        // enable_interintra_compound, enable_masked_compound,
+        // enable_warped_motion and enable_dual_filter
+        r.skip_bits(4)?;
+        seq.enable_order_hint = r.read_bit()?;
+        if seq.enable_order_hint {
+            // enable_jnt_comp and enable_ref_frame_mvs
+            r.skip_bits(2)?;
+        }
+
+        let seq_choose_screen_content_tools = r.read_bit()?;
+        let seq_force_screen_content_tools = if seq_choose_screen_content_tools {
+            SELECT_SCREEN_CONTENT_TOOLS
+        } else {
+            r.read_bits(1)?
+        };
+        if seq_force_screen_content_tools > 0 {
+            let seq_choose_integer_mv = r.read_bit()?;
+            let _seq_force_integer_mv = if seq_choose_integer_mv {
+                SELECT_INTEGER_MV
+            } else {
+                r.read_bits::<u8>(1)?
+            };
+        }
+
+        if seq.enable_order_hint {
+            seq.order_hint_bits = r.read_bits::<u8>(3)? + 1;
+        }
+    }
+
+    seq.enable_superres = r.read_bit()?;
+    seq.enable_cdef = r.read_bit()?;
+    seq.enable_restoration = r.read_bit()?;
+
+    // See 5.5.2 in the specification.
+    let high_bitdepth = r.read_bit()?;
+    seq.bit_depth = if seq.seq_profile == 2 && high_bitdepth {
+        if r.read_bit()? { 12 } else { 10 }
+    } else if high_bitdepth {
+        10
+    } else {
+        8
+    };
+
+    Ok((seq, r.get_stream().position() as usize))
+}
+
+/// Parse the start of the payload of a frame header or frame OBU. Sequences
+/// using reduced still picture headers are not supported, as their frames
+/// carry no such syntax elements.
+pub fn parse_frame_header(data: &[u8]) -> Result<FrameHeader, String> {
+    let mut r = BitReader::new(data, false);
+    let mut header = FrameHeader {
+        show_existing_frame: r.read_bit()?,
+        ..Default::default()
+    };
+    if header.show_existing_frame {
+        return Ok(header);
+    }
+
+    header.frame_type = r.read_bits(2)?;
+    header.show_frame = r.read_bit()?;
+
+    Ok(header)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The payload of a Main profile sequence header for 1920x1080 video at
+    /// 30 frames per second, level 4.0.
+    const SEQUENCE_HEADER_1080P: &[u8] = &[
+        0x04, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00, 0x7b, 0x00, 0x00, 0x08, 0x55, 0x77, 0xf8,
+        0x6e, 0x7f, 0xfc, 0xc0, 0x20,
+    ];
+
+    #[test]
+    fn sequence_header_of_1080p_video() {
+        let (seq, _) = parse_sequence_header(SEQUENCE_HEADER_1080P).unwrap();
+        assert_eq!((seq.width(), seq.height()), (1920, 1080));
+        assert_eq!(seq.seq_profile, 0);
+        assert!(!seq.still_picture);
+        assert_eq!(seq.seq_level_idx, 8);
+        assert!(!seq.seq_tier);
+        assert_eq!(seq.bit_depth, 8);
+
+        assert!(seq.timing_info_present_flag);
+        assert_eq!(seq.num_units_in_display_tick, 1);
+        assert_eq!(seq.time_scale, 30);
+        assert!(seq.equal_picture_interval);
+        assert_eq!(seq.num_ticks_per_picture_minus_1, 0);
+
+        assert!(seq.enable_order_hint);
+        assert_eq!(seq.order_hint_bits, 7);
+        assert!(seq.enable_cdef);
+        assert!(seq.enable_restoration);
+    }
+
+    #[test]
+    fn truncated_sequence_headers_are_refused() {
+        assert!(parse_sequence_header(&SEQUENCE_HEADER_1080P[..12]).is_err());
+        assert!(parse_sequence_header(&[]).is_err());
+    }
+
+    #[test]
+    fn sequence_headers_without_a_time_scale_are_refused() {
+        let mut data = SEQUENCE_HEADER_1080P.to_vec();
+        data[4..9].fill(0);
+        assert!(parse_sequence_header(&data).is_err());
+    }
+
+    #[test]
+    fn temporal_units_are_split_into_obus() {
+        let mut data = BytesMut::new();
+        // A temporal delimiter.
+        data.put(&[0x12, 0x00][..]);
+        // The sequence header, with its size.
+        data.put_u8(0x0a);
+        put_leb128(&mut data, SEQUENCE_HEADER_1080P.len());
+        data.put(SEQUENCE_HEADER_1080P);
+        // A frame with an extension, temporal id 1, running to the end.
+        data.put(&[0x34, 0x20, 0x10, 0xaa, 0xbb][..]);
+
+        let obus = split_obus(&data.freeze());
+        assert_eq!(obus.len(), 3);
+        assert_eq!(obus[0].obu_type, OBU_TEMPORAL_DELIMITER);
+        assert!(obus[0].payload.is_empty());
+        assert_eq!(obus[1].obu_type, OBU_SEQUENCE_HEADER);
+        assert_eq!(obus[1].header, [0x0a][..]);
+        assert_eq!(obus[1].payload, SEQUENCE_HEADER_1080P);
+        assert_eq!(obus[2].obu_type, OBU_FRAME);
+        assert_eq!(obus[2].header, [0x34, 0x20][..]);
+        assert_eq!(obus[2].payload, [0x10, 0xaa, 0xbb][..]);
+
+        let frame = parse_frame_header(&obus[2].payload).unwrap();
+        assert_eq!(frame.frame_type, KEY_FRAME);
+        assert!(frame.show_frame);
+    }
+
+    #[test]
+    fn splitting_stops_at_a_malformed_obu() {
+        // A padding OBU claiming more bytes than there are, after an empty
+        // temporal delimiter.
+        let obus = split_obus(&Bytes::from_static(&[0x12, 0x00, 0x7a, 0x05, 0x00]));
+        assert_eq!(obus.len(), 1);
+        assert_eq!(obus[0].obu_type, OBU_TEMPORAL_DELIMITER);
+    }
+
+    #[test]
+    fn leb128_round_trips() {
+        for value in [0, 1, 127, 128, 300, 1 << 20] {
+            let mut buf = BytesMut::new();
+            put_leb128(&mut buf, value);
+            assert_eq!(read_leb128(&buf), Some((value, buf.len())));
+        }
+        assert_eq!(read_leb128(&[0x80]), None);
+    }
+}
@@ -1,9 +1,28 @@
 use bytes::{BufMut, Bytes, BytesMut};
-use webrtc::rtp::packetizer::Depacketizer;
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    str::FromStr,
+};
+use webrtc::{
+    api::media_engine::{
+        MIME_TYPE_AV1, MIME_TYPE_H264, MIME_TYPE_HEVC, MIME_TYPE_OPUS, MIME_TYPE_VP8, MIME_TYPE_VP9,
+    },
+    rtp::packetizer::Depacketizer,
+};
+
+use super::{
+    av1_parser::{
+        KEY_FRAME, OBU_EXTENSION_FLAG_BITMASK, OBU_FRAME, OBU_FRAME_HEADER,
+        OBU_HAS_SIZE_FIELD_BITMASK, OBU_TEMPORAL_DELIMITER, OBU_TILE_LIST, OBU_TYPE_BITMASK,
+        parse_frame_header, put_leb128, read_leb128, split_obus,
+    },
+    vpx_parser::{parse_vp8_frame_header, parse_vp9_frame_header},
+};
 
 pub const STAPA_NALU_TYPE: u8 = 24;
 pub const FUA_NALU_TYPE: u8 = 28;
 pub const FUB_NALU_TYPE: u8 = 29;
+pub const IDR_NALU_TYPE: u8 = 5;
 pub const SPS_NALU_TYPE: u8 = 7;
 pub const PPS_NALU_TYPE: u8 = 8;
 pub const AUD_NALU_TYPE: u8 = 9;
@@ -20,8 +39,248 @@ pub const FU_END_BITMASK: u8 = 0x40;
 
 pub const OUTPUT_STAP_AHEADER: u8 = 0x78;
 
+pub const H265_VPS_NALU_TYPE: u8 = 32;
+pub const H265_SPS_NALU_TYPE: u8 = 33;
+pub const H265_PPS_NALU_TYPE: u8 = 34;
+pub const H265_AUD_NALU_TYPE: u8 = 35;
+pub const H265_AP_NALU_TYPE: u8 = 48;
+pub const H265_FU_NALU_TYPE: u8 = 49;
+pub const H265_PACI_NALU_TYPE: u8 = 50;
+
+pub const H265_NALU_HEADER_SIZE: usize = 2;
+pub const H265_FU_HEADER_SIZE: usize = 3;
+pub const H265_AP_NALU_LENGTH_SIZE: usize = 2;
+
+pub const AV1_AGGREGATION_HEADER_SIZE: usize = 1;
+pub const AV1_Z_BITMASK: u8 = 0x80;
+pub const AV1_Y_BITMASK: u8 = 0x40;
+pub const AV1_W_BITMASK: u8 = 0x30;
+
+pub const H265_NALU_TYPE_BITMASK: u8 = 0x7E;
+pub const H265_FU_TYPE_BITMASK: u8 = 0x3F;
+
 pub static ANNEXB_NALUSTART_CODE: Bytes = Bytes::from_static(&[0x00, 0x00, 0x00, 0x01]);
 
+/// Codecs which samples can be encoded with as they travel from a source to
+/// the mirrors.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Codec {
+    Opus,
+    /// MPEG-4 AAC, samples are framed with an ADTS header.
+    AAC,
+    /// H.264, samples are an Annex B byte stream.
+    H264,
+    /// H.265, samples are an Annex B byte stream.
+    H265,
+    /// VP8, samples are whole frames.
+    VP8,
+    /// VP9, samples are whole frames or superframes.
+    VP9,
+    /// AV1, samples are temporal units in the low overhead bitstream format.
+    AV1,
+}
+
+impl Codec {
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            Codec::Opus => MIME_TYPE_OPUS,
+            Codec::AAC => "audio/aac",
+            Codec::H264 => MIME_TYPE_H264,
+            Codec::H265 => MIME_TYPE_HEVC,
+            Codec::VP8 => MIME_TYPE_VP8,
+            Codec::VP9 => MIME_TYPE_VP9,
+            Codec::AV1 => MIME_TYPE_AV1,
+        }
+    }
+
+    /// Look up the codec of a negotiated track, ignoring case as SDP does.
+    pub fn from_mime_type(mime_type: &str) -> Option<Self> {
+        [
+            Codec::Opus,
+            Codec::AAC,
+            Codec::H264,
+            Codec::H265,
+            Codec::VP8,
+            Codec::VP9,
+            Codec::AV1,
+        ]
+        .into_iter()
+        .find(|codec| codec.mime_type().eq_ignore_ascii_case(mime_type))
+    }
+
+    /// Whether a sample can be decoded without the samples before it. Audio
+    /// frames always can.
+    pub fn is_keyframe(&self, data: &Bytes) -> bool {
+        match self {
+            Codec::Opus | Codec::AAC => true,
+            Codec::H264 => split_annexb(data).iter().any(|nalu| {
+                nalu.first()
+                    .is_some_and(|b| b & NALU_TYPE_BITMASK == IDR_NALU_TYPE)
+            }),
+            // BLA, IDR and CRA pictures are all random access points.
+            Codec::H265 => split_annexb(data).iter().any(|nalu| {
+                nalu.first()
+                    .is_some_and(|b| (16..=21).contains(&h265_nalu_type(*b)))
+            }),
+            Codec::VP8 => parse_vp8_frame_header(data).is_ok_and(|header| header.key_frame),
+            Codec::VP9 => parse_vp9_frame_header(data).is_ok_and(|header| header.key_frame),
+            Codec::AV1 => split_obus(data)
+                .iter()
+                .find(|obu| obu.obu_type == OBU_FRAME || obu.obu_type == OBU_FRAME_HEADER)
+                .and_then(|obu| parse_frame_header(&obu.payload).ok())
+                .is_some_and(|header| {
+                    !header.show_existing_frame && header.frame_type == KEY_FRAME
+                }),
+        }
+    }
+}
+
+impl Display for Codec {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Codec::Opus => f.write_str("opus"),
+            Codec::AAC => f.write_str("aac"),
+            Codec::H264 => f.write_str("h264"),
+            Codec::H265 => f.write_str("h265"),
+            Codec::VP8 => f.write_str("vp8"),
+            Codec::VP9 => f.write_str("vp9"),
+            Codec::AV1 => f.write_str("av1"),
+        }
+    }
+}
+
+impl FromStr for Codec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "opus" => Ok(Codec::Opus),
+            "aac" => Ok(Codec::AAC),
+            "h264" => Ok(Codec::H264),
+            "h265" => Ok(Codec::H265),
+            "vp8" => Ok(Codec::VP8),
+            "vp9" => Ok(Codec::VP9),
+            "av1" => Ok(Codec::AV1),
+            _ => Err(format!("unknown codec `{s}`")),
+        }
+    }
+}
+
+/// Extract the NAL unit type from the first byte of a H265 NAL unit header.
+pub fn h265_nalu_type(b0: u8) -> u8 {
+    (b0 & H265_NALU_TYPE_BITMASK) >> 1
+}
+
+/// Split an Annex B byte stream into its NAL units, without start codes.
+pub fn split_annexb(data: &Bytes) -> Vec<Bytes> {
+    let mut starts = Vec::new();
+    let mut i = 0;
+    while i + 3 <= data.len() {
+        if data[i] == 0 && data[i + 1] == 0 && data[i + 2] == 1 {
+            starts.push((i, i + 3));
+            i += 3;
+        } else {
+            i += 1;
+        }
+    }
+
+    let mut nalus = Vec::with_capacity(starts.len());
+    for (pos, &(_, start)) in starts.iter().enumerate() {
+        let mut end = starts.get(pos + 1).map(|v| v.0).unwrap_or(data.len());
+        // A four byte start code leaves its leading zero behind.
+        while end > start && data[end - 1] == 0 {
+            end -= 1;
+        }
+        if end > start {
+            nalus.push(data.slice(start..end));
+        }
+    }
+    nalus
+}
+
+/// The NAL unit type of a H264 or H265 NAL unit, `None` for other codecs.
+fn nalu_type(codec: Codec, nalu: &[u8]) -> Option<u8> {
+    let b0 = *nalu.first()?;
+    match codec {
+        Codec::H264 => Some(b0 & NALU_TYPE_BITMASK),
+        Codec::H265 => Some(h265_nalu_type(b0)),
+        _ => None,
+    }
+}
+
+/// The parameter sets of a H264 or H265 stream, as last seen in its samples,
+/// for decoders starting at a keyframe that does not carry them.
+#[derive(Clone, Default)]
+pub struct ParameterSets {
+    codec: Option<Codec>,
+    /// Latest NAL unit of each parameter set type, in decoding order.
+    nalus: Vec<(u8, Bytes)>,
+}
+
+impl ParameterSets {
+    /// NAL unit types of the parameter sets of the codec, in decoding order.
+    fn types(codec: Codec) -> &'static [u8] {
+        match codec {
+            Codec::H264 => &[SPS_NALU_TYPE, PPS_NALU_TYPE],
+            Codec::H265 => &[H265_VPS_NALU_TYPE, H265_SPS_NALU_TYPE, H265_PPS_NALU_TYPE],
+            _ => &[],
+        }
+    }
+
+    /// Keep the parameter sets carried by an Annex B sample.
+    pub fn update(&mut self, codec: Codec, data: &Bytes) {
+        let types = Self::types(codec);
+        if types.is_empty() {
+            return;
+        }
+        if self.codec != Some(codec) {
+            self.codec = Some(codec);
+            self.nalus.clear();
+        }
+
+        for nalu in split_annexb(data) {
+            let Some(nalu_type) = nalu_type(codec, &nalu).filter(|t| types.contains(t)) else {
+                continue;
+            };
+            match self.nalus.iter_mut().find(|(t, _)| *t == nalu_type) {
+                Some(kept) => kept.1 = nalu,
+                None => self.nalus.push((nalu_type, nalu)),
+            }
+        }
+        self.nalus
+            .sort_by_key(|(t, _)| types.iter().position(|kept| kept == t));
+    }
+
+    /// Lead an Annex B keyframe with the kept parameter sets it lacks.
+    pub fn prepend(&self, codec: Codec, data: &Bytes) -> Bytes {
+        if self.codec != Some(codec) || self.nalus.is_empty() {
+            return data.clone();
+        }
+
+        let carried: Vec<u8> = split_annexb(data)
+            .iter()
+            .filter_map(|nalu| nalu_type(codec, nalu))
+            .collect();
+        let missing: Vec<&Bytes> = self
+            .nalus
+            .iter()
+            .filter(|(t, _)| !carried.contains(t))
+            .map(|(_, nalu)| nalu)
+            .collect();
+        if missing.is_empty() {
+            return data.clone();
+        }
+
+        let mut buf = BytesMut::new();
+        for nalu in missing {
+            buf.put(&*ANNEXB_NALUSTART_CODE);
+            buf.put(&nalu[..]);
+        }
+        buf.put(&data[..]);
+        buf.freeze()
+    }
+}
+
 /// H264Packet represents the H264 header that is stored in the payload of an RTP Packet
 #[derive(PartialEq, Eq, Debug, Default, Clone)]
 pub struct H264Packet {
@@ -134,3 +393,405 @@ impl Depacketizer for H264Packet {
         marker
     }
 }
+
+/// H265Packet represents the H265 header that is stored in the payload of an RTP Packet
+#[derive(PartialEq, Eq, Debug, Default, Clone)]
+pub struct H265Packet {
+    fu_buffer: Option<BytesMut>,
+}
+
+impl Depacketizer for H265Packet {
+    /// depacketize parses the passed byte slice and stores the result in the H265Packet this method is called upon
+    fn depacketize(&mut self, packet: &Bytes) -> Result<Bytes, webrtc::rtp::Error> {
+        if packet.len() <= H265_NALU_HEADER_SIZE {
+            return Err(webrtc::rtp::Error::ErrShortPacket);
+        }
+
+        let mut payload = BytesMut::new();
+
+        // NALU Types
+        // https://datatracker.ietf.org/doc/html/rfc7798#section-4.4
+        let b0 = packet[0];
+        let nalu_type = h265_nalu_type(b0);
+
+        match nalu_type {
+            0..=47 => {
+                payload.put(&*ANNEXB_NALUSTART_CODE);
+                payload.put(&*packet.clone());
+                Ok(payload.freeze())
+            }
+            H265_AP_NALU_TYPE => {
+                let mut curr_offset = H265_NALU_HEADER_SIZE;
+                while curr_offset + 1 < packet.len() {
+                    let nalu_size =
+                        ((packet[curr_offset] as usize) << 8) | packet[curr_offset + 1] as usize;
+                    curr_offset += H265_AP_NALU_LENGTH_SIZE;
+
+                    if curr_offset + nalu_size > packet.len() {
+                        return Err(webrtc::rtp::Error::StapASizeLargerThanBuffer(
+                            nalu_size,
+                            packet.len() - curr_offset,
+                        ));
+                    }
+
+                    payload.put(&*ANNEXB_NALUSTART_CODE);
+                    payload.put(&*packet.slice(curr_offset..curr_offset + nalu_size));
+                    curr_offset += nalu_size;
+                }
+
+                Ok(payload.freeze())
+            }
+            H265_FU_NALU_TYPE => {
+                if packet.len() <= H265_FU_HEADER_SIZE {
+                    return Err(webrtc::rtp::Error::ErrShortPacket);
+                }
+
+                let b2 = packet[2];
+                if b2 & FU_START_BITMASK != 0 {
+                    self.fu_buffer = Some(BytesMut::new());
+                }
+
+                // A fragment whose start was lost cannot be reassembled.
+                let Some(fu_buffer) = &mut self.fu_buffer else {
+                    return Ok(Bytes::new());
+                };
+                fu_buffer.put(&*packet.slice(H265_FU_HEADER_SIZE..));
+
+                if b2 & FU_END_BITMASK != 0 {
+                    let fragmented_nalu_type = b2 & H265_FU_TYPE_BITMASK;
+
+                    if let Some(fu_buffer) = self.fu_buffer.take() {
+                        payload.put(&*ANNEXB_NALUSTART_CODE);
+                        payload
+                            .put_u8((b0 & !H265_NALU_TYPE_BITMASK) | (fragmented_nalu_type << 1));
+                        payload.put_u8(packet[1]);
+                        payload.put(fu_buffer);
+                    }
+
+                    Ok(payload.freeze())
+                } else {
+                    Ok(Bytes::new())
+                }
+            }
+            H265_PACI_NALU_TYPE => Err(webrtc::rtp::Error::ErrInvalidH265PacketType),
+            _ => Err(webrtc::rtp::Error::NaluTypeIsNotHandled(nalu_type)),
+        }
+    }
+
+    /// is_partition_head checks if this is the head of a packetized nalu stream.
+    fn is_partition_head(&self, payload: &Bytes) -> bool {
+        if payload.len() < H265_FU_HEADER_SIZE {
+            return false;
+        }
+
+        if h265_nalu_type(payload[0]) == H265_FU_NALU_TYPE {
+            (payload[2] & FU_START_BITMASK) != 0
+        } else {
+            true
+        }
+    }
+
+    fn is_partition_tail(&self, marker: bool, _payload: &Bytes) -> bool {
+        marker
+    }
+}
+
+/// Av1Packet reassembles the OBU elements carried in RTP packets into a
+/// temporal unit in the low overhead bitstream format
+#[derive(PartialEq, Eq, Debug, Default, Clone)]
+pub struct Av1Packet {
+    obu_buffer: Option<BytesMut>,
+}
+
+impl Av1Packet {
+    /// Append a complete OBU with its size field, as the low overhead bitstream
+    /// format requires. Temporal delimiters and tile lists are dropped, as per
+    /// section 5 of the RTP payload format.
+    fn put_obu(payload: &mut BytesMut, obu: &[u8]) {
+        let Some(&b0) = obu.first() else {
+            return;
+        };
+        let obu_type = (b0 & OBU_TYPE_BITMASK) >> 3;
+        if obu_type == OBU_TEMPORAL_DELIMITER || obu_type == OBU_TILE_LIST {
+            return;
+        }
+
+        let header_size = if b0 & OBU_EXTENSION_FLAG_BITMASK != 0 {
+            2
+        } else {
+            1
+        };
+        if obu.len() < header_size {
+            return;
+        }
+        let mut body = &obu[header_size..];
+        if b0 & OBU_HAS_SIZE_FIELD_BITMASK != 0 {
+            let Some((size, leb128_size)) = read_leb128(body) else {
+                return;
+            };
+            let Some(sized) = body.get(leb128_size..leb128_size + size) else {
+                return;
+            };
+            body = sized;
+        }
+
+        payload.put_u8(b0 | OBU_HAS_SIZE_FIELD_BITMASK);
+        payload.put(&obu[1..header_size]);
+        put_leb128(payload, body.len());
+        payload.put(body);
+    }
+}
+
+impl Depacketizer for Av1Packet {
+    /// depacketize parses the passed byte slice and stores the result in the Av1Packet this method is called upon
+    fn depacketize(&mut self, packet: &Bytes) -> Result<Bytes, webrtc::rtp::Error> {
+        if packet.len() <= AV1_AGGREGATION_HEADER_SIZE {
+            return Err(webrtc::rtp::Error::ErrShortPacket);
+        }
+
+        let mut payload = BytesMut::new();
+
+        // Aggregation header
+        // https://aomediacodec.github.io/av1-rtp-spec/#44-av1-aggregation-header
+        let b0 = packet[0];
+        let continues_previous = b0 & AV1_Z_BITMASK != 0;
+        let continues_next = b0 & AV1_Y_BITMASK != 0;
+        let obu_count = ((b0 & AV1_W_BITMASK) >> 4) as usize;
+
+        let mut curr_offset = AV1_AGGREGATION_HEADER_SIZE;
+        let mut index = 0;
+        while curr_offset < packet.len() {
+            index += 1;
+
+            // Only the last element of a packet with a known number of them
+            // goes without a length field.
+            let obu_size = if obu_count == 0 || index < obu_count {
+                let (obu_size, leb128_size) = read_leb128(&packet[curr_offset..])
+                    .ok_or(webrtc::rtp::Error::ErrShortPacket)?;
+                curr_offset += leb128_size;
+                obu_size
+            } else {
+                packet.len() - curr_offset
+            };
+            if curr_offset + obu_size > packet.len() {
+                return Err(webrtc::rtp::Error::ErrShortPacket);
+            }
+            let element = &packet[curr_offset..curr_offset + obu_size];
+            curr_offset += obu_size;
+
+            if index == 1 && continues_previous {
+                // A fragment whose start was lost cannot be reassembled.
+                let Some(obu_buffer) = &mut self.obu_buffer else {
+                    continue;
+                };
+                obu_buffer.put(element);
+            } else {
+                self.obu_buffer = Some(BytesMut::from(element));
+            }
+
+            if curr_offset >= packet.len() && continues_next {
+                break;
+            }
+            if let Some(obu_buffer) = self.obu_buffer.take() {
+                Self::put_obu(&mut payload, &obu_buffer);
+            }
+        }
+
+        Ok(payload.freeze())
+    }
+
+    /// is_partition_head checks if this is the head of a packetized OBU stream.
+    fn is_partition_head(&self, payload: &Bytes) -> bool {
+        payload.first().is_some_and(|b0| b0 & AV1_Z_BITMASK == 0)
+    }
+
+    fn is_partition_tail(&self, marker: bool, _payload: &Bytes) -> bool {
+        marker
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn depacketize(packet: &mut impl Depacketizer, data: &'static [u8]) -> Bytes {
+        packet.depacketize(&Bytes::from_static(data)).unwrap()
+    }
+
+    #[test]
+    fn h265_single_nalus_get_a_start_code() {
+        let mut packet = H265Packet::default();
+        assert_eq!(
+            depacketize(&mut packet, &[0x26, 0x01, 0xaf, 0x09]),
+            [0, 0, 0, 1, 0x26, 0x01, 0xaf, 0x09][..]
+        );
+    }
+
+    #[test]
+    fn h265_aggregation_packets_are_split() {
+        let mut packet = H265Packet::default();
+        // A VPS, an SPS and a PPS behind a layer 0, temporal id 1 AP header.
+        let data = depacketize(
+            &mut packet,
+            &[
+                0x60, 0x01, // AP
+                0x00, 0x03, 0x40, 0x01, 0x0c, // VPS
+                0x00, 0x04, 0x42, 0x01, 0x01, 0x60, // SPS
+                0x00, 0x02, 0x44, 0x01, // PPS
+            ],
+        );
+        assert_eq!(
+            data,
+            [
+                0, 0, 0, 1, 0x40, 0x01, 0x0c, // VPS
+                0, 0, 0, 1, 0x42, 0x01, 0x01, 0x60, // SPS
+                0, 0, 0, 1, 0x44, 0x01, // PPS
+            ][..]
+        );
+    }
+
+    #[test]
+    fn h265_aggregation_packets_overrunning_the_buffer_are_refused() {
+        let mut packet = H265Packet::default();
+        let err = packet
+            .depacketize(&Bytes::from_static(&[0x60, 0x01, 0x00, 0x05, 0x40, 0x01]))
+            .unwrap_err();
+        assert_eq!(err, webrtc::rtp::Error::StapASizeLargerThanBuffer(5, 2));
+    }
+
+    #[test]
+    fn h265_fragmentation_units_are_reassembled() {
+        let mut packet = H265Packet::default();
+        // An IDR_W_RADL (19) slice cut into three fragments.
+        assert!(depacketize(&mut packet, &[0x62, 0x01, 0x80 | 19, 0xaa, 0xbb]).is_empty());
+        assert!(depacketize(&mut packet, &[0x62, 0x01, 19, 0xcc]).is_empty());
+        assert_eq!(
+            depacketize(&mut packet, &[0x62, 0x01, 0x40 | 19, 0xdd, 0xee]),
+            [0, 0, 0, 1, 19 << 1, 0x01, 0xaa, 0xbb, 0xcc, 0xdd, 0xee][..]
+        );
+
+        // The buffer is gone with the unit it built.
+        assert!(depacketize(&mut packet, &[0x62, 0x01, 0x40 | 19, 0xff]).is_empty());
+    }
+
+    #[test]
+    fn h265_fragmentation_units_keep_the_layer_and_temporal_id() {
+        let mut packet = H265Packet::default();
+        // A TRAIL_R (1) slice from layer 1, temporal id 2 in a single fragment.
+        assert_eq!(
+            depacketize(&mut packet, &[0x62, 0x0a, 0xc0 | 1, 0x42]),
+            [0, 0, 0, 1, 1 << 1, 0x0a, 0x42][..]
+        );
+    }
+
+    #[test]
+    fn h265_fragmentation_units_without_a_start_are_dropped() {
+        let mut packet = H265Packet::default();
+        assert!(depacketize(&mut packet, &[0x62, 0x01, 19, 0xcc]).is_empty());
+        assert!(depacketize(&mut packet, &[0x62, 0x01, 0x40 | 19, 0xdd]).is_empty());
+
+        // A new start is reassembled as usual.
+        depacketize(&mut packet, &[0x62, 0x01, 0x80 | 19, 0xaa]);
+        assert_eq!(
+            depacketize(&mut packet, &[0x62, 0x01, 0x40 | 19, 0xbb]),
+            [0, 0, 0, 1, 19 << 1, 0x01, 0xaa, 0xbb][..]
+        );
+    }
+
+    #[test]
+    fn h265_fragmentation_units_restart_on_a_new_start() {
+        let mut packet = H265Packet::default();
+        depacketize(&mut packet, &[0x62, 0x01, 0x80 | 19, 0xaa]);
+        // The end of the first unit was lost, a second one begins.
+        depacketize(&mut packet, &[0x62, 0x01, 0x80 | 1, 0x11]);
+        assert_eq!(
+            depacketize(&mut packet, &[0x62, 0x01, 0x40 | 1, 0x22]),
+            [0, 0, 0, 1, 1 << 1, 0x01, 0x11, 0x22][..]
+        );
+    }
+
+    #[test]
+    fn h265_short_packets_are_refused() {
+        let mut packet = H265Packet::default();
+        for data in [&[][..], &[0x26, 0x01], &[0x62, 0x01, 0x80 | 19]] {
+            assert_eq!(
+                packet.depacketize(&Bytes::copy_from_slice(data)),
+                Err(webrtc::rtp::Error::ErrShortPacket)
+            );
+        }
+    }
+
+    #[test]
+    fn h265_paci_packets_are_refused() {
+        let mut packet = H265Packet::default();
+        assert_eq!(
+            packet.depacketize(&Bytes::from_static(&[0x64, 0x01, 0x00, 0x00])),
+            Err(webrtc::rtp::Error::ErrInvalidH265PacketType)
+        );
+    }
+
+    #[test]
+    fn av1_obus_get_a_size_field() {
+        let mut packet = Av1Packet::default();
+        // A sequence header and a frame, the last one without a length.
+        let data = depacketize(
+            &mut packet,
+            &[0x20, 0x03, 0x08, 0x00, 0x01, 0x30, 0x10, 0xaa],
+        );
+        assert_eq!(data, [0x0a, 0x02, 0x00, 0x01, 0x32, 0x02, 0x10, 0xaa][..]);
+    }
+
+    #[test]
+    fn av1_obus_keep_their_extension_and_size_field() {
+        let mut packet = Av1Packet::default();
+        // A frame with an extension and a size field narrower than the
+        // element, without a count of elements.
+        let data = depacketize(&mut packet, &[0x00, 0x05, 0x36, 0x20, 0x01, 0x10, 0xff]);
+        assert_eq!(data, [0x36, 0x20, 0x01, 0x10][..]);
+    }
+
+    #[test]
+    fn av1_temporal_delimiters_are_dropped() {
+        let mut packet = Av1Packet::default();
+        let data = depacketize(&mut packet, &[0x20, 0x01, 0x10, 0x30, 0x10]);
+        assert_eq!(data, [0x32, 0x01, 0x10][..]);
+    }
+
+    #[test]
+    fn av1_fragments_are_reassembled() {
+        let mut packet = Av1Packet::default();
+        // A whole sequence header, then the start of a frame.
+        assert_eq!(
+            depacketize(&mut packet, &[0x60, 0x02, 0x08, 0x00, 0x30, 0x10]),
+            [0x0a, 0x01, 0x00][..]
+        );
+        assert!(depacketize(&mut packet, &[0xd0, 0xaa]).is_empty());
+        // The end of the frame, then a whole frame.
+        assert_eq!(
+            depacketize(&mut packet, &[0xa0, 0x01, 0xbb, 0x30, 0x90]),
+            [0x32, 0x03, 0x10, 0xaa, 0xbb, 0x32, 0x01, 0x90][..]
+        );
+    }
+
+    #[test]
+    fn av1_fragments_without_a_start_are_dropped() {
+        let mut packet = Av1Packet::default();
+        assert!(depacketize(&mut packet, &[0xd0, 0xaa]).is_empty());
+        // The end of the lost frame is skipped, not what follows it.
+        assert_eq!(
+            depacketize(&mut packet, &[0xa0, 0x01, 0xbb, 0x30, 0x90]),
+            [0x32, 0x01, 0x90][..]
+        );
+    }
+
+    #[test]
+    fn av1_short_packets_are_refused() {
+        let mut packet = Av1Packet::default();
+        for data in [&[][..], &[0x10], &[0x00, 0x05, 0x30], &[0x00, 0x80]] {
+            assert_eq!(
+                packet.depacketize(&Bytes::copy_from_slice(data)),
+                Err(webrtc::rtp::Error::ErrShortPacket)
+            );
+        }
+    }
+}
@@ -0,0 +1,353 @@
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+
+use super::codecs::{
+    ANNEXB_NALUSTART_CODE, Codec, IDR_NALU_TYPE, NALU_TYPE_BITMASK, PPS_NALU_TYPE, SPS_NALU_TYPE,
+};
+
+pub const SOUND_FORMAT_AAC: u8 = 10;
+pub const SOUND_FORMAT_EX_HEADER: u8 = 9;
+pub const VIDEO_CODEC_AVC: u8 = 7;
+pub const VIDEO_FRAME_KEY: u8 = 1;
+pub const VIDEO_FRAME_COMMAND: u8 = 5;
+
+pub const PACKET_TYPE_SEQUENCE_START: u8 = 0;
+pub const PACKET_TYPE_CODED_FRAMES: u8 = 1;
+pub const PACKET_TYPE_SEQUENCE_END: u8 = 2;
+pub const PACKET_TYPE_CODED_FRAMES_X: u8 = 3;
+
+pub const AAC_SAMPLE_RATES: [u32; 13] = [
+    96000, 88200, 64000, 48000, 44100, 32000, 24000, 22050, 16000, 12000, 11025, 8000, 7350,
+];
+
+/// The payload of an FLV video tag, legacy or enhanced (E-RTMP).
+#[derive(Debug)]
+pub enum VideoTag {
+    SequenceStart {
+        codec: Codec,
+        config: Bytes,
+    },
+    CodedFrames {
+        codec: Codec,
+        keyframe: bool,
+        composition_time: i32,
+        data: Bytes,
+    },
+    SequenceEnd {
+        codec: Codec,
+    },
+}
+
+/// The payload of an FLV audio tag, legacy or enhanced (E-RTMP).
+#[derive(Debug)]
+pub enum AudioTag {
+    SequenceStart { codec: Codec, config: Bytes },
+    CodedFrames { codec: Codec, data: Bytes },
+    SequenceEnd { codec: Codec },
+}
+
+/// Parse a video tag body. Returns `None` for packets which carry no media,
+/// such as command frames or metadata.
+pub fn parse_video_tag(mut data: Bytes) -> Result<Option<VideoTag>, String> {
+    if !data.has_remaining() {
+        return Err("flv video tag is empty".into());
+    }
+
+    let b0 = data.get_u8();
+    if b0 & 0x80 != 0 {
+        let frame_type = (b0 >> 4) & 0x07;
+        let packet_type = b0 & 0x0F;
+        if data.remaining() < 4 {
+            return Err("flv video fourcc is truncated".into());
+        }
+        let fourcc = data.split_to(4);
+        if frame_type == VIDEO_FRAME_COMMAND {
+            return Ok(None);
+        }
+        let codec = match &fourcc[..] {
+            b"avc1" => Codec::H264,
+            fourcc => {
+                return Err(format!(
+                    "flv video fourcc {} is not supported",
+                    String::from_utf8_lossy(fourcc)
+                ));
+            }
+        };
+
+        let tag = match packet_type {
+            PACKET_TYPE_SEQUENCE_START => VideoTag::SequenceStart {
+                codec,
+                config: data,
+            },
+            PACKET_TYPE_CODED_FRAMES => {
+                let composition_time = read_i24(&mut data)?;
+                VideoTag::CodedFrames {
+                    codec,
+                    keyframe: frame_type == VIDEO_FRAME_KEY,
+                    composition_time,
+                    data,
+                }
+            }
+            PACKET_TYPE_CODED_FRAMES_X => VideoTag::CodedFrames {
+                codec,
+                keyframe: frame_type == VIDEO_FRAME_KEY,
+                composition_time: 0,
+                data,
+            },
+            PACKET_TYPE_SEQUENCE_END => VideoTag::SequenceEnd { codec },
+            _ => return Ok(None),
+        };
+        return Ok(Some(tag));
+    }
+
+    let frame_type = b0 >> 4;
+    let codec_id = b0 & 0x0F;
+    if frame_type == VIDEO_FRAME_COMMAND {
+        return Ok(None);
+    }
+    if codec_id != VIDEO_CODEC_AVC {
+        return Err(format!("flv video codec id {codec_id} is not supported"));
+    }
+    if !data.has_remaining() {
+        return Err("flv avc packet type is missing".into());
+    }
+
+    let packet_type = data.get_u8();
+    let composition_time = read_i24(&mut data)?;
+    let codec = Codec::H264;
+    let tag = match packet_type {
+        0 => VideoTag::SequenceStart {
+            codec,
+            config: data,
+        },
+        1 => VideoTag::CodedFrames {
+            codec,
+            keyframe: frame_type == VIDEO_FRAME_KEY,
+            composition_time,
+            data,
+        },
+        2 => VideoTag::SequenceEnd { codec },
+        _ => return Ok(None),
+    };
+    Ok(Some(tag))
+}
+
+/// Parse an audio tag body.
+pub fn parse_audio_tag(mut data: Bytes) -> Result<Option<AudioTag>, String> {
+    if !data.has_remaining() {
+        return Err("flv audio tag is empty".into());
+    }
+
+    let b0 = data.get_u8();
+    let sound_format = b0 >> 4;
+    match sound_format {
+        SOUND_FORMAT_AAC => {
+            if !data.has_remaining() {
+                return Err("flv aac packet type is missing".into());
+            }
+            let codec = Codec::AAC;
+            let tag = match data.get_u8() {
+                0 => AudioTag::SequenceStart {
+                    codec,
+                    config: data,
+                },
+                _ => AudioTag::CodedFrames { codec, data },
+            };
+            Ok(Some(tag))
+        }
+        SOUND_FORMAT_EX_HEADER => {
+            let packet_type = b0 & 0x0F;
+            if data.remaining() < 4 {
+                return Err("flv audio fourcc is truncated".into());
+            }
+            let fourcc = data.split_to(4);
+            let codec = match &fourcc[..] {
+                b"Opus" => Codec::Opus,
+                b"mp4a" => Codec::AAC,
+                fourcc => {
+                    return Err(format!(
+                        "flv audio fourcc {} is not supported",
+                        String::from_utf8_lossy(fourcc)
+                    ));
+                }
+            };
+            let tag = match packet_type {
+                PACKET_TYPE_SEQUENCE_START => AudioTag::SequenceStart {
+                    codec,
+                    config: data,
+                },
+                PACKET_TYPE_CODED_FRAMES => AudioTag::CodedFrames { codec, data },
+                PACKET_TYPE_SEQUENCE_END => AudioTag::SequenceEnd { codec },
+                _ => return Ok(None),
+            };
+            Ok(Some(tag))
+        }
+        _ => Err(format!("flv sound format {sound_format} is not supported")),
+    }
+}
+
+fn read_i24(data: &mut Bytes) -> Result<i32, String> {
+    if data.remaining() < 3 {
+        return Err("flv composition time is truncated".into());
+    }
+    let value =
+        ((data.get_u8() as i32) << 16) | ((data.get_u8() as i32) << 8) | data.get_u8() as i32;
+    Ok((value << 8) >> 8)
+}
+
+/// AVCDecoderConfigurationRecord, as defined in ISO/IEC 14496-15 5.3.3.1.
+#[derive(Clone, Debug, Default)]
+pub struct AvcDecoderConfig {
+    pub profile_indication: u8,
+    pub profile_compatibility: u8,
+    pub level_indication: u8,
+    pub length_size: usize,
+    pub sps: Vec<Bytes>,
+    pub pps: Vec<Bytes>,
+}
+
+impl AvcDecoderConfig {
+    pub fn parse(mut data: Bytes) -> Result<Self, String> {
+        if data.remaining() < 6 {
+            return Err("avc decoder configuration record is truncated".into());
+        }
+
+        let _version = data.get_u8();
+        let profile_indication = data.get_u8();
+        let profile_compatibility = data.get_u8();
+        let level_indication = data.get_u8();
+        let length_size = (data.get_u8() & 0x03) as usize + 1;
+
+        let num_sps = data.get_u8() & 0x1F;
+        let sps = read_parameter_sets(&mut data, num_sps as usize)?;
+        if !data.has_remaining() {
+            return Err("avc decoder configuration record has no pps".into());
+        }
+        let num_pps = data.get_u8();
+        let pps = read_parameter_sets(&mut data, num_pps as usize)?;
+
+        Ok(Self {
+            profile_indication,
+            profile_compatibility,
+            level_indication,
+            length_size,
+            sps,
+            pps,
+        })
+    }
+
+    /// Write the parameter sets as an Annex B byte stream.
+    pub fn write_annexb(&self, buf: &mut BytesMut) {
+        for nalu in self.sps.iter().chain(self.pps.iter()) {
+            buf.put(&*ANNEXB_NALUSTART_CODE);
+            buf.put(&**nalu);
+        }
+    }
+}
+
+fn read_parameter_sets(data: &mut Bytes, count: usize) -> Result<Vec<Bytes>, String> {
+    let mut sets = Vec::with_capacity(count);
+    for _ in 0..count {
+        if data.remaining() < 2 {
+            return Err("avc parameter set length is truncated".into());
+        }
+        let len = data.get_u16() as usize;
+        if data.remaining() < len {
+            return Err("avc parameter set is truncated".into());
+        }
+        sets.push(data.split_to(len));
+    }
+    Ok(sets)
+}
+
+/// Convert length prefixed (AVCC) NAL units to an Annex B byte stream.
+///
+/// Parameter sets from `config` are prepended to IDR pictures which do not
+/// carry their own, so that every keyframe can be decoded on its own.
+pub fn avcc_to_annexb(data: &[u8], config: &AvcDecoderConfig) -> Result<Bytes, String> {
+    let mut nalus = Vec::new();
+    let mut rest = data;
+    while rest.has_remaining() {
+        if rest.remaining() < config.length_size {
+            return Err("avcc nalu length is truncated".into());
+        }
+        let len = rest.get_uint(config.length_size) as usize;
+        if rest.remaining() < len {
+            return Err("avcc nalu is truncated".into());
+        }
+        if len > 0 {
+            nalus.push(&rest[..len]);
+        }
+        rest.advance(len);
+    }
+
+    let nalu_type = |nalu: &&[u8]| nalu[0] & NALU_TYPE_BITMASK;
+    let has_idr = nalus.iter().any(|nalu| nalu_type(nalu) == IDR_NALU_TYPE);
+    let has_parameter_sets = nalus
+        .iter()
+        .any(|nalu| matches!(nalu_type(nalu), SPS_NALU_TYPE | PPS_NALU_TYPE));
+
+    let mut buf = BytesMut::with_capacity(data.len() + 64);
+    if has_idr && !has_parameter_sets {
+        config.write_annexb(&mut buf);
+    }
+    for nalu in nalus {
+        buf.put(&*ANNEXB_NALUSTART_CODE);
+        buf.put(nalu);
+    }
+
+    Ok(buf.freeze())
+}
+
+/// AudioSpecificConfig, as defined in ISO/IEC 14496-3 1.6.2.1.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AudioSpecificConfig {
+    pub object_type: u8,
+    pub sampling_index: u8,
+    pub channel_config: u8,
+}
+
+impl AudioSpecificConfig {
+    pub fn parse(data: &[u8]) -> Result<Self, String> {
+        if data.len() < 2 {
+            return Err("audio specific config is truncated".into());
+        }
+
+        let object_type = data[0] >> 3;
+        let sampling_index = ((data[0] & 0x07) << 1) | (data[1] >> 7);
+        if sampling_index as usize >= AAC_SAMPLE_RATES.len() {
+            return Err(format!(
+                "audio specific config sampling index {sampling_index} is not supported"
+            ));
+        }
+        let channel_config = (data[1] >> 3) & 0x0F;
+
+        Ok(Self {
+            object_type,
+            sampling_index,
+            channel_config,
+        })
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        AAC_SAMPLE_RATES[self.sampling_index as usize]
+    }
+
+    /// Prefix a raw AAC frame with an ADTS header.
+    pub fn to_adts(&self, frame: &[u8]) -> Bytes {
+        let len = frame.len() + 7;
+        let profile = self.object_type.saturating_sub(1) & 0x03;
+
+        let mut buf = BytesMut::with_capacity(len);
+        buf.put_u8(0xFF);
+        buf.put_u8(0xF1);
+        buf.put_u8(
+            (profile << 6) | (self.sampling_index << 2) | ((self.channel_config >> 2) & 0x01),
+        );
+        buf.put_u8(((self.channel_config & 0x03) << 6) | ((len >> 11) & 0x03) as u8);
+        buf.put_u8(((len >> 3) & 0xFF) as u8);
+        buf.put_u8((((len & 0x07) << 5) | 0x1F) as u8);
+        buf.put_u8(0xFC);
+        buf.put(frame);
+        buf.freeze()
+    }
+}
@@ -0,0 +1,804 @@
+use super::{
+    bitstream::BitReader,
+    h264_parser::{Point, Rect},
+};
+
+/// The maximum number of sub-layers, as per 7.4.3.1.
+const MAX_SUB_LAYERS: usize = 7;
+
+/// The maximum number of short-term reference picture sets, as per 7.4.3.2.1.
+const MAX_SHORT_TERM_REF_PIC_SETS: u32 = 64;
+
+/// The maximum number of long-term reference pictures, as per 7.4.3.2.1.
+const MAX_LONG_TERM_REF_PICS: u32 = 32;
+
+/// The maximum number of pictures in the DPB, as per A.4.2.
+const DPB_MAX_SIZE: u32 = 16;
+
+/// General profile, tier and level of a coded video sequence. See 7.3.3.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProfileTierLevel {
+    /// Retains the same meaning as in the specification. See 7.4.4
+    pub general_profile_space: u8,
+    /// Retains the same meaning as in the specification. See 7.4.4
+    pub general_tier_flag: bool,
+    /// Profile to which the coded video sequence conforms
+    pub general_profile_idc: u8,
+    /// Profiles the coded video sequence also conforms to, one bit each
+    pub general_profile_compatibility_flags: u32,
+    /// The progressive, interlaced, non-packed and frame-only source flags
+    /// followed by the profile constraint flags, 48 bits in total
+    pub general_constraint_indicator_flags: u64,
+    /// Level to which the coded video sequence conforms, 30 times the level
+    /// number
+    pub general_level_idc: u8,
+}
+
+/// A H265 Video Parameter Set. Only the syntax elements up to and including
+/// the timing info are parsed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Vps {
+    /// Identifies the VPS for reference by other syntax elements
+    pub video_parameter_set_id: u8,
+    /// Retains the same meaning as in the specification. See 7.4.3.1
+    pub max_layers_minus1: u8,
+    /// The maximum number of temporal sub-layers minus 1
+    pub max_sub_layers_minus1: u8,
+    /// Retains the same meaning as in the specification. See 7.4.3.1
+    pub temporal_id_nesting_flag: bool,
+    pub profile_tier_level: ProfileTierLevel,
+    /// Retains the same meaning as in the specification. See 7.4.3.1
+    pub timing_info_present_flag: bool,
+    /// Number of time units of a clock operating at the frequency
+    /// time_scale Hz that corresponds to one increment of a clock tick
+    pub num_units_in_tick: u32,
+    /// Number of time units that pass in one second
+    pub time_scale: u32,
+}
+
+/// A H265 Sequence Parameter Set. Only the syntax elements up to and
+/// including the VUI timing info are parsed, which covers everything needed
+/// to describe the picture.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Sps {
+    /// Identifies the VPS referred to by this SPS
+    pub video_parameter_set_id: u8,
+    /// The maximum number of temporal sub-layers minus 1
+    pub max_sub_layers_minus1: u8,
+    /// Retains the same meaning as in the specification. See 7.4.3.2.1
+    pub temporal_id_nesting_flag: bool,
+    pub profile_tier_level: ProfileTierLevel,
+    /// Identifies the SPS for reference by other syntax elements
+    pub seq_parameter_set_id: u8,
+    /// Specifies the chroma sampling relative to the luma sampling as
+    /// specified in clause 6.2.
+    pub chroma_format_idc: u8,
+    /// Specifies whether the three colour components of the 4:4:4 chroma
+    /// format are coded separately.
+    pub separate_colour_plane_flag: bool,
+    /// Width of each decoded picture in units of luma samples
+    pub pic_width_in_luma_samples: u32,
+    /// Height of each decoded picture in units of luma samples
+    pub pic_height_in_luma_samples: u32,
+    /// Whether the conformance cropping window offsets follow
+    pub conformance_window_flag: bool,
+    /// Retains the same meaning as in the specification. See 7.4.3.2.1
+    pub conf_win_left_offset: u32,
+    /// Retains the same meaning as in the specification. See 7.4.3.2.1
+    pub conf_win_right_offset: u32,
+    /// Retains the same meaning as in the specification. See 7.4.3.2.1
+    pub conf_win_top_offset: u32,
+    /// Retains the same meaning as in the specification. See 7.4.3.2.1
+    pub conf_win_bottom_offset: u32,
+    /// Specifies the bit depth of the samples of the luma array
+    pub bit_depth_luma_minus8: u8,
+    /// Specifies the bit depth of the samples of the chroma arrays
+    pub bit_depth_chroma_minus8: u8,
+    /// Specifies MaxPicOrderCntLsb. See 7-8 in the specification.
+    pub log2_max_pic_order_cnt_lsb_minus4: u8,
+    /// Required size of the decoded picture buffer for the highest
+    /// sub-layer, in units of picture storage buffers, minus 1
+    pub max_dec_pic_buffering_minus1: u8,
+    /// Maximum allowed number of pictures preceding any picture in decoding
+    /// order and following it in output order, for the highest sub-layer
+    pub max_num_reorder_pics: u8,
+    /// Retains the same meaning as in the specification. See 7.4.3.2.1
+    pub log2_min_luma_coding_block_size_minus3: u8,
+    /// Retains the same meaning as in the specification. See 7.4.3.2.1
+    pub log2_diff_max_min_luma_coding_block_size: u8,
+    /// Retains the same meaning as in the specification. See 7.4.3.2.1
+    pub scaling_list_enabled_flag: bool,
+    /// Retains the same meaning as in the specification. See 7.4.3.2.1
+    pub amp_enabled_flag: bool,
+    /// Retains the same meaning as in the specification. See 7.4.3.2.1
+    pub sample_adaptive_offset_enabled_flag: bool,
+    /// Retains the same meaning as in the specification. See 7.4.3.2.1
+    pub pcm_enabled_flag: bool,
+    /// Number of short-term reference picture sets in the SPS
+    pub num_short_term_ref_pic_sets: u8,
+    /// Retains the same meaning as in the specification. See 7.4.3.2.1
+    pub long_term_ref_pics_present_flag: bool,
+    /// Retains the same meaning as in the specification. See 7.4.3.2.1
+    pub temporal_mvp_enabled_flag: bool,
+    /// Retains the same meaning as in the specification. See 7.4.3.2.1
+    pub strong_intra_smoothing_enabled_flag: bool,
+    pub vui_parameters_present_flag: bool,
+    pub vui_parameters: VuiParams,
+}
+
+impl Sps {
+    /// Returns the coded width of the stream.
+    pub const fn width(&self) -> u32 {
+        self.pic_width_in_luma_samples
+    }
+
+    /// Returns the coded height of the stream.
+    pub const fn height(&self) -> u32 {
+        self.pic_height_in_luma_samples
+    }
+
+    /// Returns `SubWidthC` and `SubHeightC`.
+    ///
+    /// See table 6-1 in the specification.
+    fn sub_width_height_c(&self) -> (u32, u32) {
+        match (self.chroma_format_idc, self.separate_colour_plane_flag) {
+            (1, false) => (2, 2),
+            (2, false) => (2, 1),
+            // 4:4:4 and monochrome.
+            _ => (1, 1),
+        }
+    }
+
+    /// Returns the conformance cropping window, in luma samples.
+    ///
+    /// See 7.4.3.2.1 in the specification.
+    pub fn visible_rectangle(&self) -> Rect<u32> {
+        if !self.conformance_window_flag {
+            return Rect {
+                min: Point { x: 0, y: 0 },
+                max: Point {
+                    x: self.width(),
+                    y: self.height(),
+                },
+            };
+        }
+
+        let (sub_width_c, sub_height_c) = self.sub_width_height_c();
+        let crop_left = sub_width_c * self.conf_win_left_offset;
+        let crop_right = sub_width_c * self.conf_win_right_offset;
+        let crop_top = sub_height_c * self.conf_win_top_offset;
+        let crop_bottom = sub_height_c * self.conf_win_bottom_offset;
+
+        Rect {
+            min: Point {
+                x: crop_left,
+                y: crop_top,
+            },
+            max: Point {
+                x: self.width() - crop_left - crop_right,
+                y: self.height() - crop_top - crop_bottom,
+            },
+        }
+    }
+}
+
+/// The VUI syntax elements up to and including the timing info. See E.2.1.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct VuiParams {
+    pub aspect_ratio_info_present_flag: bool,
+    /// Specifies the value of the sample aspect ratio of the luma samples
+    pub aspect_ratio_idc: u8,
+    /// Indicates the horizontal size of the sample aspect ratio
+    pub sar_width: u16,
+    /// Indicates the vertical size of the sample aspect ratio
+    pub sar_height: u16,
+    pub overscan_info_present_flag: bool,
+    /// Retains the same meaning as in the specification. See E.3.1
+    pub overscan_appropriate_flag: bool,
+    pub video_signal_type_present_flag: bool,
+    /// Indicates the representation of the pictures
+    pub video_format: u8,
+    /// Indicates the black level and range of the luma and chroma signals
+    pub video_full_range_flag: bool,
+    pub colour_description_present_flag: bool,
+    /// Indicates the chromaticity coordinates of the source primaries
+    pub colour_primaries: u8,
+    /// Indicates the opto-electronic transfer characteristic
+    pub transfer_characteristics: u8,
+    /// Describes the matrix coefficients used in deriving luma and chroma
+    /// signals from the green, blue, and red primaries
+    pub matrix_coefficients: u8,
+    pub chroma_loc_info_present_flag: bool,
+    /// Retains the same meaning as in the specification. See E.3.1
+    pub chroma_sample_loc_type_top_field: u8,
+    /// Retains the same meaning as in the specification. See E.3.1
+    pub chroma_sample_loc_type_bottom_field: u8,
+    /// Retains the same meaning as in the specification. See E.3.1
+    pub neutral_chroma_indication_flag: bool,
+    /// Whether the coded video sequence conveys pictures that represent
+    /// fields
+    pub field_seq_flag: bool,
+    /// Retains the same meaning as in the specification. See E.3.1
+    pub frame_field_info_present_flag: bool,
+    pub default_display_window_flag: bool,
+    /// Retains the same meaning as in the specification. See E.3.1
+    pub def_disp_win_left_offset: u32,
+    /// Retains the same meaning as in the specification. See E.3.1
+    pub def_disp_win_right_offset: u32,
+    /// Retains the same meaning as in the specification. See E.3.1
+    pub def_disp_win_top_offset: u32,
+    /// Retains the same meaning as in the specification. See E.3.1
+    pub def_disp_win_bottom_offset: u32,
+    pub timing_info_present_flag: bool,
+    /// Number of time units of a clock operating at the frequency
+    /// time_scale Hz that corresponds to one increment of a clock tick.
+    /// Unlike H264, a tick is a whole picture.
+    pub num_units_in_tick: u32,
+    /// Number of time units that pass in one second
+    pub time_scale: u32,
+}
+
+/// A H265 Picture Parameter Set. Only the syntax elements up to and including
+/// the tile and loop filter flags are parsed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Pps {
+    /// Identifies the PPS for reference by other syntax elements
+    pub pic_parameter_set_id: u8,
+    /// Identifies the SPS referred to by this PPS
+    pub seq_parameter_set_id: u8,
+    /// Retains the same meaning as in the specification. See 7.4.3.3.1
+    pub dependent_slice_segments_enabled_flag: bool,
+    /// Retains the same meaning as in the specification. See 7.4.3.3.1
+    pub output_flag_present_flag: bool,
+    /// Retains the same meaning as in the specification. See 7.4.3.3.1
+    pub num_extra_slice_header_bits: u8,
+    /// Retains the same meaning as in the specification. See 7.4.3.3.1
+    pub sign_data_hiding_enabled_flag: bool,
+    /// Retains the same meaning as in the specification. See 7.4.3.3.1
+    pub cabac_init_present_flag: bool,
+    /// Retains the same meaning as in the specification. See 7.4.3.3.1
+    pub num_ref_idx_l0_default_active_minus1: u8,
+    /// Retains the same meaning as in the specification. See 7.4.3.3.1
+    pub num_ref_idx_l1_default_active_minus1: u8,
+    /// Specifies the initial value of SliceQpY minus 26
+    pub init_qp_minus26: i8,
+    /// Retains the same meaning as in the specification. See 7.4.3.3.1
+    pub constrained_intra_pred_flag: bool,
+    /// Retains the same meaning as in the specification. See 7.4.3.3.1
+    pub transform_skip_enabled_flag: bool,
+    /// Retains the same meaning as in the specification. See 7.4.3.3.1
+    pub cu_qp_delta_enabled_flag: bool,
+    /// Retains the same meaning as in the specification. See 7.4.3.3.1
+    pub diff_cu_qp_delta_depth: u8,
+    /// Retains the same meaning as in the specification. See 7.4.3.3.1
+    pub cb_qp_offset: i8,
+    /// Retains the same meaning as in the specification. See 7.4.3.3.1
+    pub cr_qp_offset: i8,
+    /// Retains the same meaning as in the specification. See 7.4.3.3.1
+    pub slice_chroma_qp_offsets_present_flag: bool,
+    /// Retains the same meaning as in the specification. See 7.4.3.3.1
+    pub weighted_pred_flag: bool,
+    /// Retains the same meaning as in the specification. See 7.4.3.3.1
+    pub weighted_bipred_flag: bool,
+    /// Retains the same meaning as in the specification. See 7.4.3.3.1
+    pub transquant_bypass_enabled_flag: bool,
+    /// Whether the pictures are split into tiles
+    pub tiles_enabled_flag: bool,
+    /// Retains the same meaning as in the specification. See 7.4.3.3.1
+    pub entropy_coding_sync_enabled_flag: bool,
+    /// Number of tile columns partitioning the picture minus 1
+    pub num_tile_columns_minus1: u32,
+    /// Number of tile rows partitioning the picture minus 1
+    pub num_tile_rows_minus1: u32,
+    /// Whether the tile columns and rows are spread uniformly
+    pub uniform_spacing_flag: bool,
+    /// Retains the same meaning as in the specification. See 7.4.3.3.1
+    pub loop_filter_across_tiles_enabled_flag: bool,
+    /// Retains the same meaning as in the specification. See 7.4.3.3.1
+    pub loop_filter_across_slices_enabled_flag: bool,
+}
+
+/// The delta POCs of a short-term reference picture set, needed to parse the
+/// sets predicted from it. See 7.4.8.
+#[derive(Default)]
+struct ShortTermRefPicSet {
+    delta_poc_s0: Vec<i32>,
+    delta_poc_s1: Vec<i32>,
+}
+
+impl ShortTermRefPicSet {
+    fn num_delta_pocs(&self) -> usize {
+        self.delta_poc_s0.len() + self.delta_poc_s1.len()
+    }
+}
+
+fn read_u32(r: &mut BitReader) -> Result<u32, String> {
+    let high = r.read_bits::<u32>(16)?;
+    let low = r.read_bits::<u32>(16)?;
+    Ok((high << 16) | low)
+}
+
+fn parse_profile_tier_level(
+    r: &mut BitReader,
+    max_sub_layers_minus1: u8,
+) -> Result<ProfileTierLevel, String> {
+    let mut ptl = ProfileTierLevel {
+        general_profile_space: r.read_bits(2)?,
+        general_tier_flag: r.read_bit()?,
+        general_profile_idc: r.read_bits(5)?,
+        general_profile_compatibility_flags: read_u32(r)?,
+        ..Default::default()
+    };
+    let high = r.read_bits::<u32>(16)? as u64;
+    let low = read_u32(r)? as u64;
+    ptl.general_constraint_indicator_flags = (high << 32) | low;
+    ptl.general_level_idc = r.read_bits(8)?;
+
+    let max_sub_layers_minus1 = usize::from(max_sub_layers_minus1);
+    let mut sub_layer_profile_present_flag = [false; MAX_SUB_LAYERS];
+    let mut sub_layer_level_present_flag = [false; MAX_SUB_LAYERS];
+    for i in 0..max_sub_layers_minus1 {
+        sub_layer_profile_present_flag[i] = r.read_bit()?;
+        sub_layer_level_present_flag[i] = r.read_bit()?;
+    }
+    if max_sub_layers_minus1 > 0 {
+        // reserved_zero_2bits
+        r.skip_bits(2 * (8 - max_sub_layers_minus1))?;
+    }
+    for i in 0..max_sub_layers_minus1 {
+        if sub_layer_profile_present_flag[i] {
+            // Profile space, tier, profile, compatibility and constraint
+            // flags of the sub-layer.
+            r.skip_bits(88)?;
+        }
+        if sub_layer_level_present_flag[i] {
+            r.skip_bits(8)?;
+        }
+    }
+
+    Ok(ptl)
+}
+
+fn parse_sub_layer_ordering_info(
+    r: &mut BitReader,
+    max_sub_layers_minus1: u8,
+) -> Result<(u8, u8), String> {
+    let sub_layer_ordering_info_present_flag = r.read_bit()?;
+    let first = if sub_layer_ordering_info_present_flag {
+        0
+    } else {
+        max_sub_layers_minus1
+    };
+
+    let mut max_dec_pic_buffering_minus1 = 0;
+    let mut max_num_reorder_pics = 0;
+    for _ in first..=max_sub_layers_minus1 {
+        max_dec_pic_buffering_minus1 = r.read_ue_max(DPB_MAX_SIZE - 1)?;
+        max_num_reorder_pics = r.read_ue_max(u32::from(max_dec_pic_buffering_minus1))?;
+        // max_latency_increase_plus1
+        r.read_ue::<u32>()?;
+    }
+
+    Ok((max_dec_pic_buffering_minus1, max_num_reorder_pics))
+}
+
+fn parse_scaling_list_data(r: &mut BitReader) -> Result<(), String> {
+    for size_id in 0..4 {
+        let step = if size_id == 3 { 3 } else { 1 };
+        for _ in (0..6).step_by(step) {
+            let scaling_list_pred_mode_flag = r.read_bit()?;
+            if !scaling_list_pred_mode_flag {
+                // scaling_list_pred_matrix_id_delta
+                r.read_ue_max::<u32>(5)?;
+                continue;
+            }
+
+            let coef_num = 64.min(1 << (4 + (size_id << 1)));
+            if size_id > 1 {
+                // scaling_list_dc_coef_minus8
+                r.read_se_bounded::<i32>(-7, 247)?;
+            }
+            for _ in 0..coef_num {
+                // scaling_list_delta_coef
+                r.read_se_bounded::<i32>(-128, 127)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Parse the short-term reference picture set `idx` of the SPS. See 7.3.7.
+fn parse_short_term_ref_pic_set(
+    r: &mut BitReader,
+    idx: usize,
+    sets: &[ShortTermRefPicSet],
+) -> Result<ShortTermRefPicSet, String> {
+    let inter_ref_pic_set_prediction_flag = idx != 0 && r.read_bit()?;
+    let mut set = ShortTermRefPicSet::default();
+
+    if !inter_ref_pic_set_prediction_flag {
+        let num_negative_pics: u32 = r.read_ue_max(DPB_MAX_SIZE - 1)?;
+        let num_positive_pics: u32 = r.read_ue_max(DPB_MAX_SIZE - 1 - num_negative_pics)?;
+
+        let mut poc = 0;
+        for _ in 0..num_negative_pics {
+            let delta_poc_s0_minus1: i32 = r.read_ue_max(1 << 15)?;
+            // used_by_curr_pic_s0_flag
+            r.read_bit()?;
+            poc -= delta_poc_s0_minus1 + 1;
+            set.delta_poc_s0.push(poc);
+        }
+
+        let mut poc = 0;
+        for _ in 0..num_positive_pics {
+            let delta_poc_s1_minus1: i32 = r.read_ue_max(1 << 15)?;
+            // used_by_curr_pic_s1_flag
+            r.read_bit()?;
+            poc += delta_poc_s1_minus1 + 1;
+            set.delta_poc_s1.push(poc);
+        }
+
+        return Ok(set);
+    }
+
+    // Within the SPS delta_idx_minus1 is not present and inferred to be 0,
+    // the set is predicted from the previous one.
+    let reference = &sets[idx - 1];
+    let delta_rps_sign = r.read_bit()?;
+    let abs_delta_rps_minus1: i32 = r.read_ue_max((1 << 15) - 1)?;
+    let delta_rps = (1 - 2 * i32::from(delta_rps_sign)) * (abs_delta_rps_minus1 + 1);
+
+    let mut use_delta_flag = Vec::with_capacity(reference.num_delta_pocs() + 1);
+    for _ in 0..=reference.num_delta_pocs() {
+        let used_by_curr_pic_flag = r.read_bit()?;
+        use_delta_flag.push(used_by_curr_pic_flag || r.read_bit()?);
+    }
+
+    // See 7-61 and 7-62 in the specification.
+    let num_negative = reference.delta_poc_s0.len();
+    let num_delta_pocs = reference.num_delta_pocs();
+    for (j, &s1) in reference.delta_poc_s1.iter().enumerate().rev() {
+        let poc = s1 + delta_rps;
+        if poc < 0 && use_delta_flag[num_negative + j] {
+            set.delta_poc_s0.push(poc);
+        }
+    }
+    if delta_rps < 0 && use_delta_flag[num_delta_pocs] {
+        set.delta_poc_s0.push(delta_rps);
+    }
+    for (j, &s0) in reference.delta_poc_s0.iter().enumerate() {
+        let poc = s0 + delta_rps;
+        if poc < 0 && use_delta_flag[j] {
+            set.delta_poc_s0.push(poc);
+        }
+    }
+
+    for (j, &s0) in reference.delta_poc_s0.iter().enumerate().rev() {
+        let poc = s0 + delta_rps;
+        if poc > 0 && use_delta_flag[j] {
+            set.delta_poc_s1.push(poc);
+        }
+    }
+    if delta_rps > 0 && use_delta_flag[num_delta_pocs] {
+        set.delta_poc_s1.push(delta_rps);
+    }
+    for (j, &s1) in reference.delta_poc_s1.iter().enumerate() {
+        let poc = s1 + delta_rps;
+        if poc > 0 && use_delta_flag[num_negative + j] {
+            set.delta_poc_s1.push(poc);
+        }
+    }
+
+    Ok(set)
+}
+
+fn parse_vui(r: &mut BitReader, sps: &mut Sps) -> Result<(), String> {
+    let vui = &mut sps.vui_parameters;
+
+    vui.aspect_ratio_info_present_flag = r.read_bit()?;
+    if vui.aspect_ratio_info_present_flag {
+        vui.aspect_ratio_idc = r.read_bits(8)?;
+        if vui.aspect_ratio_idc == 255 {
+            vui.sar_width = r.read_bits(16)?;
+            vui.sar_height = r.read_bits(16)?;
+        }
+    }
+
+    vui.overscan_info_present_flag = r.read_bit()?;
+    if vui.overscan_info_present_flag {
+        vui.overscan_appropriate_flag = r.read_bit()?;
+    }
+
+    vui.video_signal_type_present_flag = r.read_bit()?;
+    if vui.video_signal_type_present_flag {
+        vui.video_format = r.read_bits(3)?;
+        vui.video_full_range_flag = r.read_bit()?;
+        vui.colour_description_present_flag = r.read_bit()?;
+        if vui.colour_description_present_flag {
+            vui.colour_primaries = r.read_bits(8)?;
+            vui.transfer_characteristics = r.read_bits(8)?;
+            vui.matrix_coefficients = r.read_bits(8)?;
+        }
+    }
+
+    vui.chroma_loc_info_present_flag = r.read_bit()?;
+    if vui.chroma_loc_info_present_flag {
+        vui.chroma_sample_loc_type_top_field = r.read_ue_max(5)?;
+        vui.chroma_sample_loc_type_bottom_field = r.read_ue_max(5)?;
+    }
+
+    vui.neutral_chroma_indication_flag = r.read_bit()?;
+    vui.field_seq_flag = r.read_bit()?;
+    vui.frame_field_info_present_flag = r.read_bit()?;
+
+    vui.default_display_window_flag = r.read_bit()?;
+    if vui.default_display_window_flag {
+        vui.def_disp_win_left_offset = r.read_ue()?;
+        vui.def_disp_win_right_offset = r.read_ue()?;
+        vui.def_disp_win_top_offset = r.read_ue()?;
+        vui.def_disp_win_bottom_offset = r.read_ue()?;
+    }
+
+    vui.timing_info_present_flag = r.read_bit()?;
+    if vui.timing_info_present_flag {
+        vui.num_units_in_tick = read_u32(r)?;
+        if vui.num_units_in_tick == 0 {
+            return Err("num_units_in_tick == 0, which is not allowed by E.3.1".into());
+        }
+
+        vui.time_scale = read_u32(r)?;
+        if vui.time_scale == 0 {
+            return Err("time_scale == 0, which is not allowed by E.3.1".into());
+        }
+    }
+
+    Ok(())
+}
+
+/// Parse a VPS, without its two byte NAL unit header.
+pub fn parse_vps(data: &[u8]) -> Result<(Vps, usize), String> {
+    let mut r = BitReader::new(data, true);
+    let mut vps = Vps {
+        video_parameter_set_id: r.read_bits(4)?,
+        ..Default::default()
+    };
+
+    // vps_base_layer_internal_flag and vps_base_layer_available_flag
+    r.skip_bits(2)?;
+    vps.max_layers_minus1 = r.read_bits(6)?;
+    vps.max_sub_layers_minus1 = r.read_bits(3)?;
+    if usize::from(vps.max_sub_layers_minus1) >= MAX_SUB_LAYERS {
+        return Err(format!(
+            "Invalid vps_max_sub_layers_minus1 {}",
+            vps.max_sub_layers_minus1
+        ));
+    }
+    vps.temporal_id_nesting_flag = r.read_bit()?;
+
+    // vps_reserved_0xffff_16bits
+    r.skip_bits(16)?;
+
+    vps.profile_tier_level = parse_profile_tier_level(&mut r, vps.max_sub_layers_minus1)?;
+    parse_sub_layer_ordering_info(&mut r, vps.max_sub_layers_minus1)?;
+
+    let vps_max_layer_id: u32 = r.read_bits(6)?;
+    let vps_num_layer_sets_minus1: u32 = r.read_ue_max(1023)?;
+    // layer_id_included_flag
+    r.skip_bits(vps_num_layer_sets_minus1 as usize * (vps_max_layer_id as usize + 1))?;
+
+    vps.timing_info_present_flag = r.read_bit()?;
+    if vps.timing_info_present_flag {
+        vps.num_units_in_tick = read_u32(&mut r)?;
+        vps.time_scale = read_u32(&mut r)?;
+    }
+
+    Ok((vps, r.get_stream().position() as usize))
+}
+
+/// Parse a SPS, without its two byte NAL unit header.
+pub fn parse_sps(data: &[u8]) -> Result<(Sps, usize), String> {
+    let mut r = BitReader::new(data, true);
+    let mut sps = Sps {
+        video_parameter_set_id: r.read_bits(4)?,
+        max_sub_layers_minus1: r.read_bits(3)?,
+        temporal_id_nesting_flag: r.read_bit()?,
+        ..Default::default()
+    };
+    if usize::from(sps.max_sub_layers_minus1) >= MAX_SUB_LAYERS {
+        return Err(format!(
+            "Invalid sps_max_sub_layers_minus1 {}",
+            sps.max_sub_layers_minus1
+        ));
+    }
+
+    sps.profile_tier_level = parse_profile_tier_level(&mut r, sps.max_sub_layers_minus1)?;
+    sps.seq_parameter_set_id = r.read_ue_max(15)?;
+
+    sps.chroma_format_idc = r.read_ue_max(3)?;
+    if sps.chroma_format_idc == 3 {
+        sps.separate_colour_plane_flag = r.read_bit()?;
+    }
+
+    sps.pic_width_in_luma_samples = r.read_ue_bounded(1, 16888)?;
+    sps.pic_height_in_luma_samples = r.read_ue_bounded(1, 16888)?;
+
+    sps.conformance_window_flag = r.read_bit()?;
+    if sps.conformance_window_flag {
+        sps.conf_win_left_offset = r.read_ue()?;
+        sps.conf_win_right_offset = r.read_ue()?;
+        sps.conf_win_top_offset = r.read_ue()?;
+        sps.conf_win_bottom_offset = r.read_ue()?;
+
+        // Validate that the conformance window is valid.
+        let (sub_width_c, sub_height_c) = sps.sub_width_height_c();
+
+        let _ = sps
+            .conf_win_left_offset
+            .checked_add(sps.conf_win_right_offset)
+            .and_then(|r| r.checked_mul(sub_width_c))
+            .and_then(|r| sps.width().checked_sub(r))
+            .ok_or::<String>("Invalid conformance window width".into())?;
+
+        let _ = sps
+            .conf_win_top_offset
+            .checked_add(sps.conf_win_bottom_offset)
+            .and_then(|r| r.checked_mul(sub_height_c))
+            .and_then(|r| sps.height().checked_sub(r))
+            .ok_or::<String>("Invalid conformance window height".into())?;
+    }
+
+    sps.bit_depth_luma_minus8 = r.read_ue_max(8)?;
+    sps.bit_depth_chroma_minus8 = r.read_ue_max(8)?;
+    sps.log2_max_pic_order_cnt_lsb_minus4 = r.read_ue_max(12)?;
+
+    (sps.max_dec_pic_buffering_minus1, sps.max_num_reorder_pics) =
+        parse_sub_layer_ordering_info(&mut r, sps.max_sub_layers_minus1)?;
+
+    sps.log2_min_luma_coding_block_size_minus3 = r.read_ue_max(3)?;
+    sps.log2_diff_max_min_luma_coding_block_size = r.read_ue_max(3)?;
+    // log2_min_luma_transform_block_size_minus2,
+    // log2_diff_max_min_luma_transform_block_size,
+    // max_transform_hierarchy_depth_inter and
+    // max_transform_hierarchy_depth_intra
+    for _ in 0..4 {
+        r.read_ue_max::<u32>(5)?;
+    }
+
+    sps.scaling_list_enabled_flag = r.read_bit()?;
+    if sps.scaling_list_enabled_flag {
+        let sps_scaling_list_data_present_flag = r.read_bit()?;
+        if sps_scaling_list_data_present_flag {
+            parse_scaling_list_data(&mut r)?;
+        }
+    }
+
+    sps.amp_enabled_flag = r.read_bit()?;
+    sps.sample_adaptive_offset_enabled_flag = r.read_bit()?;
+    sps.pcm_enabled_flag = r.read_bit()?;
+    if sps.pcm_enabled_flag {
+        // pcm_sample_bit_depth_luma_minus1 and
+        // pcm_sample_bit_depth_chroma_minus1
+        r.skip_bits(8)?;
+        // log2_min_pcm_luma_coding_block_size_minus3 and
+        // log2_diff_max_min_pcm_luma_coding_block_size
+        r.read_ue_max::<u32>(2)?;
+        r.read_ue_max::<u32>(3)?;
+        // pcm_loop_filter_disabled_flag
+        r.skip_bits(1)?;
+    }
+
+    sps.num_short_term_ref_pic_sets = r.read_ue_max(MAX_SHORT_TERM_REF_PIC_SETS)?;
+    let mut sets = Vec::with_capacity(usize::from(sps.num_short_term_ref_pic_sets));
+    for i in 0..usize::from(sps.num_short_term_ref_pic_sets) {
+        let set = parse_short_term_ref_pic_set(&mut r, i, &sets)?;
+        sets.push(set);
+    }
+
+    sps.long_term_ref_pics_present_flag = r.read_bit()?;
+    if sps.long_term_ref_pics_present_flag {
+        let num_long_term_ref_pics_sps: usize = r.read_ue_max(MAX_LONG_TERM_REF_PICS)?;
+        // lt_ref_pic_poc_lsb_sps and used_by_curr_pic_lt_sps_flag
+        let poc_lsb_bits = usize::from(sps.log2_max_pic_order_cnt_lsb_minus4) + 4;
+        r.skip_bits(num_long_term_ref_pics_sps * (poc_lsb_bits + 1))?;
+    }
+
+    sps.temporal_mvp_enabled_flag = r.read_bit()?;
+    sps.strong_intra_smoothing_enabled_flag = r.read_bit()?;
+
+    sps.vui_parameters_present_flag = r.read_bit()?;
+    if sps.vui_parameters_present_flag {
+        parse_vui(&mut r, &mut sps)?;
+    }
+
+    Ok((sps, r.get_stream().position() as usize))
+}
+
+/// Parse a PPS, without its two byte NAL unit header.
+pub fn parse_pps(data: &[u8]) -> Result<(Pps, usize), String> {
+    let mut r = BitReader::new(data, true);
+    let mut pps = Pps {
+        pic_parameter_set_id: r.read_ue_max(63)?,
+        seq_parameter_set_id: r.read_ue_max(15)?,
+        dependent_slice_segments_enabled_flag: r.read_bit()?,
+        output_flag_present_flag: r.read_bit()?,
+        num_extra_slice_header_bits: r.read_bits(3)?,
+        sign_data_hiding_enabled_flag: r.read_bit()?,
+        cabac_init_present_flag: r.read_bit()?,
+        num_ref_idx_l0_default_active_minus1: r.read_ue_max(14)?,
+        num_ref_idx_l1_default_active_minus1: r.read_ue_max(14)?,
+        init_qp_minus26: r.read_se_bounded(-(26 + 6 * 8), 25)?,
+        constrained_intra_pred_flag: r.read_bit()?,
+        transform_skip_enabled_flag: r.read_bit()?,
+        cu_qp_delta_enabled_flag: r.read_bit()?,
+        ..Default::default()
+    };
+
+    if pps.cu_qp_delta_enabled_flag {
+        pps.diff_cu_qp_delta_depth = r.read_ue_max(6)?;
+    }
+    pps.cb_qp_offset = r.read_se_bounded(-12, 12)?;
+    pps.cr_qp_offset = r.read_se_bounded(-12, 12)?;
+    pps.slice_chroma_qp_offsets_present_flag = r.read_bit()?;
+    pps.weighted_pred_flag = r.read_bit()?;
+    pps.weighted_bipred_flag = r.read_bit()?;
+    pps.transquant_bypass_enabled_flag = r.read_bit()?;
+    pps.tiles_enabled_flag = r.read_bit()?;
+    pps.entropy_coding_sync_enabled_flag = r.read_bit()?;
+
+    if pps.tiles_enabled_flag {
+        pps.num_tile_columns_minus1 = r.read_ue_max(19)?;
+        pps.num_tile_rows_minus1 = r.read_ue_max(21)?;
+        pps.uniform_spacing_flag = r.read_bit()?;
+        if !pps.uniform_spacing_flag {
+            // column_width_minus1 and row_height_minus1
+            for _ in 0..(pps.num_tile_columns_minus1 + pps.num_tile_rows_minus1) {
+                r.read_ue::<u32>()?;
+            }
+        }
+        pps.loop_filter_across_tiles_enabled_flag = r.read_bit()?;
+    }
+    pps.loop_filter_across_slices_enabled_flag = r.read_bit()?;
+
+    Ok((pps, r.get_stream().position() as usize))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The SPS x265 writes for 1920x1080 Main profile, Main tier, level 4.1
+    /// video, without its NAL unit header.
+    const SPS_1080P: &[u8] = &[
+        0x01, 0x01, 0x60, 0x00, 0x00, 0x03, 0x00, 0x90, 0x00, 0x00, 0x03, 0x00, 0x00, 0x03, 0x00,
+        0x7b, 0xa0, 0x03, 0xc0, 0x80, 0x10, 0xe5, 0x96, 0x56, 0x69, 0x24, 0xca, 0xe0, 0x10, 0x00,
+        0x00, 0x03, 0x00, 0x10, 0x00, 0x00, 0x03, 0x01, 0xe0, 0x80,
+    ];
+
+    #[test]
+    fn sps_of_1080p_video() {
+        let (sps, _) = parse_sps(SPS_1080P).unwrap();
+        assert_eq!((sps.width(), sps.height()), (1920, 1080));
+        assert_eq!(sps.visible_rectangle().max, Point { x: 1920, y: 1080 });
+        assert_eq!(sps.chroma_format_idc, 1);
+
+        let ptl = &sps.profile_tier_level;
+        assert_eq!(ptl.general_profile_space, 0);
+        assert_eq!(ptl.general_profile_idc, 1);
+        assert!(!ptl.general_tier_flag);
+        assert_eq!(ptl.general_level_idc, 123);
+        // Main profile, Main 10 compatible, progressive and frame only.
+        assert_eq!(ptl.general_profile_compatibility_flags, 0x6000_0000);
+        assert_eq!(ptl.general_constraint_indicator_flags, 0x9000_0000_0000);
+
+        assert!(sps.vui_parameters_present_flag);
+        assert_eq!(sps.vui_parameters.num_units_in_tick, 1);
+        assert_eq!(sps.vui_parameters.time_scale, 30);
+    }
+
+    #[test]
+    fn truncated_sps_are_refused() {
+        assert!(parse_sps(&SPS_1080P[..18]).is_err());
+        assert!(parse_sps(&[]).is_err());
+    }
+}
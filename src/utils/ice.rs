@@ -0,0 +1,47 @@
+use std::{net::IpAddr, time::Duration};
+use webrtc::{
+    api::setting_engine::SettingEngine,
+    ice::udp_network::{EphemeralUDP, UDPNetwork},
+    ice_transport::{ice_candidate_type::RTCIceCandidateType, ice_server::RTCIceServer},
+};
+
+/// How long ICE may stay disconnected before a peer is given up on, as it
+/// often recovers from a short network outage on its own.
+pub(crate) const ICE_DISCONNECTED_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// How the WebRTC endpoints served to publishers and viewers gather their
+/// ICE candidates.
+#[derive(Clone, Default, Debug)]
+pub struct IceConfig {
+    /// Public addresses to announce in place of the local ones, for a server
+    /// behind a 1:1 NAT.
+    pub nat_ips: Vec<IpAddr>,
+    /// First and last UDP port to take, so a firewall only has to open those.
+    pub udp_ports: Option<(u16, u16)>,
+    /// STUN and TURN servers, such as `stun:stun.l.google.com:19302`.
+    pub servers: Vec<String>,
+}
+
+impl IceConfig {
+    pub(crate) fn apply(&self, s: &mut SettingEngine) -> Result<(), webrtc::Error> {
+        if !self.nat_ips.is_empty() {
+            let ips = self.nat_ips.iter().map(ToString::to_string).collect();
+            s.set_nat_1to1_ips(ips, RTCIceCandidateType::Host);
+        }
+        if let Some((port_min, port_max)) = self.udp_ports {
+            let ephemeral = EphemeralUDP::new(port_min, port_max)?;
+            s.set_udp_network(UDPNetwork::Ephemeral(ephemeral));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn ice_servers(&self) -> Vec<RTCIceServer> {
+        if self.servers.is_empty() {
+            return vec![];
+        }
+        vec![RTCIceServer {
+            urls: self.servers.clone(),
+            ..Default::default()
+        }]
+    }
+}
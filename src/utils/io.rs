@@ -26,6 +26,7 @@ pub struct SampleBuilder<T: Depacketizer> {
     ready: Option<(u32, Vec<u8>)>,
     sample_rate: u32,
     samples: u32,
+    dropped: u64,
 }
 
 impl<T: Depacketizer> SampleBuilder<T> {
@@ -40,9 +41,15 @@ impl<T: Depacketizer> SampleBuilder<T> {
             ready: None,
             sample_rate,
             samples: 0,
+            dropped: 0,
         }
     }
 
+    /// Packets dropped since the last call.
+    pub fn take_dropped(&mut self) -> u64 {
+        std::mem::take(&mut self.dropped)
+    }
+
     pub fn push(&mut self, p: Packet) -> bool {
         if let Some(last) = self.last_emitted
             && p.header.sequence_number <= last
@@ -52,6 +59,7 @@ impl<T: Depacketizer> SampleBuilder<T> {
                 "Drop before emitted: {} <= {}",
                 p.header.sequence_number, last
             );
+            self.dropped += 1;
             return false;
         }
 
@@ -61,6 +69,7 @@ impl<T: Depacketizer> SampleBuilder<T> {
         {
             Ok(_) => {
                 trace!("Drop exactly same packet: {}", p.header.sequence_number);
+                self.dropped += 1;
             }
             Err(i) => {
                 let head = self.depack.is_partition_head(&p.payload);
@@ -94,6 +103,7 @@ impl<T: Depacketizer> SampleBuilder<T> {
             Err(_) => {
                 self.last_emitted = Some(seq);
                 self.queue.drain(0..=stop);
+                self.dropped += stop as u64 + 1;
                 return None;
             }
         };
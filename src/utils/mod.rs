@@ -1,5 +1,16 @@
+pub mod amf;
+pub mod av1_parser;
 pub mod bitstream;
 pub mod codecs;
+pub mod flv;
 pub mod h264_parser;
 pub mod h264_synthesizer;
+pub mod h265_parser;
+pub mod ice;
 pub mod io;
+pub mod mp4;
+pub mod request;
+pub mod rtcp;
+#[cfg(test)]
+pub(crate) mod testing;
+pub mod vpx_parser;
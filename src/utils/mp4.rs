@@ -0,0 +1,700 @@
+use bytes::{BufMut, Bytes, BytesMut};
+use std::time::Duration;
+
+use super::{
+    codecs::{
+        AUD_NALU_TYPE, IDR_NALU_TYPE, NALU_TYPE_BITMASK, PPS_NALU_TYPE, SPS_NALU_TYPE, split_annexb,
+    },
+    h264_parser::parse_sps,
+};
+
+pub const VIDEO_TRACK_ID: u32 = 1;
+pub const AUDIO_TRACK_ID: u32 = 2;
+pub const VIDEO_TIMESCALE: u32 = 90000;
+pub const AUDIO_TIMESCALE: u32 = 48000;
+
+const OPUS_CHANNELS: u16 = 2;
+
+const SAMPLE_FLAGS_SYNC: u32 = 0x0200_0000;
+const SAMPLE_FLAGS_NON_SYNC: u32 = 0x0101_0000;
+
+const MATRIX: [u32; 9] = [0x0001_0000, 0, 0, 0, 0x0001_0000, 0, 0, 0, 0x4000_0000];
+
+/// A sample as stored in a track fragment run.
+pub struct FragmentSample {
+    /// Duration in the timescale of the track.
+    pub duration: u32,
+    pub keyframe: bool,
+    /// For video, length prefixed NAL units.
+    pub data: Bytes,
+}
+
+/// A group of pictures starting with a keyframe, along with the audio which
+/// arrived while it was being received.
+pub struct Gop {
+    /// The initialization segment the samples must be decoded with.
+    pub init: Bytes,
+    pub video: Vec<FragmentSample>,
+    pub audio: Vec<FragmentSample>,
+}
+
+impl Gop {
+    pub fn duration(&self) -> Duration {
+        let ticks: u64 = self.video.iter().map(|s| s.duration as u64).sum();
+        Duration::from_nanos(ticks * 1_000_000_000 / VIDEO_TIMESCALE as u64)
+    }
+
+    pub fn size(&self) -> usize {
+        self.video
+            .iter()
+            .chain(self.audio.iter())
+            .map(|s| s.data.len())
+            .sum()
+    }
+}
+
+/// Groups H.264 Annex B and Opus samples into GOPs which can be written as
+/// fragmented MP4.
+///
+/// Samples received before the first keyframe are dropped. Parameter sets
+/// are taken out of the bitstream and into the initialization segment, a
+/// new one is generated whenever they change.
+#[derive(Default)]
+pub struct Fragmenter {
+    sps: Option<Bytes>,
+    pps: Option<Bytes>,
+    init: Option<Bytes>,
+    pending: Option<Gop>,
+}
+
+impl Fragmenter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a video sample. Returns the previous GOP once a keyframe starts
+    /// the next one.
+    pub fn push_video(&mut self, data: &Bytes, duration: Duration) -> Option<Gop> {
+        let mut keyframe = false;
+        let mut changed = false;
+        let mut avcc = BytesMut::with_capacity(data.len() + 16);
+        for nalu in split_annexb(data) {
+            match nalu[0] & NALU_TYPE_BITMASK {
+                SPS_NALU_TYPE => {
+                    changed |= self.sps.as_ref() != Some(&nalu);
+                    self.sps = Some(nalu);
+                }
+                PPS_NALU_TYPE => {
+                    changed |= self.pps.as_ref() != Some(&nalu);
+                    self.pps = Some(nalu);
+                }
+                AUD_NALU_TYPE => {}
+                nalu_type => {
+                    keyframe |= nalu_type == IDR_NALU_TYPE;
+                    avcc.put_u32(nalu.len() as u32);
+                    avcc.put(nalu);
+                }
+            }
+        }
+        if avcc.is_empty() {
+            return None;
+        }
+
+        let sample = FragmentSample {
+            duration: ticks(duration, VIDEO_TIMESCALE),
+            keyframe,
+            data: avcc.freeze(),
+        };
+
+        if !keyframe {
+            if let Some(gop) = &mut self.pending {
+                gop.video.push(sample);
+            }
+            return None;
+        }
+
+        if changed || self.init.is_none() {
+            self.init = self.build_init();
+        }
+        let init = self.init.clone()?;
+
+        self.pending.replace(Gop {
+            init,
+            video: vec![sample],
+            audio: Vec::new(),
+        })
+    }
+
+    pub fn push_audio(&mut self, data: &Bytes, duration: Duration) {
+        let Some(gop) = &mut self.pending else {
+            return;
+        };
+        gop.audio.push(FragmentSample {
+            duration: ticks(duration, AUDIO_TIMESCALE),
+            keyframe: true,
+            data: data.clone(),
+        });
+    }
+
+    /// Take the GOP which is still being received.
+    pub fn flush(&mut self) -> Option<Gop> {
+        self.pending.take()
+    }
+
+    fn build_init(&self) -> Option<Bytes> {
+        let (sps, pps) = (self.sps.as_ref()?, self.pps.as_ref()?);
+        let (parsed, _) = parse_sps(&sps[1..]).ok()?;
+        let size = parsed.visible_rectangle().max;
+
+        let mut buf = BytesMut::new();
+        write_init_segment(&mut buf, sps, pps, size.x as u16, size.y as u16);
+        Some(buf.freeze())
+    }
+}
+
+/// Writes GOPs as `moof` and `mdat` boxes on a timeline which starts at zero.
+#[derive(Default)]
+pub struct Timeline {
+    sequence: u32,
+    video_time: u64,
+    audio_time: u64,
+}
+
+impl Timeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn write_fragment(&mut self, gop: &Gop, buf: &mut BytesMut) {
+        self.sequence += 1;
+        let runs = [
+            (VIDEO_TRACK_ID, self.video_time, &gop.video[..]),
+            (AUDIO_TRACK_ID, self.audio_time, &gop.audio[..]),
+        ];
+        write_fragment(buf, self.sequence, &runs);
+
+        self.video_time += gop.video.iter().map(|s| s.duration as u64).sum::<u64>();
+        self.audio_time += gop.audio.iter().map(|s| s.duration as u64).sum::<u64>();
+    }
+}
+
+fn ticks(duration: Duration, timescale: u32) -> u32 {
+    let ticks = (duration.as_nanos() * timescale as u128 + 500_000_000) / 1_000_000_000;
+    (ticks as u32).max(1)
+}
+
+/// Write an `ftyp` and `moov` box describing an H.264 video track and an
+/// Opus audio track.
+pub fn write_init_segment(buf: &mut BytesMut, sps: &[u8], pps: &[u8], width: u16, height: u16) {
+    write_box(buf, b"ftyp", |buf| {
+        buf.put_slice(b"iso6");
+        buf.put_u32(0);
+        for brand in [b"iso6", b"cmfc", b"isom", b"mp41"] {
+            buf.put_slice(brand);
+        }
+    });
+
+    write_box(buf, b"moov", |buf| {
+        write_full_box(buf, b"mvhd", 0, 0, |buf| {
+            buf.put_u32(0);
+            buf.put_u32(0);
+            buf.put_u32(1000);
+            buf.put_u32(0);
+            buf.put_u32(0x0001_0000);
+            buf.put_u16(0x0100);
+            buf.put_bytes(0, 10);
+            write_matrix(buf);
+            buf.put_bytes(0, 24);
+            buf.put_u32(AUDIO_TRACK_ID + 1);
+        });
+
+        write_trak(buf, VIDEO_TRACK_ID, VIDEO_TIMESCALE, width, height, |buf| {
+            write_box(buf, b"avc1", |buf| {
+                buf.put_bytes(0, 6);
+                buf.put_u16(1);
+                buf.put_bytes(0, 16);
+                buf.put_u16(width);
+                buf.put_u16(height);
+                buf.put_u32(0x0048_0000);
+                buf.put_u32(0x0048_0000);
+                buf.put_u32(0);
+                buf.put_u16(1);
+                buf.put_bytes(0, 32);
+                buf.put_u16(0x0018);
+                buf.put_u16(0xFFFF);
+                write_box(buf, b"avcC", |buf| {
+                    buf.put_u8(1);
+                    buf.put_slice(&sps[1..4]);
+                    buf.put_u8(0xFF);
+                    buf.put_u8(0xE1);
+                    buf.put_u16(sps.len() as u16);
+                    buf.put_slice(sps);
+                    buf.put_u8(1);
+                    buf.put_u16(pps.len() as u16);
+                    buf.put_slice(pps);
+                });
+            });
+        });
+
+        write_trak(buf, AUDIO_TRACK_ID, AUDIO_TIMESCALE, 0, 0, |buf| {
+            write_box(buf, b"Opus", |buf| {
+                buf.put_bytes(0, 6);
+                buf.put_u16(1);
+                buf.put_bytes(0, 8);
+                buf.put_u16(OPUS_CHANNELS);
+                buf.put_u16(16);
+                buf.put_u32(0);
+                buf.put_u32(AUDIO_TIMESCALE << 16);
+                write_box(buf, b"dOps", |buf| {
+                    buf.put_u8(0);
+                    buf.put_u8(OPUS_CHANNELS as u8);
+                    buf.put_u16(0);
+                    buf.put_u32(AUDIO_TIMESCALE);
+                    buf.put_u16(0);
+                    buf.put_u8(0);
+                });
+            });
+        });
+
+        write_box(buf, b"mvex", |buf| {
+            for track_id in [VIDEO_TRACK_ID, AUDIO_TRACK_ID] {
+                write_full_box(buf, b"trex", 0, 0, |buf| {
+                    buf.put_u32(track_id);
+                    buf.put_u32(1);
+                    buf.put_u32(0);
+                    buf.put_u32(0);
+                    buf.put_u32(0);
+                });
+            }
+        });
+    });
+}
+
+fn write_trak(
+    buf: &mut BytesMut,
+    track_id: u32,
+    timescale: u32,
+    width: u16,
+    height: u16,
+    sample_entry: impl FnOnce(&mut BytesMut),
+) {
+    let is_video = track_id == VIDEO_TRACK_ID;
+
+    write_box(buf, b"trak", |buf| {
+        write_full_box(buf, b"tkhd", 0, 0x03, |buf| {
+            buf.put_u32(0);
+            buf.put_u32(0);
+            buf.put_u32(track_id);
+            buf.put_u32(0);
+            buf.put_u32(0);
+            buf.put_bytes(0, 8);
+            buf.put_u16(0);
+            buf.put_u16(0);
+            buf.put_u16(if is_video { 0 } else { 0x0100 });
+            buf.put_u16(0);
+            write_matrix(buf);
+            buf.put_u32((width as u32) << 16);
+            buf.put_u32((height as u32) << 16);
+        });
+
+        write_box(buf, b"mdia", |buf| {
+            write_full_box(buf, b"mdhd", 0, 0, |buf| {
+                buf.put_u32(0);
+                buf.put_u32(0);
+                buf.put_u32(timescale);
+                buf.put_u32(0);
+                // "und", packed as three 5 bit characters.
+                buf.put_u16(0x55C4);
+                buf.put_u16(0);
+            });
+
+            write_full_box(buf, b"hdlr", 0, 0, |buf| {
+                buf.put_u32(0);
+                buf.put_slice(if is_video { b"vide" } else { b"soun" });
+                buf.put_bytes(0, 12);
+                buf.put_slice(if is_video {
+                    b"VideoHandler\0"
+                } else {
+                    b"SoundHandler\0"
+                });
+            });
+
+            write_box(buf, b"minf", |buf| {
+                if is_video {
+                    write_full_box(buf, b"vmhd", 0, 0x01, |buf| buf.put_bytes(0, 8));
+                } else {
+                    write_full_box(buf, b"smhd", 0, 0, |buf| buf.put_u32(0));
+                }
+
+                write_box(buf, b"dinf", |buf| {
+                    write_full_box(buf, b"dref", 0, 0, |buf| {
+                        buf.put_u32(1);
+                        write_full_box(buf, b"url ", 0, 0x01, |_| {});
+                    });
+                });
+
+                write_box(buf, b"stbl", |buf| {
+                    write_full_box(buf, b"stsd", 0, 0, |buf| {
+                        buf.put_u32(1);
+                        sample_entry(buf);
+                    });
+                    write_full_box(buf, b"stts", 0, 0, |buf| buf.put_u32(0));
+                    write_full_box(buf, b"stsc", 0, 0, |buf| buf.put_u32(0));
+                    write_full_box(buf, b"stsz", 0, 0, |buf| buf.put_u64(0));
+                    write_full_box(buf, b"stco", 0, 0, |buf| buf.put_u32(0));
+                });
+            });
+        });
+    });
+}
+
+/// Write a `moof` box followed by the `mdat` box it refers to. Runs without
+/// samples are left out.
+pub fn write_fragment(buf: &mut BytesMut, sequence: u32, runs: &[(u32, u64, &[FragmentSample])]) {
+    let runs: Vec<_> = runs.iter().filter(|run| !run.2.is_empty()).collect();
+
+    // The data offsets do not change the size of the moof box, so measure it
+    // once to learn where the mdat payload begins.
+    let mut moof = BytesMut::new();
+    write_moof(&mut moof, sequence, &runs, 0);
+    let data_offset = moof.len() as u32 + 8;
+
+    write_moof(buf, sequence, &runs, data_offset);
+    write_box(buf, b"mdat", |buf| {
+        for (_, _, samples) in &runs {
+            for sample in samples.iter() {
+                buf.put_slice(&sample.data);
+            }
+        }
+    });
+}
+
+fn write_moof(
+    buf: &mut BytesMut,
+    sequence: u32,
+    runs: &[&(u32, u64, &[FragmentSample])],
+    mut data_offset: u32,
+) {
+    write_box(buf, b"moof", |buf| {
+        write_full_box(buf, b"mfhd", 0, 0, |buf| buf.put_u32(sequence));
+
+        for (track_id, base_decode_time, samples) in runs {
+            write_box(buf, b"traf", |buf| {
+                // default-base-is-moof
+                write_full_box(buf, b"tfhd", 0, 0x02_0000, |buf| buf.put_u32(*track_id));
+                write_full_box(buf, b"tfdt", 1, 0, |buf| buf.put_u64(*base_decode_time));
+                // data-offset, sample-duration, sample-size and sample-flags
+                write_full_box(buf, b"trun", 0, 0x0701, |buf| {
+                    buf.put_u32(samples.len() as u32);
+                    buf.put_u32(data_offset);
+                    for sample in samples.iter() {
+                        buf.put_u32(sample.duration);
+                        buf.put_u32(sample.data.len() as u32);
+                        buf.put_u32(if sample.keyframe {
+                            SAMPLE_FLAGS_SYNC
+                        } else {
+                            SAMPLE_FLAGS_NON_SYNC
+                        });
+                    }
+                });
+            });
+            data_offset += samples.iter().map(|s| s.data.len() as u32).sum::<u32>();
+        }
+    });
+}
+
+fn write_matrix(buf: &mut BytesMut) {
+    for value in MATRIX {
+        buf.put_u32(value);
+    }
+}
+
+fn write_box(buf: &mut BytesMut, kind: &[u8; 4], f: impl FnOnce(&mut BytesMut)) {
+    let start = buf.len();
+    buf.put_u32(0);
+    buf.put_slice(kind);
+    f(buf);
+    let size = (buf.len() - start) as u32;
+    buf[start..start + 4].copy_from_slice(&size.to_be_bytes());
+}
+
+fn write_full_box(
+    buf: &mut BytesMut,
+    kind: &[u8; 4],
+    version: u8,
+    flags: u32,
+    f: impl FnOnce(&mut BytesMut),
+) {
+    write_box(buf, kind, |buf| {
+        buf.put_u32(((version as u32) << 24) | flags);
+        f(buf);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A Constrained Baseline level 3.1 SPS for 1280x720 video.
+    const SPS_720P: &[u8] = &[0x67, 0x42, 0xc0, 0x1f, 0xda, 0x01, 0x40, 0x16, 0xe4];
+    /// The same for 640x480 video.
+    const SPS_480P: &[u8] = &[0x67, 0x42, 0xc0, 0x1f, 0xda, 0x02, 0x80, 0xf6, 0x40];
+    const PPS: &[u8] = &[0x68, 0xce, 0x3c, 0x80];
+
+    const FRAME: Duration = Duration::from_millis(40);
+
+    /// Split a run of boxes, checking their sizes add up to the data.
+    fn boxes(mut data: &[u8]) -> Vec<([u8; 4], &[u8])> {
+        let mut boxes = Vec::new();
+        while !data.is_empty() {
+            let size = u32::from_be_bytes(data[..4].try_into().unwrap()) as usize;
+            assert!(size >= 8 && size <= data.len(), "box overruns its parent");
+            boxes.push((data[4..8].try_into().unwrap(), &data[8..size]));
+            data = &data[size..];
+        }
+        boxes
+    }
+
+    /// The payload of the only box of the kind in a run of boxes.
+    fn child<'a>(data: &'a [u8], kind: &[u8; 4]) -> &'a [u8] {
+        let mut found = boxes(data).into_iter().filter(|(k, _)| k == kind);
+        let (_, payload) = found.next().expect("missing box");
+        assert!(found.next().is_none(), "duplicate box");
+        payload
+    }
+
+    fn u32_at(data: &[u8], offset: usize) -> u32 {
+        u32::from_be_bytes(data[offset..offset + 4].try_into().unwrap())
+    }
+
+    fn annexb(nalus: &[&[u8]]) -> Bytes {
+        let mut buf = BytesMut::new();
+        for nalu in nalus {
+            buf.put_slice(&[0, 0, 0, 1]);
+            buf.put_slice(nalu);
+        }
+        buf.freeze()
+    }
+
+    fn sample(data: &'static [u8], duration: u32, keyframe: bool) -> FragmentSample {
+        FragmentSample {
+            duration,
+            keyframe,
+            data: Bytes::from_static(data),
+        }
+    }
+
+    /// The `avc1` sample entry of an initialization segment.
+    fn avc1(init: &[u8]) -> &[u8] {
+        let moov = child(init, b"moov");
+        let trak = boxes(moov)
+            .into_iter()
+            .find(|(kind, trak)| kind == b"trak" && u32_at(child(trak, b"tkhd"), 12) == 1)
+            .unwrap()
+            .1;
+        let stbl = child(child(child(trak, b"mdia"), b"minf"), b"stbl");
+        // Skip the version, flags and entry count of the stsd box.
+        child(&child(stbl, b"stsd")[8..], b"avc1")
+    }
+
+    #[test]
+    fn init_segments_describe_both_tracks() {
+        let mut buf = BytesMut::new();
+        write_init_segment(&mut buf, SPS_720P, PPS, 1280, 720);
+
+        let top: Vec<_> = boxes(&buf).iter().map(|(kind, _)| *kind).collect();
+        assert_eq!(top, [*b"ftyp", *b"moov"]);
+        assert_eq!(&child(&buf, b"ftyp")[..4], b"iso6");
+
+        let moov: Vec<_> = boxes(child(&buf, b"moov"))
+            .iter()
+            .map(|(kind, _)| *kind)
+            .collect();
+        assert_eq!(moov, [*b"mvhd", *b"trak", *b"trak", *b"mvex"]);
+
+        let avc1 = avc1(&buf);
+        assert_eq!(u16::from_be_bytes([avc1[24], avc1[25]]), 1280);
+        assert_eq!(u16::from_be_bytes([avc1[26], avc1[27]]), 720);
+
+        // Skip the 78 bytes of the visual sample entry.
+        let avcc = child(&avc1[78..], b"avcC");
+        assert_eq!(avcc[..4], [1, 0x42, 0xc0, 0x1f]);
+        assert_eq!(
+            usize::from(u16::from_be_bytes([avcc[6], avcc[7]])),
+            SPS_720P.len()
+        );
+        assert_eq!(&avcc[8..8 + SPS_720P.len()], SPS_720P);
+        let pps = &avcc[8 + SPS_720P.len()..];
+        assert_eq!(pps[0], 1);
+        assert_eq!(usize::from(u16::from_be_bytes([pps[1], pps[2]])), PPS.len());
+        assert_eq!(&pps[3..], PPS);
+    }
+
+    #[test]
+    fn fragment_runs_point_into_the_mdat() {
+        let video = [sample(&[1, 2, 3], 3600, true), sample(&[4, 5], 3600, false)];
+        let audio = [sample(&[6], 960, true), sample(&[7, 8, 9, 10], 960, true)];
+        let mut buf = BytesMut::new();
+        write_fragment(
+            &mut buf,
+            7,
+            &[
+                (VIDEO_TRACK_ID, 9000, &video[..]),
+                (AUDIO_TRACK_ID, 4800, &audio[..]),
+            ],
+        );
+
+        let top = boxes(&buf);
+        assert_eq!(top.len(), 2);
+        assert_eq!(&top[0].0, b"moof");
+        assert_eq!(&top[1].0, b"mdat");
+        assert_eq!(top[1].1, [1, 2, 3, 4, 5, 6, 7, 8, 9, 10]);
+
+        let moof = top[0].1;
+        assert_eq!(u32_at(child(moof, b"mfhd"), 4), 7);
+
+        let trafs: Vec<_> = boxes(moof)
+            .into_iter()
+            .filter(|(kind, _)| kind == b"traf")
+            .map(|(_, traf)| traf)
+            .collect();
+        assert_eq!(trafs.len(), 2);
+        for (traf, (track_id, base_decode_time, samples)) in trafs
+            .into_iter()
+            .zip([(1, 9000, &video), (2, 4800, &audio)])
+        {
+            let tfhd = child(traf, b"tfhd");
+            assert_eq!(u32_at(tfhd, 0), 0x02_0000);
+            assert_eq!(u32_at(tfhd, 4), track_id);
+            let tfdt = child(traf, b"tfdt");
+            assert_eq!(
+                u64::from_be_bytes(tfdt[4..12].try_into().unwrap()),
+                base_decode_time
+            );
+
+            let trun = child(traf, b"trun");
+            assert_eq!(u32_at(trun, 4) as usize, samples.len());
+            // The offset is from the start of the moof box.
+            let mut offset = u32_at(trun, 8) as usize;
+            for (i, sample) in samples.iter().enumerate() {
+                let entry = &trun[12 + i * 12..];
+                assert_eq!(u32_at(entry, 0), sample.duration);
+                let size = u32_at(entry, 4) as usize;
+                assert_eq!(&buf[offset..offset + size], sample.data);
+                let flags = if sample.keyframe {
+                    SAMPLE_FLAGS_SYNC
+                } else {
+                    SAMPLE_FLAGS_NON_SYNC
+                };
+                assert_eq!(u32_at(entry, 8), flags);
+                offset += size;
+            }
+        }
+    }
+
+    #[test]
+    fn fragments_leave_out_empty_runs() {
+        let video = [sample(&[1, 2, 3], 3600, true)];
+        let mut buf = BytesMut::new();
+        write_fragment(
+            &mut buf,
+            1,
+            &[(VIDEO_TRACK_ID, 0, &video[..]), (AUDIO_TRACK_ID, 0, &[])],
+        );
+
+        let moof = child(&buf, b"moof");
+        let trafs = boxes(moof)
+            .into_iter()
+            .filter(|(kind, _)| kind == b"traf")
+            .count();
+        assert_eq!(trafs, 1);
+        assert_eq!(child(&buf, b"mdat"), [1, 2, 3]);
+    }
+
+    #[test]
+    fn fragmenter_splits_gops_on_idr() {
+        let mut fragmenter = Fragmenter::new();
+        // Nothing can be decoded before the first keyframe.
+        assert!(
+            fragmenter
+                .push_video(&annexb(&[&[0x41, 0x9a]]), FRAME)
+                .is_none()
+        );
+        fragmenter.push_audio(&Bytes::from_static(&[0xf8]), Duration::from_millis(20));
+
+        let idr = annexb(&[&[0x09, 0xf0], SPS_720P, PPS, &[0x65, 0x88, 0x84]]);
+        assert!(fragmenter.push_video(&idr, FRAME).is_none());
+        assert!(
+            fragmenter
+                .push_video(&annexb(&[&[0x41, 0x9b]]), FRAME)
+                .is_none()
+        );
+        fragmenter.push_audio(
+            &Bytes::from_static(&[0xf8, 0x01]),
+            Duration::from_millis(20),
+        );
+
+        let gop = fragmenter.push_video(&idr, FRAME).unwrap();
+        assert_eq!(gop.video.len(), 2);
+        assert!(gop.video[0].keyframe);
+        assert!(!gop.video[1].keyframe);
+        // Parameter sets and access unit delimiters are left to the init
+        // segment, the rest is length prefixed.
+        assert_eq!(gop.video[0].data, [0, 0, 0, 3, 0x65, 0x88, 0x84][..]);
+        assert_eq!(gop.video[1].data, [0, 0, 0, 2, 0x41, 0x9b][..]);
+        assert_eq!(gop.video[0].duration, 3600);
+        assert_eq!(gop.duration(), FRAME * 2);
+
+        assert_eq!(gop.audio.len(), 1);
+        assert_eq!(gop.audio[0].duration, 960);
+        assert_eq!(gop.size(), 7 + 6 + 2);
+
+        let avc1 = avc1(&gop.init);
+        assert_eq!(u16::from_be_bytes([avc1[24], avc1[25]]), 1280);
+
+        let gop = fragmenter.flush().unwrap();
+        assert_eq!(gop.video.len(), 1);
+        assert!(fragmenter.flush().is_none());
+    }
+
+    #[test]
+    fn fragmenter_renews_the_init_segment_with_the_parameter_sets() {
+        let mut fragmenter = Fragmenter::new();
+        let idr = |sps| annexb(&[sps, PPS, &[0x65, 0x88]]);
+
+        fragmenter.push_video(&idr(SPS_720P), FRAME);
+        let first = fragmenter.push_video(&idr(SPS_720P), FRAME).unwrap();
+        let second = fragmenter.push_video(&idr(SPS_480P), FRAME).unwrap();
+        // The same parameter sets share the same segment.
+        assert_eq!(first.init.as_ptr(), second.init.as_ptr());
+
+        let third = fragmenter.flush().unwrap();
+        assert_ne!(third.init, second.init);
+        let avc1 = avc1(&third.init);
+        assert_eq!(u16::from_be_bytes([avc1[24], avc1[25]]), 640);
+        assert_eq!(u16::from_be_bytes([avc1[26], avc1[27]]), 480);
+    }
+
+    #[test]
+    fn timelines_follow_the_gops() {
+        let gop = Gop {
+            init: Bytes::new(),
+            video: vec![sample(&[1], 3600, true), sample(&[2], 3600, false)],
+            audio: vec![sample(&[3], 960, true)],
+        };
+        let mut timeline = Timeline::new();
+        let mut buf = BytesMut::new();
+        timeline.write_fragment(&gop, &mut buf);
+        buf.clear();
+        timeline.write_fragment(&gop, &mut buf);
+
+        let moof = child(&buf, b"moof");
+        assert_eq!(u32_at(child(moof, b"mfhd"), 4), 2);
+        let times: Vec<_> = boxes(moof)
+            .into_iter()
+            .filter(|(kind, _)| kind == b"traf")
+            .map(|(_, traf)| u64::from_be_bytes(child(traf, b"tfdt")[4..12].try_into().unwrap()))
+            .collect();
+        assert_eq!(times, [7200, 960]);
+    }
+}
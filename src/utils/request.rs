@@ -0,0 +1,21 @@
+use http::{Request, header::CONTENT_TYPE};
+use http_body::Body;
+use http_body_util::BodyExt;
+
+pub(crate) const SDP_CONTENT_TYPE: &str = "application/sdp";
+
+/// Whether the request body is of this media type, parameters aside.
+pub(crate) fn has_content_type<ReqBody>(req: &Request<ReqBody>, media_type: &str) -> bool {
+    req.headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .is_some_and(|value| value.trim().eq_ignore_ascii_case(media_type))
+}
+
+/// Read the request body as text, `None` if it cannot be read or is not
+/// UTF-8.
+pub(crate) async fn read_body<ReqBody: Body>(req: Request<ReqBody>) -> Option<String> {
+    let body = req.into_body().collect().await.ok()?;
+    String::from_utf8(body.to_bytes().into()).ok()
+}
@@ -0,0 +1,43 @@
+use std::sync::Arc;
+use tokio::sync::Notify;
+use tracing::debug;
+use webrtc::{
+    rtcp::payload_feedbacks::{
+        full_intra_request::FullIntraRequest, picture_loss_indication::PictureLossIndication,
+    },
+    rtp_transceiver::rtp_sender::RTCRtpSender,
+};
+
+/// Keyframe requests a mirror received from its viewers, coalesced until the
+/// hub reads them.
+#[derive(Clone, Default)]
+pub struct KeyframeRequests {
+    notify: Arc<Notify>,
+}
+
+impl KeyframeRequests {
+    pub fn request(&self) {
+        self.notify.notify_one();
+    }
+
+    /// Wait for the next request, or return at once if one came in since the
+    /// last read.
+    pub async fn read(&self) {
+        self.notify.notified().await;
+    }
+}
+
+/// Read the RTCP of a video sender until it closes, turning every PLI and
+/// FIR into a keyframe request.
+pub async fn read_keyframe_requests(sender: Arc<RTCRtpSender>, requests: KeyframeRequests) {
+    while let Ok((packets, _)) = sender.read_rtcp().await {
+        let wants_keyframe = packets.iter().any(|packet| {
+            let packet = packet.as_any();
+            packet.is::<PictureLossIndication>() || packet.is::<FullIntraRequest>()
+        });
+        if wants_keyframe {
+            debug!("[RTCP] keyframe requested");
+            requests.request();
+        }
+    }
+}
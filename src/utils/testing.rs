@@ -0,0 +1,38 @@
+//! Fixtures shared by the tests of the library and of the binary, which
+//! includes this file as a module of its own.
+
+use webrtc::{
+    api::{APIBuilder, media_engine::MediaEngine},
+    peer_connection::configuration::RTCConfiguration,
+    rtp_transceiver::{
+        RTCRtpTransceiverInit, rtp_codec::RTPCodecType,
+        rtp_transceiver_direction::RTCRtpTransceiverDirection,
+    },
+};
+
+/// An offer as a peer with an audio and a video transceiver going in
+/// `direction` would make, such as a publisher sending both or a viewer
+/// receiving both.
+pub async fn offer(direction: RTCRtpTransceiverDirection) -> String {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs().unwrap();
+    let api = APIBuilder::new().with_media_engine(m).build();
+    let peer = api
+        .new_peer_connection(RTCConfiguration::default())
+        .await
+        .unwrap();
+    for kind in [RTPCodecType::Audio, RTPCodecType::Video] {
+        let init = RTCRtpTransceiverInit {
+            direction,
+            send_encodings: vec![],
+        };
+        peer.add_transceiver_from_kind(kind, Some(init))
+            .await
+            .unwrap();
+    }
+    let offer = peer.create_offer(None).await.unwrap();
+    let sdp = offer.sdp.clone();
+    peer.set_local_description(offer).await.unwrap();
+    peer.close().await.unwrap();
+    sdp
+}
@@ -0,0 +1,324 @@
+use super::bitstream::BitReader;
+
+/// Start code following the frame tag of VP8 keyframes.
+const VP8_START_CODE: [u8; 3] = [0x9d, 0x01, 0x2a];
+
+/// Sync code at the start of VP9 keyframes.
+const VP9_SYNC_CODE: u32 = 0x498342;
+
+/// Marks a VP9 superframe index, in the top three bits of its first and last
+/// byte.
+const VP9_SUPERFRAME_MARKER: u8 = 0b110;
+
+/// Color space of VP9 frames coded in RGB.
+const VP9_CS_RGB: u8 = 7;
+
+/// The uncompressed data chunk at the start of a VP8 frame. See section 9.1
+/// of RFC 6386.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Vp8FrameHeader {
+    /// Whether the frame is decodable without any previous frame
+    pub key_frame: bool,
+    /// Determines the reconstruction filter and loop filter type
+    pub version: u8,
+    /// Whether the frame is meant to be displayed
+    pub show_frame: bool,
+    /// Size in bytes of the first data partition
+    pub first_part_size: u32,
+    /// Width of the frame in pixels, only known for keyframes
+    pub width: u16,
+    /// Upscaling of the width, only known for keyframes
+    pub horizontal_scale: u8,
+    /// Height of the frame in pixels, only known for keyframes
+    pub height: u16,
+    /// Upscaling of the height, only known for keyframes
+    pub vertical_scale: u8,
+}
+
+/// The uncompressed header of a VP9 frame, up to and including the frame
+/// size. See section 6.2 of the VP9 bitstream specification.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Vp9FrameHeader {
+    pub profile: u8,
+    /// The frame is a copy of an already decoded one, nothing else is coded
+    pub show_existing_frame: bool,
+    /// Whether the frame is decodable without any previous frame
+    pub key_frame: bool,
+    /// Whether the frame is meant to be displayed
+    pub show_frame: bool,
+    /// Retains the same meaning as in the specification. See 7.2
+    pub error_resilient_mode: bool,
+    /// Bits per sample, only known for keyframes
+    pub bit_depth: u8,
+    /// Only known for keyframes. See 7.2.2
+    pub color_space: u8,
+    /// Whether the samples use the full range, only known for keyframes
+    pub color_range: bool,
+    /// Retains the same meaning as in the specification. See 7.2.2
+    pub subsampling_x: bool,
+    /// Retains the same meaning as in the specification. See 7.2.2
+    pub subsampling_y: bool,
+    /// Width of the frame in pixels, only known for keyframes
+    pub width: u32,
+    /// Height of the frame in pixels, only known for keyframes
+    pub height: u32,
+}
+
+/// Parse the frame header of a VP8 frame.
+pub fn parse_vp8_frame_header(data: &[u8]) -> Result<Vp8FrameHeader, String> {
+    if data.len() < 3 {
+        return Err("VP8 frame shorter than its frame tag".into());
+    }
+
+    let tag = u32::from(data[0]) | (u32::from(data[1]) << 8) | (u32::from(data[2]) << 16);
+    let mut header = Vp8FrameHeader {
+        key_frame: tag & 1 == 0,
+        version: ((tag >> 1) & 7) as u8,
+        show_frame: (tag >> 4) & 1 == 1,
+        first_part_size: tag >> 5,
+        ..Default::default()
+    };
+    if !header.key_frame {
+        return Ok(header);
+    }
+
+    if data.len() < 10 {
+        return Err("VP8 keyframe shorter than its header".into());
+    }
+    if data[3..6] != VP8_START_CODE {
+        return Err("Invalid VP8 start code".into());
+    }
+
+    let width = u16::from_le_bytes([data[6], data[7]]);
+    let height = u16::from_le_bytes([data[8], data[9]]);
+    header.width = width & 0x3fff;
+    header.horizontal_scale = (width >> 14) as u8;
+    header.height = height & 0x3fff;
+    header.vertical_scale = (height >> 14) as u8;
+
+    Ok(header)
+}
+
+/// Parse the uncompressed header of a VP9 frame. Of a superframe, only the
+/// first frame is parsed.
+pub fn parse_vp9_frame_header(data: &[u8]) -> Result<Vp9FrameHeader, String> {
+    let data = vp9_first_frame(data);
+    let mut r = BitReader::new(data, false);
+
+    let frame_marker: u8 = r.read_bits(2)?;
+    if frame_marker != 2 {
+        return Err(format!("Invalid VP9 frame marker {frame_marker}"));
+    }
+
+    let profile_low_bit: u8 = r.read_bits(1)?;
+    let profile_high_bit: u8 = r.read_bits(1)?;
+    let mut header = Vp9FrameHeader {
+        profile: (profile_high_bit << 1) | profile_low_bit,
+        ..Default::default()
+    };
+    if header.profile == 3 {
+        // reserved_zero
+        r.skip_bits(1)?;
+    }
+
+    header.show_existing_frame = r.read_bit()?;
+    if header.show_existing_frame {
+        return Ok(header);
+    }
+
+    header.key_frame = !r.read_bit()?;
+    header.show_frame = r.read_bit()?;
+    header.error_resilient_mode = r.read_bit()?;
+    if !header.key_frame {
+        return Ok(header);
+    }
+
+    let sync_code: u32 = r.read_bits(24)?;
+    if sync_code != VP9_SYNC_CODE {
+        return Err("Invalid VP9 sync code".into());
+    }
+
+    // See 6.2.2 in the specification.
+    header.bit_depth = if header.profile >= 2 {
+        if r.read_bit()? { 12 } else { 10 }
+    } else {
+        8
+    };
+    header.color_space = r.read_bits(3)?;
+    if header.color_space != VP9_CS_RGB {
+        header.color_range = r.read_bit()?;
+        if header.profile == 1 || header.profile == 3 {
+            header.subsampling_x = r.read_bit()?;
+            header.subsampling_y = r.read_bit()?;
+            // reserved_zero
+            r.skip_bits(1)?;
+        } else {
+            header.subsampling_x = true;
+            header.subsampling_y = true;
+        }
+    } else {
+        header.color_range = true;
+        if header.profile == 1 || header.profile == 3 {
+            // reserved_zero
+            r.skip_bits(1)?;
+        }
+    }
+
+    header.width = r.read_bits::<u32>(16)? + 1;
+    header.height = r.read_bits::<u32>(16)? + 1;
+
+    Ok(header)
+}
+
+/// Return the first frame of a VP9 superframe, or the data itself when it is
+/// not one. See Annex B of the specification.
+fn vp9_first_frame(data: &[u8]) -> &[u8] {
+    let Some(&marker) = data.last() else {
+        return data;
+    };
+    if marker >> 5 != VP9_SUPERFRAME_MARKER {
+        return data;
+    }
+
+    let frames = usize::from(marker & 7) + 1;
+    let size_bytes = usize::from((marker >> 3) & 3) + 1;
+    let index_size = 2 + size_bytes * frames;
+    if data.len() < index_size || data[data.len() - index_size] != marker {
+        return data;
+    }
+
+    let index = &data[data.len() - index_size + 1..];
+    let size = index[..size_bytes]
+        .iter()
+        .rev()
+        .fold(0, |size, &b| (size << 8) | usize::from(b));
+    data.get(..size).unwrap_or(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The start of a shown 640x480 VP8 keyframe with a 530 byte first
+    /// partition.
+    const VP8_KEYFRAME: &[u8] = &[
+        0x50, 0x42, 0x00, 0x9d, 0x01, 0x2a, 0x80, 0x02, 0xe0, 0x01, 0x39,
+    ];
+
+    /// The start of a shown 1280x720 VP9 profile 0 keyframe in BT.709.
+    const VP9_KEYFRAME: &[u8] = &[0x82, 0x49, 0x83, 0x42, 0x40, 0x4f, 0xf0, 0x2c, 0xf0];
+
+    /// The start of a shown VP9 profile 0 inter frame.
+    const VP9_INTER_FRAME: &[u8] = &[0x86, 0x00];
+
+    #[test]
+    fn vp8_keyframes() {
+        let header = parse_vp8_frame_header(VP8_KEYFRAME).unwrap();
+        assert_eq!(
+            header,
+            Vp8FrameHeader {
+                key_frame: true,
+                version: 0,
+                show_frame: true,
+                first_part_size: 530,
+                width: 640,
+                horizontal_scale: 0,
+                height: 480,
+                vertical_scale: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn vp8_keyframes_keep_their_scaling_apart() {
+        let mut data = VP8_KEYFRAME.to_vec();
+        data[7] |= 0x40;
+        data[9] |= 0xc0;
+        let header = parse_vp8_frame_header(&data).unwrap();
+        assert_eq!((header.width, header.horizontal_scale), (640, 1));
+        assert_eq!((header.height, header.vertical_scale), (480, 3));
+    }
+
+    #[test]
+    fn vp8_inter_frames() {
+        let header = parse_vp8_frame_header(&[0x31, 0x0b, 0x00]).unwrap();
+        assert!(!header.key_frame);
+        assert!(header.show_frame);
+        assert_eq!(header.first_part_size, 89);
+        assert_eq!((header.width, header.height), (0, 0));
+    }
+
+    #[test]
+    fn vp8_truncated_or_invalid_frames_are_refused() {
+        assert!(parse_vp8_frame_header(&[]).is_err());
+        assert!(parse_vp8_frame_header(&VP8_KEYFRAME[..2]).is_err());
+        assert!(parse_vp8_frame_header(&VP8_KEYFRAME[..9]).is_err());
+
+        let mut data = VP8_KEYFRAME.to_vec();
+        data[3] = 0;
+        assert!(parse_vp8_frame_header(&data).is_err());
+    }
+
+    #[test]
+    fn vp9_keyframes() {
+        let header = parse_vp9_frame_header(VP9_KEYFRAME).unwrap();
+        assert_eq!(
+            header,
+            Vp9FrameHeader {
+                profile: 0,
+                show_existing_frame: false,
+                key_frame: true,
+                show_frame: true,
+                error_resilient_mode: false,
+                bit_depth: 8,
+                color_space: 2,
+                color_range: false,
+                subsampling_x: true,
+                subsampling_y: true,
+                width: 1280,
+                height: 720,
+            }
+        );
+    }
+
+    #[test]
+    fn vp9_inter_frames() {
+        let header = parse_vp9_frame_header(VP9_INTER_FRAME).unwrap();
+        assert!(!header.key_frame);
+        assert!(header.show_frame);
+        assert_eq!((header.width, header.height), (0, 0));
+    }
+
+    #[test]
+    fn vp9_frames_showing_an_existing_one() {
+        let header = parse_vp9_frame_header(&[0x88]).unwrap();
+        assert!(header.show_existing_frame);
+        assert!(!header.key_frame);
+    }
+
+    #[test]
+    fn vp9_superframes_are_parsed_by_their_first_frame() {
+        let mut data = VP9_KEYFRAME.to_vec();
+        data.extend_from_slice(VP9_INTER_FRAME);
+        // Two frames, each size on one byte.
+        data.extend_from_slice(&[0xc1, VP9_KEYFRAME.len() as u8, 2, 0xc1]);
+
+        let header = parse_vp9_frame_header(&data).unwrap();
+        assert!(header.key_frame);
+        assert_eq!((header.width, header.height), (1280, 720));
+    }
+
+    #[test]
+    fn vp9_truncated_or_invalid_frames_are_refused() {
+        assert!(parse_vp9_frame_header(&[]).is_err());
+        assert!(parse_vp9_frame_header(&VP9_KEYFRAME[..3]).is_err());
+        assert!(parse_vp9_frame_header(&VP9_KEYFRAME[..7]).is_err());
+
+        // A frame marker of 0.
+        assert!(parse_vp9_frame_header(&[0x02, 0x00]).is_err());
+
+        let mut data = VP9_KEYFRAME.to_vec();
+        data[2] = 0;
+        assert!(parse_vp9_frame_header(&data).is_err());
+    }
+}
@@ -1 +1,13 @@
 // place files you want to import through the `$lib` alias in this folder.
+
+const MIRROR_KINDS: Record<string, string> = {
+    discord: "Discord Live",
+    hls: "HLS",
+    recorder: "Recording",
+    whep: "WHEP",
+    whip: "WHIP Client",
+};
+
+export function mirrorLabel(mirror: { id: number; kind: string }): string {
+    return `${MIRROR_KINDS[mirror.kind] ?? mirror.kind} #${mirror.id}`;
+}
@@ -1,3 +1,4 @@
 var mn=e=>{throw TypeError(e)};var wn=(e,t,r)=>t.has(e)||mn("Cannot "+r);var T=(e,t,r)=>(wn(e,t,"read from private field"),r?r.call(e):t.get(e)),K=(e,t,r)=>t.has(e)?mn("Cannot add the same private member more than once"):t instanceof WeakSet?t.add(e):t.set(e,r),Er=(e,t,r,n)=>(wn(e,t,"write to private field"),n?n.call(e,r):t.set(e,r),r);var qr=Array.isArray,_o=Array.prototype.indexOf,Hr=Array.from,Jn=Object.defineProperty,Be=Object.getOwnPropertyDescriptor,po=Object.getOwnPropertyDescriptors,go=Object.prototype,mo=Array.prototype,Xn=Object.getPrototypeOf,yn=Object.isExtensible;const Bt=()=>{};function wo(e){return e()}function Gt(e){for(var t=0;t<e.length;t++)e[t]()}const le=2,Zn=4,cr=8,$r=16,Se=32,Xe=64,Wt=128,X=256,Kt=512,G=1024,xe=2048,Ue=4096,ye=8192,fr=16384,yo=32768,Ot=65536,bo=1<<17,Qn=1<<19,ea=1<<20,Lr=1<<21,Ne=Symbol("$state"),ta=Symbol("legacy props"),xo=Symbol("");function ra(e){return e===this.v}function na(e,t){return e!=e?t==t:e!==t||e!==null&&typeof e=="object"||typeof e=="function"}function Yr(e){return!na(e,this.v)}function Eo(e){throw new Error("https://svelte.dev/e/effect_in_teardown")}function ko(){throw new Error("https://svelte.dev/e/effect_in_unowned_derived")}function So(e){throw new Error("https://svelte.dev/e/effect_orphan")}function Ao(){throw new Error("https://svelte.dev/e/effect_update_depth_exceeded")}function To(){throw new Error("https://svelte.dev/e/hydration_failed")}function Ro(e){throw new Error("https://svelte.dev/e/props_invalid_value")}function Io(){throw new Error("https://svelte.dev/e/state_descriptors_fixed")}function Po(){throw new Error("https://svelte.dev/e/state_prototype_fixed")}function Oo(){throw new Error("https://svelte.dev/e/state_unsafe_mutation")}let Ze=!1;function Co(){Ze=!0}const Lo=1,No=2,Do=16,Mo=1,jo=2,Uo=4,zo=8,Fo=16,Vo=1,Bo=2,ur="[",Gr="[!",Wr="]",at={},Y=Symbol(),qo="http://www.w3.org/1999/xhtml";function Ho(e){throw new Error("https://svelte.dev/e/lifecycle_outside_component")}let j=null;function bn(e){j=e}function Qe(e,t=!1,r){var n=j={p:j,c:null,d:!1,e:null,m:!1,s:e,x:null,l:null};Ze&&!t&&(j.l={s:null,u:null,r1:[],r2:dt(!1)}),Go(()=>{n.d=!0})}function et(e){const t=j;if(t!==null){const s=t.e;if(s!==null){var r=L,n=C;t.e=null;try{for(var a=0;a<s.length;a++){var o=s[a];Oe(o.effect),pe(o.reaction),Xr(o.fn)}}finally{Oe(r),pe(n)}}j=t.p,t.m=!0}return{}}function Ct(){return!Ze||j!==null&&j.l===null}function me(e){if(typeof e!="object"||e===null||Ne in e)return e;const t=Xn(e);if(t!==go&&t!==mo)return e;var r=new Map,n=qr(e),a=O(0),o=C,s=i=>{var l=C;pe(o);var c=i();return pe(l),c};return n&&r.set("length",O(e.length)),new Proxy(e,{defineProperty(i,l,c){(!("value"in c)||c.configurable===!1||c.enumerable===!1||c.writable===!1)&&Io();var u=r.get(l);return u===void 0?(u=s(()=>O(c.value)),r.set(l,u)):S(u,s(()=>me(c.value))),!0},deleteProperty(i,l){var c=r.get(l);if(c===void 0)l in i&&r.set(l,s(()=>O(Y)));else{if(n&&typeof l=="string"){var u=r.get("length"),f=Number(l);Number.isInteger(f)&&f<u.v&&S(u,f)}S(c,Y),xn(a)}return!0},get(i,l,c){var d;if(l===Ne)return e;var u=r.get(l),f=l in i;if(u===void 0&&(!f||(d=Be(i,l))!=null&&d.writable)&&(u=s(()=>O(me(f?i[l]:Y))),r.set(l,u)),u!==void 0){var v=y(u);return v===Y?void 0:v}return Reflect.get(i,l,c)},getOwnPropertyDescriptor(i,l){var c=Reflect.getOwnPropertyDescriptor(i,l);if(c&&"value"in c){var u=r.get(l);u&&(c.value=y(u))}else if(c===void 0){var f=r.get(l),v=f==null?void 0:f.v;if(f!==void 0&&v!==Y)return{enumerable:!0,configurable:!0,value:v,writable:!0}}return c},has(i,l){var v;if(l===Ne)return!0;var c=r.get(l),u=c!==void 0&&c.v!==Y||Reflect.has(i,l);if(c!==void 0||L!==null&&(!u||(v=Be(i,l))!=null&&v.writable)){c===void 0&&(c=s(()=>O(u?me(i[l]):Y)),r.set(l,c));var f=y(c);if(f===Y)return!1}return u},set(i,l,c,u){var x;var f=r.get(l),v=l in i;if(n&&l==="length")for(var d=c;d<f.v;d+=1){var p=r.get(d+"");p!==void 0?S(p,Y):d in i&&(p=s(()=>O(Y)),r.set(d+"",p))}f===void 0?(!v||(x=Be(i,l))!=null&&x.writable)&&(f=s(()=>O(void 0)),S(f,s(()=>me(c))),r.set(l,f)):(v=f.v!==Y,S(f,s(()=>me(c))));var _=Reflect.getOwnPropertyDescriptor(i,l);if(_!=null&&_.set&&_.set.call(u,c),!v){if(n&&typeof l=="string"){var h=r.get("length"),g=Number(l);Number.isInteger(g)&&g>=h.v&&S(h,g+1)}xn(a)}return!0},ownKeys(i){y(a);var l=Reflect.ownKeys(i).filter(f=>{var v=r.get(f);return v===void 0||v.v!==Y});for(var[c,u]of r)u.v!==Y&&!(c in i)&&l.push(c);return l},setPrototypeOf(){Po()}})}function xn(e,t=1){S(e,e.v+t)}function Ye(e){var t=le|xe,r=C!==null&&(C.f&le)!==0?C:null;return L===null||r!==null&&(r.f&X)!==0?t|=X:L.f|=ea,{ctx:j,deps:null,effects:null,equals:ra,f:t,fn:e,reactions:null,rv:0,v:null,wv:0,parent:r??L}}function ot(e){const t=Ye(e);return ka(t),t}function aa(e){const t=Ye(e);return t.equals=Yr,t}function oa(e){var t=e.effects;if(t!==null){e.effects=null;for(var r=0;r<t.length;r+=1)ke(t[r])}}function $o(e){for(var t=e.parent;t!==null;){if((t.f&le)===0)return t;t=t.parent}return null}function ia(e){var t,r=L;Oe($o(e));try{oa(e),t=Ra(e)}finally{Oe(r)}return t}function sa(e){var t=ia(e),r=(Ie||(e.f&X)!==0)&&e.deps!==null?Ue:G;fe(e,r),e.equals(t)||(e.v=t,e.wv=Aa())}const ut=new Map;function dt(e,t){var r={f:0,v:e,reactions:null,equals:ra,rv:0,wv:0};return r}function O(e,t){const r=dt(e);return ka(r),r}function Kr(e,t=!1){var n;const r=dt(e);return t||(r.equals=Yr),Ze&&j!==null&&j.l!==null&&((n=j.l).s??(n.s=[])).push(r),r}function S(e,t,r=!1){C!==null&&!ve&&Ct()&&(C.f&(le|$r))!==0&&!($!=null&&$.includes(e))&&Oo();let n=r?me(t):t;return la(e,n)}function la(e,t){if(!e.equals(t)){var r=e.v;Nt?ut.set(e,t):ut.set(e,r),e.v=t,(e.f&le)!==0&&((e.f&xe)!==0&&ia(e),fe(e,(e.f&X)===0?G:Ue)),e.wv=Aa(),ca(e,xe),Ct()&&L!==null&&(L.f&G)!==0&&(L.f&(Se|Xe))===0&&(ee===null?Qo([e]):ee.push(e))}return t}function ca(e,t){var r=e.reactions;if(r!==null)for(var n=Ct(),a=r.length,o=0;o<a;o++){var s=r[o],i=s.f;(i&xe)===0&&(!n&&s===L||(fe(s,t),(i&(G|X))!==0&&((i&le)!==0?ca(s,Ue):vr(s))))}}function Jr(e){console.warn("https://svelte.dev/e/hydration_mismatch")}let A=!1;function ie(e){A=e}let R;function Z(e){if(e===null)throw Jr(),at;return R=e}function Lt(){return Z(ce(R))}function I(e){if(A){if(ce(R)!==null)throw Jr(),at;R=e}}function En(e=1){if(A){for(var t=e,r=R;t--;)r=ce(r);R=r}}function Nr(){for(var e=0,t=R;;){if(t.nodeType===8){var r=t.data;if(r===Wr){if(e===0)return t;e-=1}else(r===ur||r===Gr)&&(e+=1)}var n=ce(t);t.remove(),t=n}}var kn,fa,ua,da,va;function Dr(){if(kn===void 0){kn=window,fa=document,ua=/Firefox/.test(navigator.userAgent);var e=Element.prototype,t=Node.prototype,r=Text.prototype;da=Be(t,"firstChild").get,va=Be(t,"nextSibling").get,yn(e)&&(e.__click=void 0,e.__className=void 0,e.__attributes=null,e.__style=void 0,e.__e=void 0),yn(r)&&(r.__t=void 0)}}function Pe(e=""){return document.createTextNode(e)}function Ee(e){return da.call(e)}function ce(e){return va.call(e)}function P(e,t){if(!A)return Ee(e);var r=Ee(R);if(r===null)r=R.appendChild(Pe());else if(t&&r.nodeType!==3){var n=Pe();return r==null||r.before(n),Z(n),n}return Z(r),r}function se(e,t){if(!A){var r=Ee(e);return r instanceof Comment&&r.data===""?ce(r):r}return R}function D(e,t=1,r=!1){let n=A?R:e;for(var a;t--;)a=n,n=ce(n);if(!A)return n;var o=n==null?void 0:n.nodeType;if(r&&o!==3){var s=Pe();return n===null?a==null||a.after(s):n.before(s),Z(s),s}return Z(n),n}function ha(e){e.textContent=""}function _a(e){L===null&&C===null&&So(),C!==null&&(C.f&X)!==0&&L===null&&ko(),Nt&&Eo()}function Yo(e,t){var r=t.last;r===null?t.last=t.first=e:(r.next=e,e.prev=r,t.last=e)}function tt(e,t,r,n=!0){var a=L,o={ctx:j,deps:null,nodes_start:null,nodes_end:null,f:e|xe,first:null,fn:t,last:null,next:null,parent:a,prev:null,teardown:null,transitions:null,wv:0};if(r)try{en(o),o.f|=yo}catch(l){throw ke(o),l}else t!==null&&vr(o);var s=r&&o.deps===null&&o.first===null&&o.nodes_start===null&&o.teardown===null&&(o.f&(ea|Wt))===0;if(!s&&n&&(a!==null&&Yo(o,a),C!==null&&(C.f&le)!==0)){var i=C;(i.effects??(i.effects=[])).push(o)}return o}function Go(e){const t=tt(cr,null,!1);return fe(t,G),t.teardown=e,t}function vt(e){_a();var t=L!==null&&(L.f&Se)!==0&&j!==null&&!j.m;if(t){var r=j;(r.e??(r.e=[])).push({fn:e,effect:L,reaction:C})}else{var n=Xr(e);return n}}function pa(e){return _a(),Zr(e)}function Wo(e){const t=tt(Xe,e,!0);return(r={})=>new Promise(n=>{r.outro?ht(t,()=>{ke(t),n(void 0)}):(ke(t),n(void 0))})}function Xr(e){return tt(Zn,e,!1)}function Zr(e){return tt(cr,e,!0)}function Ge(e,t=[],r=Ye){const n=t.map(r);return rt(()=>e(...n.map(y)))}function rt(e,t=0){return tt(cr|$r|t,e,!0)}function Me(e,t=!0){return tt(cr|Se,e,!0,t)}function ga(e){var t=e.teardown;if(t!==null){const r=Nt,n=C;An(!0),pe(null);try{t.call(null)}finally{An(r),pe(n)}}}function ma(e,t=!1){var r=e.first;for(e.first=e.last=null;r!==null;){var n=r.next;(r.f&Xe)!==0?r.parent=null:ke(r,t),r=n}}function Ko(e){for(var t=e.first;t!==null;){var r=t.next;(t.f&Se)===0&&ke(t),t=r}}function ke(e,t=!0){var r=!1;if((t||(e.f&Qn)!==0)&&e.nodes_start!==null){for(var n=e.nodes_start,a=e.nodes_end;n!==null;){var o=n===a?null:ce(n);n.remove(),n=o}r=!0}ma(e,t&&!r),er(e,0),fe(e,fr);var s=e.transitions;if(s!==null)for(const l of s)l.stop();ga(e);var i=e.parent;i!==null&&i.first!==null&&wa(e),e.next=e.prev=e.teardown=e.ctx=e.deps=e.fn=e.nodes_start=e.nodes_end=null}function wa(e){var t=e.parent,r=e.prev,n=e.next;r!==null&&(r.next=n),n!==null&&(n.prev=r),t!==null&&(t.first===e&&(t.first=n),t.last===e&&(t.last=r))}function ht(e,t){var r=[];Qr(e,r,!0),ya(r,()=>{ke(e),t&&t()})}function ya(e,t){var r=e.length;if(r>0){var n=()=>--r||t();for(var a of e)a.out(n)}else t()}function Qr(e,t,r){if((e.f&ye)===0){if(e.f^=ye,e.transitions!==null)for(const s of e.transitions)(s.is_global||r)&&t.push(s);for(var n=e.first;n!==null;){var a=n.next,o=(n.f&Ot)!==0||(n.f&Se)!==0;Qr(n,t,o?r:!1),n=a}}}function Jt(e){ba(e,!0)}function ba(e,t){if((e.f&ye)!==0){e.f^=ye,(e.f&G)===0&&(e.f^=G),Dt(e)&&(fe(e,xe),vr(e));for(var r=e.first;r!==null;){var n=r.next,a=(r.f&Ot)!==0||(r.f&Se)!==0;ba(r,a?t:!1),r=n}if(e.transitions!==null)for(const o of e.transitions)(o.is_global||t)&&o.in()}}const Jo=typeof requestIdleCallback>"u"?e=>setTimeout(e,1):requestIdleCallback;let _t=[],pt=[];function xa(){var e=_t;_t=[],Gt(e)}function Ea(){var e=pt;pt=[],Gt(e)}function Xo(e){_t.length===0&&queueMicrotask(xa),_t.push(e)}function Zo(e){pt.length===0&&Jo(Ea),pt.push(e)}function Sn(){_t.length>0&&xa(),pt.length>0&&Ea()}let qt=!1,Xt=!1,Zt=null,De=!1,Nt=!1;function An(e){Nt=e}let it=[];let C=null,ve=!1;function pe(e){C=e}let L=null;function Oe(e){L=e}let $=null;function ka(e){C!==null&&C.f&Lr&&($===null?$=[e]:$.push(e))}let H=null,J=0,ee=null;function Qo(e){ee=e}let Sa=1,Qt=0,Ie=!1;function Aa(){return++Sa}function Dt(e){var f;var t=e.f;if((t&xe)!==0)return!0;if((t&Ue)!==0){var r=e.deps,n=(t&X)!==0;if(r!==null){var a,o,s=(t&Kt)!==0,i=n&&L!==null&&!Ie,l=r.length;if(s||i){var c=e,u=c.parent;for(a=0;a<l;a++)o=r[a],(s||!((f=o==null?void 0:o.reactions)!=null&&f.includes(c)))&&(o.reactions??(o.reactions=[])).push(c);s&&(c.f^=Kt),i&&u!==null&&(u.f&X)===0&&(c.f^=X)}for(a=0;a<l;a++)if(o=r[a],Dt(o)&&sa(o),o.wv>e.wv)return!0}(!n||L!==null&&!Ie)&&fe(e,G)}return!1}function ei(e,t){for(var r=t;r!==null;){if((r.f&Wt)!==0)try{r.fn(e);return}catch{r.f^=Wt}r=r.parent}throw qt=!1,e}function ti(e){return(e.f&fr)===0&&(e.parent===null||(e.parent.f&Wt)===0)}function dr(e,t,r,n){if(qt){if(r===null&&(qt=!1),ti(t))throw e;return}r!==null&&(qt=!0);{ei(e,t);return}}function Ta(e,t,r=!0){var n=e.reactions;if(n!==null)for(var a=0;a<n.length;a++){var o=n[a];$!=null&&$.includes(e)||((o.f&le)!==0?Ta(o,t,!1):t===o&&(r?fe(o,xe):(o.f&G)!==0&&fe(o,Ue),vr(o)))}}function Ra(e){var d;var t=H,r=J,n=ee,a=C,o=Ie,s=$,i=j,l=ve,c=e.f;H=null,J=0,ee=null,Ie=(c&X)!==0&&(ve||!De||C===null),C=(c&(Se|Xe))===0?e:null,$=null,bn(e.ctx),ve=!1,Qt++,e.f|=Lr;try{var u=(0,e.fn)(),f=e.deps;if(H!==null){var v;if(er(e,J),f!==null&&J>0)for(f.length=J+H.length,v=0;v<H.length;v++)f[J+v]=H[v];else e.deps=f=H;if(!Ie)for(v=J;v<f.length;v++)((d=f[v]).reactions??(d.reactions=[])).push(e)}else f!==null&&J<f.length&&(er(e,J),f.length=J);if(Ct()&&ee!==null&&!ve&&f!==null&&(e.f&(le|Ue|xe))===0)for(v=0;v<ee.length;v++)Ta(ee[v],e);return a!==e&&(Qt++,ee!==null&&(n===null?n=ee:n.push(...ee))),u}finally{H=t,J=r,ee=n,C=a,Ie=o,$=s,bn(i),ve=l,e.f^=Lr}}function ri(e,t){let r=t.reactions;if(r!==null){var n=_o.call(r,e);if(n!==-1){var a=r.length-1;a===0?r=t.reactions=null:(r[n]=r[a],r.pop())}}r===null&&(t.f&le)!==0&&(H===null||!H.includes(t))&&(fe(t,Ue),(t.f&(X|Kt))===0&&(t.f^=Kt),oa(t),er(t,0))}function er(e,t){var r=e.deps;if(r!==null)for(var n=t;n<r.length;n++)ri(e,r[n])}function en(e){var t=e.f;if((t&fr)===0){fe(e,G);var r=L,n=j,a=De;L=e,De=!0;try{(t&$r)!==0?Ko(e):ma(e),ga(e);var o=Ra(e);e.teardown=typeof o=="function"?o:null,e.wv=Sa;var s=e.deps,i}catch(l){dr(l,e,r,n||e.ctx)}finally{De=a,L=r}}}function ni(){try{Ao()}catch(e){if(Zt!==null)dr(e,Zt,null);else throw e}}function Ia(){var e=De;try{var t=0;for(De=!0;it.length>0;){t++>1e3&&ni();var r=it,n=r.length;it=[];for(var a=0;a<n;a++){var o=oi(r[a]);ai(o)}ut.clear()}}finally{Xt=!1,De=e,Zt=null}}function ai(e){var t=e.length;if(t!==0)for(var r=0;r<t;r++){var n=e[r];if((n.f&(fr|ye))===0)try{Dt(n)&&(en(n),n.deps===null&&n.first===null&&n.nodes_start===null&&(n.teardown===null?wa(n):n.fn=null))}catch(a){dr(a,n,null,n.ctx)}}}function vr(e){Xt||(Xt=!0,queueMicrotask(Ia));for(var t=Zt=e;t.parent!==null;){t=t.parent;var r=t.f;if((r&(Xe|Se))!==0){if((r&G)===0)return;t.f^=G}}it.push(t)}function oi(e){for(var t=[],r=e;r!==null;){var n=r.f,a=(n&(Se|Xe))!==0,o=a&&(n&G)!==0;if(!o&&(n&ye)===0){if((n&Zn)!==0)t.push(r);else if(a)r.f^=G;else{var s=C;try{C=r,Dt(r)&&en(r)}catch(c){dr(c,r,null,r.ctx)}finally{C=s}}var i=r.first;if(i!==null){r=i;continue}}var l=r.parent;for(r=r.next;r===null&&l!==null;)r=l.next,l=l.parent}return t}function Pa(e){var t;for(Sn();it.length>0;)Xt=!0,Ia(),Sn();return t}async function Oa(){await Promise.resolve(),Pa()}function y(e){var t=e.f,r=(t&le)!==0;if(C!==null&&!ve){if(!($!=null&&$.includes(e))){var n=C.deps;e.rv<Qt&&(e.rv=Qt,H===null&&n!==null&&n[J]===e?J++:H===null?H=[e]:(!Ie||!H.includes(e))&&H.push(e))}}else if(r&&e.deps===null&&e.effects===null){var a=e,o=a.parent;o!==null&&(o.f&X)===0&&(a.f^=X)}return r&&(a=e,Dt(a)&&sa(a)),Nt&&ut.has(e)?ut.get(e):e.v}function We(e){var t=ve;try{return ve=!0,e()}finally{ve=t}}const ii=-7169;function fe(e,t){e.f=e.f&ii|t}function si(e){if(!(typeof e!="object"||!e||e instanceof EventTarget)){if(Ne in e)Mr(e);else if(!Array.isArray(e))for(let t in e){const r=e[t];typeof r=="object"&&r&&Ne in r&&Mr(r)}}}function Mr(e,t=new Set){if(typeof e=="object"&&e!==null&&!(e instanceof EventTarget)&&!t.has(e)){t.add(e),e instanceof Date&&e.getTime();for(let n in e)try{Mr(e[n],t)}catch{}const r=Xn(e);if(r!==Object.prototype&&r!==Array.prototype&&r!==Map.prototype&&r!==Set.prototype&&r!==Date.prototype){const n=po(r);for(let a in n){const o=n[a].get;if(o)try{o.call(e)}catch{}}}}}const li=["touchstart","touchmove"];function ci(e){return li.includes(e)}let Tn=!1;function Ca(){Tn||(Tn=!0,document.addEventListener("reset",e=>{Promise.resolve().then(()=>{var t;if(!e.defaultPrevented)for(const r of e.target.elements)(t=r.__on_r)==null||t.call(r)})},{capture:!0}))}function fi(e){var t=C,r=L;pe(null),Oe(null);try{return e()}finally{pe(t),Oe(r)}}function ui(e,t,r,n=r){e.addEventListener(t,()=>fi(r));const a=e.__on_r;a?e.__on_r=()=>{a(),n(!0)}:e.__on_r=()=>n(!0),Ca()}const La=new Set,jr=new Set;function tn(e){for(var t=0;t<e.length;t++)La.add(e[t]);for(var r of jr)r(e)}function zt(e){var x;var t=this,r=t.ownerDocument,n=e.type,a=((x=e.composedPath)==null?void 0:x.call(e))||[],o=a[0]||e.target,s=0,i=e.__root;if(i){var l=a.indexOf(i);if(l!==-1&&(t===document||t===window)){e.__root=t;return}var c=a.indexOf(t);if(c===-1)return;l<=c&&(s=l)}if(o=a[s]||e.target,o!==t){Jn(e,"currentTarget",{configurable:!0,get(){return o||r}});var u=C,f=L;pe(null),Oe(null);try{for(var v,d=[];o!==null;){var p=o.assignedSlot||o.parentNode||o.host||null;try{var _=o["__"+n];if(_!=null&&(!o.disabled||e.target===o))if(qr(_)){var[h,...g]=_;h.apply(o,[e,...g])}else _.call(o,e)}catch(E){v?d.push(E):v=E}if(e.cancelBubble||p===t||p===null)break;o=p}if(v){for(let E of d)queueMicrotask(()=>{throw E});throw v}}finally{e.__root=t,delete e.currentTarget,pe(u),Oe(f)}}}let Q;function di(){Q=void 0}function vi(e){let t=null,r=A;var n;if(A){for(t=R,Q===void 0&&(Q=Ee(document.head));Q!==null&&(Q.nodeType!==8||Q.data!==ur);)Q=ce(Q);Q===null?ie(!1):Q=Z(ce(Q))}A||(n=document.head.appendChild(Pe()));try{rt(()=>e(n),Qn)}finally{r&&(ie(!0),Q=R,Z(t))}}function Na(e){var t=document.createElement("template");return t.innerHTML=e,t.content}function he(e,t){var r=L;r.nodes_start===null&&(r.nodes_start=e,r.nodes_end=t)}function ue(e,t){var r=(t&Vo)!==0,n=(t&Bo)!==0,a,o=!e.startsWith("<!>");return()=>{if(A)return he(R,null),R;a===void 0&&(a=Na(o?e:"<!>"+e),r||(a=Ee(a)));var s=n||ua?document.importNode(a,!0):a.cloneNode(!0);if(r){var i=Ee(s),l=s.lastChild;he(i,l)}else he(s,s);return s}}function hi(e,t,r="svg"){var n=!e.startsWith("<!>"),a=`<${r}>${n?e:"<!>"+e}</${r}>`,o;return()=>{if(A)return he(R,null),R;if(!o){var s=Na(a),i=Ee(s);o=Ee(i)}var l=o.cloneNode(!0);return he(l,l),l}}function _i(e=""){if(!A){var t=Pe(e+"");return he(t,t),t}var r=R;return r.nodeType!==3&&(r.before(r=Pe()),Z(r)),he(r,r),r}function qe(){if(A)return he(R,null),R;var e=document.createDocumentFragment(),t=document.createComment(""),r=Pe();return e.append(t,r),he(t,r),e}function z(e,t){if(A){L.nodes_end=R,Lt();return}e!==null&&e.before(t)}function Ke(e,t){var r=t==null?"":typeof t=="object"?t+"":t;r!==(e.__t??(e.__t=e.nodeValue))&&(e.__t=r,e.nodeValue=r+"")}function Da(e,t){return Ma(e,t)}function pi(e,t){Dr(),t.intro=t.intro??!1;const r=t.target,n=A,a=R;try{for(var o=Ee(r);o&&(o.nodeType!==8||o.data!==ur);)o=ce(o);if(!o)throw at;ie(!0),Z(o),Lt();const s=Ma(e,{...t,anchor:o});if(R===null||R.nodeType!==8||R.data!==Wr)throw Jr(),at;return ie(!1),s}catch(s){if(s===at)return t.recover===!1&&To(),Dr(),ha(r),ie(!1),Da(e,t);throw s}finally{ie(n),Z(a),di()}}const ze=new Map;function Ma(e,{target:t,anchor:r,props:n={},events:a,context:o,intro:s=!0}){Dr();var i=new Set,l=f=>{for(var v=0;v<f.length;v++){var d=f[v];if(!i.has(d)){i.add(d);var p=ci(d);t.addEventListener(d,zt,{passive:p});var _=ze.get(d);_===void 0?(document.addEventListener(d,zt,{passive:p}),ze.set(d,1)):ze.set(d,_+1)}}};l(Hr(La)),jr.add(l);var c=void 0,u=Wo(()=>{var f=r??t.appendChild(Pe());return Me(()=>{if(o){Qe({});var v=j;v.c=o}a&&(n.$$events=a),A&&he(f,null),c=e(f,n)||{},A&&(L.nodes_end=R),o&&et()}),()=>{var p;for(var v of i){t.removeEventListener(v,zt);var d=ze.get(v);--d===0?(document.removeEventListener(v,zt),ze.delete(v)):ze.set(v,d)}jr.delete(l),f!==r&&((p=f.parentNode)==null||p.removeChild(f))}});return Ur.set(c,u),c}let Ur=new WeakMap;function gi(e,t){const r=Ur.get(e);return r?(Ur.delete(e),r(t)):Promise.resolve()}function _e(e,t,[r,n]=[0,0]){A&&r===0&&Lt();var a=e,o=null,s=null,i=Y,l=r>0?Ot:0,c=!1;const u=(v,d=!0)=>{c=!0,f(d,v)},f=(v,d)=>{if(i===(i=v))return;let p=!1;if(A&&n!==-1){if(r===0){const h=a.data;h===ur?n=0:h===Gr?n=1/0:(n=parseInt(h.substring(1)),n!==n&&(n=i?1/0:-1))}const _=n>r;!!i===_&&(a=Nr(),Z(a),ie(!1),p=!0,n=-1)}i?(o?Jt(o):d&&(o=Me(()=>d(a))),s&&ht(s,()=>{s=null})):(s?Jt(s):d&&(s=Me(()=>d(a,[r+1,n]))),o&&ht(o,()=>{o=null})),p&&ie(!0)};rt(()=>{c=!1,t(u),c||f(null,null)},l),A&&(a=R)}function mi(e,t){return t}function wi(e,t,r,n){for(var a=[],o=t.length,s=0;s<o;s++)Qr(t[s].e,a,!0);var i=o>0&&a.length===0&&r!==null;if(i){var l=r.parentNode;ha(l),l.append(r),n.clear(),Re(e,t[0].prev,t[o-1].next)}ya(a,()=>{for(var c=0;c<o;c++){var u=t[c];i||(n.delete(u.k),Re(e,u.prev,u.next)),ke(u.e,!i)}})}function yi(e,t,r,n,a,o=null){var s=e,i={flags:t,items:new Map,first:null};A&&Lt();var l=null,c=!1,u=aa(()=>{var f=r();return qr(f)?f:f==null?[]:Hr(f)});rt(()=>{var f=y(u),v=f.length;if(c&&v===0)return;c=v===0;let d=!1;if(A){var p=s.data===Gr;p!==(v===0)&&(s=Nr(),Z(s),ie(!1),d=!0)}if(A){for(var _=null,h,g=0;g<v;g++){if(R.nodeType===8&&R.data===Wr){s=R,d=!0,ie(!1);break}var x=f[g],E=n(x,g);h=ja(R,i,_,null,x,E,g,a,t,r),i.items.set(E,h),_=h}v>0&&Z(Nr())}A||bi(f,i,s,a,t,n,r),o!==null&&(v===0?l?Jt(l):l=Me(()=>o(s)):l!==null&&ht(l,()=>{l=null})),d&&ie(!0),y(u)}),A&&(s=R)}function bi(e,t,r,n,a,o,s){var i=e.length,l=t.items,c=t.first,u=c,f,v=null,d=[],p=[],_,h,g,x;for(x=0;x<i;x+=1){if(_=e[x],h=o(_,x),g=l.get(h),g===void 0){var E=u?u.e.nodes_start:r;v=ja(E,t,v,v===null?t.first:v.next,_,h,x,n,a,s),l.set(h,v),d=[],p=[],u=v.next;continue}if(xi(g,_,x),(g.e.f&ye)!==0&&Jt(g.e),g!==u){if(f!==void 0&&f.has(g)){if(d.length<p.length){var m=p[0],w;v=m.prev;var k=d[0],b=d[d.length-1];for(w=0;w<d.length;w+=1)Rn(d[w],m,r);for(w=0;w<p.length;w+=1)f.delete(p[w]);Re(t,k.prev,b.next),Re(t,v,k),Re(t,b,m),u=m,v=b,x-=1,d=[],p=[]}else f.delete(g),Rn(g,u,r),Re(t,g.prev,g.next),Re(t,g,v===null?t.first:v.next),Re(t,v,g),v=g;continue}for(d=[],p=[];u!==null&&u.k!==h;)(u.e.f&ye)===0&&(f??(f=new Set)).add(u),p.push(u),u=u.next;if(u===null)continue;g=u}d.push(g),v=g,u=g.next}if(u!==null||f!==void 0){for(var N=f===void 0?[]:Hr(f);u!==null;)(u.e.f&ye)===0&&N.push(u),u=u.next;var V=N.length;if(V>0){var oe=null;wi(t,N,oe,l)}}L.first=t.first&&t.first.e,L.last=v&&v.e}function xi(e,t,r,n){la(e.v,t),e.i=r}function ja(e,t,r,n,a,o,s,i,l,c){var u=(l&Lo)!==0,f=(l&Do)===0,v=u?f?Kr(a):dt(a):a,d=(l&No)===0?s:dt(s),p={i:d,v,k:o,a:null,e:null,prev:r,next:n};try{return p.e=Me(()=>i(e,v,d,c),A),p.e.prev=r&&r.e,p.e.next=n&&n.e,r===null?t.first=p:(r.next=p,r.e.next=p.e),n!==null&&(n.prev=p,n.e.prev=p.e),p}finally{}}function Rn(e,t,r){for(var n=e.next?e.next.e.nodes_start:r,a=t?t.e.nodes_start:r,o=e.e.nodes_start;o!==n;){var s=ce(o);a.before(o),o=s}}function Re(e,t,r){t===null?e.first=r:(t.next=r,t.e.next=r&&r.e),r!==null&&(r.prev=t,r.e.prev=t&&t.e)}function Ei(e,t,...r){var n=e,a=Bt,o;rt(()=>{a!==(a=t())&&(o&&(ke(o),o=null),o=Me(()=>a(n,...r)))},Ot),A&&(n=R)}function Ht(e,t,r){A&&Lt();var n=e,a,o;rt(()=>{a!==(a=t())&&(o&&(ht(o),o=null),a&&(o=Me(()=>r(n,a))))},Ot),A&&(n=R)}function ki(e,t,r){var n=e==null?"":""+e;return n===""?null:n}function Ua(e,t,r,n,a,o){var s=e.__className;if(A||s!==r||s===void 0){var i=ki(r);(!A||i!==e.getAttribute("class"))&&(i==null?e.removeAttribute("class"):e.className=i),e.__className=r}return o}const Si=Symbol("is custom element"),Ai=Symbol("is html");function $t(e){if(A){var t=!1,r=()=>{if(!t){if(t=!0,e.hasAttribute("value")){var n=e.value;In(e,"value",null),e.value=n}if(e.hasAttribute("checked")){var a=e.checked;In(e,"checked",null),e.checked=a}}};e.__on_r=r,Zo(r),Ca()}}function Ti(e,t){var r=za(e);r.value===(r.value=t??void 0)||e.value===t&&(t!==0||e.nodeName!=="PROGRESS")||(e.value=t??"")}function In(e,t,r,n){var a=za(e);A&&(a[t]=e.getAttribute(t),t==="src"||t==="srcset"||t==="href"&&e.nodeName==="LINK")||a[t]!==(a[t]=r)&&(t==="loading"&&(e[xo]=r),e.removeAttribute(t))}function za(e){return e.__attributes??(e.__attributes={[Si]:e.nodeName.includes("-"),[Ai]:e.namespaceURI===qo})}function kr(e,t,r=t){var n=Ct();ui(e,"input",a=>{var o=a?e.defaultValue:e.value;if(o=Sr(e)?Ar(o):o,r(o),n&&o!==(o=t())){var s=e.selectionStart,i=e.selectionEnd;e.value=o??"",i!==null&&(e.selectionStart=s,e.selectionEnd=Math.min(i,e.value.length))}}),(A&&e.defaultValue!==e.value||We(t)==null&&e.value)&&r(Sr(e)?Ar(e.value):e.value),Zr(()=>{var a=t();Sr(e)&&a===Ar(e.value)||e.type==="date"&&!a&&!e.value||a!==e.value&&(e.value=a??"")})}function Sr(e){var t=e.type;return t==="number"||t==="range"}function Ar(e){return e===""?null:+e}function Pn(e,t){return e===t||(e==null?void 0:e[Ne])===t}function Yt(e={},t,r,n){return Xr(()=>{var a,o;return Zr(()=>{a=o,o=[],We(()=>{e!==r(...o)&&(t(e,...o),a&&Pn(r(...a),e)&&t(null,...a))})}),()=>{Xo(()=>{o&&Pn(r(...o),e)&&t(null,...o)})}}),e}function Ri(e=!1){const t=j,r=t.l.u;if(!r)return;let n=()=>si(t.s);if(e){let a=0,o={};const s=Ye(()=>{let i=!1;const l=t.s;for(const c in l)l[c]!==o[c]&&(o[c]=l[c],i=!0);return i&&a++,a});n=()=>y(s)}r.b.length&&pa(()=>{On(t,n),Gt(r.b)}),vt(()=>{const a=We(()=>r.m.map(wo));return()=>{for(const o of a)typeof o=="function"&&o()}}),r.a.length&&vt(()=>{On(t,n),Gt(r.a)})}function On(e,t){if(e.l.s)for(const r of e.l.s)y(r);t()}const Fe=[];function rn(e,t=Bt){let r=null;const n=new Set;function a(i){if(na(e,i)&&(e=i,r)){const l=!Fe.length;for(const c of n)c[1](),Fe.push(c,e);if(l){for(let c=0;c<Fe.length;c+=2)Fe[c][0](Fe[c+1]);Fe.length=0}}}function o(i){a(i(e))}function s(i,l=Bt){const c=[i,l];return n.add(c),n.size===1&&(r=t(a,o)||Bt),i(e),()=>{n.delete(c),n.size===0&&r&&(r(),r=null)}}return{set:a,update:o,subscribe:s}}let Ft=!1;function Ii(e){var t=Ft;try{return Ft=!1,[e(),Ft]}finally{Ft=t}}function Cn(e){var t;return((t=e.ctx)==null?void 0:t.d)??!1}function Tr(e,t,r,n){var k;var a=(r&Mo)!==0,o=!Ze||(r&jo)!==0,s=(r&zo)!==0,i=(r&Fo)!==0,l=!1,c;s?[c,l]=Ii(()=>e[t]):c=e[t];var u=Ne in e||ta in e,f=s&&(((k=Be(e,t))==null?void 0:k.set)??(u&&t in e&&(b=>e[t]=b)))||void 0,v=n,d=!0,p=!1,_=()=>(p=!0,d&&(d=!1,i?v=We(n):v=n),v);c===void 0&&n!==void 0&&(f&&o&&Ro(),c=_(),f&&f(c));var h;if(o)h=()=>{var b=e[t];return b===void 0?_():(d=!0,p=!1,b)};else{var g=(a?Ye:aa)(()=>e[t]);g.f|=bo,h=()=>{var b=y(g);return b!==void 0&&(v=void 0),b===void 0?v:b}}if((r&Uo)===0)return h;if(f){var x=e.$$legacy;return function(b,N){return arguments.length>0?((!o||!N||x||l)&&f(N?h():b),b):h()}}var E=!1,m=Kr(c),w=Ye(()=>{var b=h(),N=y(m);return E?(E=!1,N):m.v=b});return s&&y(w),a||(w.equals=Yr),function(b,N){if(arguments.length>0){const V=N?y(w):o&&s?me(b):b;if(!w.equals(V)){if(E=!0,S(m,V),p&&v!==void 0&&(v=V),Cn(w))return b;We(()=>y(w))}return b}return Cn(w)?w.v:y(w)}}function Pi(e){return class extends Oi{constructor(t){super({component:e,...t})}}}var ge,te;class Oi{constructor(t){K(this,ge);K(this,te);var o;var r=new Map,n=(s,i)=>{var l=Kr(i);return r.set(s,l),l};const a=new Proxy({...t.props||{},$$events:{}},{get(s,i){return y(r.get(i)??n(i,Reflect.get(s,i)))},has(s,i){return i===ta?!0:(y(r.get(i)??n(i,Reflect.get(s,i))),Reflect.has(s,i))},set(s,i,l){return S(r.get(i)??n(i,l),l),Reflect.set(s,i,l)}});Er(this,te,(t.hydrate?pi:Da)(t.component,{target:t.target,anchor:t.anchor,props:a,context:t.context,intro:t.intro??!1,recover:t.recover})),(!((o=t==null?void 0:t.props)!=null&&o.$$host)||t.sync===!1)&&Pa(),Er(this,ge,a.$$events);for(const s of Object.keys(T(this,te)))s==="$set"||s==="$destroy"||s==="$on"||Jn(this,s,{get(){return T(this,te)[s]},set(i){T(this,te)[s]=i},enumerable:!0});T(this,te).$set=s=>{Object.assign(a,s)},T(this,te).$destroy=()=>{gi(T(this,te))}}$set(t){T(this,te).$set(t)}$on(t,r){T(this,ge)[t]=T(this,ge)[t]||[];const n=(...a)=>r.call(this,...a);return T(this,ge)[t].push(n),()=>{T(this,ge)[t]=T(this,ge)[t].filter(a=>a!==n)}}$destroy(){T(this,te).$destroy()}}ge=new WeakMap,te=new WeakMap;function zr(e){j===null&&Ho(),Ze&&j.l!==null?Ci(j).m.push(e):vt(()=>{const t=We(e);if(typeof t=="function")return t})}function Ci(e){var t=e.l;return t.u??(t.u={a:[],b:[],m:[]})}new URL("sveltekit-internal://");function Li(e,t){return e==="/"||t==="ignore"?e:t==="never"?e.endsWith("/")?e.slice(0,-1):e:t==="always"&&!e.endsWith("/")?e+"/":e}function Ni(e){return e.split("%25").map(decodeURI).join("%25")}function Di(e){for(const t in e)e[t]=decodeURIComponent(e[t]);return e}function Rr({href:e}){return e.split("#")[0]}function Mi(e,t,r,n=!1){const a=new URL(e);Object.defineProperty(a,"searchParams",{value:new Proxy(a.searchParams,{get(s,i){if(i==="get"||i==="getAll"||i==="has")return c=>(r(c),s[i](c));t();const l=Reflect.get(s,i);return typeof l=="function"?l.bind(s):l}}),enumerable:!0,configurable:!0});const o=["href","pathname","search","toString","toJSON"];n&&o.push("hash");for(const s of o)Object.defineProperty(a,s,{get(){return t(),e[s]},enumerable:!0,configurable:!0});return a}function ji(...e){let t=5381;for(const r of e)if(typeof r=="string"){let n=r.length;for(;n;)t=t*33^r.charCodeAt(--n)}else if(ArrayBuffer.isView(r)){const n=new Uint8Array(r.buffer,r.byteOffset,r.byteLength);let a=n.length;for(;a;)t=t*33^n[--a]}else throw new TypeError("value must be a string or TypedArray");return(t>>>0).toString(36)}function Ui(e){const t=atob(e),r=new Uint8Array(t.length);for(let n=0;n<t.length;n++)r[n]=t.charCodeAt(n);return r.buffer}const zi=window.fetch;window.fetch=(e,t)=>((e instanceof Request?e.method:(t==null?void 0:t.method)||"GET")!=="GET"&&st.delete(nn(e)),zi(e,t));const st=new Map;function Fi(e,t){const r=nn(e,t),n=document.querySelector(r);if(n!=null&&n.textContent){let{body:a,...o}=JSON.parse(n.textContent);const s=n.getAttribute("data-ttl");return s&&st.set(r,{body:a,init:o,ttl:1e3*Number(s)}),n.getAttribute("data-b64")!==null&&(a=Ui(a)),Promise.resolve(new Response(a,o))}return window.fetch(e,t)}function Vi(e,t,r){if(st.size>0){const n=nn(e,r),a=st.get(n);if(a){if(performance.now()<a.ttl&&["default","force-cache","only-if-cached",void 0].includes(r==null?void 0:r.cache))return new Response(a.body,a.init);st.delete(n)}}return window.fetch(t,r)}function nn(e,t){let n=`script[data-sveltekit-fetched][data-url=${JSON.stringify(e instanceof Request?e.url:e)}]`;if(t!=null&&t.headers||t!=null&&t.body){const a=[];t.headers&&a.push([...new Headers(t.headers)].join(",")),t.body&&(typeof t.body=="string"||ArrayBuffer.isView(t.body))&&a.push(t.body),n+=`[data-hash="${ji(...a)}"]`}return n}const Bi=/^(\[)?(\.\.\.)?(\w+)(?:=(\w+))?(\])?$/;function qi(e){const t=[];return{pattern:e==="/"?/^\/$/:new RegExp(`^${$i(e).map(n=>{const a=/^\[\.\.\.(\w+)(?:=(\w+))?\]$/.exec(n);if(a)return t.push({name:a[1],matcher:a[2],optional:!1,rest:!0,chained:!0}),"(?:/(.*))?";const o=/^\[\[(\w+)(?:=(\w+))?\]\]$/.exec(n);if(o)return t.push({name:o[1],matcher:o[2],optional:!0,rest:!1,chained:!0}),"(?:/([^/]+))?";if(!n)return;const s=n.split(/\[(.+?)\](?!\])/);return"/"+s.map((l,c)=>{if(c%2){if(l.startsWith("x+"))return Ir(String.fromCharCode(parseInt(l.slice(2),16)));if(l.startsWith("u+"))return Ir(String.fromCharCode(...l.slice(2).split("-").map(_=>parseInt(_,16))));const u=Bi.exec(l),[,f,v,d,p]=u;return t.push({name:d,matcher:p,optional:!!f,rest:!!v,chained:v?c===1&&s[0]==="":!1}),v?"(.*?)":f?"([^/]*)?":"([^/]+?)"}return Ir(l)}).join("")}).join("")}/?$`),params:t}}function Hi(e){return!/^\([^)]+\)$/.test(e)}function $i(e){return e.slice(1).split("/").filter(Hi)}function Yi(e,t,r){const n={},a=e.slice(1),o=a.filter(i=>i!==void 0);let s=0;for(let i=0;i<t.length;i+=1){const l=t[i];let c=a[i-s];if(l.chained&&l.rest&&s&&(c=a.slice(i-s,i+1).filter(u=>u).join("/"),s=0),c===void 0){l.rest&&(n[l.name]="");continue}if(!l.matcher||r[l.matcher](c)){n[l.name]=c;const u=t[i+1],f=a[i+1];u&&!u.rest&&u.optional&&f&&l.chained&&(s=0),!u&&!f&&Object.keys(n).length===o.length&&(s=0);continue}if(l.optional&&l.chained){s++;continue}return}if(!s)return n}function Ir(e){return e.normalize().replace(/[[\]]/g,"\\$&").replace(/%/g,"%25").replace(/\//g,"%2[Ff]").replace(/\?/g,"%3[Ff]").replace(/#/g,"%23").replace(/[.*+?^${}()|\\]/g,"\\$&")}function Gi({nodes:e,server_loads:t,dictionary:r,matchers:n}){const a=new Set(t);return Object.entries(r).map(([i,[l,c,u]])=>{const{pattern:f,params:v}=qi(i),d={id:i,exec:p=>{const _=f.exec(p);if(_)return Yi(_,v,n)},errors:[1,...u||[]].map(p=>e[p]),layouts:[0,...c||[]].map(s),leaf:o(l)};return d.errors.length=d.layouts.length=Math.max(d.errors.length,d.layouts.length),d});function o(i){const l=i<0;return l&&(i=~i),[l,e[i]]}function s(i){return i===void 0?i:[a.has(i),e[i]]}}function Fa(e,t=JSON.parse){try{return t(sessionStorage[e])}catch{}}function Ln(e,t,r=JSON.stringify){const n=r(t);try{sessionStorage[e]=n}catch{}}var Hn;const ae=((Hn=globalThis.__sveltekit_wk2zel)==null?void 0:Hn.base)??"";var $n;const Wi=(($n=globalThis.__sveltekit_wk2zel)==null?void 0:$n.assets)??ae,Ki="1747086074077",Va="sveltekit:snapshot",Ba="sveltekit:scroll",qa="sveltekit:states",Ji="sveltekit:pageurl",He="sveltekit:history",gt="sveltekit:navigation",Ce={tap:1,hover:2,viewport:3,eager:4,off:-1,false:-1},hr=location.origin;function Ha(e){if(e instanceof URL)return e;let t=document.baseURI;if(!t){const r=document.getElementsByTagName("base");t=r.length?r[0].href:document.URL}return new URL(e,t)}function an(){return{x:pageXOffset,y:pageYOffset}}function Ve(e,t){return e.getAttribute(`data-sveltekit-${t}`)}const Nn={...Ce,"":Ce.hover};function $a(e){let t=e.assignedSlot??e.parentNode;return(t==null?void 0:t.nodeType)===11&&(t=t.host),t}function Ya(e,t){for(;e&&e!==t;){if(e.nodeName.toUpperCase()==="A"&&e.hasAttribute("href"))return e;e=$a(e)}}function Fr(e,t,r){let n;try{if(n=new URL(e instanceof SVGAElement?e.href.baseVal:e.href,document.baseURI),r&&n.hash.match(/^#[^/]/)){const i=location.hash.split("#")[1]||"/";n.hash=`#${i}${n.hash}`}}catch{}const a=e instanceof SVGAElement?e.target.baseVal:e.target,o=!n||!!a||_r(n,t,r)||(e.getAttribute("rel")||"").split(/\s+/).includes("external"),s=(n==null?void 0:n.origin)===hr&&e.hasAttribute("download");return{url:n,external:o,target:a,download:s}}function tr(e){let t=null,r=null,n=null,a=null,o=null,s=null,i=e;for(;i&&i!==document.documentElement;)n===null&&(n=Ve(i,"preload-code")),a===null&&(a=Ve(i,"preload-data")),t===null&&(t=Ve(i,"keepfocus")),r===null&&(r=Ve(i,"noscroll")),o===null&&(o=Ve(i,"reload")),s===null&&(s=Ve(i,"replacestate")),i=$a(i);function l(c){switch(c){case"":case"true":return!0;case"off":case"false":return!1;default:return}}return{preload_code:Nn[n??"off"],preload_data:Nn[a??"off"],keepfocus:l(t),noscroll:l(r),reload:l(o),replace_state:l(s)}}function Dn(e){const t=rn(e);let r=!0;function n(){r=!0,t.update(s=>s)}function a(s){r=!1,t.set(s)}function o(s){let i;return t.subscribe(l=>{(i===void 0||r&&l!==i)&&s(i=l)})}return{notify:n,set:a,subscribe:o}}const Ga={v:()=>{}};function Xi(){const{set:e,subscribe:t}=rn(!1);let r;async function n(){clearTimeout(r);try{const a=await fetch(`${Wi}/_app/version.json`,{headers:{pragma:"no-cache","cache-control":"no-cache"}});if(!a.ok)return!1;const s=(await a.json()).version!==Ki;return s&&(e(!0),Ga.v(),clearTimeout(r)),s}catch{return!1}}return{subscribe:t,check:n}}function _r(e,t,r){return e.origin!==hr||!e.pathname.startsWith(t)?!0:r?!(e.pathname===t+"/"||e.pathname===t+"/index.html"||e.protocol==="file:"&&e.pathname.replace(/\/[^/]+\.html?$/,"")===t):!1}function Mn(e){const t=Qi(e),r=new ArrayBuffer(t.length),n=new DataView(r);for(let a=0;a<r.byteLength;a++)n.setUint8(a,t.charCodeAt(a));return r}const Zi="ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";function Qi(e){e.length%4===0&&(e=e.replace(/==?$/,""));let t="",r=0,n=0;for(let a=0;a<e.length;a++)r<<=6,r|=Zi.indexOf(e[a]),n+=6,n===24&&(t+=String.fromCharCode((r&16711680)>>16),t+=String.fromCharCode((r&65280)>>8),t+=String.fromCharCode(r&255),r=n=0);return n===12?(r>>=4,t+=String.fromCharCode(r)):n===18&&(r>>=2,t+=String.fromCharCode((r&65280)>>8),t+=String.fromCharCode(r&255)),t}const es=-1,ts=-2,rs=-3,ns=-4,as=-5,os=-6;function is(e,t){if(typeof e=="number")return a(e,!0);if(!Array.isArray(e)||e.length===0)throw new Error("Invalid input");const r=e,n=Array(r.length);function a(o,s=!1){if(o===es)return;if(o===rs)return NaN;if(o===ns)return 1/0;if(o===as)return-1/0;if(o===os)return-0;if(s)throw new Error("Invalid input");if(o in n)return n[o];const i=r[o];if(!i||typeof i!="object")n[o]=i;else if(Array.isArray(i))if(typeof i[0]=="string"){const l=i[0],c=t==null?void 0:t[l];if(c)return n[o]=c(a(i[1]));switch(l){case"Date":n[o]=new Date(i[1]);break;case"Set":const u=new Set;n[o]=u;for(let d=1;d<i.length;d+=1)u.add(a(i[d]));break;case"Map":const f=new Map;n[o]=f;for(let d=1;d<i.length;d+=2)f.set(a(i[d]),a(i[d+1]));break;case"RegExp":n[o]=new RegExp(i[1],i[2]);break;case"Object":n[o]=Object(i[1]);break;case"BigInt":n[o]=BigInt(i[1]);break;case"null":const v=Object.create(null);n[o]=v;for(let d=1;d<i.length;d+=2)v[i[d]]=a(i[d+1]);break;case"Int8Array":case"Uint8Array":case"Uint8ClampedArray":case"Int16Array":case"Uint16Array":case"Int32Array":case"Uint32Array":case"Float32Array":case"Float64Array":case"BigInt64Array":case"BigUint64Array":{const d=globalThis[l],p=i[1],_=Mn(p),h=new d(_);n[o]=h;break}case"ArrayBuffer":{const d=i[1],p=Mn(d);n[o]=p;break}default:throw new Error(`Unknown type ${l}`)}}else{const l=new Array(i.length);n[o]=l;for(let c=0;c<i.length;c+=1){const u=i[c];u!==ts&&(l[c]=a(u))}}else{const l={};n[o]=l;for(const c in i){const u=i[c];l[c]=a(u)}}return n[o]}return a(0)}const Wa=new Set(["load","prerender","csr","ssr","trailingSlash","config"]);[...Wa];const ss=new Set([...Wa]);[...ss];function ls(e){return e.filter(t=>t!=null)}class pr{constructor(t,r){this.status=t,typeof r=="string"?this.body={message:r}:r?this.body=r:this.body={message:`Error: ${t}`}}toString(){return JSON.stringify(this.body)}}class on{constructor(t,r){this.status=t,this.location=r}}class sn extends Error{constructor(t,r,n){super(n),this.status=t,this.text=r}}const cs="x-sveltekit-invalidated",fs="x-sveltekit-trailing-slash";function rr(e){return e instanceof pr||e instanceof sn?e.status:500}function us(e){return e instanceof sn?e.text:"Internal Error"}let q,mt,Pr;const ds=zr.toString().includes("$$")||/function \w+\(\) \{\}/.test(zr.toString());var bt,xt,Et,kt,St,At,Tt,Rt,Yn,It,Gn,Pt,Wn;ds?(q={data:{},form:null,error:null,params:{},route:{id:null},state:{},status:-1,url:new URL("https://example.com")},mt={current:null},Pr={current:!1}):(q=new(Yn=class{constructor(){K(this,bt,O({}));K(this,xt,O(null));K(this,Et,O(null));K(this,kt,O({}));K(this,St,O({id:null}));K(this,At,O({}));K(this,Tt,O(-1));K(this,Rt,O(new URL("https://example.com")))}get data(){return y(T(this,bt))}set data(t){S(T(this,bt),t)}get form(){return y(T(this,xt))}set form(t){S(T(this,xt),t)}get error(){return y(T(this,Et))}set error(t){S(T(this,Et),t)}get params(){return y(T(this,kt))}set params(t){S(T(this,kt),t)}get route(){return y(T(this,St))}set route(t){S(T(this,St),t)}get state(){return y(T(this,At))}set state(t){S(T(this,At),t)}get status(){return y(T(this,Tt))}set status(t){S(T(this,Tt),t)}get url(){return y(T(this,Rt))}set url(t){S(T(this,Rt),t)}},bt=new WeakMap,xt=new WeakMap,Et=new WeakMap,kt=new WeakMap,St=new WeakMap,At=new WeakMap,Tt=new WeakMap,Rt=new WeakMap,Yn),mt=new(Gn=class{constructor(){K(this,It,O(null))}get current(){return y(T(this,It))}set current(t){S(T(this,It),t)}},It=new WeakMap,Gn),Pr=new(Wn=class{constructor(){K(this,Pt,O(!1))}get current(){return y(T(this,Pt))}set current(t){S(T(this,Pt),t)}},Pt=new WeakMap,Wn),Ga.v=()=>Pr.current=!0);function vs(e){Object.assign(q,e)}const hs="/__data.json",_s=".html__data.json";function ps(e){return e.endsWith(".html")?e.replace(/\.html$/,_s):e.replace(/\/$/,"")+hs}const gs=new Set(["icon","shortcut icon","apple-touch-icon"]),je=Fa(Ba)??{},wt=Fa(Va)??{},be={url:Dn({}),page:Dn({}),navigating:rn(null),updated:Xi()};function ln(e){je[e]=an()}function ms(e,t){let r=e+1;for(;je[r];)delete je[r],r+=1;for(r=t+1;wt[r];)delete wt[r],r+=1}function Je(e){return location.href=e.href,new Promise(()=>{})}async function Ka(){if("serviceWorker"in navigator){const e=await navigator.serviceWorker.getRegistration(ae||"/");e&&await e.update()}}function jn(){}let cn,Vr,nr,we,Br,U;const ar=[],or=[];let re=null;const Vt=new Map,Ja=new Set,ws=new Set,lt=new Set;let M={branch:[],error:null,url:null},fn=!1,ir=!1,Un=!0,yt=!1,nt=!1,Xa=!1,un=!1,Za,B,ne,Le;const ct=new Set;async function ys(e,t,r){var a,o,s,i;document.URL!==location.href&&(location.href=location.href),U=e,await((o=(a=e.hooks).init)==null?void 0:o.call(a)),cn=Gi(e),we=document.documentElement,Br=t,Vr=e.nodes[0],nr=e.nodes[1],Vr(),nr(),B=(s=history.state)==null?void 0:s[He],ne=(i=history.state)==null?void 0:i[gt],B||(B=ne=Date.now(),history.replaceState({...history.state,[He]:B,[gt]:ne},""));const n=je[B];n&&(history.scrollRestoration="manual",scrollTo(n.x,n.y)),r?await Ps(Br,r):await ft({type:"enter",url:Ha(U.hash?Cs(new URL(location.href)):location.href),replace_state:!0}),Is()}function bs(){ar.length=0,un=!1}function Qa(e){or.some(t=>t==null?void 0:t.snapshot)&&(wt[e]=or.map(t=>{var r;return(r=t==null?void 0:t.snapshot)==null?void 0:r.capture()}))}function eo(e){var t;(t=wt[e])==null||t.forEach((r,n)=>{var a,o;(o=(a=or[n])==null?void 0:a.snapshot)==null||o.restore(r)})}function zn(){ln(B),Ln(Ba,je),Qa(ne),Ln(Va,wt)}async function to(e,t,r,n){return ft({type:"goto",url:Ha(e),keepfocus:t.keepFocus,noscroll:t.noScroll,replace_state:t.replaceState,state:t.state,redirect_count:r,nav_token:n,accept:()=>{t.invalidateAll&&(un=!0),t.invalidate&&t.invalidate.forEach(Rs)}})}async function xs(e){if(e.id!==(re==null?void 0:re.id)){const t={};ct.add(t),re={id:e.id,token:t,promise:ao({...e,preload:t}).then(r=>(ct.delete(t),r.type==="loaded"&&r.state.error&&(re=null),r))}}return re.promise}async function Or(e){var r;const t=(r=await mr(e,!1))==null?void 0:r.route;t&&await Promise.all([...t.layouts,t.leaf].map(n=>n==null?void 0:n[1]()))}function ro(e,t,r){var a;M=e.state;const n=document.querySelector("style[data-sveltekit]");if(n&&n.remove(),Object.assign(q,e.props.page),Za=new U.root({target:t,props:{...e.props,stores:be,components:or},hydrate:r,sync:!1}),eo(ne),r){const o={from:null,to:{params:M.params,route:{id:((a=M.route)==null?void 0:a.id)??null},url:new URL(location.href)},willUnload:!1,type:"enter",complete:Promise.resolve()};lt.forEach(s=>s(o))}ir=!0}function sr({url:e,params:t,branch:r,status:n,error:a,route:o,form:s}){let i="never";if(ae&&(e.pathname===ae||e.pathname===ae+"/"))i="always";else for(const d of r)(d==null?void 0:d.slash)!==void 0&&(i=d.slash);e.pathname=Li(e.pathname,i),e.search=e.search;const l={type:"loaded",state:{url:e,params:t,branch:r,error:a,route:o},props:{constructors:ls(r).map(d=>d.node.component),page:_n(q)}};s!==void 0&&(l.props.form=s);let c={},u=!q,f=0;for(let d=0;d<Math.max(r.length,M.branch.length);d+=1){const p=r[d],_=M.branch[d];(p==null?void 0:p.data)!==(_==null?void 0:_.data)&&(u=!0),p&&(c={...c,...p.data},u&&(l.props[`data_${f}`]=c),f+=1)}return(!M.url||e.href!==M.url.href||M.error!==a||s!==void 0&&s!==q.form||u)&&(l.props.page={error:a,params:t,route:{id:(o==null?void 0:o.id)??null},state:{},status:n,url:new URL(e),form:s??null,data:u?c:q.data}),l}async function dn({loader:e,parent:t,url:r,params:n,route:a,server_data_node:o}){var u,f,v;let s=null,i=!0;const l={dependencies:new Set,params:new Set,parent:!1,route:!1,url:!1,search_params:new Set},c=await e();if((u=c.universal)!=null&&u.load){let d=function(..._){for(const h of _){const{href:g}=new URL(h,r);l.dependencies.add(g)}};const p={route:new Proxy(a,{get:(_,h)=>(i&&(l.route=!0),_[h])}),params:new Proxy(n,{get:(_,h)=>(i&&l.params.add(h),_[h])}),data:(o==null?void 0:o.data)??null,url:Mi(r,()=>{i&&(l.url=!0)},_=>{i&&l.search_params.add(_)},U.hash),async fetch(_,h){_ instanceof Request&&(h={body:_.method==="GET"||_.method==="HEAD"?void 0:await _.blob(),cache:_.cache,credentials:_.credentials,headers:[..._.headers].length?_.headers:void 0,integrity:_.integrity,keepalive:_.keepalive,method:_.method,mode:_.mode,redirect:_.redirect,referrer:_.referrer,referrerPolicy:_.referrerPolicy,signal:_.signal,...h});const{resolved:g,promise:x}=no(_,h,r);return i&&d(g.href),x},setHeaders:()=>{},depends:d,parent(){return i&&(l.parent=!0),t()},untrack(_){i=!1;try{return _()}finally{i=!0}}};s=await c.universal.load.call(null,p)??null}return{node:c,loader:e,server:o,universal:(f=c.universal)!=null&&f.load?{type:"data",data:s,uses:l}:null,data:s??(o==null?void 0:o.data)??null,slash:((v=c.universal)==null?void 0:v.trailingSlash)??(o==null?void 0:o.slash)}}function no(e,t,r){let n=e instanceof Request?e.url:e;const a=new URL(n,r);a.origin===r.origin&&(n=a.href.slice(r.origin.length));const o=ir?Vi(n,a.href,t):Fi(n,t);return{resolved:a,promise:o}}function Fn(e,t,r,n,a,o){if(un)return!0;if(!a)return!1;if(a.parent&&e||a.route&&t||a.url&&r)return!0;for(const s of a.search_params)if(n.has(s))return!0;for(const s of a.params)if(o[s]!==M.params[s])return!0;for(const s of a.dependencies)if(ar.some(i=>i(new URL(s))))return!0;return!1}function vn(e,t){return(e==null?void 0:e.type)==="data"?e:(e==null?void 0:e.type)==="skip"?t??null:null}function Es(e,t){if(!e)return new Set(t.searchParams.keys());const r=new Set([...e.searchParams.keys(),...t.searchParams.keys()]);for(const n of r){const a=e.searchParams.getAll(n),o=t.searchParams.getAll(n);a.every(s=>o.includes(s))&&o.every(s=>a.includes(s))&&r.delete(n)}return r}function Vn({error:e,url:t,route:r,params:n}){return{type:"loaded",state:{error:e,url:t,route:r,params:n,branch:[]},props:{page:_n(q),constructors:[]}}}async function ao({id:e,invalidating:t,url:r,params:n,route:a,preload:o}){if((re==null?void 0:re.id)===e)return ct.delete(re.token),re.promise;const{errors:s,layouts:i,leaf:l}=a,c=[...i,l];s.forEach(m=>m==null?void 0:m().catch(()=>{})),c.forEach(m=>m==null?void 0:m[1]().catch(()=>{}));let u=null;const f=M.url?e!==lr(M.url):!1,v=M.route?a.id!==M.route.id:!1,d=Es(M.url,r);let p=!1;const _=c.map((m,w)=>{var N;const k=M.branch[w],b=!!(m!=null&&m[0])&&((k==null?void 0:k.loader)!==m[1]||Fn(p,v,f,d,(N=k.server)==null?void 0:N.uses,n));return b&&(p=!0),b});if(_.some(Boolean)){try{u=await so(r,_)}catch(m){const w=await $e(m,{url:r,params:n,route:{id:e}});return ct.has(o)?Vn({error:w,url:r,params:n,route:a}):gr({status:rr(m),error:w,url:r,route:a})}if(u.type==="redirect")return u}const h=u==null?void 0:u.nodes;let g=!1;const x=c.map(async(m,w)=>{var V;if(!m)return;const k=M.branch[w],b=h==null?void 0:h[w];if((!b||b.type==="skip")&&m[1]===(k==null?void 0:k.loader)&&!Fn(g,v,f,d,(V=k.universal)==null?void 0:V.uses,n))return k;if(g=!0,(b==null?void 0:b.type)==="error")throw b;return dn({loader:m[1],url:r,params:n,route:a,parent:async()=>{var W;const oe={};for(let Ae=0;Ae<w;Ae+=1)Object.assign(oe,(W=await x[Ae])==null?void 0:W.data);return oe},server_data_node:vn(b===void 0&&m[0]?{type:"skip"}:b??null,m[0]?k==null?void 0:k.server:void 0)})});for(const m of x)m.catch(()=>{});const E=[];for(let m=0;m<c.length;m+=1)if(c[m])try{E.push(await x[m])}catch(w){if(w instanceof on)return{type:"redirect",location:w.location};if(ct.has(o))return Vn({error:await $e(w,{params:n,url:r,route:{id:a.id}}),url:r,params:n,route:a});let k=rr(w),b;if(h!=null&&h.includes(w))k=w.status??k,b=w.error;else if(w instanceof pr)b=w.body;else{if(await be.updated.check())return await Ka(),await Je(r);b=await $e(w,{params:n,url:r,route:{id:a.id}})}const N=await ks(m,E,s);return N?sr({url:r,params:n,branch:E.slice(0,N.idx).concat(N.node),status:k,error:b,route:a}):await io(r,{id:a.id},b,k)}else E.push(void 0);return sr({url:r,params:n,branch:E,status:200,error:null,route:a,form:t?void 0:null})}async function ks(e,t,r){for(;e--;)if(r[e]){let n=e;for(;!t[n];)n-=1;try{return{idx:n+1,node:{node:await r[e](),loader:r[e],data:{},server:null,universal:null}}}catch{continue}}}async function gr({status:e,error:t,url:r,route:n}){const a={};let o=null;if(U.server_loads[0]===0)try{const i=await so(r,[!0]);if(i.type!=="data"||i.nodes[0]&&i.nodes[0].type!=="data")throw 0;o=i.nodes[0]??null}catch{(r.origin!==hr||r.pathname!==location.pathname||fn)&&await Je(r)}try{const i=await dn({loader:Vr,url:r,params:a,route:n,parent:()=>Promise.resolve({}),server_data_node:vn(o)}),l={node:await nr(),loader:nr,universal:null,server:null,data:null};return sr({url:r,params:a,branch:[i,l],status:e,error:t,route:null})}catch(i){if(i instanceof on)return to(new URL(i.location,location.href),{},0);throw i}}async function Ss(e){const t=e.href;if(Vt.has(t))return Vt.get(t);let r;try{const n=(async()=>{let a=await U.hooks.reroute({url:new URL(e),fetch:async(o,s)=>no(o,s,e).promise})??e;if(typeof a=="string"){const o=new URL(e);U.hash?o.hash=a:o.pathname=a,a=o}return a})();Vt.set(t,n),r=await n}catch{Vt.delete(t);return}return r}async function mr(e,t){if(e&&!_r(e,ae,U.hash)){const r=await Ss(e);if(!r)return;const n=As(r);for(const a of cn){const o=a.exec(n);if(o)return{id:lr(e),invalidating:t,route:a,params:Di(o),url:e}}}}function As(e){return Ni(U.hash?e.hash.replace(/^#/,"").replace(/[?#].+/,""):e.pathname.slice(ae.length))||"/"}function lr(e){return(U.hash?e.hash.replace(/^#/,""):e.pathname)+e.search}function oo({url:e,type:t,intent:r,delta:n}){let a=!1;const o=hn(M,r,e,t);n!==void 0&&(o.navigation.delta=n);const s={...o.navigation,cancel:()=>{a=!0,o.reject(new Error("navigation cancelled"))}};return yt||Ja.forEach(i=>i(s)),a?null:o}async function ft({type:e,url:t,popped:r,keepfocus:n,noscroll:a,replace_state:o,state:s={},redirect_count:i=0,nav_token:l={},accept:c=jn,block:u=jn}){const f=Le;Le=l;const v=await mr(t,!1),d=e==="enter"?hn(M,v,t,e):oo({url:t,type:e,delta:r==null?void 0:r.delta,intent:v});if(!d){u(),Le===l&&(Le=f);return}const p=B,_=ne;c(),yt=!0,ir&&d.navigation.type!=="enter"&&be.navigating.set(mt.current=d.navigation);let h=v&&await ao(v);if(!h){if(_r(t,ae,U.hash))return await Je(t);h=await io(t,{id:null},await $e(new sn(404,"Not Found",`Not found: ${t.pathname}`),{url:t,params:{},route:{id:null}}),404)}if(t=(v==null?void 0:v.url)||t,Le!==l)return d.reject(new Error("navigation aborted")),!1;if(h.type==="redirect")if(i>=20)h=await gr({status:500,error:await $e(new Error("Redirect loop"),{url:t,params:{},route:{id:null}}),url:t,route:{id:null}});else return await to(new URL(h.location,t).href,{},i+1,l),!1;else h.props.page.status>=400&&await be.updated.check()&&(await Ka(),await Je(t));if(bs(),ln(p),Qa(_),h.props.page.url.pathname!==t.pathname&&(t.pathname=h.props.page.url.pathname),s=r?r.state:s,!r){const m=o?0:1,w={[He]:B+=m,[gt]:ne+=m,[qa]:s};(o?history.replaceState:history.pushState).call(history,w,"",t),o||ms(B,ne)}if(re=null,h.props.page.state=s,ir){M=h.state,h.props.page&&(h.props.page.url=t);const m=(await Promise.all(Array.from(ws,w=>w(d.navigation)))).filter(w=>typeof w=="function");if(m.length>0){let w=function(){m.forEach(k=>{lt.delete(k)})};m.push(w),m.forEach(k=>{lt.add(k)})}Za.$set(h.props),vs(h.props.page),Xa=!0}else ro(h,Br,!1);const{activeElement:g}=document;await Oa();const x=r?r.scroll:a?an():null;if(Un){const m=t.hash&&document.getElementById(decodeURIComponent(U.hash?t.hash.split("#")[2]??"":t.hash.slice(1)));x?scrollTo(x.x,x.y):m?m.scrollIntoView():scrollTo(0,0)}const E=document.activeElement!==g&&document.activeElement!==document.body;!n&&!E&&Os(),Un=!0,h.props.page&&Object.assign(q,h.props.page),yt=!1,e==="popstate"&&eo(ne),d.fulfil(void 0),lt.forEach(m=>m(d.navigation)),be.navigating.set(mt.current=null)}async function io(e,t,r,n){return e.origin===hr&&e.pathname===location.pathname&&!fn?await gr({status:n,error:r,url:e,route:t}):await Je(e)}function Ts(){let e,t,r;we.addEventListener("mousemove",i=>{const l=i.target;clearTimeout(e),e=setTimeout(()=>{o(l,Ce.hover)},20)});function n(i){i.defaultPrevented||o(i.composedPath()[0],Ce.tap)}we.addEventListener("mousedown",n),we.addEventListener("touchstart",n,{passive:!0});const a=new IntersectionObserver(i=>{for(const l of i)l.isIntersecting&&(Or(new URL(l.target.href)),a.unobserve(l.target))},{threshold:0});async function o(i,l){const c=Ya(i,we),u=c===t&&l>=r;if(!c||u)return;const{url:f,external:v,download:d}=Fr(c,ae,U.hash);if(v||d)return;const p=tr(c),_=f&&lr(M.url)===lr(f);if(!(p.reload||_))if(l<=p.preload_data){t=c,r=Ce.tap;const h=await mr(f,!1);if(!h)return;xs(h)}else l<=p.preload_code&&(t=c,r=l,Or(f))}function s(){a.disconnect();for(const i of we.querySelectorAll("a")){const{url:l,external:c,download:u}=Fr(i,ae,U.hash);if(c||u)continue;const f=tr(i);f.reload||(f.preload_code===Ce.viewport&&a.observe(i),f.preload_code===Ce.eager&&Or(l))}}lt.add(s),s()}function $e(e,t){if(e instanceof pr)return e.body;const r=rr(e),n=us(e);return U.hooks.handleError({error:e,event:t,status:r,message:n})??{message:n}}function Rs(e){if(typeof e=="function")ar.push(e);else{const{href:t}=new URL(e,location.href);ar.push(r=>r.href===t)}}function Is(){var t;history.scrollRestoration="manual",addEventListener("beforeunload",r=>{let n=!1;if(zn(),!yt){const a=hn(M,void 0,null,"leave"),o={...a.navigation,cancel:()=>{n=!0,a.reject(new Error("navigation cancelled"))}};Ja.forEach(s=>s(o))}n?(r.preventDefault(),r.returnValue=""):history.scrollRestoration="auto"}),addEventListener("visibilitychange",()=>{document.visibilityState==="hidden"&&zn()}),(t=navigator.connection)!=null&&t.saveData||Ts(),we.addEventListener("click",async r=>{if(r.button||r.which!==1||r.metaKey||r.ctrlKey||r.shiftKey||r.altKey||r.defaultPrevented)return;const n=Ya(r.composedPath()[0],we);if(!n)return;const{url:a,external:o,target:s,download:i}=Fr(n,ae,U.hash);if(!a)return;if(s==="_parent"||s==="_top"){if(window.parent!==window)return}else if(s&&s!=="_self")return;const l=tr(n);if(!(n instanceof SVGAElement)&&a.protocol!==location.protocol&&!(a.protocol==="https:"||a.protocol==="http:")||i)return;const[u,f]=(U.hash?a.hash.replace(/^#/,""):a.href).split("#"),v=u===Rr(location);if(o||l.reload&&(!v||!f)){oo({url:a,type:"link"})?yt=!0:r.preventDefault();return}if(f!==void 0&&v){const[,d]=M.url.href.split("#");if(d===f){if(r.preventDefault(),f===""||f==="top"&&n.ownerDocument.getElementById("top")===null)window.scrollTo({top:0});else{const p=n.ownerDocument.getElementById(decodeURIComponent(f));p&&(p.scrollIntoView(),p.focus())}return}if(nt=!0,ln(B),e(a),!l.replace_state)return;nt=!1}r.preventDefault(),await new Promise(d=>{requestAnimationFrame(()=>{setTimeout(d,0)}),setTimeout(d,100)}),await ft({type:"link",url:a,keepfocus:l.keepfocus,noscroll:l.noscroll,replace_state:l.replace_state??a.href===location.href})}),we.addEventListener("submit",r=>{if(r.defaultPrevented)return;const n=HTMLFormElement.prototype.cloneNode.call(r.target),a=r.submitter;if(((a==null?void 0:a.formTarget)||n.target)==="_blank"||((a==null?void 0:a.formMethod)||n.method)!=="get")return;const i=new URL((a==null?void 0:a.hasAttribute("formaction"))&&(a==null?void 0:a.formAction)||n.action);if(_r(i,ae,!1))return;const l=r.target,c=tr(l);if(c.reload)return;r.preventDefault(),r.stopPropagation();const u=new FormData(l),f=a==null?void 0:a.getAttribute("name");f&&u.append(f,(a==null?void 0:a.getAttribute("value"))??""),i.search=new URLSearchParams(u).toString(),ft({type:"form",url:i,keepfocus:c.keepfocus,noscroll:c.noscroll,replace_state:c.replace_state??i.href===location.href})}),addEventListener("popstate",async r=>{var n;if((n=r.state)!=null&&n[He]){const a=r.state[He];if(Le={},a===B)return;const o=je[a],s=r.state[qa]??{},i=new URL(r.state[Ji]??location.href),l=r.state[gt],c=M.url?Rr(location)===Rr(M.url):!1;if(l===ne&&(Xa||c)){s!==q.state&&(q.state=s),e(i),je[B]=an(),o&&scrollTo(o.x,o.y),B=a;return}const f=a-B;await ft({type:"popstate",url:i,popped:{state:s,scroll:o,delta:f},accept:()=>{B=a,ne=l},block:()=>{history.go(-f)},nav_token:Le})}else if(!nt){const a=new URL(location.href);e(a),U.hash&&location.reload()}}),addEventListener("hashchange",()=>{nt&&(nt=!1,history.replaceState({...history.state,[He]:++B,[gt]:ne},"",location.href))});for(const r of document.querySelectorAll("link"))gs.has(r.rel)&&(r.href=r.href);addEventListener("pageshow",r=>{r.persisted&&be.navigating.set(mt.current=null)});function e(r){M.url=q.url=r,be.page.set(_n(q)),be.page.notify()}}async function Ps(e,{status:t=200,error:r,node_ids:n,params:a,route:o,server_route:s,data:i,form:l}){fn=!0;const c=new URL(location.href);let u;({params:a={},route:o={id:null}}=await mr(c,!1)||{}),u=cn.find(({id:d})=>d===o.id);let f,v=!0;try{const d=n.map(async(_,h)=>{const g=i[h];return g!=null&&g.uses&&(g.uses=lo(g.uses)),dn({loader:U.nodes[_],url:c,params:a,route:o,parent:async()=>{const x={};for(let E=0;E<h;E+=1)Object.assign(x,(await d[E]).data);return x},server_data_node:vn(g)})}),p=await Promise.all(d);if(u){const _=u.layouts;for(let h=0;h<_.length;h++)_[h]||p.splice(h,0,void 0)}f=sr({url:c,params:a,branch:p,status:t,error:r,form:l,route:u??null})}catch(d){if(d instanceof on){await Je(new URL(d.location,location.href));return}f=await gr({status:rr(d),error:await $e(d,{url:c,params:a,route:o}),url:c,route:o}),e.textContent="",v=!1}f.props.page&&(f.props.page.state={}),ro(f,e,v)}async function so(e,t){var o;const r=new URL(e);r.pathname=ps(e.pathname),e.pathname.endsWith("/")&&r.searchParams.append(fs,"1"),r.searchParams.append(cs,t.map(s=>s?"1":"0").join(""));const n=window.fetch,a=await n(r.href,{});if(!a.ok){let s;throw(o=a.headers.get("content-type"))!=null&&o.includes("application/json")?s=await a.json():a.status===404?s="Not Found":a.status===500&&(s="Internal Error"),new pr(a.status,s)}return new Promise(async s=>{var v;const i=new Map,l=a.body.getReader(),c=new TextDecoder;function u(d){return is(d,{...U.decoders,Promise:p=>new Promise((_,h)=>{i.set(p,{fulfil:_,reject:h})})})}let f="";for(;;){const{done:d,value:p}=await l.read();if(d&&!f)break;for(f+=!p&&f?`
 `:c.decode(p,{stream:!0});;){const _=f.indexOf(`
-`);if(_===-1)break;const h=JSON.parse(f.slice(0,_));if(f=f.slice(_+1),h.type==="redirect")return s(h);if(h.type==="data")(v=h.nodes)==null||v.forEach(g=>{(g==null?void 0:g.type)==="data"&&(g.uses=lo(g.uses),g.data=u(g.data))}),s(h);else if(h.type==="chunk"){const{id:g,data:x,error:E}=h,m=i.get(g);i.delete(g),E?m.reject(u(E)):m.fulfil(u(x))}}}})}function lo(e){return{dependencies:new Set((e==null?void 0:e.dependencies)??[]),params:new Set((e==null?void 0:e.params)??[]),parent:!!(e!=null&&e.parent),route:!!(e!=null&&e.route),url:!!(e!=null&&e.url),search_params:new Set((e==null?void 0:e.search_params)??[])}}function Os(){const e=document.querySelector("[autofocus]");if(e)e.focus();else{const t=document.body,r=t.getAttribute("tabindex");t.tabIndex=-1,t.focus({preventScroll:!0,focusVisible:!1}),r!==null?t.setAttribute("tabindex",r):t.removeAttribute("tabindex");const n=getSelection();if(n&&n.type!=="None"){const a=[];for(let o=0;o<n.rangeCount;o+=1)a.push(n.getRangeAt(o));setTimeout(()=>{if(n.rangeCount===a.length){for(let o=0;o<n.rangeCount;o+=1){const s=a[o],i=n.getRangeAt(o);if(s.commonAncestorContainer!==i.commonAncestorContainer||s.startContainer!==i.startContainer||s.endContainer!==i.endContainer||s.startOffset!==i.startOffset||s.endOffset!==i.endOffset)return}n.removeAllRanges()}})}}}function hn(e,t,r,n){var l,c;let a,o;const s=new Promise((u,f)=>{a=u,o=f});return s.catch(()=>{}),{navigation:{from:{params:e.params,route:{id:((l=e.route)==null?void 0:l.id)??null},url:e.url},to:r&&{params:(t==null?void 0:t.params)??null,route:{id:((c=t==null?void 0:t.route)==null?void 0:c.id)??null},url:r},willUnload:!t,type:n,complete:s},fulfil:a,reject:o}}function _n(e){return{data:e.data,error:e.error,form:e.form,params:e.params,route:e.route,state:e.state,status:e.status,url:e.url}}function Cs(e){const t=new URL(e);return t.hash=decodeURIComponent(e.hash),t}const Ls="modulepreload",Ns=function(e,t){return new URL(e,t).href},Bn={},Cr=function(t,r,n){let a=Promise.resolve();if(r&&r.length>0){const s=document.getElementsByTagName("link"),i=document.querySelector("meta[property=csp-nonce]"),l=(i==null?void 0:i.nonce)||(i==null?void 0:i.getAttribute("nonce"));a=Promise.allSettled(r.map(c=>{if(c=Ns(c,n),c in Bn)return;Bn[c]=!0;const u=c.endsWith(".css"),f=u?'[rel="stylesheet"]':"";if(n)for(let d=s.length-1;d>=0;d--){const p=s[d];if(p.href===c&&(!u||p.rel==="stylesheet"))return}else if(document.querySelector(`link[href="${c}"]${f}`))return;const v=document.createElement("link");if(v.rel=u?"stylesheet":Ls,u||(v.as="script"),v.crossOrigin="",v.href=c,l&&v.setAttribute("nonce",l),document.head.appendChild(v),u)return new Promise((d,p)=>{v.addEventListener("load",d),v.addEventListener("error",()=>p(new Error(`Unable to preload CSS for ${c}`)))})}))}function o(s){const i=new Event("vite:preloadError",{cancelable:!0});if(i.payload=s,window.dispatchEvent(i),!i.defaultPrevented)throw s}return a.then(s=>{for(const i of s||[])i.status==="rejected"&&o(i.reason);return t().catch(o)})},Ds={},Ms="5";var Kn;typeof window<"u"&&((Kn=window.__svelte??(window.__svelte={})).v??(Kn.v=new Set)).add(Ms);var js=ue('<div id="svelte-announcer" aria-live="assertive" aria-atomic="true" style="position: absolute; left: 0; top: 0; clip: rect(0 0 0 0); clip-path: inset(50%); overflow: hidden; white-space: nowrap; width: 1px; height: 1px"><!></div>'),Us=ue("<!> <!>",1);function zs(e,t){Qe(t,!0);let r=Tr(t,"components",23,()=>[]),n=Tr(t,"data_0",3,null),a=Tr(t,"data_1",3,null);pa(()=>t.stores.page.set(t.page)),vt(()=>{t.stores,t.page,t.constructors,r(),t.form,n(),a(),t.stores.page.notify()});let o=O(!1),s=O(!1),i=O(null);zr(()=>{const _=t.stores.page.subscribe(()=>{y(o)&&(S(s,!0),Oa().then(()=>{S(i,document.title||"untitled page",!0)}))});return S(o,!0),_});const l=ot(()=>t.constructors[1]);var c=Us(),u=se(c);{var f=_=>{var h=qe();const g=ot(()=>t.constructors[0]);var x=se(h);Ht(x,()=>y(g),(E,m)=>{Yt(m(E,{get data(){return n()},get form(){return t.form},children:(w,k)=>{var b=qe(),N=se(b);Ht(N,()=>y(l),(V,oe)=>{Yt(oe(V,{get data(){return a()},get form(){return t.form}}),W=>r()[1]=W,()=>{var W;return(W=r())==null?void 0:W[1]})}),z(w,b)},$$slots:{default:!0}}),w=>r()[0]=w,()=>{var w;return(w=r())==null?void 0:w[0]})}),z(_,h)},v=_=>{var h=qe();const g=ot(()=>t.constructors[0]);var x=se(h);Ht(x,()=>y(g),(E,m)=>{Yt(m(E,{get data(){return n()},get form(){return t.form}}),w=>r()[0]=w,()=>{var w;return(w=r())==null?void 0:w[0]})}),z(_,h)};_e(u,_=>{t.constructors[1]?_(f):_(v,!1)})}var d=D(u,2);{var p=_=>{var h=js(),g=P(h);{var x=E=>{var m=_i();Ge(()=>Ke(m,y(i))),z(E,m)};_e(g,E=>{y(s)&&E(x)})}I(h),z(_,h)};_e(d,_=>{y(o)&&_(p)})}z(e,c),et()}const Fs=Pi(zs),Vs=[()=>Cr(()=>Promise.resolve().then(()=>Js),void 0,import.meta.url),()=>Cr(()=>Promise.resolve().then(()=>el),void 0,import.meta.url),()=>Cr(()=>Promise.resolve().then(()=>gl),void 0,import.meta.url)],Bs=[],qs={"/":[2]},co={handleError:({error:e})=>{console.error(e)},reroute:()=>{},transport:{}},fo=Object.fromEntries(Object.entries(co.transport).map(([e,t])=>[e,t.decode])),Hs=!1,$s=(e,t)=>fo[e](t),Ys=Object.freeze(Object.defineProperty({__proto__:null,decode:$s,decoders:fo,dictionary:qs,hash:Hs,hooks:co,matchers:Ds,nodes:Vs,root:Fs,server_loads:Bs},Symbol.toStringTag,{value:"Module"}));function xl(e,t){ys(Ys,e,t)}const Gs=!0,Ws=Object.freeze(Object.defineProperty({__proto__:null,prerender:Gs},Symbol.toStringTag,{value:"Module"}));function Ks(e,t){var r=qe(),n=se(r);Ei(n,()=>t.children),z(e,r)}const Js=Object.freeze(Object.defineProperty({__proto__:null,component:Ks,universal:Ws},Symbol.toStringTag,{value:"Module"}));Co();const Xs={get error(){return q.error},get status(){return q.status}};be.updated.check;const qn=Xs;var Zs=ue("<h1> </h1> <p> </p>",1);function Qs(e,t){Qe(t,!1),Ri();var r=Zs(),n=se(r),a=P(n,!0);I(n);var o=D(n,2),s=P(o,!0);I(o),Ge(()=>{var i;Ke(a,qn.status),Ke(s,(i=qn.error)==null?void 0:i.message)}),z(e,r),et()}const el=Object.freeze(Object.defineProperty({__proto__:null,component:Qs},Symbol.toStringTag,{value:"Module"}));async function tl(e,t,r,n,a,o,s){if(y(t))return;S(t,!0),S(r,[],!0);const i=await fetch("/api/mirrors?action=create",{method:"POST",headers:{"Content-Type":"application/json"},body:JSON.stringify({token:y(n),guild_id:JSON.rawJSON(y(a)),channel_id:JSON.rawJSON(y(o))})});if(!i.body)return;const l=i.body.getReader(),c=new TextDecoder;for(;;){const{value:f,done:v}=await l.read();if(v)break;const d=c.decode(f,{stream:!0});y(r).push(d)}const u=c.decode();u.length&&y(r).push(u),S(t,!1),y(r).at(-1)==="success"&&(await s.states.refreshItems(),s.states.closeModal())}var rl=(e,t)=>t.states.closeModal(),nl=hi('<svg class="animate-spin" xmlns="http://www.w3.org/2000/svg" fill="none" width="16" height="16" viewBox="0 0 24 24"><circle class="opacity-25" cx="12" cy="12" r="10" stroke="currentColor" stroke-width="4"></circle><path class="opacity-75" fill="currentColor" d="M4 12a8 8 0 018-8V0C5.373 0 0 5.373 0 12h4zm2 5.291A7.962 7.962 0 014 12H0c0 3.042 1.135 5.824 3 7.938l3-2.647z"></path></svg>'),al=ue('<div class="flex items-center space-x-1.5"><!> <span> </span></div>'),ol=ue('<div class="flex"><span class="flex-auto uppercase tracking-widest">Create Discord Mirror</span> <button class="my-0.5 cursor-pointer"><svg xmlns="http://www.w3.org/2000/svg" width="16" height="16" viewBox="0 0 24 24"><path fill="none" stroke="currentColor" stroke-linecap="round" stroke-linejoin="round" stroke-width="2" d="M18 6L6 18M6 6l12 12"></path></svg></button></div> <div class="h-px mt-2 mb-3 bg-zinc-700"></div> <div class="flex flex-col space-y-1"><input type="text" class="min-w-64 h-8 px-3 bg-zinc-800 rounded-md border border-zinc-700" placeholder="Token"> <input type="text" class="min-w-64 h-8 px-3 bg-zinc-800 rounded-md border border-zinc-700" placeholder="Guild ID"> <input type="text" class="min-w-64 h-8 px-3 bg-zinc-800 rounded-md border border-zinc-700" placeholder="Voice Channel ID"> <div class="mt-3 mb-1.5 flex flex-col items-center"><button><svg xmlns="http://www.w3.org/2000/svg" width="16" height="16" viewBox="0 0 24 24"><path fill="none" stroke="currentColor" stroke-linecap="round" stroke-linejoin="round" stroke-width="2" d="M5 12h14m-7-7v14"></path></svg> <span>Add</span></button> <!></div></div>',1),il=ue('<div class="px-4 py-2.5 flex flex-col text-sm text-gray-100"><!></div>');function sl(e,t){Qe(t,!0);let r=O(void 0),n=O(void 0),a=O(void 0),o=O(!1),s=ot(()=>y(o)?"bg-indigo-300 cursor-not-allowed":"bg-indigo-500 cursor-pointer"),i=O(me([]));var l=il(),c=P(l);{var u=f=>{var v=ol(),d=se(v),p=D(P(d),2);p.__click=[rl,t],I(d);var _=D(d,4),h=P(_);$t(h);var g=D(h,2);$t(g);var x=D(g,2);$t(x);var E=D(x,2),m=P(E);m.__click=[tl,o,i,r,n,a,t];var w=D(m,2);{var k=b=>{var N=al(),V=P(N);{var oe=Te=>{var Mt=nl();z(Te,Mt)};_e(V,Te=>{y(o)&&Te(oe)})}var W=D(V,2),Ae=P(W,!0);I(W),I(N),Ge(Te=>Ke(Ae,Te),[()=>y(i).at(-1)]),z(b,N)};_e(w,b=>{y(i).length&&b(k)})}I(E),I(_),Ge(()=>Ua(m,1,`py-1.5 px-2 mb-1.5 flex items-center space-x-1 rounded-lg ${y(s)??""}`)),kr(h,()=>y(r),b=>S(r,b)),kr(g,()=>y(n),b=>S(n,b)),kr(x,()=>y(a),b=>S(a,b)),z(f,v)};_e(c,f=>{t.states&&f(u)})}I(l),z(e,l),et()}tn(["click"]);async function ll(e,t,r){if(y(t))return;S(t,!0);const n=await fetch("/api/mirrors?action=delete",{method:"POST",headers:{"Content-Type":"application/json"},body:JSON.stringify({id:r.mirror})});S(t,!1),n.status===200&&(await r.states.refreshItems(),r.states.closeModal())}var cl=(e,t)=>t.states.closeModal(),fl=ue('<div class="flex"><span class="flex-auto uppercase tracking-widest"> </span> <button class="my-0.5 cursor-pointer"><svg xmlns="http://www.w3.org/2000/svg" width="16" height="16" viewBox="0 0 24 24"><path fill="none" stroke="currentColor" stroke-linecap="round" stroke-linejoin="round" stroke-width="2" d="M18 6L6 18M6 6l12 12"></path></svg></button></div> <div class="h-px mt-2 mb-3 bg-zinc-700"></div> <div class="flex flex-col space-y-4.5"><span class="mb-0"> </span> <span>This action cannot be undone.</span> <span>Please confirm that you would like to proceed.</span> <div class="mb-3.5 flex flex-col items-center"><button><svg xmlns="http://www.w3.org/2000/svg" width="16" height="16" viewBox="-2 -2 28 28"><path fill="none" stroke="currentColor" stroke-linecap="round" stroke-linejoin="round" stroke-width="2" d="M3 6h18m-2 0v14c0 1-1 2-2 2H7c-1 0-2-1-2-2V6m3 0V4c0-1 1-2 2-2h4c1 0 2 1 2 2v2m-6 5v6m4-6v6"></path></svg> <span>Remove</span></button></div></div>',1),ul=ue('<div class="px-4 py-2.5 flex flex-col text-sm text-gray-100"><!></div>');function dl(e,t){Qe(t,!0);let r=O(!1),n=ot(()=>y(r)?"bg-red-300 cursor-not-allowed":"bg-red-500 cursor-pointer");var a=ul(),o=P(a);{var s=i=>{var l=fl(),c=se(l),u=P(c),f=P(u);I(u);var v=D(u,2);v.__click=[cl,t],I(c);var d=D(c,4),p=P(d),_=P(p);I(p);var h=D(p,6),g=P(h);g.__click=[ll,r,t],I(h),I(d),Ge(()=>{Ke(f,`Delete Discord Live ${t.mirror+1}`),Ke(_,`You are about to delete Discord Live ${t.mirror+1}.`),Ua(g,1,`py-1.5 px-2 flex items-center space-x-1 rounded-lg ${y(n)??""}`)}),z(i,l)};_e(o,i=>{t.states&&i(s)})}I(a),z(e,a),et()}tn(["click"]);var vl=ue('<span class="text-zinc-400/40 group-hover:text-zinc-500 transition">There are no active mirrors yet.</span>'),hl=ue('<div class="nth-2:mt-2.5 last:mb-2.5 relative flex group/mirrors hover:text-zinc-300 hover:bg-zinc-700 rounded-md transition"><div class="relative mx-2 w-1"><div class="absolute top-0 group-nth-2/mirrors:-top-2.5 right-0 bottom-0 group-last/mirrors:-bottom-2.5 left-0 bg-zinc-700 group-nth-2/mirrors:rounded-t-lg group-last/mirrors:rounded-b-lg"></div></div> <div class="flex-auto px-1.5 py-0.5"><span></span></div> <button class="my-1 mr-1 cursor-pointer"><svg xmlns="http://www.w3.org/2000/svg" width="16" height="16" viewBox="-2 -2 28 28"><path fill="none" stroke="currentColor" stroke-linecap="round" stroke-linejoin="round" stroke-width="2" d="M3 6h18m-2 0v14c0 1-1 2-2 2H7c-1 0-2-1-2-2V6m3 0V4c0-1 1-2 2-2h4c1 0 2 1 2 2v2m-6 5v6m4-6v6"></path></svg></button> <div class="flex absolute left-1.5 top-0 bottom-0 items-center"><div class="w-2 h-2 bg-zinc-500 group-hover/mirrors:bg-zinc-300 rounded-full transition"></div></div></div>'),_l=ue('<div class="m-auto bg-zinc-900 rounded-2xl backdrop:bg-zinc-600/40 backdrop:backdrop-blur" popover=""><!></div> <div class="h-screen flex justify-center items-center"><div class="size-full md:max-w-192 md:max-h-108 overflow-hidden flex flex-col bg-zinc-900 rounded-2xl border border-zinc-800 text-sm text-zinc-500"><div class="px-3 py-1.5 flex space-x-1.5 bg-zinc-700 text-gray-100"><span class="flex-auto">utsuru</span> <a class="my-0.5 cursor-pointer" href="https://github.com/VincentVerdynanta/utsuru" target="_blank"><svg xmlns="http://www.w3.org/2000/svg" width="16" height="16" viewBox="0 0 496 512"><path fill="currentColor" d="M165.9 397.4c0 2-2.3 3.6-5.2 3.6c-3.3.3-5.6-1.3-5.6-3.6c0-2 2.3-3.6 5.2-3.6c3-.3 5.6 1.3 5.6 3.6m-31.1-4.5c-.7 2 1.3 4.3 4.3 4.9c2.6 1 5.6 0 6.2-2s-1.3-4.3-4.3-5.2c-2.6-.7-5.5.3-6.2 2.3m44.2-1.7c-2.9.7-4.9 2.6-4.6 4.9c.3 2 2.9 3.3 5.9 2.6c2.9-.7 4.9-2.6 4.6-4.6c-.3-1.9-3-3.2-5.9-2.9M244.8 8C106.1 8 0 113.3 0 252c0 110.9 69.8 205.8 169.5 239.2c12.8 2.3 17.3-5.6 17.3-12.1c0-6.2-.3-40.4-.3-61.4c0 0-70 15-84.7-29.8c0 0-11.4-29.1-27.8-36.6c0 0-22.9-15.7 1.6-15.4c0 0 24.9 2 38.6 25.8c21.9 38.6 58.6 27.5 72.9 20.9c2.3-16 8.8-27.1 16-33.7c-55.9-6.2-112.3-14.3-112.3-110.5c0-27.5 7.6-41.3 23.6-58.9c-2.6-6.5-11.1-33.3 2.6-67.9c20.9-6.5 69 27 69 27c20-5.6 41.5-8.5 62.8-8.5s42.8 2.9 62.8 8.5c0 0 48.1-33.6 69-27c13.7 34.7 5.2 61.4 2.6 67.9c16 17.7 25.8 31.5 25.8 58.9c0 96.5-58.9 104.2-114.8 110.5c9.2 7.9 17 22.9 17 46.4c0 33.7-.3 75.4-.3 83.6c0 6.5 4.6 14.4 17.3 12.1C428.2 457.8 496 362.9 496 252C496 113.3 383.5 8 244.8 8M97.2 352.9c-1.3 1-1 3.3.7 5.2c1.6 1.6 3.9 2.3 5.2 1c1.3-1 1-3.3-.7-5.2c-1.6-1.6-3.9-2.3-5.2-1m-10.8-8.1c-.7 1.3.3 2.9 2.3 3.9c1.6 1 3.6.7 4.3-.7c.7-1.3-.3-2.9-2.3-3.9c-2-.6-3.6-.3-4.3.7m32.4 35.6c-1.6 1.3-1 4.3 1.3 6.2c2.3 2.3 5.2 2.6 6.5 1c1.3-1.3.7-4.3-1.3-6.2c-2.2-2.3-5.2-2.6-6.5-1m-11.4-14.7c-1.6 1-1.6 3.6 0 5.9s4.3 3.3 5.6 2.3c1.6-1.3 1.6-3.9 0-6.2c-1.4-2.3-4-3.3-5.6-2"></path></svg></a></div> <div class="flex-auto relative"><div class="grid overflow-auto absolute inset-0 md:grid-cols-2 gap-4 p-4"><div class="px-4 py-2.5 flex flex-col bg-zinc-800 rounded-md border border-transparent hover:border-indigo-500 transition group"><div class="mb-2 flex items-center group-hover:text-zinc-400 uppercase tracking-widest"><span class="my-1.5 mr-2 underline underline-offset-4 decoration-zinc-400/20 group-hover:decoration-zinc-400/40 transition">Mirrors</span> <button class="p-2 hover:bg-zinc-700 rounded-full transition cursor-pointer"><svg xmlns="http://www.w3.org/2000/svg" width="16" height="16" viewBox="0 0 24 24"><path fill="none" stroke="currentColor" stroke-linecap="round" stroke-linejoin="round" stroke-width="2" d="M5 12h14m-7-7v14"></path></svg></button> <button class="p-2 hover:bg-zinc-700 rounded-full transition cursor-pointer"><svg xmlns="http://www.w3.org/2000/svg" width="16" height="16" viewBox="0 0 24 24"><g fill="none" stroke="currentColor" stroke-linecap="round" stroke-linejoin="round" stroke-width="2"><path d="M21 12a9 9 0 0 0-9-9a9.75 9.75 0 0 0-6.74 2.74L3 8"></path><path d="M3 3v5h5m-5 4a9 9 0 0 0 9 9a9.75 9.75 0 0 0 6.74-2.74L21 16"></path><path d="M16 16h5v5"></path></g></svg></button></div> <!> <!></div> <div class="px-4 py-2.5 flex flex-col bg-zinc-800 rounded-md border border-transparent hover:border-indigo-500 transition group"><div class="mb-2 flex items-center space-x-2 group-hover:text-zinc-400 uppercase tracking-widest"><span class="my-1.5 underline underline-offset-4 decoration-zinc-400/20 group-hover:decoration-zinc-400/40 transition">WHIP</span></div> <div class="flex flex-col space-y-2 group-hover:text-zinc-400 transition"><span class="mb-3">Instructions for using utsuru with OBS:</span> <div class="flex space-x-2"><div class="w-8 h-8 flex justify-center items-center group-hover:bg-zinc-700 rounded-full border border-zinc-700 transition"><span>1</span></div> <div class="flex-auto flex flex-col justify-center *:my-1.5"><span>Open <b>Settings</b> &gt; <b>Stream</b></span></div></div> <div class="flex space-x-2"><div class="w-8 h-8 flex justify-center items-center group-hover:bg-zinc-700 rounded-full border border-zinc-700 transition"><span>2</span></div> <div class="flex-auto flex flex-col justify-center *:my-1.5"><span>Set the <b>Service</b> to <b>WHIP</b></span></div></div> <div class="flex space-x-2"><div class="w-8 h-8 flex justify-center items-center group-hover:bg-zinc-700 rounded-full border border-zinc-700 transition"><span>3</span></div> <div class="flex-auto flex flex-col justify-center *:my-1.5"><span>Fill the <b>Destination</b> properties with:</span> <div class="overflow-hidden flex flex-col group-hover:bg-zinc-700/40 rounded-md border border-zinc-700 transition"><div class="px-1.5 py-0.5 bg-zinc-500/20 group-hover:bg-zinc-700 text-xs transition"><span>Server</span></div> <div class="h-8 relative"><div class="flex absolute inset-0 items-center overflow-auto"><input type="text" class="size-full px-3" disabled></div></div></div> <div class="overflow-hidden flex flex-col group-hover:bg-zinc-700/40 rounded-md border border-zinc-700 transition"><div class="px-1.5 py-0.5 bg-zinc-500/20 group-hover:bg-zinc-700 text-xs transition"><span>Bearer Token (WIP, any value works)</span></div> <div class="h-8 relative"><div class="flex absolute inset-0 items-center overflow-auto"><input type="text" class="size-full px-3" value="utsuru" disabled></div></div></div></div></div></div></div></div></div></div></div>',1);function pl(e,t){Qe(t,!0);let r=O(me([])),n=O(void 0),a,o=O(void 0),s={closeModal:()=>a.hidePopover(),refreshItems:async()=>await c(),getItems:()=>y(r)};function i(){S(o,F=>sl(F,{states:s})),a.showPopover()}function l(F){S(o,de=>dl(de,{mirror:F,states:s})),a.showPopover()}async function c(){const F=await fetch("/api/mirrors");S(r,await F.json(),!0)}vt(async()=>{await c(),S(n,window.location.origin+"/whip")});var u=_l();vi(F=>{fa.title="utsuru"});var f=se(u),v=P(f);{var d=F=>{var de=qe(),jt=se(de);Ht(jt,()=>y(o),(Ut,wr)=>{wr(Ut,{})}),z(F,de)};_e(v,F=>{y(o)&&F(d)})}I(f),Yt(f,F=>a=F,()=>a);var p=D(f,2),_=P(p),h=D(P(_),2),g=P(h),x=P(g),E=P(x),m=D(P(E),2);m.__click=i;var w=D(m,2);w.__click=c,I(E);var k=D(E,2);{var b=F=>{var de=vl();z(F,de)};_e(k,F=>{y(r).some(de=>de)||F(b)})}var N=D(k,2);yi(N,17,()=>y(r),mi,(F,de,jt)=>{var Ut=qe(),wr=se(Ut);{var uo=yr=>{var br=hl(),xr=D(P(br),2),vo=P(xr);vo.textContent=`Discord Live ${jt+1}`,I(xr);var ho=D(xr,2);ho.__click=()=>l(jt),En(2),I(br),z(yr,br)};_e(wr,yr=>{y(de)&&yr(uo)})}z(F,Ut)}),I(x);var V=D(x,2),oe=D(P(V),2),W=D(P(oe),6),Ae=D(P(W),2),Te=D(P(Ae),2),Mt=D(P(Te),2),pn=P(Mt),gn=P(pn);$t(gn),I(pn),I(Mt),I(Te),En(2),I(Ae),I(W),I(oe),I(V),I(g),I(h),I(_),I(p),Ge(()=>Ti(gn,y(n))),z(e,u),et()}tn(["click"]);const gl=Object.freeze(Object.defineProperty({__proto__:null,component:pl},Symbol.toStringTag,{value:"Module"}));export{xl as start};
+`);if(_===-1)break;const h=JSON.parse(f.slice(0,_));if(f=f.slice(_+1),h.type==="redirect")return s(h);if(h.type==="data")(v=h.nodes)==null||v.forEach(g=>{(g==null?void 0:g.type)==="data"&&(g.uses=lo(g.uses),g.data=u(g.data))}),s(h);else if(h.type==="chunk"){const{id:g,data:x,error:E}=h,m=i.get(g);i.delete(g),E?m.reject(u(E)):m.fulfil(u(x))}}}})}function lo(e){return{dependencies:new Set((e==null?void 0:e.dependencies)??[]),params:new Set((e==null?void 0:e.params)??[]),parent:!!(e!=null&&e.parent),route:!!(e!=null&&e.route),url:!!(e!=null&&e.url),search_params:new Set((e==null?void 0:e.search_params)??[])}}function Os(){const e=document.querySelector("[autofocus]");if(e)e.focus();else{const t=document.body,r=t.getAttribute("tabindex");t.tabIndex=-1,t.focus({preventScroll:!0,focusVisible:!1}),r!==null?t.setAttribute("tabindex",r):t.removeAttribute("tabindex");const n=getSelection();if(n&&n.type!=="None"){const a=[];for(let o=0;o<n.rangeCount;o+=1)a.push(n.getRangeAt(o));setTimeout(()=>{if(n.rangeCount===a.length){for(let o=0;o<n.rangeCount;o+=1){const s=a[o],i=n.getRangeAt(o);if(s.commonAncestorContainer!==i.commonAncestorContainer||s.startContainer!==i.startContainer||s.endContainer!==i.endContainer||s.startOffset!==i.startOffset||s.endOffset!==i.endOffset)return}n.removeAllRanges()}})}}}function hn(e,t,r,n){var l,c;let a,o;const s=new Promise((u,f)=>{a=u,o=f});return s.catch(()=>{}),{navigation:{from:{params:e.params,route:{id:((l=e.route)==null?void 0:l.id)??null},url:e.url},to:r&&{params:(t==null?void 0:t.params)??null,route:{id:((c=t==null?void 0:t.route)==null?void 0:c.id)??null},url:r},willUnload:!t,type:n,complete:s},fulfil:a,reject:o}}function _n(e){return{data:e.data,error:e.error,form:e.form,params:e.params,route:e.route,state:e.state,status:e.status,url:e.url}}function Cs(e){const t=new URL(e);return t.hash=decodeURIComponent(e.hash),t}const Ls="modulepreload",Ns=function(e,t){return new URL(e,t).href},Bn={},Cr=function(t,r,n){let a=Promise.resolve();if(r&&r.length>0){const s=document.getElementsByTagName("link"),i=document.querySelector("meta[property=csp-nonce]"),l=(i==null?void 0:i.nonce)||(i==null?void 0:i.getAttribute("nonce"));a=Promise.allSettled(r.map(c=>{if(c=Ns(c,n),c in Bn)return;Bn[c]=!0;const u=c.endsWith(".css"),f=u?'[rel="stylesheet"]':"";if(n)for(let d=s.length-1;d>=0;d--){const p=s[d];if(p.href===c&&(!u||p.rel==="stylesheet"))return}else if(document.querySelector(`link[href="${c}"]${f}`))return;const v=document.createElement("link");if(v.rel=u?"stylesheet":Ls,u||(v.as="script"),v.crossOrigin="",v.href=c,l&&v.setAttribute("nonce",l),document.head.appendChild(v),u)return new Promise((d,p)=>{v.addEventListener("load",d),v.addEventListener("error",()=>p(new Error(`Unable to preload CSS for ${c}`)))})}))}function o(s){const i=new Event("vite:preloadError",{cancelable:!0});if(i.payload=s,window.dispatchEvent(i),!i.defaultPrevented)throw s}return a.then(s=>{for(const i of s||[])i.status==="rejected"&&o(i.reason);return t().catch(o)})},Ds={},Ms="5";var Kn;typeof window<"u"&&((Kn=window.__svelte??(window.__svelte={})).v??(Kn.v=new Set)).add(Ms);var js=ue('<div id="svelte-announcer" aria-live="assertive" aria-atomic="true" style="position: absolute; left: 0; top: 0; clip: rect(0 0 0 0); clip-path: inset(50%); overflow: hidden; white-space: nowrap; width: 1px; height: 1px"><!></div>'),Us=ue("<!> <!>",1);function zs(e,t){Qe(t,!0);let r=Tr(t,"components",23,()=>[]),n=Tr(t,"data_0",3,null),a=Tr(t,"data_1",3,null);pa(()=>t.stores.page.set(t.page)),vt(()=>{t.stores,t.page,t.constructors,r(),t.form,n(),a(),t.stores.page.notify()});let o=O(!1),s=O(!1),i=O(null);zr(()=>{const _=t.stores.page.subscribe(()=>{y(o)&&(S(s,!0),Oa().then(()=>{S(i,document.title||"untitled page",!0)}))});return S(o,!0),_});const l=ot(()=>t.constructors[1]);var c=Us(),u=se(c);{var f=_=>{var h=qe();const g=ot(()=>t.constructors[0]);var x=se(h);Ht(x,()=>y(g),(E,m)=>{Yt(m(E,{get data(){return n()},get form(){return t.form},children:(w,k)=>{var b=qe(),N=se(b);Ht(N,()=>y(l),(V,oe)=>{Yt(oe(V,{get data(){return a()},get form(){return t.form}}),W=>r()[1]=W,()=>{var W;return(W=r())==null?void 0:W[1]})}),z(w,b)},$$slots:{default:!0}}),w=>r()[0]=w,()=>{var w;return(w=r())==null?void 0:w[0]})}),z(_,h)},v=_=>{var h=qe();const g=ot(()=>t.constructors[0]);var x=se(h);Ht(x,()=>y(g),(E,m)=>{Yt(m(E,{get data(){return n()},get form(){return t.form}}),w=>r()[0]=w,()=>{var w;return(w=r())==null?void 0:w[0]})}),z(_,h)};_e(u,_=>{t.constructors[1]?_(f):_(v,!1)})}var d=D(u,2);{var p=_=>{var h=js(),g=P(h);{var x=E=>{var m=_i();Ge(()=>Ke(m,y(i))),z(E,m)};_e(g,E=>{y(s)&&E(x)})}I(h),z(_,h)};_e(d,_=>{y(o)&&_(p)})}z(e,c),et()}const Fs=Pi(zs),Vs=[()=>Cr(()=>Promise.resolve().then(()=>Js),void 0,import.meta.url),()=>Cr(()=>Promise.resolve().then(()=>el),void 0,import.meta.url),()=>Cr(()=>Promise.resolve().then(()=>gl),void 0,import.meta.url)],Bs=[],qs={"/":[2]},co={handleError:({error:e})=>{console.error(e)},reroute:()=>{},transport:{}},fo=Object.fromEntries(Object.entries(co.transport).map(([e,t])=>[e,t.decode])),Hs=!1,$s=(e,t)=>fo[e](t),Ys=Object.freeze(Object.defineProperty({__proto__:null,decode:$s,decoders:fo,dictionary:qs,hash:Hs,hooks:co,matchers:Ds,nodes:Vs,root:Fs,server_loads:Bs},Symbol.toStringTag,{value:"Module"}));function xl(e,t){ys(Ys,e,t)}const Gs=!0,Ws=Object.freeze(Object.defineProperty({__proto__:null,prerender:Gs},Symbol.toStringTag,{value:"Module"}));function Ks(e,t){var r=qe(),n=se(r);Ei(n,()=>t.children),z(e,r)}const Js=Object.freeze(Object.defineProperty({__proto__:null,component:Ks,universal:Ws},Symbol.toStringTag,{value:"Module"}));Co();const Xs={get error(){return q.error},get status(){return q.status}};be.updated.check;const qn=Xs;var Zs=ue("<h1> </h1> <p> </p>",1);function Qs(e,t){Qe(t,!1),Ri();var r=Zs(),n=se(r),a=P(n,!0);I(n);var o=D(n,2),s=P(o,!0);I(o),Ge(()=>{var i;Ke(a,qn.status),Ke(s,(i=qn.error)==null?void 0:i.message)}),z(e,r),et()}const el=Object.freeze(Object.defineProperty({__proto__:null,component:Qs},Symbol.toStringTag,{value:"Module"}));async function tl(e,t,r,n,a,o,s){if(y(t))return;S(t,!0),S(r,[],!0);const i=await fetch("/api/mirrors?action=create",{method:"POST",headers:{"Content-Type":"application/json"},body:JSON.stringify({token:y(n),guild_id:JSON.rawJSON(y(a)),channel_id:JSON.rawJSON(y(o))})});if(!i.body)return;const l=i.body.getReader(),c=new TextDecoder;let p="";for(;;){const{value:f,done:v}=await l.read();if(v)break;const d=(p+c.decode(f,{stream:!0})).split(`
+`);p=d.pop(),y(r).push(...d)}const u=p+c.decode();u.length&&y(r).push(u),S(t,!1),y(r).at(-1)==="success"&&(await s.states.refreshItems(),s.states.closeModal())}var rl=(e,t)=>t.states.closeModal(),nl=hi('<svg class="animate-spin" xmlns="http://www.w3.org/2000/svg" fill="none" width="16" height="16" viewBox="0 0 24 24"><circle class="opacity-25" cx="12" cy="12" r="10" stroke="currentColor" stroke-width="4"></circle><path class="opacity-75" fill="currentColor" d="M4 12a8 8 0 018-8V0C5.373 0 0 5.373 0 12h4zm2 5.291A7.962 7.962 0 014 12H0c0 3.042 1.135 5.824 3 7.938l3-2.647z"></path></svg>'),al=ue('<div class="flex items-center space-x-1.5"><!> <span> </span></div>'),ol=ue('<div class="flex"><span class="flex-auto uppercase tracking-widest">Create Discord Mirror</span> <button class="my-0.5 cursor-pointer"><svg xmlns="http://www.w3.org/2000/svg" width="16" height="16" viewBox="0 0 24 24"><path fill="none" stroke="currentColor" stroke-linecap="round" stroke-linejoin="round" stroke-width="2" d="M18 6L6 18M6 6l12 12"></path></svg></button></div> <div class="h-px mt-2 mb-3 bg-zinc-700"></div> <div class="flex flex-col space-y-1"><input type="text" class="min-w-64 h-8 px-3 bg-zinc-800 rounded-md border border-zinc-700" placeholder="Token"> <input type="text" class="min-w-64 h-8 px-3 bg-zinc-800 rounded-md border border-zinc-700" placeholder="Guild ID"> <input type="text" class="min-w-64 h-8 px-3 bg-zinc-800 rounded-md border border-zinc-700" placeholder="Voice Channel ID"> <div class="mt-3 mb-1.5 flex flex-col items-center"><button><svg xmlns="http://www.w3.org/2000/svg" width="16" height="16" viewBox="0 0 24 24"><path fill="none" stroke="currentColor" stroke-linecap="round" stroke-linejoin="round" stroke-width="2" d="M5 12h14m-7-7v14"></path></svg> <span>Add</span></button> <!></div></div>',1),il=ue('<div class="px-4 py-2.5 flex flex-col text-sm text-gray-100"><!></div>');function sl(e,t){Qe(t,!0);let r=O(void 0),n=O(void 0),a=O(void 0),o=O(!1),s=ot(()=>y(o)?"bg-indigo-300 cursor-not-allowed":"bg-indigo-500 cursor-pointer"),i=O(me([]));var l=il(),c=P(l);{var u=f=>{var v=ol(),d=se(v),p=D(P(d),2);p.__click=[rl,t],I(d);var _=D(d,4),h=P(_);$t(h);var g=D(h,2);$t(g);var x=D(g,2);$t(x);var E=D(x,2),m=P(E);m.__click=[tl,o,i,r,n,a,t];var w=D(m,2);{var k=b=>{var N=al(),V=P(N);{var oe=Te=>{var Mt=nl();z(Te,Mt)};_e(V,Te=>{y(o)&&Te(oe)})}var W=D(V,2),Ae=P(W,!0);I(W),I(N),Ge(Te=>Ke(Ae,Te),[()=>y(i).at(-1)]),z(b,N)};_e(w,b=>{y(i).length&&b(k)})}I(E),I(_),Ge(()=>Ua(m,1,`py-1.5 px-2 mb-1.5 flex items-center space-x-1 rounded-lg ${y(s)??""}`)),kr(h,()=>y(r),b=>S(r,b)),kr(g,()=>y(n),b=>S(n,b)),kr(x,()=>y(a),b=>S(a,b)),z(f,v)};_e(c,f=>{t.states&&f(u)})}I(l),z(e,l),et()}tn(["click"]);const mirrorKinds={discord:"Discord Live",hls:"HLS",recorder:"Recording",whep:"WHEP",whip:"WHIP Client"};function mirrorLabel(e){return`${mirrorKinds[e.kind]??e.kind} #${e.id}`}async function ll(e,t,r){if(y(t))return;S(t,!0);const n=await fetch("/api/mirrors?action=delete",{method:"POST",headers:{"Content-Type":"application/json"},body:JSON.stringify({id:r.mirror.id})});S(t,!1),n.status===200&&(await r.states.refreshItems(),r.states.closeModal())}var cl=(e,t)=>t.states.closeModal(),fl=ue('<div class="flex"><span class="flex-auto uppercase tracking-widest"> </span> <button class="my-0.5 cursor-pointer"><svg xmlns="http://www.w3.org/2000/svg" width="16" height="16" viewBox="0 0 24 24"><path fill="none" stroke="currentColor" stroke-linecap="round" stroke-linejoin="round" stroke-width="2" d="M18 6L6 18M6 6l12 12"></path></svg></button></div> <div class="h-px mt-2 mb-3 bg-zinc-700"></div> <div class="flex flex-col space-y-4.5"><span class="mb-0"> </span> <span>This action cannot be undone.</span> <span>Please confirm that you would like to proceed.</span> <div class="mb-3.5 flex flex-col items-center"><button><svg xmlns="http://www.w3.org/2000/svg" width="16" height="16" viewBox="-2 -2 28 28"><path fill="none" stroke="currentColor" stroke-linecap="round" stroke-linejoin="round" stroke-width="2" d="M3 6h18m-2 0v14c0 1-1 2-2 2H7c-1 0-2-1-2-2V6m3 0V4c0-1 1-2 2-2h4c1 0 2 1 2 2v2m-6 5v6m4-6v6"></path></svg> <span>Remove</span></button></div></div>',1),ul=ue('<div class="px-4 py-2.5 flex flex-col text-sm text-gray-100"><!></div>');function dl(e,t){Qe(t,!0);let r=O(!1),n=ot(()=>y(r)?"bg-red-300 cursor-not-allowed":"bg-red-500 cursor-pointer");var a=ul(),o=P(a);{var s=i=>{var l=fl(),c=se(l),u=P(c),f=P(u);I(u);var v=D(u,2);v.__click=[cl,t],I(c);var d=D(c,4),p=P(d),_=P(p);I(p);var h=D(p,6),g=P(h);g.__click=[ll,r,t],I(h),I(d),Ge(()=>{Ke(f,`Delete ${mirrorLabel(t.mirror)}`),Ke(_,`You are about to delete ${mirrorLabel(t.mirror)}.`),Ua(g,1,`py-1.5 px-2 flex items-center space-x-1 rounded-lg ${y(n)??""}`)}),z(i,l)};_e(o,i=>{t.states&&i(s)})}I(a),z(e,a),et()}tn(["click"]);var vl=ue('<span class="text-zinc-400/40 group-hover:text-zinc-500 transition">There are no active mirrors yet.</span>'),hl=ue('<div class="nth-2:mt-2.5 last:mb-2.5 relative flex group/mirrors hover:text-zinc-300 hover:bg-zinc-700 rounded-md transition"><div class="relative mx-2 w-1"><div class="absolute top-0 group-nth-2/mirrors:-top-2.5 right-0 bottom-0 group-last/mirrors:-bottom-2.5 left-0 bg-zinc-700 group-nth-2/mirrors:rounded-t-lg group-last/mirrors:rounded-b-lg"></div></div> <div class="flex-auto px-1.5 py-0.5"><span></span></div> <button class="my-1 mr-1 cursor-pointer"><svg xmlns="http://www.w3.org/2000/svg" width="16" height="16" viewBox="-2 -2 28 28"><path fill="none" stroke="currentColor" stroke-linecap="round" stroke-linejoin="round" stroke-width="2" d="M3 6h18m-2 0v14c0 1-1 2-2 2H7c-1 0-2-1-2-2V6m3 0V4c0-1 1-2 2-2h4c1 0 2 1 2 2v2m-6 5v6m4-6v6"></path></svg></button> <div class="flex absolute left-1.5 top-0 bottom-0 items-center"><div class="w-2 h-2 bg-zinc-500 group-hover/mirrors:bg-zinc-300 rounded-full transition"></div></div></div>'),_l=ue('<div class="m-auto bg-zinc-900 rounded-2xl backdrop:bg-zinc-600/40 backdrop:backdrop-blur" popover=""><!></div> <div class="h-screen flex justify-center items-center"><div class="size-full md:max-w-192 md:max-h-108 overflow-hidden flex flex-col bg-zinc-900 rounded-2xl border border-zinc-800 text-sm text-zinc-500"><div class="px-3 py-1.5 flex space-x-1.5 bg-zinc-700 text-gray-100"><span class="flex-auto">utsuru</span> <a class="my-0.5 cursor-pointer" href="https://github.com/VincentVerdynanta/utsuru" target="_blank"><svg xmlns="http://www.w3.org/2000/svg" width="16" height="16" viewBox="0 0 496 512"><path fill="currentColor" d="M165.9 397.4c0 2-2.3 3.6-5.2 3.6c-3.3.3-5.6-1.3-5.6-3.6c0-2 2.3-3.6 5.2-3.6c3-.3 5.6 1.3 5.6 3.6m-31.1-4.5c-.7 2 1.3 4.3 4.3 4.9c2.6 1 5.6 0 6.2-2s-1.3-4.3-4.3-5.2c-2.6-.7-5.5.3-6.2 2.3m44.2-1.7c-2.9.7-4.9 2.6-4.6 4.9c.3 2 2.9 3.3 5.9 2.6c2.9-.7 4.9-2.6 4.6-4.6c-.3-1.9-3-3.2-5.9-2.9M244.8 8C106.1 8 0 113.3 0 252c0 110.9 69.8 205.8 169.5 239.2c12.8 2.3 17.3-5.6 17.3-12.1c0-6.2-.3-40.4-.3-61.4c0 0-70 15-84.7-29.8c0 0-11.4-29.1-27.8-36.6c0 0-22.9-15.7 1.6-15.4c0 0 24.9 2 38.6 25.8c21.9 38.6 58.6 27.5 72.9 20.9c2.3-16 8.8-27.1 16-33.7c-55.9-6.2-112.3-14.3-112.3-110.5c0-27.5 7.6-41.3 23.6-58.9c-2.6-6.5-11.1-33.3 2.6-67.9c20.9-6.5 69 27 69 27c20-5.6 41.5-8.5 62.8-8.5s42.8 2.9 62.8 8.5c0 0 48.1-33.6 69-27c13.7 34.7 5.2 61.4 2.6 67.9c16 17.7 25.8 31.5 25.8 58.9c0 96.5-58.9 104.2-114.8 110.5c9.2 7.9 17 22.9 17 46.4c0 33.7-.3 75.4-.3 83.6c0 6.5 4.6 14.4 17.3 12.1C428.2 457.8 496 362.9 496 252C496 113.3 383.5 8 244.8 8M97.2 352.9c-1.3 1-1 3.3.7 5.2c1.6 1.6 3.9 2.3 5.2 1c1.3-1 1-3.3-.7-5.2c-1.6-1.6-3.9-2.3-5.2-1m-10.8-8.1c-.7 1.3.3 2.9 2.3 3.9c1.6 1 3.6.7 4.3-.7c.7-1.3-.3-2.9-2.3-3.9c-2-.6-3.6-.3-4.3.7m32.4 35.6c-1.6 1.3-1 4.3 1.3 6.2c2.3 2.3 5.2 2.6 6.5 1c1.3-1.3.7-4.3-1.3-6.2c-2.2-2.3-5.2-2.6-6.5-1m-11.4-14.7c-1.6 1-1.6 3.6 0 5.9s4.3 3.3 5.6 2.3c1.6-1.3 1.6-3.9 0-6.2c-1.4-2.3-4-3.3-5.6-2"></path></svg></a></div> <div class="flex-auto relative"><div class="grid overflow-auto absolute inset-0 md:grid-cols-2 gap-4 p-4"><div class="px-4 py-2.5 flex flex-col bg-zinc-800 rounded-md border border-transparent hover:border-indigo-500 transition group"><div class="mb-2 flex items-center group-hover:text-zinc-400 uppercase tracking-widest"><span class="my-1.5 mr-2 underline underline-offset-4 decoration-zinc-400/20 group-hover:decoration-zinc-400/40 transition">Mirrors</span> <button class="p-2 hover:bg-zinc-700 rounded-full transition cursor-pointer"><svg xmlns="http://www.w3.org/2000/svg" width="16" height="16" viewBox="0 0 24 24"><path fill="none" stroke="currentColor" stroke-linecap="round" stroke-linejoin="round" stroke-width="2" d="M5 12h14m-7-7v14"></path></svg></button> <button class="p-2 hover:bg-zinc-700 rounded-full transition cursor-pointer"><svg xmlns="http://www.w3.org/2000/svg" width="16" height="16" viewBox="0 0 24 24"><g fill="none" stroke="currentColor" stroke-linecap="round" stroke-linejoin="round" stroke-width="2"><path d="M21 12a9 9 0 0 0-9-9a9.75 9.75 0 0 0-6.74 2.74L3 8"></path><path d="M3 3v5h5m-5 4a9 9 0 0 0 9 9a9.75 9.75 0 0 0 6.74-2.74L21 16"></path><path d="M16 16h5v5"></path></g></svg></button></div> <!> <!></div> <div class="px-4 py-2.5 flex flex-col bg-zinc-800 rounded-md border border-transparent hover:border-indigo-500 transition group"><div class="mb-2 flex items-center space-x-2 group-hover:text-zinc-400 uppercase tracking-widest"><span class="my-1.5 underline underline-offset-4 decoration-zinc-400/20 group-hover:decoration-zinc-400/40 transition">WHIP</span></div> <div class="flex flex-col space-y-2 group-hover:text-zinc-400 transition"><span class="mb-3">Instructions for using utsuru with OBS:</span> <div class="flex space-x-2"><div class="w-8 h-8 flex justify-center items-center group-hover:bg-zinc-700 rounded-full border border-zinc-700 transition"><span>1</span></div> <div class="flex-auto flex flex-col justify-center *:my-1.5"><span>Open <b>Settings</b> &gt; <b>Stream</b></span></div></div> <div class="flex space-x-2"><div class="w-8 h-8 flex justify-center items-center group-hover:bg-zinc-700 rounded-full border border-zinc-700 transition"><span>2</span></div> <div class="flex-auto flex flex-col justify-center *:my-1.5"><span>Set the <b>Service</b> to <b>WHIP</b></span></div></div> <div class="flex space-x-2"><div class="w-8 h-8 flex justify-center items-center group-hover:bg-zinc-700 rounded-full border border-zinc-700 transition"><span>3</span></div> <div class="flex-auto flex flex-col justify-center *:my-1.5"><span>Fill the <b>Destination</b> properties with:</span> <div class="overflow-hidden flex flex-col group-hover:bg-zinc-700/40 rounded-md border border-zinc-700 transition"><div class="px-1.5 py-0.5 bg-zinc-500/20 group-hover:bg-zinc-700 text-xs transition"><span>Server</span></div> <div class="h-8 relative"><div class="flex absolute inset-0 items-center overflow-auto"><input type="text" class="size-full px-3" disabled></div></div></div> <div class="overflow-hidden flex flex-col group-hover:bg-zinc-700/40 rounded-md border border-zinc-700 transition"><div class="px-1.5 py-0.5 bg-zinc-500/20 group-hover:bg-zinc-700 text-xs transition"><span>Bearer Token (WIP, any value works)</span></div> <div class="h-8 relative"><div class="flex absolute inset-0 items-center overflow-auto"><input type="text" class="size-full px-3" value="utsuru" disabled></div></div></div></div></div></div></div></div></div></div></div>',1);function pl(e,t){Qe(t,!0);let r=O(me([])),n=O(void 0),a,o=O(void 0),s={closeModal:()=>a.hidePopover(),refreshItems:async()=>await c(),getItems:()=>y(r)};function i(){S(o,F=>sl(F,{states:s})),a.showPopover()}function l(F){S(o,de=>dl(de,{mirror:F,states:s})),a.showPopover()}async function c(){const F=await fetch("/api/mirrors");S(r,await F.json(),!0)}vt(async()=>{await c(),S(n,window.location.origin+"/whip")});var u=_l();vi(F=>{fa.title="utsuru"});var f=se(u),v=P(f);{var d=F=>{var de=qe(),jt=se(de);Ht(jt,()=>y(o),(Ut,wr)=>{wr(Ut,{})}),z(F,de)};_e(v,F=>{y(o)&&F(d)})}I(f),Yt(f,F=>a=F,()=>a);var p=D(f,2),_=P(p),h=D(P(_),2),g=P(h),x=P(g),E=P(x),m=D(P(E),2);m.__click=i;var w=D(m,2);w.__click=c,I(E);var k=D(E,2);{var b=F=>{var de=vl();z(F,de)};_e(k,F=>{y(r).length||F(b)})}var N=D(k,2);yi(N,17,()=>y(r),mi,(F,de,jt)=>{var Ut=qe(),wr=se(Ut);{var uo=yr=>{var br=hl(),xr=D(P(br),2),vo=P(xr);vo.textContent=mirrorLabel(y(de)),I(xr);var ho=D(xr,2);ho.__click=()=>l(y(de)),En(2),I(br),z(yr,br)};_e(wr,yr=>{y(de)&&yr(uo)})}z(F,Ut)}),I(x);var V=D(x,2),oe=D(P(V),2),W=D(P(oe),6),Ae=D(P(W),2),Te=D(P(Ae),2),Mt=D(P(Te),2),pn=P(Mt),gn=P(pn);$t(gn),I(pn),I(Mt),I(Te),En(2),I(Ae),I(W),I(oe),I(V),I(g),I(h),I(_),I(p),Ge(()=>Ti(gn,y(n))),z(e,u),et()}tn(["click"]);const gl=Object.freeze(Object.defineProperty({__proto__:null,component:pl},Symbol.toStringTag,{value:"Module"}));export{xl as start};